@@ -0,0 +1,28 @@
+CREATE TABLE IF NOT EXISTS tasks (
+    id SERIAL PRIMARY KEY,
+    project_id INT,
+    title TEXT NOT NULL,
+    description TEXT,
+    status TEXT NOT NULL DEFAULT 'Pending',
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    due_date BIGINT,
+    priority INT,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    parent_task INT REFERENCES tasks (id),
+    assigned_to INT,
+    assigned_by INT,
+    completed_at BIGINT,
+    archived_at BIGINT,
+    deleted_at BIGINT,
+    recurrence TEXT,
+    recurrence_end BIGINT,
+    dependencies INT[] NOT NULL DEFAULT '{}',
+    collaborators JSONB NOT NULL DEFAULT '[]',
+    progress SMALLINT,
+    comments JSONB NOT NULL DEFAULT '[]',
+    activity_log TEXT[] NOT NULL DEFAULT '{}',
+    custom_fields JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX IF NOT EXISTS tasks_project_id_idx ON tasks (project_id);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS workflows (
+    project_id INT PRIMARY KEY,
+    statuses TEXT[] NOT NULL,
+    initial_status TEXT NOT NULL,
+    transitions JSONB NOT NULL DEFAULT '[]',
+    updated_at BIGINT NOT NULL
+);
@@ -0,0 +1,7 @@
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS position INT NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS original_estimate BIGINT;
+
+ALTER TABLE tasks DROP CONSTRAINT IF EXISTS tasks_parent_not_self;
+ALTER TABLE tasks ADD CONSTRAINT tasks_parent_not_self CHECK (parent_task IS NULL OR parent_task <> id);
+
+CREATE INDEX IF NOT EXISTS tasks_parent_task_idx ON tasks (parent_task, position);
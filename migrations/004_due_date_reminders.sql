@@ -0,0 +1,9 @@
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS overdue_at BIGINT;
+
+CREATE INDEX IF NOT EXISTS tasks_due_date_idx ON tasks (due_date) WHERE due_date IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS reminder_settings (
+    user_id INT PRIMARY KEY,
+    offsets BIGINT[] NOT NULL,
+    updated_at BIGINT NOT NULL
+);
@@ -0,0 +1,48 @@
+CREATE TABLE IF NOT EXISTS activity_events (
+    id BIGSERIAL PRIMARY KEY,
+    subject_type TEXT NOT NULL,
+    subject_id INT NOT NULL,
+    actor_id INT,
+    kind TEXT NOT NULL,
+    old_value JSONB,
+    new_value JSONB,
+    correlation_id TEXT,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS activity_events_subject_idx ON activity_events (subject_type, subject_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS activity_events_correlation_idx ON activity_events (correlation_id) WHERE correlation_id IS NOT NULL;
+
+-- Old entries were "{rfc3339} - {message}"; keep the message as a legacy event. The column is
+-- dropped once copied, so a re-run finds nothing to copy.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns
+               WHERE table_schema = current_schema() AND table_name = 'tasks' AND column_name = 'activity_log') THEN
+        INSERT INTO activity_events (subject_type, subject_id, kind, new_value, timestamp)
+        SELECT 'Task',
+               t.id,
+               'Legacy',
+               to_jsonb(substr(entry, strpos(entry, ' - ') + 3)),
+               (EXTRACT(EPOCH FROM split_part(entry, ' - ', 1)::TIMESTAMPTZ) * 1000)::BIGINT
+        FROM tasks t, unnest(t.activity_log) AS entry
+        WHERE strpos(entry, ' - ') > 0;
+
+        ALTER TABLE tasks DROP COLUMN activity_log;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM information_schema.columns
+               WHERE table_schema = current_schema() AND table_name = 'users' AND column_name = 'activity_log') THEN
+        INSERT INTO activity_events (subject_type, subject_id, kind, new_value, timestamp)
+        SELECT 'User',
+               u.id,
+               'Legacy',
+               to_jsonb(substr(entry, strpos(entry, ' - ') + 3)),
+               (EXTRACT(EPOCH FROM split_part(entry, ' - ', 1)::TIMESTAMPTZ) * 1000)::BIGINT
+        FROM users u, unnest(u.activity_log) AS entry
+        WHERE strpos(entry, ' - ') > 0;
+
+        ALTER TABLE users DROP COLUMN activity_log;
+    END IF;
+END
+$$;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS task_versions (
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    changed_at BIGINT NOT NULL,
+    actor_id INT,
+    correlation_id TEXT,
+    changes JSONB NOT NULL,
+    PRIMARY KEY (task_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS task_versions_changed_at_idx ON task_versions (task_id, changed_at);
@@ -0,0 +1,7 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS archived_at BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS updated_at BIGINT;
+
+CREATE INDEX IF NOT EXISTS tasks_deleted_at_idx ON tasks (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS tasks_archived_at_idx ON tasks (archived_at) WHERE archived_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS users_deleted_at_idx ON users (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS custom_field_definitions (
+    id SERIAL PRIMARY KEY,
+    target TEXT NOT NULL,
+    key TEXT NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL,
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    allowed_values TEXT[] NOT NULL DEFAULT '{}',
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    UNIQUE (target, key)
+);
+
+-- Supports key-presence checks when definitions change or are removed
+CREATE INDEX IF NOT EXISTS tasks_custom_fields_idx ON tasks USING GIN (custom_fields);
@@ -0,0 +1,22 @@
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS remaining_estimate BIGINT;
+
+CREATE TABLE IF NOT EXISTS work_logs (
+    id BIGSERIAL PRIMARY KEY,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    user_id INT NOT NULL,
+    started_at BIGINT NOT NULL,
+    minutes BIGINT NOT NULL CHECK (minutes > 0),
+    note TEXT,
+    source TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS work_logs_task_idx ON work_logs (task_id, started_at DESC);
+CREATE INDEX IF NOT EXISTS work_logs_started_at_idx ON work_logs (started_at, user_id);
+
+-- One running timer per user
+CREATE TABLE IF NOT EXISTS active_timers (
+    user_id INT PRIMARY KEY,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    started_at BIGINT NOT NULL
+);
@@ -0,0 +1,62 @@
+CREATE TABLE IF NOT EXISTS projects (
+    id SERIAL PRIMARY KEY,
+    key TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    description TEXT,
+    owner_id INT NOT NULL,
+    archived_at BIGINT,
+    task_counter INT NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS project_members (
+    project_id INT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    user_id INT NOT NULL,
+    role TEXT NOT NULL,
+    added_at BIGINT NOT NULL,
+    PRIMARY KEY (project_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS project_members_user_idx ON project_members (user_id);
+
+-- Project ids already used by tasks or workflows become placeholder projects keyed P<id>.
+-- Owner 0 marks a project whose owner could not be inferred from its tasks.
+INSERT INTO projects (id, key, name, owner_id, created_at, updated_at)
+SELECT ids.project_id,
+       'P' || ids.project_id,
+       'Project ' || ids.project_id,
+       COALESCE((SELECT MIN(t.assigned_by) FROM tasks t WHERE t.project_id = ids.project_id),
+                (SELECT MIN(t.assigned_to) FROM tasks t WHERE t.project_id = ids.project_id),
+                0),
+       (EXTRACT(EPOCH FROM now()) * 1000)::BIGINT,
+       (EXTRACT(EPOCH FROM now()) * 1000)::BIGINT
+FROM (
+    SELECT project_id FROM tasks WHERE project_id IS NOT NULL
+    UNION
+    SELECT project_id FROM workflows
+) ids
+ON CONFLICT DO NOTHING;
+
+SELECT setval(pg_get_serial_sequence('projects', 'id'), GREATEST((SELECT MAX(id) FROM projects), 1));
+
+INSERT INTO project_members (project_id, user_id, role, added_at)
+SELECT id, owner_id, 'Admin', created_at FROM projects WHERE owner_id <> 0
+ON CONFLICT DO NOTHING;
+
+-- Existing tasks are numbered in creation order within their project
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS number INT;
+
+UPDATE tasks t SET number = numbered.number
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY project_id ORDER BY created_at, id) AS number
+    FROM tasks
+    WHERE project_id IS NOT NULL
+) numbered
+WHERE t.id = numbered.id AND t.number IS NULL;
+
+UPDATE projects p SET task_counter = COALESCE((SELECT MAX(number) FROM tasks t WHERE t.project_id = p.id), 0);
+
+ALTER TABLE tasks DROP CONSTRAINT IF EXISTS tasks_project_id_fkey;
+ALTER TABLE tasks ADD CONSTRAINT tasks_project_id_fkey FOREIGN KEY (project_id) REFERENCES projects (id);
+CREATE UNIQUE INDEX IF NOT EXISTS tasks_project_number_idx ON tasks (project_id, number);
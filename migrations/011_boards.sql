@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS boards (
+    id SERIAL PRIMARY KEY,
+    project_id INT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    columns JSONB NOT NULL,
+    swimlane TEXT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS boards_project_idx ON boards (project_id);
+
+-- Manual card order within a board; cards never moved have no row
+CREATE TABLE IF NOT EXISTS board_cards (
+    board_id INT NOT NULL REFERENCES boards (id) ON DELETE CASCADE,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    rank BIGINT NOT NULL,
+    PRIMARY KEY (board_id, task_id)
+);
+
+CREATE INDEX IF NOT EXISTS tasks_project_status_idx ON tasks (project_id, status);
@@ -0,0 +1,60 @@
+CREATE TABLE IF NOT EXISTS sprints (
+    id SERIAL PRIMARY KEY,
+    project_id INT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    goal TEXT,
+    start_date BIGINT NOT NULL,
+    end_date BIGINT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'Planned',
+    started_at BIGINT,
+    closed_at BIGINT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS sprints_project_idx ON sprints (project_id, state);
+
+-- At most one running sprint per project
+CREATE UNIQUE INDEX IF NOT EXISTS sprints_active_idx ON sprints (project_id) WHERE state = 'Active';
+
+CREATE TABLE IF NOT EXISTS sprint_tasks (
+    sprint_id INT NOT NULL REFERENCES sprints (id) ON DELETE CASCADE,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    added_at BIGINT NOT NULL,
+    PRIMARY KEY (sprint_id, task_id)
+);
+
+CREATE INDEX IF NOT EXISTS sprint_tasks_task_idx ON sprint_tasks (task_id);
+
+-- Append-only log used to report committed vs. added/removed scope
+CREATE TABLE IF NOT EXISTS sprint_scope_changes (
+    id BIGSERIAL PRIMARY KEY,
+    sprint_id INT NOT NULL REFERENCES sprints (id) ON DELETE CASCADE,
+    task_id INT NOT NULL,
+    kind TEXT NOT NULL,
+    actor_id INT,
+    changed_at BIGINT NOT NULL,
+    after_start BOOLEAN NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS sprint_scope_changes_sprint_idx ON sprint_scope_changes (sprint_id, id);
+
+CREATE TABLE IF NOT EXISTS milestones (
+    id SERIAL PRIMARY KEY,
+    project_id INT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    start_date BIGINT,
+    due_date BIGINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS milestones_project_idx ON milestones (project_id, due_date);
+
+-- A task belongs to at most one milestone
+CREATE TABLE IF NOT EXISTS milestone_tasks (
+    milestone_id INT NOT NULL REFERENCES milestones (id) ON DELETE CASCADE,
+    task_id INT NOT NULL UNIQUE REFERENCES tasks (id) ON DELETE CASCADE,
+    PRIMARY KEY (milestone_id, task_id)
+);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS task_watchers (
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    added_at BIGINT NOT NULL,
+    PRIMARY KEY (task_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    actor_id INT,
+    payload JSONB,
+    created_at BIGINT NOT NULL,
+    read_at BIGINT
+);
+
+-- Inbox listing and the unread badge
+CREATE INDEX IF NOT EXISTS notifications_user_idx ON notifications (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS notifications_unread_idx ON notifications (user_id) WHERE read_at IS NULL;
+
+-- Mentions look users up by name regardless of case
+CREATE INDEX IF NOT EXISTS users_username_lower_idx ON users (LOWER(username));
@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS task_comments (
+    id BIGSERIAL PRIMARY KEY,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    parent_id BIGINT REFERENCES task_comments (id) ON DELETE CASCADE,
+    user_id INT NOT NULL,
+    body TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    edited_at BIGINT,
+    deleted_at BIGINT,
+    -- Earlier bodies, oldest first
+    edits JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS task_comments_task_idx ON task_comments (task_id, created_at) WHERE parent_id IS NULL;
+CREATE INDEX IF NOT EXISTS task_comments_parent_idx ON task_comments (parent_id);
+
+CREATE TABLE IF NOT EXISTS comment_reactions (
+    comment_id BIGINT NOT NULL REFERENCES task_comments (id) ON DELETE CASCADE,
+    user_id INT NOT NULL,
+    emoji TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (comment_id, user_id, emoji)
+);
+
+-- Move the embedded comments into their own rows, keeping their original order
+INSERT INTO task_comments (task_id, user_id, body, created_at)
+SELECT t.id, (c.value ->> 'user_id')::INT, c.value ->> 'comment', (c.value ->> 'timestamp')::BIGINT
+FROM tasks t, jsonb_array_elements(t.comments) WITH ORDINALITY AS c (value, ordinal)
+ORDER BY t.id, c.ordinal;
+
+ALTER TABLE tasks DROP COLUMN IF EXISTS comments;
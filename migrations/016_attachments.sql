@@ -0,0 +1,35 @@
+CREATE TABLE IF NOT EXISTS task_attachments (
+    id BIGSERIAL PRIMARY KEY,
+    task_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    -- Hex-encoded SHA-256 of the content
+    checksum TEXT NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    uploaded_by INT,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS task_attachments_task_idx ON task_attachments (task_id, created_at);
+
+-- Storage keys whose content still has to be removed from the blob store
+CREATE TABLE IF NOT EXISTS attachment_deletions (
+    storage_key TEXT PRIMARY KEY,
+    queued_at BIGINT NOT NULL
+);
+
+-- Queues the content of every removed attachment, including rows removed when a task is purged
+CREATE OR REPLACE FUNCTION queue_attachment_deletion() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO attachment_deletions (storage_key, queued_at)
+    VALUES (OLD.storage_key, (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT)
+    ON CONFLICT (storage_key) DO NOTHING;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS task_attachments_deleted ON task_attachments;
+CREATE TRIGGER task_attachments_deleted
+    AFTER DELETE ON task_attachments
+    FOR EACH ROW EXECUTE FUNCTION queue_attachment_deletion();
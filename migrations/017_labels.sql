@@ -0,0 +1,45 @@
+CREATE TABLE IF NOT EXISTS labels (
+    id SERIAL PRIMARY KEY,
+    -- Normalized: trimmed, lowercase, inner whitespace replaced by a dash
+    name TEXT NOT NULL UNIQUE,
+    color TEXT,
+    description TEXT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+-- Normalize the free-form tags already on tasks, dropping blanks and duplicates
+UPDATE tasks t SET tags = ARRAY(
+    SELECT normalized FROM (
+        SELECT lower(regexp_replace(regexp_replace(tag, '^\s+|\s+$', '', 'g'), '\s+', '-', 'g')) AS normalized,
+               MIN(ordinal) AS first
+        FROM unnest(t.tags) WITH ORDINALITY AS u (tag, ordinal)
+        GROUP BY 1
+    ) n
+    WHERE normalized <> ''
+    ORDER BY first
+)
+WHERE cardinality(t.tags) > 0;
+
+INSERT INTO labels (name, created_at, updated_at)
+SELECT DISTINCT tag, (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT, (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT
+FROM tasks, unnest(tags) AS tag
+ON CONFLICT (name) DO NOTHING;
+
+CREATE INDEX IF NOT EXISTS tasks_tags_idx ON tasks USING GIN (tags);
+
+-- Every tag written to a task has a label, however the task was saved
+CREATE OR REPLACE FUNCTION ensure_task_labels() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO labels (name, created_at, updated_at)
+    SELECT tag, NEW.updated_at, NEW.updated_at FROM unnest(NEW.tags) AS tag
+    ON CONFLICT (name) DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS tasks_ensure_labels ON tasks;
+CREATE TRIGGER tasks_ensure_labels
+    AFTER INSERT OR UPDATE OF tags ON tasks
+    FOR EACH ROW WHEN (cardinality(NEW.tags) > 0)
+    EXECUTE FUNCTION ensure_task_labels();
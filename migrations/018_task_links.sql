@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS task_links (
+    id BIGSERIAL PRIMARY KEY,
+    -- Read as "source <link_type> target"; RelatesTo links keep the lower id as the source
+    source_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    target_id INT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+    link_type TEXT NOT NULL,
+    created_by INT,
+    created_at BIGINT NOT NULL,
+    CHECK (source_id <> target_id),
+    UNIQUE (source_id, target_id, link_type)
+);
+
+CREATE INDEX IF NOT EXISTS task_links_target_idx ON task_links (target_id);
+
+-- Existing dependencies become blocking links: each dependency blocks the task listing it
+INSERT INTO task_links (source_id, target_id, link_type, created_at)
+SELECT DISTINCT d.dependency, t.id, 'Blocks', t.updated_at
+FROM tasks t, unnest(t.dependencies) AS d (dependency)
+WHERE d.dependency <> t.id AND EXISTS (SELECT 1 FROM tasks o WHERE o.id = d.dependency)
+ON CONFLICT DO NOTHING;
+
+UPDATE tasks t SET dependencies = ARRAY(
+    SELECT DISTINCT d FROM unnest(t.dependencies) AS d
+    WHERE d <> t.id AND EXISTS (SELECT 1 FROM tasks o WHERE o.id = d)
+)
+WHERE cardinality(t.dependencies) > 0;
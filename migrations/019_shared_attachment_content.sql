@@ -0,0 +1,15 @@
+-- Cloned tasks share the stored content of their attachments
+ALTER TABLE task_attachments DROP CONSTRAINT IF EXISTS task_attachments_storage_key_key;
+CREATE INDEX IF NOT EXISTS task_attachments_storage_key_idx ON task_attachments (storage_key);
+
+-- Content is only queued for removal once the last row referring to it is gone
+CREATE OR REPLACE FUNCTION queue_attachment_deletion() RETURNS TRIGGER AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM task_attachments WHERE storage_key = OLD.storage_key) THEN
+        INSERT INTO attachment_deletions (storage_key, queued_at)
+        VALUES (OLD.storage_key, (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT)
+        ON CONFLICT (storage_key) DO NOTHING;
+    END IF;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
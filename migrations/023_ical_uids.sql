@@ -0,0 +1,3 @@
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS ical_uid TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS tasks_ical_uid_idx ON tasks (ical_uid) WHERE ical_uid IS NOT NULL;
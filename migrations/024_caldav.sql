@@ -0,0 +1,59 @@
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS caldav_name TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS tasks_caldav_name_idx ON tasks (caldav_name) WHERE caldav_name IS NOT NULL;
+
+-- Orders every task write, so CalDAV clients can ask for what changed since their last sync
+CREATE SEQUENCE IF NOT EXISTS task_sync_seq;
+
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS sync_seq BIGINT NOT NULL DEFAULT nextval('task_sync_seq');
+
+CREATE INDEX IF NOT EXISTS tasks_sync_seq_idx ON tasks (sync_seq);
+
+CREATE OR REPLACE FUNCTION bump_task_sync_seq() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.sync_seq := nextval('task_sync_seq');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS tasks_bump_sync_seq ON tasks;
+CREATE TRIGGER tasks_bump_sync_seq
+    BEFORE UPDATE ON tasks
+    FOR EACH ROW EXECUTE FUNCTION bump_task_sync_seq();
+
+-- A task leaves a calendar when it moves to another project or assignee, is trashed or is
+-- purged; the tombstone keeps its old place and resource name so syncing clients drop it
+CREATE TABLE IF NOT EXISTS task_tombstones (
+    id BIGSERIAL PRIMARY KEY,
+    task_id INT NOT NULL,
+    project_id INT,
+    assigned_to INT,
+    resource_name TEXT NOT NULL,
+    sync_seq BIGINT NOT NULL DEFAULT nextval('task_sync_seq')
+);
+
+CREATE INDEX IF NOT EXISTS task_tombstones_sync_seq_idx ON task_tombstones (sync_seq);
+
+CREATE OR REPLACE FUNCTION record_task_tombstone() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND OLD.project_id IS NOT DISTINCT FROM NEW.project_id
+        AND OLD.assigned_to IS NOT DISTINCT FROM NEW.assigned_to
+        AND NOT (OLD.archived_at IS NULL AND OLD.deleted_at IS NULL
+            AND (NEW.archived_at IS NOT NULL OR NEW.deleted_at IS NOT NULL)) THEN
+        RETURN NULL;
+    END IF;
+    INSERT INTO task_tombstones (task_id, project_id, assigned_to, resource_name)
+    VALUES (
+        OLD.id,
+        OLD.project_id,
+        OLD.assigned_to,
+        COALESCE(OLD.caldav_name, COALESCE(OLD.ical_uid, 'task-' || OLD.id || '@taskflow') || '.ics')
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS tasks_record_tombstone ON tasks;
+CREATE TRIGGER tasks_record_tombstone
+    AFTER UPDATE OR DELETE ON tasks
+    FOR EACH ROW EXECUTE FUNCTION record_task_tombstone();
@@ -1,25 +1,112 @@
+use chrono_tz::Tz;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
 use crate::domain::entities::{
-    task::{Task, TaskStatus, Recurrence},
+    activity::{ActivityEvent, ActivityKind},
+    attachment::{sanitize_filename, AttachmentLimits},
+    board::{Board, BoardView, Card, ColumnSummary, Lane, Swimlane},
+    bulk::BulkOperation,
+    clone::{CloneOptions, PlannedClone, MAX_CLONE_TASKS},
+    comment::{Comment, CommentPolicy, MAX_REACTION_LENGTH},
+    custom_field::{CustomFieldDefinition, CustomFieldTarget},
+    history::{self, FieldChange, TaskVersion},
+    ical::ImportedTodo,
+    label::{normalize_color, normalize_label, Label, MAX_LABEL_LENGTH},
+    link::{LinkRelation, LinkedTask, TaskLink},
+    milestone::{Milestone, MilestoneProgress},
+    notification::{Notification, NotificationKind},
+    project::{Project, ProjectRole},
+    quick_add::QuickAdd,
+    sprint::{ScopeChange, ScopeChangeKind, Sprint, SprintScope, SprintState},
+    reminder::{ReminderEvent, ReminderKind, ReminderSettings, MAX_REMINDER_OFFSETS, MAX_REMINDER_OFFSET_MINUTES},
+    task::{ChecklistItem, DueDate, Task, TaskStatus, Recurrence, MAX_CHECKLIST_ITEMS, MAX_CHECKLIST_TEXT_LENGTH},
+    template::{PlannedTask, TaskTemplate, MAX_TEMPLATE_DEPTH, MAX_TEMPLATE_TASKS},
+    time_tracking::{TimeSummary, WorkLog, MAX_REPORT_RANGE_DAYS, MAX_WORK_LOG_MINUTES},
+    trash::TrashState,
     user::{User, Role},
+    workflow::Workflow,
 };
 use crate::domain::errors::AppError;
+use crate::domain::time::{end_of_day, resolve_local, to_rfc3339};
 
 pub struct TaskService;
 
 impl TaskService {
-    pub fn create_task(title: String, description: Option<String>, description0: Option<String>) -> Result<Task, AppError> {
+    // New tasks start in the initial status of their project's workflow
+    pub fn create_task(title: String, description: Option<String>, workflow: &Workflow) -> Result<Task, AppError> {
         if title.is_empty() {
             return Err(AppError::ValidationError { field: "".to_string(), message: "Title cannot be empty".to_string() });
         }
-        Ok(Task::new(title, description))
+        let mut task = Task::new(title, description);
+        task.status = workflow.initial_status;
+        Ok(task)
+    }
+
+    pub fn change_task_status(task: &mut Task, workflow: &Workflow, status: TaskStatus) -> Result<(), AppError> {
+        if task.status == status {
+            return Err(AppError::validation_error("status", &format!("Task is already {}", status)));
+        }
+        if !workflow.has_status(status) {
+            return Err(AppError::validation_error(
+                "status",
+                &format!("Status {} is not part of this workflow", status),
+            ));
+        }
+        if !workflow.allows(task.status, status) {
+            let allowed = workflow
+                .next_statuses(task.status)
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>();
+            let allowed = if allowed.is_empty() { "none".to_string() } else { allowed.join(", ") };
+            return Err(AppError::validation_error(
+                "status",
+                &format!("Cannot move task from {} to {}; allowed next statuses: {}", task.status, status, allowed),
+            ));
+        }
+        task.set_status(status);
+        Ok(())
+    }
+
+    pub fn task_as_of(current: &Task, versions: &[TaskVersion], timestamp: i64) -> Result<Task, AppError> {
+        history::task_as_of(current, versions, timestamp)
+            .map_err(|e| AppError::invalid_input(&format!("Task history of task {} cannot be replayed: {}", current.id, e)))?
+            .ok_or_else(|| AppError::validation_error("at", &format!("Task {} did not exist at {}", current.id, timestamp)))
+    }
+
+    pub fn diff_task_versions(current: &Task, versions: &[TaskVersion], from: i32, to: i32) -> Result<Vec<FieldChange>, AppError> {
+        for version in [from, to] {
+            if !versions.iter().any(|v| v.version == version) {
+                return Err(AppError::validation_error("version", &format!("Task {} has no version {}", current.id, version)));
+            }
+        }
+        let replay = |version| {
+            history::task_at_version(current, versions, version)
+                .map_err(|e| AppError::invalid_input(&format!("Task history of task {} cannot be replayed: {}", current.id, e)))
+        };
+        Ok(history::diff_tasks(&replay(from)?, &replay(to)?))
+    }
+
+    // Stores each value through its definition, then checks that every required field is present
+    pub fn set_custom_fields(
+        task: &mut Task,
+        definitions: &[CustomFieldDefinition],
+        values: HashMap<String, serde_json::Value>,
+    ) -> Result<(), AppError> {
+        for (key, value) in values {
+            let definition = CustomFieldService::find_definition(definitions, CustomFieldTarget::Task, &key)?;
+            task.add_custom_field(definition, value)?;
+        }
+        CustomFieldService::check_values(&task.custom_fields, definitions, CustomFieldTarget::Task)
     }
 
-    pub fn complete_task(task: &mut Task) -> Result<(), AppError> {
+    // Completion is a status change like any other and must be allowed by the workflow
+    pub fn complete_task(task: &mut Task, workflow: &Workflow) -> Result<(), AppError> {
         if task.status == TaskStatus::Completed {
             return Err(AppError::ValidationError { field: "".to_string(), message: "Task is already completed".to_string() });
         }
-        task.complete();
-        Ok(())
+        Self::change_task_status(task, workflow, TaskStatus::Completed)
     }
 
     pub fn archive_task(task: &mut Task) -> Result<(), AppError> {
@@ -38,19 +125,412 @@ impl TaskService {
         Ok(())
     }
 
-    pub fn add_comment_to_task(task: &mut Task, user_id: i32, comment: String) -> Result<(), AppError> {
-        if task.status == TaskStatus::Completed {
-            return Err(AppError::ValidationError { field: "".to_string(), message: "Cannot add comments to completed tasks".to_string() });
+    // Applies one bulk operation to `root_id`, or to its whole subtree for archive, delete and
+    // project moves. Returns the ids of the tasks that actually changed.
+    pub fn apply_bulk_operation(
+        tasks: &mut HashMap<i32, Task>,
+        root_id: i32,
+        operation: &BulkOperation,
+        workflow: &Workflow,
+        actor_id: Option<i32>,
+    ) -> Result<Vec<i32>, AppError> {
+        match operation {
+            BulkOperation::Archive => return Self::trash_subtree(tasks, root_id, TrashState::Archived),
+            BulkOperation::Delete => return Self::trash_subtree(tasks, root_id, TrashState::Deleted),
+            BulkOperation::MoveToProject { project_id } => return Self::move_subtree(tasks, root_id, *project_id),
+            _ => {}
+        }
+
+        let task = tasks.get_mut(&root_id).ok_or_else(|| AppError::not_found("Task", root_id))?;
+        if task.is_trashed() {
+            return Err(AppError::validation_error("ids", &format!("Task {} is in the trash", root_id)));
+        }
+        let changed = match operation {
+            BulkOperation::SetStatus { status } if task.status != *status => {
+                Self::change_task_status(task, workflow, *status)?;
+                true
+            }
+            BulkOperation::Assign { user_id } if task.assigned_to != *user_id => {
+                task.assign(*user_id, actor_id);
+                true
+            }
+            BulkOperation::AddTag { tag } => {
+                if normalize_label(tag).is_none() {
+                    return Err(AppError::validation_error("tag", "Tag cannot be empty"));
+                }
+                task.add_tag(tag)
+            }
+            BulkOperation::RemoveTag { tag } => task.remove_tag(tag),
+            BulkOperation::SetPriority { priority } if task.priority != *priority => {
+                task.set_priority(*priority);
+                true
+            }
+            _ => false,
+        };
+        Ok(if changed { vec![root_id] } else { Vec::new() })
+    }
+
+    // Subtasks always live in their parent's project, so only whole trees move
+    pub fn move_subtree(subtree: &mut HashMap<i32, Task>, root_id: i32, project_id: Option<i32>) -> Result<Vec<i32>, AppError> {
+        let root = subtree.get(&root_id).ok_or_else(|| AppError::not_found("Task", root_id))?;
+        if let Some(parent_id) = root.parent_task {
+            return Err(AppError::validation_error(
+                "project_id",
+                &format!("Task {} is a subtask of task {} and moves with it", root_id, parent_id),
+            ));
+        }
+        let mut changed = Vec::new();
+        for task in subtree.values_mut().filter(|t| t.project_id != project_id) {
+            task.move_to_project(project_id);
+            changed.push(task.id);
+        }
+        Ok(changed)
+    }
+
+    // A new task from parsed quick-add text; an assignee that was named must exist
+    // Dates without a time become date-only due dates in `timezone`
+    pub fn quick_add_task(
+        parsed: &QuickAdd,
+        timezone: Tz,
+        assignee_id: Option<i32>,
+        actor_id: Option<i32>,
+        workflow: &Workflow,
+    ) -> Result<Task, AppError> {
+        if let (Some(username), None) = (&parsed.assignee, assignee_id) {
+            return Err(AppError::validation_error("assignee", &format!("No user named '{}'", username)));
+        }
+        let mut task = Self::create_task(parsed.title.clone(), None, workflow)?;
+        task.tags = parsed.tags.clone();
+        task.priority = parsed.priority;
+        task.due_date = parsed.due_date;
+        task.due_all_day = parsed.due_date.is_some() && !parsed.due_has_time;
+        task.due_timezone = parsed.due_date.map(|_| timezone.name().to_string());
+        task.recurrence = parsed.recurrence;
+        task.assigned_to = assignee_id;
+        task.assigned_by = assignee_id.and(actor_id);
+        Ok(task)
+    }
+
+    pub fn add_checklist_item(task: &mut Task, text: &str, position: Option<usize>) -> Result<ChecklistItem, AppError> {
+        if task.checklist.len() >= MAX_CHECKLIST_ITEMS {
+            return Err(AppError::validation_error(
+                "checklist",
+                &format!("Checklists hold at most {} items", MAX_CHECKLIST_ITEMS),
+            ));
+        }
+        let text = Self::checklist_text(text)?;
+        Ok(task.add_checklist_item(text, position).clone())
+    }
+
+    pub fn checklist_text(text: &str) -> Result<String, AppError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(AppError::validation_error("text", "Checklist items need text"));
+        }
+        if text.chars().count() > MAX_CHECKLIST_TEXT_LENGTH {
+            return Err(AppError::validation_error(
+                "text",
+                &format!("Checklist items are limited to {} characters", MAX_CHECKLIST_TEXT_LENGTH),
+            ));
+        }
+        Ok(text.to_string())
+    }
+
+    // Fresh copies of `root_id` and, when asked for, its live subtasks in their order. The
+    // copies have no parent, subtasks or dependencies yet, and start in the workflow's initial status.
+    pub fn plan_clone(
+        subtree: &HashMap<i32, Task>,
+        root_id: i32,
+        options: &CloneOptions,
+        workflow: &Workflow,
+    ) -> Result<Vec<PlannedClone>, AppError> {
+        let root = subtree.get(&root_id).ok_or_else(|| AppError::not_found("Task", root_id))?;
+        if root.is_trashed() {
+            return Err(AppError::validation_error("task", &format!("Task {} is archived or deleted", root_id)));
+        }
+
+        let mut planned: Vec<PlannedClone> = Vec::new();
+        let mut stack = vec![(None, root)];
+        while let Some((parent, source)) = stack.pop() {
+            let title = match (&options.title, parent) {
+                (Some(title), None) => title.clone(),
+                _ => source.title.clone(),
+            };
+            let mut task = Self::create_task(title, source.description.clone(), workflow)?;
+            task.project_id = source.project_id;
+            task.priority = source.priority;
+            task.due_date = source.due_date;
+            task.due_all_day = source.due_all_day;
+            task.due_timezone = source.due_timezone.clone();
+            task.recurrence = source.recurrence;
+            task.recurrence_end = source.recurrence_end;
+            task.original_estimate = source.original_estimate;
+            task.remaining_estimate = source.original_estimate;
+            task.assigned_to = source.assigned_to;
+            task.assigned_by = source.assigned_by;
+            if options.include_tags {
+                task.tags = source.tags.clone();
+            }
+            if options.include_custom_fields {
+                task.custom_fields = source.custom_fields.clone();
+            }
+            if options.include_collaborators {
+                task.collaborators = source.collaborators.clone();
+            }
+            if options.include_checklist {
+                task.checklist = source
+                    .checklist
+                    .iter()
+                    .map(|item| ChecklistItem { checked: false, checked_by: None, checked_at: None, ..item.clone() })
+                    .collect();
+                task.checklist_progress = source.checklist_progress;
+            }
+
+            let index = planned.len();
+            planned.push(PlannedClone { source_id: source.id, parent, task });
+            if options.include_subtasks {
+                let children = source.subtasks.iter().filter_map(|id| subtree.get(id)).filter(|t| !t.is_trashed());
+                stack.extend(children.rev().map(|child| (Some(index), child)));
+            }
+        }
+        if planned.len() > MAX_CLONE_TASKS {
+            return Err(AppError::validation_error(
+                "include_subtasks",
+                &format!("At most {} tasks can be cloned at once", MAX_CLONE_TASKS),
+            ));
+        }
+        Ok(planned)
+    }
+
+    // Gives every saved clone the blockers of its source, pointing at the clone of a blocker
+    // cloned along with it and at the original one otherwise. Trashed or unknown blockers
+    // outside the subtree are dropped.
+    pub fn clone_dependencies(
+        planned: &[PlannedClone],
+        sources: &HashMap<i32, Task>,
+        clones: &mut [Task],
+        blockers: &mut HashMap<i32, Task>,
+        created_by: Option<i32>,
+    ) -> Vec<TaskLink> {
+        let clone_ids = planned
+            .iter()
+            .zip(clones.iter())
+            .map(|(plan, clone)| (plan.source_id, clone.id))
+            .collect::<HashMap<_, _>>();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let mut links = Vec::new();
+        for (index, plan) in planned.iter().enumerate() {
+            let Some(source) = sources.get(&plan.source_id) else { continue };
+            for dependency in source.dependencies.iter().filter(|d| **d != source.id) {
+                let clone_id = clones[index].id;
+                let blocker_id = match clone_ids.get(dependency) {
+                    Some(blocker_clone) => {
+                        let blocker = clones.iter_mut().find(|t| t.id == *blocker_clone).expect("clone ids map to saved clones");
+                        blocker.add_link(LinkRelation::Blocks, clone_id);
+                        *blocker_clone
+                    }
+                    None => match blockers.get_mut(dependency).filter(|t| !t.is_trashed()) {
+                        Some(blocker) => {
+                            blocker.add_link(LinkRelation::Blocks, clone_id);
+                            blocker.id
+                        }
+                        None => continue,
+                    },
+                };
+                clones[index].add_link(LinkRelation::BlockedBy, blocker_id);
+                links.push(TaskLink::new(clone_id, LinkRelation::BlockedBy, blocker_id, created_by, now));
+            }
+        }
+        links
+    }
+
+    // Trashes the root of `subtree` and every descendant that is not trashed the same way yet.
+    // Returns the ids of the tasks that changed.
+    pub fn trash_subtree(subtree: &mut HashMap<i32, Task>, root_id: i32, state: TrashState) -> Result<Vec<i32>, AppError> {
+        let root = subtree.get_mut(&root_id).ok_or_else(|| AppError::not_found("Task", root_id))?;
+        let at = match state {
+            TrashState::Archived => {
+                Self::archive_task(root)?;
+                root.archived_at
+            }
+            TrashState::Deleted => {
+                Self::delete_task(root)?;
+                root.deleted_at
+            }
+        };
+        let at = at.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+
+        let mut changed = vec![root_id];
+        for task in subtree.values_mut().filter(|t| t.id != root_id) {
+            match state {
+                TrashState::Archived if task.archived_at.is_none() => task.archive_at(at),
+                TrashState::Deleted if task.deleted_at.is_none() => task.delete_at(at),
+                _ => continue,
+            }
+            changed.push(task.id);
+        }
+        Ok(changed)
+    }
+
+    // Restores the root of `subtree` and the descendants that were trashed together with it.
+    // The root is detached when its parent is still trashed or gone. Returns the changed ids.
+    pub fn restore_subtree(subtree: &mut HashMap<i32, Task>, root_id: i32, parent: Option<&Task>) -> Result<Vec<i32>, AppError> {
+        let root = subtree.get_mut(&root_id).ok_or_else(|| AppError::not_found("Task", root_id))?;
+        if !root.is_trashed() {
+            return Err(AppError::validation_error("", "Task is neither archived nor deleted"));
+        }
+        let (archived_at, deleted_at) = (root.archived_at, root.deleted_at);
+        root.restore();
+        if root.parent_task.is_some() && parent.is_none_or(|p| p.is_trashed()) {
+            root.set_parent(None);
+        }
+
+        let mut changed = vec![root_id];
+        for task in subtree.values_mut().filter(|t| t.id != root_id && t.is_trashed()) {
+            let archived_together = archived_at.is_some() && task.archived_at == archived_at;
+            let deleted_together = deleted_at.is_some() && task.deleted_at == deleted_at;
+            if archived_together || deleted_together {
+                task.restore();
+                changed.push(task.id);
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn ensure_purgeable(task: &Task) -> Result<(), AppError> {
+        if !task.is_trashed() {
+            return Err(AppError::validation_error("", "Only archived or deleted tasks can be purged"));
         }
-        task.add_comment(user_id, comment);
         Ok(())
     }
 
-    pub fn add_subtask(task: &mut Task, subtask: Task) -> Result<(), AppError> {
+    // Checks a new comment against the policy before it is stored. `parent_depth` is the depth
+    // of the comment being replied to, top-level comments having depth 1.
+    pub fn add_comment_to_task(
+        task: &Task,
+        policy: &CommentPolicy,
+        comment: &mut Comment,
+        parent: Option<(&Comment, usize)>,
+    ) -> Result<(), AppError> {
+        if policy.closed_statuses.contains(&task.status) {
+            return Err(AppError::validation_error("status", &format!("Cannot add comments to {} tasks", task.status)));
+        }
+        if task.is_trashed() && !policy.allow_on_trashed {
+            return Err(AppError::validation_error("task", &format!("Task {} is archived or deleted", task.id)));
+        }
+        comment.body = CommentService::normalize_body(policy, &comment.body)?;
+        if let Some((parent, depth)) = parent {
+            if parent.task_id != task.id {
+                return Err(AppError::validation_error(
+                    "parent_id",
+                    &format!("Comment {} belongs to another task", parent.id),
+                ));
+            }
+            if parent.is_deleted() {
+                return Err(AppError::validation_error("parent_id", "Cannot reply to a deleted comment"));
+            }
+            if depth >= policy.max_depth {
+                return Err(AppError::validation_error(
+                    "parent_id",
+                    &format!("Threads can nest at most {} levels deep", policy.max_depth),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // `parent_ancestors` lists every ancestor of `task`, used to keep the hierarchy acyclic
+    pub fn add_subtask(task: &mut Task, subtask: &mut Task, parent_ancestors: &[i32], position: Option<usize>) -> Result<(), AppError> {
         if task.status == TaskStatus::Completed {
             return Err(AppError::ValidationError { field: "".to_string(), message: "Cannot add subtasks to completed tasks".to_string() });
         }
-        task.add_subtask(subtask);
+        if subtask.id == task.id || parent_ancestors.contains(&subtask.id) {
+            return Err(AppError::validation_error(
+                "parent_task",
+                &format!("Task {} cannot become a subtask of its own descendant {}", subtask.id, task.id),
+            ));
+        }
+        if let Some(current_parent) = subtask.parent_task.filter(|&p| p != task.id) {
+            return Err(AppError::validation_error(
+                "parent_task",
+                &format!("Task {} is already a subtask of task {}", subtask.id, current_parent),
+            ));
+        }
+        subtask.set_parent(Some(task.id));
+        task.add_subtask(subtask.id, position);
+        Ok(())
+    }
+
+    // Recomputes the progress of the tasks in order, nearest to the leaves first, so each one
+    // sees the updated progress of those below it. `subtree` must hold the topmost task's whole
+    // subtree. Returns the tasks whose progress changed, each once even if the hierarchy loops.
+    pub fn rollup_progress(subtree: &mut HashMap<i32, Task>, task_ids: &[i32]) -> Vec<i32> {
+        let mut changed = Vec::new();
+        let mut seen = HashSet::with_capacity(task_ids.len());
+        for &task_id in task_ids {
+            if !seen.insert(task_id) {
+                continue;
+            }
+            let Some(mut task) = subtree.get(&task_id).cloned() else {
+                continue;
+            };
+            let previous = task.progress;
+            task.update_progress(subtree, false);
+            if task.progress != previous {
+                subtree.insert(task_id, task);
+                changed.push(task_id);
+            }
+        }
+        changed
+    }
+
+    pub fn detach_subtask(task: &mut Task, subtask: &mut Task) -> Result<(), AppError> {
+        if subtask.parent_task != Some(task.id) || !task.remove_subtask(subtask.id) {
+            return Err(AppError::validation_error(
+                "parent_task",
+                &format!("Task {} is not a subtask of task {}", subtask.id, task.id),
+            ));
+        }
+        subtask.set_parent(None);
+        Ok(())
+    }
+
+    pub fn reparent_subtask(
+        subtask: &mut Task,
+        old_parent: Option<&mut Task>,
+        new_parent: &mut Task,
+        new_parent_ancestors: &[i32],
+        position: Option<usize>,
+    ) -> Result<(), AppError> {
+        // The subtree would be split across projects
+        if subtask.project_id != new_parent.project_id {
+            return Err(AppError::validation_error(
+                "parent_task",
+                &format!("Task {} belongs to a different project than task {}", subtask.id, new_parent.id),
+            ));
+        }
+        if let Some(old_parent) = old_parent {
+            Self::detach_subtask(old_parent, subtask)?;
+        }
+        Self::add_subtask(new_parent, subtask, new_parent_ancestors, position)
+    }
+
+    pub fn reorder_subtask(task: &mut Task, subtask_id: i32, position: usize) -> Result<(), AppError> {
+        if !task.move_subtask(subtask_id, position) {
+            return Err(AppError::validation_error(
+                "subtasks",
+                &format!("Task {} is not a subtask of task {}", subtask_id, task.id),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn set_task_estimate(task: &mut Task, minutes: Option<i64>) -> Result<(), AppError> {
+        if minutes.is_some_and(|m| m < 0) {
+            return Err(AppError::validation_error("original_estimate", "Estimate cannot be negative"));
+        }
+        task.set_original_estimate(minutes);
         Ok(())
     }
 
@@ -58,6 +538,10 @@ impl TaskService {
         task.set_due_date(due_date);
     }
 
+    pub fn set_task_due(task: &mut Task, due: Option<DueDate>, timezone: Tz) {
+        task.set_due(due, timezone);
+    }
+
     pub fn set_task_priority(task: &mut Task, priority: Option<i32>) {
         task.set_priority(priority);
     }
@@ -65,6 +549,1029 @@ impl TaskService {
     pub fn set_task_recurrence(task: &mut Task, recurrence: Option<Recurrence>, recurrence_end: Option<i64>) {
         task.set_recurrence(recurrence, recurrence_end);
     }
+
+    // Makes the task match an imported VTODO, which is authoritative for every field it maps.
+    // Status changes still follow the workflow; refused ones become warnings. Returns whether
+    // anything changed.
+    pub fn apply_imported_todo(task: &mut Task, todo: &ImportedTodo, workflow: &Workflow, warnings: &mut Vec<String>) -> bool {
+        let recorded = task.activity_log.len();
+        if let Some(summary) = todo.summary.as_ref().filter(|s| **s != task.title) {
+            task.set_title(summary.clone());
+        }
+        if todo.description != task.description {
+            task.set_description(todo.description.clone());
+        }
+
+        let due = todo.due.map(|(due, zone)| match due {
+            DueDate::Date(day) => (end_of_day(day, &zone), true),
+            DueDate::DateTime(at) => (at.timestamp_millis(), false),
+        });
+        if due != task.due_date.map(|at| (at, task.due_all_day)) {
+            match todo.due {
+                Some((due, zone)) => task.set_due(Some(due), zone),
+                None => task.set_due_date(None),
+            }
+        }
+        if todo.priority != task.priority {
+            task.set_priority(todo.priority);
+        }
+
+        if let Some(status) = todo.status.filter(|s| *s != task.status) {
+            match Self::change_task_status(task, workflow, status) {
+                Ok(()) if status == TaskStatus::Completed => task.completed_at = todo.completed_at.or(task.completed_at),
+                Ok(()) => {}
+                Err(AppError::ValidationError { message, .. }) => warnings.push(message),
+                Err(_) => warnings.push(format!("Status {} was not applied", status)),
+            }
+        }
+        if todo.recurrence != task.recurrence || todo.recurrence_end != task.recurrence_end {
+            task.set_recurrence(todo.recurrence, todo.recurrence_end);
+        }
+
+        let categories = todo.categories.iter().filter_map(|c| normalize_label(c)).collect::<Vec<_>>();
+        for tag in task.tags.clone().iter().filter(|t| !categories.contains(t)) {
+            task.remove_tag(tag);
+        }
+        for category in &categories {
+            task.add_tag(category);
+        }
+        task.activity_log.len() > recorded
+    }
+}
+
+pub struct WorkflowService;
+
+impl WorkflowService {
+    pub fn validate_workflow(workflow: &Workflow) -> Result<(), AppError> {
+        if workflow.statuses.is_empty() {
+            return Err(AppError::validation_error("statuses", "Workflow must define at least one status"));
+        }
+        if !workflow.has_status(workflow.initial_status) {
+            return Err(AppError::validation_error(
+                "initial_status",
+                &format!("Initial status {} is not one of the workflow statuses", workflow.initial_status),
+            ));
+        }
+        for transition in &workflow.transitions {
+            if transition.from == transition.to {
+                return Err(AppError::validation_error(
+                    "transitions",
+                    &format!("Transition from {} to itself is not allowed", transition.from),
+                ));
+            }
+            if !workflow.has_status(transition.from) || !workflow.has_status(transition.to) {
+                return Err(AppError::validation_error(
+                    "transitions",
+                    &format!("Transition {} -> {} uses a status outside the workflow", transition.from, transition.to),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct AttachmentService;
+
+impl AttachmentService {
+    // Checks an upload against the limits and returns the filename to store
+    pub fn validate_upload(
+        task: &Task,
+        limits: &AttachmentLimits,
+        existing: usize,
+        filename: &str,
+        content_type: &str,
+        size: usize,
+    ) -> Result<String, AppError> {
+        if task.is_trashed() {
+            return Err(AppError::validation_error("task", &format!("Task {} is archived or deleted", task.id)));
+        }
+        if existing >= limits.max_per_task {
+            return Err(AppError::validation_error(
+                "file",
+                &format!("Tasks can hold at most {} attachments", limits.max_per_task),
+            ));
+        }
+        let filename = sanitize_filename(filename).ok_or_else(|| AppError::validation_error("file", "A file name is required"))?;
+        if size == 0 {
+            return Err(AppError::validation_error("file", &format!("{} is empty", filename)));
+        }
+        if size > limits.max_bytes {
+            return Err(AppError::validation_error(
+                "file",
+                &format!("{} exceeds the {} byte limit", filename, limits.max_bytes),
+            ));
+        }
+        if !limits.allows_type(content_type) {
+            return Err(AppError::validation_error(
+                "file",
+                &format!("Files of type {} cannot be attached", content_type),
+            ));
+        }
+        Ok(filename)
+    }
+}
+
+pub struct BoardService;
+
+impl BoardService {
+    pub fn validate_board(board: &mut Board, workflow: &Workflow) -> Result<(), AppError> {
+        board.name = board.name.trim().to_string();
+        if board.name.is_empty() {
+            return Err(AppError::validation_error("name", "Name cannot be empty"));
+        }
+        if board.columns.is_empty() {
+            return Err(AppError::validation_error("columns", "Board must have at least one column"));
+        }
+
+        let mut seen_names: Vec<String> = Vec::new();
+        let mut seen_statuses: Vec<TaskStatus> = Vec::new();
+        for column in &mut board.columns {
+            column.name = column.name.trim().to_string();
+            if column.name.is_empty() {
+                return Err(AppError::validation_error("columns", "Column names cannot be empty"));
+            }
+            if seen_names.iter().any(|n| n.eq_ignore_ascii_case(&column.name)) {
+                return Err(AppError::validation_error("columns", &format!("Column {} appears twice", column.name)));
+            }
+            seen_names.push(column.name.clone());
+
+            if column.statuses.is_empty() {
+                return Err(AppError::validation_error("columns", &format!("Column {} has no statuses", column.name)));
+            }
+            for status in &column.statuses {
+                if seen_statuses.contains(status) {
+                    return Err(AppError::validation_error(
+                        "columns",
+                        &format!("Status {} is mapped to more than one column", status),
+                    ));
+                }
+                if !workflow.has_status(*status) {
+                    return Err(AppError::validation_error(
+                        "columns",
+                        &format!("Status {} is not part of the project workflow", status),
+                    ));
+                }
+                seen_statuses.push(*status);
+            }
+            if column.wip_limit == Some(0) {
+                return Err(AppError::validation_error("wip_limit", "WIP limits must be at least 1"));
+            }
+        }
+        Ok(())
+    }
+
+    // Status a task gets when dropped into `column`: the requested one, its current one if it
+    // already belongs there, or the column's first status
+    pub fn target_status(board: &Board, column: usize, current: TaskStatus, requested: Option<TaskStatus>) -> Result<TaskStatus, AppError> {
+        let column = &board.columns[column];
+        match requested {
+            Some(status) if column.statuses.contains(&status) => Ok(status),
+            Some(status) => Err(AppError::validation_error(
+                "status",
+                &format!("Status {} is not part of column {}", status, column.name),
+            )),
+            None if column.statuses.contains(&current) => Ok(current),
+            None => Ok(column.statuses[0]),
+        }
+    }
+
+    // `occupied` counts the tasks already in the column, not including the one being moved
+    pub fn check_wip_limit(board: &Board, column: usize, occupied: usize) -> Result<(), AppError> {
+        let column = &board.columns[column];
+        match column.wip_limit {
+            Some(limit) if occupied >= limit as usize => Err(AppError::validation_error(
+                "wip_limit",
+                &format!("Column {} is at its WIP limit of {}", column.name, limit),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Card order of a column after dropping `task_id` at `position` (the end by default)
+    pub fn place_card(mut order: Vec<i32>, task_id: i32, position: Option<usize>) -> Vec<i32> {
+        order.retain(|id| *id != task_id);
+        let position = position.unwrap_or(order.len()).min(order.len());
+        order.insert(position, task_id);
+        order
+    }
+
+    // Cards are ordered by their board rank, then by priority and age for cards never moved
+    pub fn sort_cards(tasks: &mut [Task], ranks: &HashMap<i32, i64>) {
+        tasks.sort_by_key(|t| {
+            (
+                ranks.get(&t.id).copied().unwrap_or(i64::MAX),
+                std::cmp::Reverse(t.priority.unwrap_or(i32::MIN)),
+                t.created_at,
+                t.id,
+            )
+        });
+    }
+
+    // Tasks whose status is not on the board are left out. With tag swimlanes a task sits in
+    // the lane of its alphabetically first tag.
+    pub fn build_view(board: &Board, mut tasks: Vec<Task>, ranks: &HashMap<i32, i64>) -> BoardView {
+        tasks.retain(|t| board.column_of(t.status).is_some());
+        Self::sort_cards(&mut tasks, ranks);
+
+        let lane_key = |task: &Task| match board.swimlane {
+            None => None,
+            Some(Swimlane::Assignee) => task.assigned_to.map(|id| id.to_string()),
+            Some(Swimlane::Priority) => task.priority.map(|p| p.to_string()),
+            Some(Swimlane::Tag) => task.tags.iter().min().cloned(),
+        };
+        let mut keys = tasks.iter().map(lane_key).collect::<Vec<_>>();
+        keys.sort_by(|a, b| match (a, b) {
+            (None, None) => std::cmp::Ordering::Equal,
+            (None, _) => std::cmp::Ordering::Greater,
+            (_, None) => std::cmp::Ordering::Less,
+            (Some(a), Some(b)) => match (a.parse::<i64>(), b.parse::<i64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            },
+        });
+        keys.dedup();
+        if keys.is_empty() {
+            keys.push(None);
+        }
+
+        let mut lanes = keys
+            .into_iter()
+            .map(|key| Lane { key, columns: vec![Vec::new(); board.columns.len()] })
+            .collect::<Vec<_>>();
+        let mut counts = vec![0; board.columns.len()];
+        for task in &tasks {
+            let Some(column) = board.column_of(task.status) else { continue };
+            let key = lane_key(task);
+            if let Some(lane) = lanes.iter_mut().find(|l| l.key == key) {
+                counts[column] += 1;
+                lane.columns[column].push(Card {
+                    task_id: task.id,
+                    reference: task.reference.clone(),
+                    title: task.title.clone(),
+                    status: task.status,
+                    priority: task.priority,
+                    assigned_to: task.assigned_to,
+                    tags: task.tags.clone(),
+                    due_date: task.due_date,
+                    due_all_day: task.due_all_day,
+                });
+            }
+        }
+
+        BoardView {
+            columns: board
+                .columns
+                .iter()
+                .zip(counts)
+                .map(|(column, count)| ColumnSummary {
+                    name: column.name.clone(),
+                    statuses: column.statuses.clone(),
+                    wip_limit: column.wip_limit,
+                    count,
+                    over_limit: column.wip_limit.is_some_and(|limit| count > limit as usize),
+                })
+                .collect(),
+            lanes,
+            board: board.clone(),
+        }
+    }
+}
+
+pub struct SprintService;
+
+impl SprintService {
+    pub fn validate_sprint(sprint: &mut Sprint) -> Result<(), AppError> {
+        sprint.name = sprint.name.trim().to_string();
+        sprint.goal = sprint.goal.as_deref().map(str::trim).filter(|g| !g.is_empty()).map(str::to_string);
+        if sprint.name.is_empty() {
+            return Err(AppError::validation_error("name", "Name cannot be empty"));
+        }
+        if sprint.end_date <= sprint.start_date {
+            return Err(AppError::validation_error("end_date", "Sprint must end after it starts"));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_open(sprint: &Sprint) -> Result<(), AppError> {
+        if sprint.state == SprintState::Closed {
+            return Err(AppError::validation_error("state", &format!("Sprint {} is closed", sprint.name)));
+        }
+        Ok(())
+    }
+
+    // Only one sprint per project runs at a time
+    pub fn start_sprint(sprint: &mut Sprint, active_sprint: Option<&Sprint>, now: i64) -> Result<(), AppError> {
+        if sprint.state != SprintState::Planned {
+            return Err(AppError::validation_error("state", &format!("Sprint {} has already been started", sprint.name)));
+        }
+        if let Some(active) = active_sprint.filter(|a| a.id != sprint.id) {
+            return Err(AppError::validation_error(
+                "state",
+                &format!("Sprint {} is still active in this project", active.name),
+            ));
+        }
+        sprint.start(now);
+        Ok(())
+    }
+
+    // Closes the sprint and returns the unfinished tasks to carry over. Completed and cancelled
+    // tasks stay with the closed sprint.
+    pub fn close_sprint(sprint: &mut Sprint, tasks: &[Task], target: Option<&Sprint>, now: i64) -> Result<Vec<i32>, AppError> {
+        if sprint.state != SprintState::Active {
+            return Err(AppError::validation_error("state", &format!("Sprint {} is not active", sprint.name)));
+        }
+        if let Some(target) = target {
+            if target.id == sprint.id || target.project_id != sprint.project_id || target.state == SprintState::Closed {
+                return Err(AppError::validation_error(
+                    "carry_over_to",
+                    "Unfinished tasks can only move to another open sprint of the same project",
+                ));
+            }
+        }
+        let unfinished = tasks
+            .iter()
+            .filter(|t| sprint.task_ids.contains(&t.id) && !t.status.is_closed() && !t.is_trashed())
+            .map(|t| t.id)
+            .collect::<Vec<_>>();
+        sprint.task_ids.retain(|id| !unfinished.contains(id));
+        sprint.close(now);
+        Ok(unfinished)
+    }
+
+    pub fn scope(sprint: &Sprint, changes: Vec<ScopeChange>, tasks: &[Task]) -> SprintScope {
+        let mut committed: Vec<i32> = Vec::new();
+        for change in changes.iter().filter(|c| !c.after_start) {
+            match change.kind {
+                ScopeChangeKind::Added | ScopeChangeKind::CarriedIn => committed.push(change.task_id),
+                ScopeChangeKind::Removed | ScopeChangeKind::CarriedOut => committed.retain(|id| *id != change.task_id),
+            }
+        }
+        committed.sort_unstable();
+        committed.dedup();
+        let after_start = |kinds: &[ScopeChangeKind]| changes.iter().filter(|c| c.after_start && kinds.contains(&c.kind)).count();
+
+        SprintScope {
+            sprint_id: sprint.id,
+            committed: if sprint.state == SprintState::Planned { 0 } else { committed.len() },
+            added: after_start(&[ScopeChangeKind::Added, ScopeChangeKind::CarriedIn]),
+            removed: after_start(&[ScopeChangeKind::Removed, ScopeChangeKind::CarriedOut]),
+            current: sprint.task_ids.len(),
+            completed: tasks
+                .iter()
+                .filter(|t| sprint.task_ids.contains(&t.id) && t.status == TaskStatus::Completed)
+                .count(),
+            changes,
+        }
+    }
+}
+
+pub struct MilestoneService;
+
+impl MilestoneService {
+    pub fn validate_milestone(milestone: &mut Milestone) -> Result<(), AppError> {
+        milestone.name = milestone.name.trim().to_string();
+        if milestone.name.is_empty() {
+            return Err(AppError::validation_error("name", "Name cannot be empty"));
+        }
+        if milestone.start_date.is_some_and(|start| start >= milestone.due_date) {
+            return Err(AppError::validation_error("due_date", "Milestone must be due after it starts"));
+        }
+        Ok(())
+    }
+
+    pub fn progress(milestone: &Milestone, tasks: &[Task], now: i64) -> MilestoneProgress {
+        let tasks = tasks
+            .iter()
+            .filter(|t| milestone.task_ids.contains(&t.id) && !t.is_trashed())
+            .collect::<Vec<_>>();
+        let completed = tasks.iter().filter(|t| t.status == TaskStatus::Completed).count();
+        let cancelled = tasks.iter().filter(|t| t.status == TaskStatus::Cancelled).count();
+        let counted = tasks.len() - cancelled;
+        let percent = (completed * 100).checked_div(counted).unwrap_or(0) as u8;
+
+        MilestoneProgress {
+            milestone_id: milestone.id,
+            total: tasks.len(),
+            completed,
+            cancelled,
+            open: counted - completed,
+            percent,
+            overdue: milestone.due_date < now && completed < counted,
+        }
+    }
+}
+
+pub struct TemplateService;
+
+impl TemplateService {
+    pub fn validate_template(template: &mut TaskTemplate, definitions: &[CustomFieldDefinition]) -> Result<(), AppError> {
+        template.name = template.name.trim().to_string();
+        if template.name.is_empty() {
+            return Err(AppError::validation_error("name", "Name cannot be empty"));
+        }
+        let nodes = template.root.walk();
+        if nodes.len() > MAX_TEMPLATE_TASKS {
+            return Err(AppError::validation_error(
+                "root",
+                &format!("Templates can hold at most {} tasks", MAX_TEMPLATE_TASKS),
+            ));
+        }
+        for (depth, node) in nodes {
+            if depth > MAX_TEMPLATE_DEPTH {
+                return Err(AppError::validation_error(
+                    "subtasks",
+                    &format!("Templates can nest at most {} levels deep", MAX_TEMPLATE_DEPTH),
+                ));
+            }
+            if node.title.trim().is_empty() {
+                return Err(AppError::validation_error("title", "Title cannot be empty"));
+            }
+            if node.due_in_days.is_some_and(|days| days < 0) {
+                return Err(AppError::validation_error("due_in_days", "Due dates cannot fall before the start date"));
+            }
+            for key in node.custom_fields.keys() {
+                CustomFieldService::find_definition(definitions, CustomFieldTarget::Task, key)?;
+            }
+        }
+        template.variables = template.root.variables().into_iter().collect();
+        Ok(())
+    }
+
+    // Substitutes variables and anchors due dates to `start_date`, parents listed before their
+    // subtasks. Days are calendar days in `timezone`, so the time of day survives DST changes.
+    pub fn plan(
+        template: &TaskTemplate,
+        values: &HashMap<String, String>,
+        start_date: i64,
+        timezone: Tz,
+    ) -> Result<Vec<PlannedTask>, AppError> {
+        let missing = template.root.variables().into_iter().filter(|v| !values.contains_key(v)).collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(AppError::validation_error(
+                "variables",
+                &format!("Missing values for {}", missing.join(", ")),
+            ));
+        }
+
+        let start = chrono::DateTime::from_timestamp_millis(start_date)
+            .ok_or_else(|| AppError::validation_error("start_date", "Start date is out of range"))?
+            .with_timezone(&timezone)
+            .naive_local();
+        let mut planned = Vec::new();
+        let mut stack = vec![(None, &template.root)];
+        while let Some((parent, node)) = stack.pop() {
+            let index = planned.len();
+            planned.push(PlannedTask {
+                parent,
+                task: node.substitute(values),
+                due_date: node
+                    .due_in_days
+                    .map(|days| resolve_local(&timezone, start + chrono::Duration::days(days)).timestamp_millis()),
+            });
+            stack.extend(node.subtasks.iter().rev().map(|s| (Some(index), s)));
+        }
+        Ok(planned)
+    }
+}
+
+pub struct NotificationService;
+
+impl NotificationService {
+    // Mentioned users are told once, whoever else watches the task
+    pub fn mention_notifications(task: &Task, user_ids: &[i32], actor_id: Option<i32>, excerpt: &str, now: i64) -> Vec<Notification> {
+        user_ids
+            .iter()
+            .filter(|&&user_id| Some(user_id) != actor_id)
+            .map(|&user_id| Notification {
+                id: 0,
+                user_id,
+                task_id: task.id,
+                kind: NotificationKind::Mentioned,
+                actor_id,
+                payload: Some(json!({ "title": task.title, "excerpt": excerpt })),
+                created_at: now,
+                read_at: None,
+            })
+            .collect()
+    }
+
+    // One notification per watcher for each comment, status change or reassignment in `events`.
+    // The actor and anyone in `skip` are left out.
+    pub fn watcher_notifications(task: &Task, events: &[ActivityEvent], watchers: &[i32], skip: &[i32], now: i64) -> Vec<Notification> {
+        let mut notifications = Vec::new();
+        for event in events {
+            let kind = match event.kind {
+                ActivityKind::CommentAdded => NotificationKind::Commented,
+                ActivityKind::StatusChanged | ActivityKind::Reopened | ActivityKind::Completed => NotificationKind::StatusChanged,
+                ActivityKind::AssigneeChanged => NotificationKind::Reassigned,
+                _ => continue,
+            };
+            for &user_id in watchers.iter().filter(|&&w| Some(w) != event.actor_id && !skip.contains(&w)) {
+                notifications.push(Notification {
+                    id: 0,
+                    user_id,
+                    task_id: task.id,
+                    kind,
+                    actor_id: event.actor_id,
+                    payload: Some(json!({ "title": task.title, "old": event.old_value, "new": event.new_value })),
+                    created_at: now,
+                    read_at: None,
+                });
+            }
+        }
+        notifications
+    }
+}
+
+pub struct ProjectService;
+
+impl ProjectService {
+    pub fn validate_project(project: &mut Project) -> Result<(), AppError> {
+        project.key = project.key.trim().to_ascii_uppercase();
+        project.name = project.name.trim().to_string();
+        let mut chars = project.key.chars();
+        let valid_key = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+            && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && (2..=10).contains(&project.key.len());
+        if !valid_key {
+            return Err(AppError::validation_error(
+                "key",
+                "Key must be 2 to 10 letters or digits and start with a letter",
+            ));
+        }
+        if project.name.is_empty() {
+            return Err(AppError::validation_error("name", "Name cannot be empty"));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_can_manage(project: &Project, actor_id: Option<i32>) -> Result<(), AppError> {
+        match actor_id {
+            Some(user_id) if project.can_manage(user_id) => Ok(()),
+            Some(user_id) => Err(AppError::forbidden(user_id, &format!("manage project {}", project.key))),
+            None => Err(AppError::invalid_input("x-user-id is required to manage a project")),
+        }
+    }
+
+    // Anonymous callers are let through like elsewhere in the API; known users must be able to edit
+    pub fn ensure_can_edit_tasks(project: &Project, actor_id: Option<i32>) -> Result<(), AppError> {
+        if project.is_archived() {
+            return Err(AppError::validation_error("project_id", &format!("Project {} is archived", project.key)));
+        }
+        match actor_id {
+            Some(user_id) if !project.can_edit_tasks(user_id) => {
+                Err(AppError::forbidden(user_id, &format!("edit tasks in project {}", project.key)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn add_member(project: &mut Project, user_id: i32, role: ProjectRole) -> Result<(), AppError> {
+        if user_id == project.owner_id && role != ProjectRole::Admin {
+            return Err(AppError::validation_error("role", "The project owner is always an admin"));
+        }
+        project.add_member(user_id, role);
+        Ok(())
+    }
+
+    pub fn remove_member(project: &mut Project, user_id: i32) -> Result<(), AppError> {
+        if user_id == project.owner_id {
+            return Err(AppError::validation_error("user_id", "The project owner cannot be removed"));
+        }
+        if project.member(user_id).is_none() {
+            return Err(AppError::not_found("Project member", user_id));
+        }
+        project.remove_member(user_id);
+        Ok(())
+    }
+
+    pub fn archive_project(project: &mut Project) -> Result<(), AppError> {
+        if project.is_archived() {
+            return Err(AppError::validation_error("archived_at", "Project is already archived"));
+        }
+        project.archive();
+        Ok(())
+    }
+
+    pub fn unarchive_project(project: &mut Project) -> Result<(), AppError> {
+        if !project.is_archived() {
+            return Err(AppError::validation_error("archived_at", "Project is not archived"));
+        }
+        project.unarchive();
+        Ok(())
+    }
+}
+
+pub struct CommentService;
+
+impl CommentService {
+    pub fn normalize_body(policy: &CommentPolicy, body: &str) -> Result<String, AppError> {
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(AppError::validation_error("comment", "Comment cannot be empty"));
+        }
+        if body.chars().count() > policy.max_length {
+            return Err(AppError::validation_error(
+                "comment",
+                &format!("Comments are limited to {} characters", policy.max_length),
+            ));
+        }
+        Ok(body.to_string())
+    }
+
+    // Only the author edits, and only within the policy's edit window
+    pub fn edit_comment(comment: &mut Comment, policy: &CommentPolicy, actor_id: Option<i32>, body: &str, now: i64) -> Result<(), AppError> {
+        Self::ensure_author(comment, actor_id, "edit")?;
+        if comment.is_deleted() {
+            return Err(AppError::validation_error("comment", "Deleted comments cannot be edited"));
+        }
+        if policy.edit_window_minutes.is_some_and(|minutes| now - comment.created_at > minutes * 60_000) {
+            return Err(AppError::validation_error("comment", "The edit window for this comment has passed"));
+        }
+        let body = Self::normalize_body(policy, body)?;
+        if body == comment.body {
+            return Err(AppError::validation_error("comment", "Comment is unchanged"));
+        }
+        comment.edit(body, now);
+        Ok(())
+    }
+
+    // Authors delete their own comments; moderators, e.g. project managers, anyone's
+    pub fn delete_comment(comment: &mut Comment, actor_id: Option<i32>, can_moderate: bool, now: i64) -> Result<(), AppError> {
+        if comment.is_deleted() {
+            return Err(AppError::validation_error("comment", "Comment is already deleted"));
+        }
+        if !can_moderate {
+            Self::ensure_author(comment, actor_id, "delete")?;
+        }
+        comment.delete(now);
+        Ok(())
+    }
+
+    fn ensure_author(comment: &Comment, actor_id: Option<i32>, action: &str) -> Result<(), AppError> {
+        match actor_id {
+            Some(user_id) if user_id == comment.user_id => Ok(()),
+            Some(user_id) => Err(AppError::forbidden(user_id, &format!("{} comment {}", action, comment.id))),
+            None => Err(AppError::invalid_input(&format!("x-user-id is required to {} a comment", action))),
+        }
+    }
+
+    pub fn validate_reaction(comment: &Comment, emoji: &str) -> Result<String, AppError> {
+        if comment.is_deleted() {
+            return Err(AppError::validation_error("comment", "Cannot react to a deleted comment"));
+        }
+        let emoji = emoji.trim();
+        if emoji.is_empty() || emoji.chars().count() > MAX_REACTION_LENGTH || emoji.chars().any(|c| c.is_ascii()) {
+            return Err(AppError::validation_error("emoji", "Reactions must be a single emoji"));
+        }
+        Ok(emoji.to_string())
+    }
+
+    // Nests replies under their parents; `comments` must be ordered oldest first
+    pub fn build_threads(comments: Vec<Comment>) -> Vec<Comment> {
+        let mut children: HashMap<Option<i64>, Vec<Comment>> = HashMap::new();
+        let ids = comments.iter().map(|c| c.id).collect::<Vec<_>>();
+        for comment in comments {
+            // Replies whose parent is not in the set are shown at the top level
+            let parent = comment.parent_id.filter(|p| ids.contains(p));
+            children.entry(parent).or_default().push(comment);
+        }
+        fn attach(comment: &mut Comment, children: &mut HashMap<Option<i64>, Vec<Comment>>) {
+            let mut replies = children.remove(&Some(comment.id)).unwrap_or_default();
+            replies.iter_mut().for_each(|reply| attach(reply, children));
+            comment.replies = replies;
+        }
+        let mut roots = children.remove(&None).unwrap_or_default();
+        roots.iter_mut().for_each(|root| attach(root, &mut children));
+        roots
+    }
+}
+
+pub struct LinkService;
+
+impl LinkService {
+    // Builds the link `task <relation> other` and records it on both ends. Two tasks share
+    // at most one link of each type, whichever way it points.
+    pub fn link_tasks(
+        task: &mut Task,
+        relation: LinkRelation,
+        other: &mut Task,
+        existing: &[TaskLink],
+        created_by: Option<i32>,
+    ) -> Result<TaskLink, AppError> {
+        if task.id == other.id {
+            return Err(AppError::validation_error("task_id", "A task cannot be linked to itself"));
+        }
+        if let Some(trashed) = [&*task, &*other].into_iter().find(|t| t.is_trashed()) {
+            return Err(AppError::validation_error("task_id", &format!("Task {} is archived or deleted", trashed.id)));
+        }
+        let link = TaskLink::new(task.id, relation, other.id, created_by, chrono::Utc::now().timestamp_millis());
+        if let Some(linked) = existing.iter().find(|l| l.link_type == link.link_type && l.other_end(task.id) == other.id) {
+            return Err(AppError::validation_error(
+                "relation",
+                &format!("Task {} is already linked to {} as {:?}", task.id, other.id, linked.relation_for(task.id)),
+            ));
+        }
+        task.add_link(relation, other.id);
+        other.add_link(relation.inverse(), task.id);
+        Ok(link)
+    }
+
+    pub fn unlink_tasks(task: &mut Task, other: &mut Task, link: &TaskLink) {
+        let relation = link.relation_for(task.id);
+        task.remove_link(relation, other.id);
+        other.remove_link(relation.inverse(), task.id);
+    }
+
+    // Links `duplicate` to the task it repeats and completes it, if its workflow allows that
+    pub fn close_as_duplicate(
+        duplicate: &mut Task,
+        original: &mut Task,
+        existing: &[TaskLink],
+        workflow: &Workflow,
+        closed_by: Option<i32>,
+    ) -> Result<TaskLink, AppError> {
+        if original.is_trashed() {
+            return Err(AppError::validation_error("duplicate_of", &format!("Task {} is archived or deleted", original.id)));
+        }
+        if duplicate.status != TaskStatus::Completed {
+            TaskService::change_task_status(duplicate, workflow, TaskStatus::Completed)?;
+        }
+        Self::link_tasks(duplicate, LinkRelation::Duplicates, original, existing, closed_by)
+    }
+
+    // Links of `task_id` paired with the task at their other end; links to tasks missing
+    // from `tasks` are left out
+    pub fn linked_tasks(task_id: i32, links: &[TaskLink], tasks: &[Task]) -> Vec<LinkedTask> {
+        links
+            .iter()
+            .filter_map(|link| {
+                let other = tasks.iter().find(|t| t.id == link.other_end(task_id))?;
+                Some(LinkedTask {
+                    link_id: link.id,
+                    relation: link.relation_for(task_id),
+                    task_id: other.id,
+                    reference: other.reference.clone(),
+                    title: other.title.clone(),
+                    status: other.status,
+                    created_at: link.created_at,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct LabelService;
+
+impl LabelService {
+    pub fn validate_label(label: &mut Label) -> Result<(), AppError> {
+        label.name = normalize_label(&label.name).ok_or_else(|| AppError::validation_error("name", "Name cannot be empty"))?;
+        if label.name.chars().count() > MAX_LABEL_LENGTH {
+            return Err(AppError::validation_error(
+                "name",
+                &format!("Label names are limited to {} characters", MAX_LABEL_LENGTH),
+            ));
+        }
+        if let Some(color) = &label.color {
+            label.color = Some(normalize_color(color).ok_or_else(|| {
+                AppError::validation_error("color", &format!("'{}' is not a #rgb or #rrggbb color", color))
+            })?);
+        }
+        label.description = label.description.as_deref().map(str::trim).filter(|d| !d.is_empty()).map(str::to_string);
+        Ok(())
+    }
+
+    // Sources are folded into the target; merging a label into itself is a mistake
+    pub fn validate_merge(source_ids: &[i32], target_id: i32) -> Result<(), AppError> {
+        if source_ids.is_empty() {
+            return Err(AppError::validation_error("sources", "At least one label to merge is required"));
+        }
+        if source_ids.contains(&target_id) {
+            return Err(AppError::validation_error("target", "A label cannot be merged into itself"));
+        }
+        Ok(())
+    }
+}
+
+pub struct CustomFieldService;
+
+impl CustomFieldService {
+    pub fn validate_definition(definition: &mut CustomFieldDefinition) -> Result<(), AppError> {
+        definition.key = definition.key.trim().to_string();
+        definition.name = definition.name.trim().to_string();
+        if definition.key.is_empty()
+            || !definition.key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(AppError::validation_error(
+                "key",
+                "Key must be non-empty and use only lowercase letters, digits and underscores",
+            ));
+        }
+        if definition.name.is_empty() {
+            return Err(AppError::validation_error("name", "Name cannot be empty"));
+        }
+
+        if definition.field_type.is_select() {
+            definition.allowed_values = definition.allowed_values.iter().map(|v| v.trim().to_string()).collect();
+            if definition.allowed_values.is_empty() || definition.allowed_values.iter().any(|v| v.is_empty()) {
+                return Err(AppError::validation_error("allowed_values", "Select fields need non-empty allowed values"));
+            }
+            let mut unique = definition.allowed_values.clone();
+            unique.sort();
+            unique.dedup();
+            if unique.len() != definition.allowed_values.len() {
+                return Err(AppError::validation_error("allowed_values", "Allowed values must be unique"));
+            }
+        } else if !definition.allowed_values.is_empty() {
+            return Err(AppError::validation_error(
+                "allowed_values",
+                &format!("{:?} fields do not take allowed values", definition.field_type),
+            ));
+        }
+        Ok(())
+    }
+
+    // Key, target and type are fixed once values may have been stored against them
+    pub fn validate_update(existing: &CustomFieldDefinition, updated: &mut CustomFieldDefinition) -> Result<(), AppError> {
+        if existing.key != updated.key || existing.target != updated.target || existing.field_type != updated.field_type {
+            return Err(AppError::validation_error(
+                "field_type",
+                "Key, target and type of a custom field cannot be changed",
+            ));
+        }
+        Self::validate_definition(updated)
+    }
+
+    // Every stored value must be defined, and every required field must be present
+    pub fn check_values(
+        values: &HashMap<String, serde_json::Value>,
+        definitions: &[CustomFieldDefinition],
+        target: CustomFieldTarget,
+    ) -> Result<(), AppError> {
+        let definitions = definitions.iter().filter(|d| d.target == target).collect::<Vec<_>>();
+        if let Some(key) = values.keys().find(|key| !definitions.iter().any(|d| &d.key == *key)) {
+            return Err(AppError::validation_error(key, "No custom field is defined with this key"));
+        }
+        if let Some(missing) = definitions.iter().find(|d| d.required && !values.contains_key(&d.key)) {
+            return Err(AppError::validation_error(&missing.key, "Required custom field is missing"));
+        }
+        Ok(())
+    }
+
+    pub fn find_definition<'a>(
+        definitions: &'a [CustomFieldDefinition],
+        target: CustomFieldTarget,
+        key: &str,
+    ) -> Result<&'a CustomFieldDefinition, AppError> {
+        definitions
+            .iter()
+            .find(|d| d.target == target && d.key == key)
+            .ok_or_else(|| AppError::validation_error(key, "No custom field is defined with this key"))
+    }
+}
+
+pub struct TimeTrackingService;
+
+impl TimeTrackingService {
+    pub fn validate_work_log(log: &mut WorkLog, now: i64) -> Result<(), AppError> {
+        if log.minutes <= 0 || log.minutes > MAX_WORK_LOG_MINUTES {
+            return Err(AppError::validation_error(
+                "minutes",
+                &format!("Work logs must be between 1 and {} minutes", MAX_WORK_LOG_MINUTES),
+            ));
+        }
+        if log.started_at > now {
+            return Err(AppError::validation_error("started_at", "Work cannot be logged in the future"));
+        }
+        log.note = log.note.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string);
+        if log.note.as_ref().is_some_and(|n| n.chars().count() > 2000) {
+            return Err(AppError::validation_error("note", "Note cannot exceed 2000 characters"));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_loggable(task: &Task) -> Result<(), AppError> {
+        if task.is_trashed() {
+            return Err(AppError::validation_error("task", &format!("Task {} is archived or deleted", task.id)));
+        }
+        Ok(())
+    }
+
+    // Timers only run on open work
+    pub fn ensure_timer_allowed(task: &Task) -> Result<(), AppError> {
+        Self::ensure_loggable(task)?;
+        if task.status.is_closed() {
+            return Err(AppError::validation_error("status", &format!("Task {} is already {}", task.id, task.status)));
+        }
+        Ok(())
+    }
+
+    pub fn set_remaining_estimate(task: &mut Task, minutes: Option<i64>) -> Result<(), AppError> {
+        if minutes.is_some_and(|m| m < 0) {
+            return Err(AppError::validation_error("remaining_estimate", "Estimate cannot be negative"));
+        }
+        task.set_remaining_estimate(minutes);
+        Ok(())
+    }
+
+    // Totals cover the whole subtree; estimates skip cancelled and trashed subtasks like progress does
+    pub fn summarize(task: &Task, subtree: &HashMap<i32, Task>, spent: &HashMap<i32, i64>) -> TimeSummary {
+        TimeSummary {
+            task_id: task.id,
+            original_estimate: task.original_estimate,
+            remaining_estimate: task.remaining_estimate,
+            time_spent: spent.get(&task.id).copied().unwrap_or(0),
+            total_original_estimate: task.rollup_estimate(subtree),
+            total_remaining_estimate: task.rollup_remaining(subtree),
+            total_time_spent: subtree.keys().filter_map(|id| spent.get(id)).sum(),
+        }
+    }
+
+    pub fn validate_report_range(from: i64, to: i64) -> Result<(), AppError> {
+        if from >= to {
+            return Err(AppError::validation_error("to", "Report range must end after it starts"));
+        }
+        if to - from > MAX_REPORT_RANGE_DAYS * 24 * 60 * 60 * 1000 {
+            return Err(AppError::validation_error(
+                "to",
+                &format!("Report range cannot exceed {} days", MAX_REPORT_RANGE_DAYS),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct ReminderService;
+
+impl ReminderService {
+    pub fn validate_settings(settings: &mut ReminderSettings) -> Result<(), AppError> {
+        if settings.offsets.len() > MAX_REMINDER_OFFSETS {
+            return Err(AppError::validation_error(
+                "offsets",
+                &format!("At most {} reminder offsets are allowed", MAX_REMINDER_OFFSETS),
+            ));
+        }
+        if let Some(offset) = settings.offsets.iter().find(|&&o| o <= 0 || o > MAX_REMINDER_OFFSET_MINUTES) {
+            return Err(AppError::validation_error(
+                "offsets",
+                &format!("Offset {} must be between 1 and {} minutes", offset, MAX_REMINDER_OFFSET_MINUTES),
+            ));
+        }
+        settings.offsets.sort_unstable_by(|a, b| b.cmp(a));
+        settings.offsets.dedup();
+        Ok(())
+    }
+
+    // Reminders that are due at `now` for every recipient of the task. Only the closest passed
+    // offset is reported, so a task created an hour before its due date skips the one-day reminder.
+    pub fn due_reminders(task: &Task, settings: &HashMap<i32, ReminderSettings>, now: i64) -> Vec<ReminderEvent> {
+        let Some(due_date) = task.due_date else {
+            return Vec::new();
+        };
+        if task.status.is_closed() || task.archived_at.is_some() || task.deleted_at.is_some() {
+            return Vec::new();
+        }
+
+        task.reminder_recipients()
+            .into_iter()
+            .filter_map(|user_id| {
+                let kind = if due_date <= now {
+                    ReminderKind::Overdue
+                } else {
+                    let defaults = ReminderSettings::default_for(user_id);
+                    let offsets = &settings.get(&user_id).unwrap_or(&defaults).offsets;
+                    let minutes_before = offsets
+                        .iter()
+                        .copied()
+                        .filter(|&minutes| due_date - minutes * 60_000 <= now)
+                        .min()?;
+                    ReminderKind::Upcoming { minutes_before }
+                };
+                Some(ReminderEvent {
+                    task_id: task.id,
+                    user_id,
+                    title: task.title.clone(),
+                    due_date,
+                    kind,
+                    fired_at: now,
+                })
+            })
+            .collect()
+    }
+
+    // Inbox entry for a queued reminder; reminders have no acting user
+    pub fn reminder_notification(event: &ReminderEvent, now: i64) -> Notification {
+        Notification {
+            id: 0,
+            user_id: event.user_id,
+            task_id: event.task_id,
+            kind: NotificationKind::Reminder,
+            actor_id: None,
+            payload: Some(json!({ "title": event.title, "due_date": to_rfc3339(event.due_date), "reminder": event.kind })),
+            created_at: now,
+            read_at: None,
+        }
+    }
 }
 
 pub struct UserService;
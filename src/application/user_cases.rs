@@ -1,6 +1,9 @@
 use crate::application::services::{TaskService, UserService};
+use crate::domain::entities::activity::ActivityContext;
+use crate::domain::entities::comment::{Comment, CommentPolicy};
 use crate::domain::entities::task::{Task, Recurrence};
 use crate::domain::entities::user::{User, Role};
+use crate::domain::entities::workflow::Workflow;
 use crate::domain::errors::AppError;
 
 pub struct TaskUseCases;
@@ -11,43 +14,51 @@ impl TaskUseCases {
         user_id: i32,
         title: String,
         description: Option<String>,
+        workflow: &Workflow,
     ) -> Result<Task, AppError> {
-        TaskService::create_task(user_id.to_string(), Option::from(title), description).await
+        let mut task = TaskService::create_task(title, description, workflow)?;
+        task.set_activity_context(ActivityContext { actor_id: Some(user_id), ..Default::default() });
+        Ok(task)
     }
 
-    pub async fn complete_existing_task(task: &mut Task) -> Result<(), AppError> {
-        TaskService::complete_task(task).await
+    pub async fn complete_existing_task(task: &mut Task, workflow: &Workflow) -> Result<(), AppError> {
+        TaskService::complete_task(task, workflow)
     }
 
     pub async fn archive_existing_task(task: &mut Task) -> Result<(), AppError> {
-        TaskService::archive_task(task).await
+        TaskService::archive_task(task)
     }
 
     pub async fn delete_existing_task(task: &mut Task) -> Result<(), AppError> {
-        TaskService::delete_task(task).await
+        TaskService::delete_task(task)
     }
 
     pub async fn add_comment_to_task(
-        task: &mut Task,
-        user_id: i32,
-        comment: String,
+        task: &Task,
+        policy: &CommentPolicy,
+        comment: &mut Comment,
+        parent: Option<(&Comment, usize)>,
     ) -> Result<(), AppError> {
-        TaskService::add_comment_to_task(task, user_id, comment).await
+        TaskService::add_comment_to_task(task, policy, comment, parent)
     }
 
     pub async fn add_subtask_to_task(
         task: &mut Task,
-        subtask: Task
+        subtask: &mut Task,
+        parent_ancestors: &[i32],
+        position: Option<usize>,
     ) -> Result<(), AppError> {
-        TaskService::add_subtask(task, subtask).await
+        TaskService::add_subtask(task, subtask, parent_ancestors, position)
     }
 
     pub async fn set_task_due_date(task: &mut Task, due_date: Option<i64>) -> Result<(), AppError> {
-        TaskService::set_task_due_date(task, due_date).await
+        TaskService::set_task_due_date(task, due_date);
+        Ok(())
     }
 
     pub async fn set_task_priority(task: &mut Task, priority: Option<i32>) -> Result<(), AppError> {
-        TaskService::set_task_priority(task, priority).await
+        TaskService::set_task_priority(task, priority);
+        Ok(())
     }
 
     pub async fn set_task_recurrence(
@@ -55,7 +66,8 @@ impl TaskUseCases {
         recurrence: Option<Recurrence>,
         recurrence_end: Option<i64>
     ) -> Result<(), AppError> {
-        TaskService::set_task_recurrence(task, recurrence, recurrence_end).await
+        TaskService::set_task_recurrence(task, recurrence, recurrence_end);
+        Ok(())
     }
 }
 
@@ -65,7 +77,7 @@ impl UserUseCases {
         password_hash: String,
         role: Role,
     ) -> Result<User, AppError> {
-        UserService::create_user(username, password_hash, role).await
+        UserService::create_user(username, password_hash, role)
     }
 
     pub async fn update_user_info(
@@ -74,32 +86,80 @@ impl UserUseCases {
         surname: Option<String>,
         email: Option<String>,
     ) -> Result<(), AppError> {
-        UserService::update_user_details(user, name, surname, email).await
+        UserService::update_user_details(user, name, surname, email)
     }
 
     pub async fn follow_another_user(
         follower: &mut User,
         followee_id: i32,
     ) -> Result<(), AppError> {
-        UserService::follow_user(follower, followee_id).await
+        UserService::follow_user(follower, followee_id)
     }
 
     pub async fn unfollow_another_user(
         follower: &mut User,
         followee_id: i32,
     ) -> Result<(), AppError> {
-        UserService::unfollow_user(follower, followee_id).await
+        UserService::unfollow_user(follower, followee_id)
     }
 
     pub async fn archive_existing_user(user: &mut User) -> Result<(), AppError> {
-        UserService::archive_user(user).await
+        UserService::archive_user(user)
     }
 
     pub async fn delete_existing_user(user: &mut User) -> Result<(), AppError> {
-        UserService::delete_user(user).await
+        UserService::delete_user(user)
     }
 
     pub async fn change_user_role(user: &mut User, role: Role) -> Result<(), AppError> {
-        UserService::set_user_role(user, role).await
+        UserService::set_user_role(user, role);
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::task::TaskStatus;
+
+    fn task() -> Task {
+        let mut task = Task::new("Write release notes".to_string(), None);
+        task.id = 7;
+        task
+    }
+
+    #[tokio::test]
+    async fn comments_are_normalized_before_they_are_stored() {
+        let mut comment = Comment::new(7, None, 3, "  Looks good  ".to_string());
+
+        TaskUseCases::add_comment_to_task(&task(), &CommentPolicy::default(), &mut comment, None).await.unwrap();
+        assert_eq!(comment.body, "Looks good");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn comments_follow_the_policy() {
+        let policy = CommentPolicy::default();
+        let mut completed = task();
+        completed.set_status(TaskStatus::Completed);
+        let mut comment = Comment::new(7, None, 3, "Reopen?".to_string());
+        assert!(TaskUseCases::add_comment_to_task(&completed, &policy, &mut comment, None).await.is_err());
+
+        let mut blank = Comment::new(7, None, 3, "   ".to_string());
+        assert!(TaskUseCases::add_comment_to_task(&task(), &policy, &mut blank, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn replies_stay_in_their_thread() {
+        let policy = CommentPolicy::default();
+        let mut parent = Comment::new(7, None, 3, "Question".to_string());
+        parent.id = 40;
+        let mut reply = Comment::new(7, Some(40), 4, "Answer".to_string());
+        assert!(TaskUseCases::add_comment_to_task(&task(), &policy, &mut reply, Some((&parent, 1))).await.is_ok());
+        assert!(TaskUseCases::add_comment_to_task(&task(), &policy, &mut reply, Some((&parent, policy.max_depth))).await.is_err());
+
+        parent.task_id = 8;
+        assert!(TaskUseCases::add_comment_to_task(&task(), &policy, &mut reply, Some((&parent, 1))).await.is_err());
+        parent.task_id = 7;
+        parent.delete(0);
+        assert!(TaskUseCases::add_comment_to_task(&task(), &policy, &mut reply, Some((&parent, 1))).await.is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::domain::time::rfc3339;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActivitySubject {
+    Task,
+    User,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActivityKind {
+    // Task events
+    Created,
+    TitleChanged,
+    DescriptionChanged,
+    StatusChanged,
+    Reopened,
+    Completed,
+    DueDateChanged,
+    MarkedOverdue,
+    PriorityChanged,
+    TagAdded,
+    TagRemoved,
+    TagRenamed,
+    AssigneeChanged,
+    SubtaskAdded,
+    SubtaskRemoved,
+    SubtaskMoved,
+    ParentChanged,
+    ProjectChanged,
+    EstimateChanged,
+    RemainingEstimateChanged,
+    WorkLogged,
+    WorkLogRemoved,
+    RecurrenceChanged,
+    DependencyAdded,
+    LinkAdded,
+    LinkRemoved,
+    CollaboratorAdded,
+    CollaboratorRemoved,
+    CollaboratorRoleChanged,
+    ProgressChanged,
+    CommentAdded,
+    CommentEdited,
+    CommentDeleted,
+    // Another task mentioned this one with `#id` or `#KEY-n`
+    Referenced,
+    AttachmentAdded,
+    AttachmentRemoved,
+    ChecklistItemAdded,
+    ChecklistItemChecked,
+    ChecklistItemUnchecked,
+    ChecklistItemRenamed,
+    ChecklistItemMoved,
+    ChecklistItemRemoved,
+    // User events
+    NameChanged,
+    SurnameChanged,
+    EmailChanged,
+    BioChanged,
+    ImageChanged,
+    FollowerAdded,
+    FollowerRemoved,
+    FollowingAdded,
+    FollowingRemoved,
+    PasswordChanged,
+    RoleChanged,
+    VerificationChanged,
+    TimeZoneChanged,
+    // Shared events
+    CustomFieldSet,
+    Archived,
+    Deleted,
+    Restored,
+    // Free-form entries migrated from the old string log
+    Legacy,
+}
+
+// Who is acting and on behalf of which request; set by the caller before mutating an entity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityContext {
+    pub actor_id: Option<i32>,
+    pub correlation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    pub id: i64,
+    pub subject: ActivitySubject,
+    pub subject_id: i32,
+    pub actor_id: Option<i32>,
+    pub kind: ActivityKind,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub correlation_id: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub timestamp: i64,
+}
+
+impl ActivityEvent {
+    pub fn new(
+        subject: ActivitySubject,
+        subject_id: i32,
+        context: &ActivityContext,
+        kind: ActivityKind,
+        old_value: Option<Value>,
+        new_value: Option<Value>,
+    ) -> Self {
+        ActivityEvent {
+            id: 0,
+            subject,
+            subject_id,
+            actor_id: context.actor_id,
+            kind,
+            old_value,
+            new_value,
+            correlation_id: context.correlation_id.clone(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
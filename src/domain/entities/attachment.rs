@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::rfc3339;
+
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: i64,
+    pub task_id: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    // Hex-encoded SHA-256 of the content
+    pub checksum: String,
+    #[serde(skip)]
+    pub storage_key: String,
+    pub uploaded_by: Option<i32>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+}
+
+// Limits applied to every upload; content types may end in `/*` to allow a whole family
+#[derive(Debug, Clone)]
+pub struct AttachmentLimits {
+    pub max_bytes: usize,
+    pub max_per_task: usize,
+    // Upper bound on all files of one upload request together
+    pub max_request_bytes: usize,
+    pub allowed_types: Vec<String>,
+}
+
+impl Default for AttachmentLimits {
+    fn default() -> Self {
+        AttachmentLimits {
+            max_bytes: 25 * 1024 * 1024,
+            max_per_task: 100,
+            max_request_bytes: 100 * 1024 * 1024,
+            allowed_types: [
+                "image/*",
+                "text/plain",
+                "text/markdown",
+                "text/csv",
+                "application/pdf",
+                "application/json",
+                "application/zip",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl AttachmentLimits {
+    pub fn allows_type(&self, content_type: &str) -> bool {
+        let content_type = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        self.allowed_types.iter().any(|allowed| match allowed.strip_suffix("/*") {
+            Some(family) => content_type.split('/').next() == Some(family),
+            None => *allowed == content_type,
+        })
+    }
+}
+
+impl Attachment {
+    // Content is stored per task; the checksum keeps keys of same-named uploads apart
+    pub fn storage_key_for(task_id: i32, created_at: i64, checksum: &str) -> String {
+        format!("tasks/{}/{}-{}", task_id, created_at, checksum)
+    }
+}
+
+// Keeps the last path segment only and drops characters that would break a
+// Content-Disposition header
+pub fn sanitize_filename(filename: &str) -> Option<String> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or("");
+    let name = name
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILENAME_LENGTH)
+        .collect::<String>();
+    let name = name.trim();
+    (!name.is_empty() && name != "." && name != "..").then(|| name.to_string())
+}
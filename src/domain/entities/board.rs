@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::task::TaskStatus;
+use crate::domain::time::{rfc3339, rfc3339_option};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Swimlane {
+    Assignee,
+    Priority,
+    Tag,
+}
+
+// A column shows every task whose status is one of `statuses`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardColumn {
+    pub name: String,
+    pub statuses: Vec<TaskStatus>,
+    pub wip_limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Board {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    pub columns: Vec<BoardColumn>,
+    pub swimlane: Option<Swimlane>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+impl Board {
+    pub fn column_of(&self, status: TaskStatus) -> Option<usize> {
+        self.columns.iter().position(|c| c.statuses.contains(&status))
+    }
+
+    pub fn column_named(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    // Every status shown on the board
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.columns.iter().flat_map(|c| c.statuses.iter().copied()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Card {
+    pub task_id: i32,
+    pub reference: Option<String>,
+    pub title: String,
+    pub status: TaskStatus,
+    pub priority: Option<i32>,
+    pub assigned_to: Option<i32>,
+    pub tags: Vec<String>,
+    #[serde(with = "rfc3339_option")]
+    pub due_date: Option<i64>,
+    pub due_all_day: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub statuses: Vec<TaskStatus>,
+    pub wip_limit: Option<u32>,
+    pub count: usize,
+    pub over_limit: bool,
+}
+
+// One row of the board; `columns` holds the ordered cards of each column in board order
+#[derive(Debug, Clone, Serialize)]
+pub struct Lane {
+    pub key: Option<String>,
+    pub columns: Vec<Vec<Card>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BoardView {
+    pub board: Board,
+    pub columns: Vec<ColumnSummary>,
+    pub lanes: Vec<Lane>,
+}
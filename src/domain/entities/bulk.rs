@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::task::TaskStatus;
+
+pub const MAX_BULK_TASKS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    SetStatus { status: TaskStatus },
+    Assign { user_id: Option<i32> },
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+    SetPriority { priority: Option<i32> },
+    Archive,
+    Delete,
+    MoveToProject { project_id: Option<i32> },
+}
+
+impl BulkOperation {
+    // These operations carry the subtasks of each selected task along
+    pub fn applies_to_subtree(&self) -> bool {
+        matches!(self, BulkOperation::Archive | BulkOperation::Delete | BulkOperation::MoveToProject { .. })
+    }
+}
+
+// Atomic rolls everything back on the first failure; best effort skips failing tasks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    #[default]
+    Atomic,
+    BestEffort,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkItemResult {
+    pub task_id: i32,
+    pub ok: bool,
+    // False when the task already matched and nothing was written
+    pub changed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkResult {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+impl BulkItemResult {
+    pub fn new<E: std::fmt::Display>(task_id: i32, outcome: Result<bool, E>) -> Self {
+        match outcome {
+            Ok(changed) => BulkItemResult { task_id, ok: true, changed, error: None },
+            Err(e) => BulkItemResult { task_id, ok: false, changed: false, error: Some(e.to_string()) },
+        }
+    }
+}
+
+impl BulkResult {
+    pub fn new(results: Vec<BulkItemResult>) -> Self {
+        let succeeded = results.iter().filter(|r| r.ok).count();
+        BulkResult { succeeded, failed: results.len() - succeeded, results }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::domain::entities::{ical::uid_of, task::Task};
+
+pub const CALDAV_ROOT: &str = "/caldav/";
+// Sync tokens must be URIs; the number is the task write sequence at the time of the sync
+const SYNC_TOKEN_PREFIX: &str = "https://taskflow/ns/sync/";
+
+// A task list as CalDAV clients see it: the tasks assigned to a user, or one project's tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskCollection {
+    Assigned(i32),
+    Project(i32),
+}
+
+impl TaskCollection {
+    // `tasks` or `project-<id>` under the user's calendar home
+    pub fn parse(user_id: i32, name: &str) -> Option<Self> {
+        match name {
+            "tasks" => Some(TaskCollection::Assigned(user_id)),
+            _ => name.strip_prefix("project-")?.parse().ok().map(TaskCollection::Project),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            TaskCollection::Assigned(_) => "tasks".to_string(),
+            TaskCollection::Project(project_id) => format!("project-{}", project_id),
+        }
+    }
+
+    pub fn project_id(&self) -> Option<i32> {
+        match self {
+            TaskCollection::Project(project_id) => Some(*project_id),
+            TaskCollection::Assigned(_) => None,
+        }
+    }
+
+    pub fn assignee_id(&self) -> Option<i32> {
+        match self {
+            TaskCollection::Assigned(user_id) => Some(*user_id),
+            TaskCollection::Project(_) => None,
+        }
+    }
+}
+
+// The user's principal, which is also their calendar home
+pub fn home_path(user_id: i32) -> String {
+    format!("{}{}/", CALDAV_ROOT, user_id)
+}
+
+pub fn collection_path(user_id: i32, collection: TaskCollection) -> String {
+    format!("{}{}/", home_path(user_id), collection.name())
+}
+
+pub fn resource_path(user_id: i32, collection: TaskCollection, name: &str) -> String {
+    format!("{}{}", collection_path(user_id, collection), encode_segment(name))
+}
+
+// Mirrored by the tombstone trigger in the database
+pub fn resource_name(task: &Task) -> String {
+    task.caldav_name.clone().unwrap_or_else(|| format!("{}.ics", uid_of(task)))
+}
+
+pub fn sync_token(sequence: i64) -> String {
+    format!("{}{}", SYNC_TOKEN_PREFIX, sequence)
+}
+
+pub fn parse_sync_token(token: &str) -> Option<i64> {
+    token.trim().strip_prefix(SYNC_TOKEN_PREFIX)?.parse().ok().filter(|s| *s >= 0)
+}
+
+pub fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub fn decode_segment(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+// Filters of a calendar-query report (RFC 4791, section 9.7)
+#[derive(Debug, Clone, Default)]
+pub struct CompFilter {
+    pub name: String,
+    pub is_not_defined: bool,
+    pub time_range: Option<TimeRange>,
+    pub props: Vec<PropFilter>,
+    pub comps: Vec<CompFilter>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PropFilter {
+    pub name: String,
+    pub is_not_defined: bool,
+    pub text_match: Option<TextMatch>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextMatch {
+    pub text: String,
+    pub negate: bool,
+    // The i;octet collation; i;ascii-casemap is the default
+    pub case_sensitive: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeRange {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+impl CompFilter {
+    // Applies a VCALENDAR filter to a task; `properties` are those of its rendered VTODO
+    pub fn matches(&self, task: &Task, properties: &[(String, String)]) -> bool {
+        if self.name != "VCALENDAR" || self.is_not_defined {
+            return false;
+        }
+        self.comps.iter().all(|comp| match comp.name.as_str() {
+            "VTODO" => comp.matches_todo(task, properties),
+            // Tasks are only ever VTODOs without nested components
+            _ => comp.is_not_defined,
+        })
+    }
+
+    fn matches_todo(&self, task: &Task, properties: &[(String, String)]) -> bool {
+        if self.is_not_defined {
+            return false;
+        }
+        // A to-do without a due date overlaps every range
+        let in_range = match (self.time_range, task.due_date) {
+            (Some(range), Some(due)) => range.start.is_none_or(|s| s < due) && range.end.is_none_or(|e| e >= due),
+            _ => true,
+        };
+        in_range
+            && self.props.iter().all(|prop| prop.matches(properties))
+            && self.comps.iter().all(|comp| comp.is_not_defined)
+    }
+}
+
+impl PropFilter {
+    fn matches(&self, properties: &[(String, String)]) -> bool {
+        let mut values = properties.iter().filter(|(name, _)| *name == self.name).map(|(_, value)| value);
+        if self.is_not_defined {
+            return values.next().is_none();
+        }
+        match &self.text_match {
+            Some(text_match) => values.any(|value| text_match.matches(value)),
+            None => values.next().is_some(),
+        }
+    }
+}
+
+impl TextMatch {
+    fn matches(&self, value: &str) -> bool {
+        let found = if self.case_sensitive {
+            value.contains(&self.text)
+        } else {
+            value.to_lowercase().contains(&self.text.to_lowercase())
+        };
+        found != self.negate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_due(due_date: Option<i64>) -> Task {
+        let mut task = Task::new("Renew passport".to_string(), None);
+        task.id = 12;
+        task.due_date = due_date;
+        task
+    }
+
+    fn vcalendar(comps: Vec<CompFilter>) -> CompFilter {
+        CompFilter { name: "VCALENDAR".to_string(), comps, ..Default::default() }
+    }
+
+    fn vtodo(time_range: Option<TimeRange>, props: Vec<PropFilter>) -> CompFilter {
+        CompFilter { name: "VTODO".to_string(), time_range, props, ..Default::default() }
+    }
+
+    fn summary_match(text: &str, negate: bool, case_sensitive: bool) -> PropFilter {
+        PropFilter {
+            name: "SUMMARY".to_string(),
+            text_match: Some(TextMatch { text: text.to_string(), negate, case_sensitive }),
+            ..Default::default()
+        }
+    }
+
+    fn properties() -> Vec<(String, String)> {
+        vec![("UID".to_string(), "task-12@taskflow".to_string()), ("SUMMARY".to_string(), "Renew Passport".to_string())]
+    }
+
+    #[test]
+    fn collections_round_trip_through_their_names() {
+        assert_eq!(TaskCollection::parse(4, "tasks"), Some(TaskCollection::Assigned(4)));
+        assert_eq!(TaskCollection::parse(4, "project-17"), Some(TaskCollection::Project(17)));
+        assert_eq!(TaskCollection::parse(4, "project-"), None);
+        assert_eq!(TaskCollection::parse(4, "project-x"), None);
+        assert_eq!(TaskCollection::parse(4, "inbox"), None);
+
+        assert_eq!(TaskCollection::Project(17).name(), "project-17");
+        assert_eq!(collection_path(4, TaskCollection::Assigned(4)), "/caldav/4/tasks/");
+        assert_eq!(resource_path(4, TaskCollection::Project(17), "a b.ics"), "/caldav/4/project-17/a%20b.ics");
+    }
+
+    #[test]
+    fn sync_tokens_round_trip_and_reject_foreign_values() {
+        assert_eq!(parse_sync_token(&sync_token(0)), Some(0));
+        assert_eq!(parse_sync_token(&format!(" {} ", sync_token(381))), Some(381));
+        assert_eq!(parse_sync_token("381"), None);
+        assert_eq!(parse_sync_token("https://taskflow/ns/sync/-1"), None);
+        assert_eq!(parse_sync_token("https://taskflow/ns/sync/abc"), None);
+        assert_eq!(parse_sync_token("http://example.com/ns/sync/381"), None);
+    }
+
+    #[test]
+    fn segments_round_trip_through_percent_encoding() {
+        let name = "Einkäufe 50%/Woche.ics";
+        let encoded = encode_segment(name);
+
+        assert_eq!(encoded, "Eink%C3%A4ufe%2050%25%2FWoche.ics");
+        assert_eq!(decode_segment(&encoded).as_deref(), Some(name));
+        assert_eq!(encode_segment("task-12@taskflow.ics"), "task-12@taskflow.ics");
+        assert_eq!(decode_segment("broken%2"), None);
+        assert_eq!(decode_segment("broken%zz"), None);
+        assert_eq!(decode_segment("%FF"), None);
+    }
+
+    #[test]
+    fn resources_are_named_after_the_uid_unless_the_client_chose_a_name() {
+        let mut task = task_due(None);
+        assert_eq!(resource_name(&task), "task-12@taskflow.ics");
+
+        task.ical_uid = Some("0B1C@example.com".to_string());
+        assert_eq!(resource_name(&task), "0B1C@example.com.ics");
+
+        task.caldav_name = Some("0B1C.ics".to_string());
+        assert_eq!(resource_name(&task), "0B1C.ics");
+    }
+
+    #[test]
+    fn time_ranges_match_on_the_due_date() {
+        let range = TimeRange { start: Some(1_000), end: Some(2_000) };
+        let filter = vcalendar(vec![vtodo(Some(range), Vec::new())]);
+
+        assert!(filter.matches(&task_due(Some(1_500)), &properties()));
+        assert!(filter.matches(&task_due(Some(2_000)), &properties()));
+        assert!(!filter.matches(&task_due(Some(1_000)), &properties()));
+        assert!(!filter.matches(&task_due(Some(2_001)), &properties()));
+        assert!(filter.matches(&task_due(None), &properties()));
+
+        let open_ended = vcalendar(vec![vtodo(Some(TimeRange { start: Some(1_000), end: None }), Vec::new())]);
+        assert!(open_ended.matches(&task_due(Some(90_000)), &properties()));
+    }
+
+    #[test]
+    fn text_matches_follow_the_collation_and_negation() {
+        let task = task_due(None);
+        let matching = |prop: PropFilter| vcalendar(vec![vtodo(None, vec![prop])]).matches(&task, &properties());
+
+        assert!(matching(summary_match("passport", false, false)));
+        assert!(!matching(summary_match("passport", false, true)));
+        assert!(matching(summary_match("Passport", false, true)));
+        assert!(!matching(summary_match("passport", true, false)));
+        assert!(matching(summary_match("visa", true, false)));
+    }
+
+    #[test]
+    fn undefined_properties_and_components_are_matched() {
+        let task = task_due(None);
+        let undefined = |name: &str| PropFilter { name: name.to_string(), is_not_defined: true, ..Default::default() };
+        let defined = PropFilter { name: "UID".to_string(), ..Default::default() };
+
+        assert!(vcalendar(vec![vtodo(None, vec![undefined("DUE")])]).matches(&task, &properties()));
+        assert!(!vcalendar(vec![vtodo(None, vec![undefined("SUMMARY")])]).matches(&task, &properties()));
+        assert!(vcalendar(vec![vtodo(None, vec![defined])]).matches(&task, &properties()));
+
+        let no_events = CompFilter { name: "VEVENT".to_string(), is_not_defined: true, ..Default::default() };
+        assert!(vcalendar(vec![no_events]).matches(&task, &properties()));
+        let events = CompFilter { name: "VEVENT".to_string(), ..Default::default() };
+        assert!(!vcalendar(vec![events]).matches(&task, &properties()));
+        let no_todos = CompFilter { name: "VTODO".to_string(), is_not_defined: true, ..Default::default() };
+        assert!(!vcalendar(vec![no_todos]).matches(&task, &properties()));
+        assert!(!CompFilter { name: "VTODO".to_string(), ..Default::default() }.matches(&task, &properties()));
+    }
+}
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+use crate::domain::time::rfc3339;
+
+// Subscribing clients are told to poll at this interval
+pub const FEED_REFRESH_MINUTES: i64 = 60;
+pub const MAX_CALENDAR_TASKS: i64 = 2_000;
+
+// A secret, rotatable address under which a user's tasks are published as a calendar
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarFeed {
+    pub user_id: i32,
+    pub token: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+}
+
+impl CalendarFeed {
+    pub fn path(&self) -> String {
+        format!("/calendar/feeds/{}.ics", self.token)
+    }
+}
@@ -0,0 +1,46 @@
+use serde::Deserialize;
+
+use crate::domain::entities::task::Task;
+
+pub const MAX_CLONE_TASKS: usize = 200;
+
+// What a clone copies besides title, description, priority, due date, estimate and
+// recurrence. Status, timestamps, progress, comments and activity always start afresh.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CloneOptions {
+    pub title: Option<String>,
+    pub include_subtasks: bool,
+    pub include_tags: bool,
+    pub include_custom_fields: bool,
+    pub include_collaborators: bool,
+    // Items are copied unchecked
+    pub include_checklist: bool,
+    // Clones share the stored content; only the metadata rows are copied
+    pub include_attachments: bool,
+    // Blockers inside the cloned subtree are remapped to their clones
+    pub include_dependencies: bool,
+}
+
+impl Default for CloneOptions {
+    fn default() -> Self {
+        CloneOptions {
+            title: None,
+            include_subtasks: true,
+            include_tags: true,
+            include_custom_fields: true,
+            include_collaborators: true,
+            include_checklist: true,
+            include_attachments: true,
+            include_dependencies: true,
+        }
+    }
+}
+
+// A copy waiting to be saved, listed parents first
+#[derive(Debug, Clone)]
+pub struct PlannedClone {
+    pub source_id: i32,
+    pub parent: Option<usize>,
+    pub task: Task,
+}
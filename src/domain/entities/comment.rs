@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::task::TaskStatus;
+use crate::domain::time::{rfc3339, rfc3339_option};
+
+pub const MAX_REACTION_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentEdit {
+    pub body: String,
+    #[serde(with = "rfc3339")]
+    pub edited_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reaction {
+    pub emoji: String,
+    pub user_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: i64,
+    pub task_id: i32,
+    pub parent_id: Option<i64>,
+    pub user_id: i32,
+    pub body: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(default, with = "rfc3339_option")]
+    pub edited_at: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
+    pub deleted_at: Option<i64>,
+    // Earlier bodies, oldest first
+    #[serde(default)]
+    pub edits: Vec<CommentEdit>,
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<Comment>,
+}
+
+// Rules for commenting; the defaults match the original behaviour of refusing comments on
+// completed tasks
+#[derive(Debug, Clone)]
+pub struct CommentPolicy {
+    pub closed_statuses: Vec<TaskStatus>,
+    pub allow_on_trashed: bool,
+    pub max_length: usize,
+    pub max_depth: usize,
+    // Minutes after posting during which the author may still edit; unlimited when None
+    pub edit_window_minutes: Option<i64>,
+}
+
+impl Default for CommentPolicy {
+    fn default() -> Self {
+        CommentPolicy {
+            closed_statuses: vec![TaskStatus::Completed],
+            allow_on_trashed: false,
+            max_length: 10_000,
+            max_depth: 5,
+            edit_window_minutes: None,
+        }
+    }
+}
+
+impl Comment {
+    pub fn new(task_id: i32, parent_id: Option<i64>, user_id: i32, body: String) -> Self {
+        Comment {
+            id: 0,
+            task_id,
+            parent_id,
+            user_id,
+            body,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            edited_at: None,
+            deleted_at: None,
+            edits: Vec::new(),
+            reactions: Vec::new(),
+            replies: Vec::new(),
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn edit(&mut self, body: String, now: i64) {
+        let previous = std::mem::replace(&mut self.body, body);
+        self.edits.push(CommentEdit { body: previous, edited_at: now });
+        self.edited_at = Some(now);
+    }
+
+    // Deleted comments keep their place in the thread but lose their content
+    pub fn delete(&mut self, now: i64) {
+        self.deleted_at = Some(now);
+        self.redact();
+    }
+
+    pub fn redact(&mut self) {
+        if self.is_deleted() {
+            self.body.clear();
+            self.edits.clear();
+            self.reactions.clear();
+        }
+    }
+}
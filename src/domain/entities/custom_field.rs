@@ -0,0 +1,133 @@
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::domain::time::rfc3339;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CustomFieldTarget {
+    Task,
+    User,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    // Stored as milliseconds since the epoch
+    Date,
+    SingleSelect,
+    MultiSelect,
+    // Stored as the referenced user's id
+    UserRef,
+    Url,
+}
+
+impl CustomFieldType {
+    pub fn is_select(&self) -> bool {
+        matches!(self, CustomFieldType::SingleSelect | CustomFieldType::MultiSelect)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomFieldDefinition {
+    pub id: i32,
+    pub target: CustomFieldTarget,
+    pub key: String,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub required: bool,
+    pub allowed_values: Vec<String>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+impl CustomFieldDefinition {
+    // Checks `value` against the definition and returns it in its stored form
+    pub fn normalize(&self, value: &Value) -> Result<Value, String> {
+        match (self.field_type, value) {
+            (_, Value::Null) => Err("value cannot be null".to_string()),
+            (CustomFieldType::Text, Value::String(s)) => Ok(Value::from(s.as_str())),
+            (CustomFieldType::Number, Value::Number(n)) => Ok(Value::Number(n.clone())),
+            (CustomFieldType::Number, Value::String(s)) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(Value::from)
+                .ok_or_else(|| format!("'{}' is not a number", s)),
+            (CustomFieldType::Date, Value::Number(n)) => n
+                .as_i64()
+                .map(Value::from)
+                .ok_or_else(|| "dates must be whole milliseconds".to_string()),
+            (CustomFieldType::Date, Value::String(s)) => parse_date(s).map(Value::from),
+            (CustomFieldType::SingleSelect, Value::String(s)) => self.allowed(s).map(Value::from),
+            (CustomFieldType::MultiSelect, Value::String(s)) => Ok(Value::from(vec![self.allowed(s)?])),
+            (CustomFieldType::MultiSelect, Value::Array(items)) => {
+                let mut selected: Vec<String> = Vec::new();
+                for item in items {
+                    let option = item.as_str().ok_or_else(|| "options must be strings".to_string())?;
+                    let option = self.allowed(option)?;
+                    if !selected.contains(&option) {
+                        selected.push(option);
+                    }
+                }
+                if selected.is_empty() && self.required {
+                    return Err("at least one option is required".to_string());
+                }
+                Ok(Value::from(selected))
+            }
+            (CustomFieldType::UserRef, Value::Number(n)) => n
+                .as_i64()
+                .filter(|id| *id > 0 && *id <= i32::MAX as i64)
+                .map(Value::from)
+                .ok_or_else(|| format!("{} is not a user id", n)),
+            (CustomFieldType::UserRef, Value::String(s)) => s
+                .trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|id| *id > 0)
+                .map(Value::from)
+                .ok_or_else(|| format!("'{}' is not a user id", s)),
+            (CustomFieldType::Url, Value::String(s)) => parse_url(s).map(Value::from),
+            (field_type, other) => Err(format!("{} is not a valid {:?} value", other, field_type)),
+        }
+    }
+
+    // Parses a raw query-string value for filtering; a multi-select filter matches a single option
+    pub fn normalize_filter(&self, raw: &str) -> Result<Value, String> {
+        match self.field_type {
+            CustomFieldType::MultiSelect => self.allowed(raw).map(Value::from),
+            _ => self.normalize(&Value::from(raw)),
+        }
+    }
+
+    fn allowed(&self, option: &str) -> Result<String, String> {
+        if self.allowed_values.iter().any(|v| v == option) {
+            Ok(option.to_string())
+        } else {
+            Err(format!("'{}' is not one of: {}", option, self.allowed_values.join(", ")))
+        }
+    }
+}
+
+// Accepts RFC 3339 timestamps and plain dates, the latter as midnight UTC
+fn parse_date(s: &str) -> Result<i64, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(s.trim()) {
+        return Ok(date.timestamp_millis());
+    }
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc().timestamp_millis())
+        .ok_or_else(|| format!("'{}' is not an RFC 3339 timestamp or YYYY-MM-DD date", s))
+}
+
+fn parse_url(s: &str) -> Result<String, String> {
+    match url::Url::parse(s.trim()) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => Ok(url.to_string()),
+        _ => Err(format!("'{}' is not an http(s) URL", s)),
+    }
+}
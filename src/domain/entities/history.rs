@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::domain::entities::task::Task;
+use crate::domain::time::rfc3339;
+
+// Bumped on every save, so it would turn each version into noise
+const IGNORED_FIELDS: [&str; 1] = ["updated_at"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Value,
+    pub new_value: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskVersion {
+    pub task_id: i32,
+    pub version: i32,
+    #[serde(with = "rfc3339")]
+    pub changed_at: i64,
+    pub actor_id: Option<i32>,
+    pub correlation_id: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+pub fn diff_tasks(old: &Task, new: &Task) -> Vec<FieldChange> {
+    diff_values(&to_object(old), &to_object(new))
+}
+
+pub fn diff_values(old: &Map<String, Value>, new: &Map<String, Value>) -> Vec<FieldChange> {
+    let mut fields = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old_value = old.get(field).cloned().unwrap_or(Value::Null);
+            let new_value = new.get(field).cloned().unwrap_or(Value::Null);
+            (old_value != new_value).then(|| FieldChange { field: field.clone(), old_value, new_value })
+        })
+        .collect()
+}
+
+// Rewinds `current` by undoing, newest first, every version accepted by `undo`
+pub fn rewind<F>(current: &Task, versions: &[TaskVersion], undo: F) -> Result<Task, serde_json::Error>
+where
+    F: Fn(&TaskVersion) -> bool,
+{
+    let mut state = to_object(current);
+    let mut ordered = versions.iter().filter(|v| undo(v)).collect::<Vec<_>>();
+    ordered.sort_by_key(|v| std::cmp::Reverse(v.version));
+
+    for version in &ordered {
+        for change in &version.changes {
+            state.insert(change.field.clone(), change.old_value.clone());
+        }
+    }
+    if let Some(oldest_undone) = ordered.last() {
+        let restored_at = versions
+            .iter()
+            .filter(|v| v.version < oldest_undone.version)
+            .map(|v| v.changed_at)
+            .max()
+            .unwrap_or(current.created_at);
+        state.insert("updated_at".to_string(), Value::from(restored_at));
+    }
+    serde_json::from_value(Value::Object(state))
+}
+
+// State of the task as of `timestamp`, or None if it did not exist yet
+pub fn task_as_of(current: &Task, versions: &[TaskVersion], timestamp: i64) -> Result<Option<Task>, serde_json::Error> {
+    if timestamp < current.created_at {
+        return Ok(None);
+    }
+    rewind(current, versions, |v| v.changed_at > timestamp).map(Some)
+}
+
+// State of the task right after `version` was saved
+pub fn task_at_version(current: &Task, versions: &[TaskVersion], version: i32) -> Result<Task, serde_json::Error> {
+    rewind(current, versions, |v| v.version > version)
+}
+
+fn to_object(task: &Task) -> Map<String, Value> {
+    match serde_json::to_value(task) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: i64 = 1_700_000_000_000;
+    const T1: i64 = T0 + 60_000;
+    const T2: i64 = T0 + 120_000;
+    const T3: i64 = T0 + 180_000;
+
+    type Fields = (String, Option<String>, Option<i64>, Vec<String>, Option<i32>, i64);
+
+    fn fields(task: &Task) -> Fields {
+        (task.title.clone(), task.description.clone(), task.due_date, task.tags.clone(), task.priority, task.updated_at)
+    }
+
+    fn save(versions: &mut Vec<TaskVersion>, before: &Task, after: &mut Task, at: i64) {
+        after.updated_at = at;
+        versions.push(TaskVersion {
+            task_id: after.id,
+            version: versions.len() as i32 + 1,
+            changed_at: at,
+            actor_id: None,
+            correlation_id: None,
+            changes: diff_tasks(before, after),
+        });
+    }
+
+    // Four saves: created, description, due date and tag set, those cleared and renamed,
+    // then a priority. Returns the task after each save and the recorded versions.
+    fn history() -> (Vec<Task>, Vec<TaskVersion>) {
+        let mut versions = Vec::new();
+        let mut task = Task::new("Draft".to_string(), None);
+        task.id = 3;
+        task.created_at = T0;
+        task.activity_log.clear();
+        save(&mut versions, &Task::new(String::new(), None), &mut task, T0);
+        let mut states = vec![task.clone()];
+
+        task.description = Some("First pass".to_string());
+        task.due_date = Some(T0 + 24 * 60 * 60_000);
+        task.tags = vec!["q3".to_string()];
+        save(&mut versions, &states[0], &mut task, T1);
+        states.push(task.clone());
+
+        task.description = None;
+        task.due_date = None;
+        task.title = "Final".to_string();
+        save(&mut versions, &states[1], &mut task, T2);
+        states.push(task.clone());
+
+        task.priority = Some(2);
+        save(&mut versions, &states[2], &mut task, T3);
+        states.push(task);
+        (states, versions)
+    }
+
+    #[test]
+    fn task_as_of_replays_the_state_at_each_timestamp() {
+        let (states, versions) = history();
+        let current = states.last().unwrap();
+
+        assert!(task_as_of(current, &versions, T0 - 1).unwrap().is_none());
+        let expected = [(T0, 0), (T1 - 1, 0), (T1, 1), (T2 - 1, 1), (T2, 2), (T3 - 1, 2), (T3, 3), (T3 + 60_000, 3)];
+        for (timestamp, state) in expected {
+            let task = task_as_of(current, &versions, timestamp).unwrap().unwrap();
+            assert_eq!(fields(&task), fields(&states[state]), "as of {}", timestamp);
+        }
+    }
+
+    #[test]
+    fn fields_cleared_later_come_back_before_and_vanish_after() {
+        let (states, versions) = history();
+        let current = states.last().unwrap();
+
+        let before_clear = task_as_of(current, &versions, T2 - 1).unwrap().unwrap();
+        assert_eq!(before_clear.description.as_deref(), Some("First pass"));
+        assert_eq!(before_clear.due_date, Some(T0 + 24 * 60 * 60_000));
+        assert_eq!(before_clear.priority, None);
+
+        let before_set = task_as_of(current, &versions, T1 - 1).unwrap().unwrap();
+        assert_eq!((before_set.description, before_set.due_date), (None, None));
+        assert!(before_set.tags.is_empty());
+    }
+
+    #[test]
+    fn task_at_version_matches_the_state_after_each_save() {
+        let (states, versions) = history();
+        let current = states.last().unwrap();
+
+        for (index, state) in states.iter().enumerate() {
+            let task = task_at_version(current, &versions, index as i32 + 1).unwrap();
+            assert_eq!(fields(&task), fields(state), "version {}", index + 1);
+        }
+    }
+
+    #[test]
+    fn diffs_skip_updated_at() {
+        let (_, versions) = history();
+
+        assert!(versions.iter().all(|v| v.changes.iter().all(|c| c.field != "updated_at")));
+        let fields = versions[2].changes.iter().map(|c| c.field.as_str()).collect::<Vec<_>>();
+        assert_eq!(fields, vec!["description", "due_date", "title"]);
+    }
+}
@@ -0,0 +1,883 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::domain::entities::task::{DueDate, Recurrence, Task, TaskStatus};
+use crate::domain::time::{end_of_day, local_date, resolve_local, start_of_day};
+
+const PRODUCT_ID: &str = "-//Taskflow//Tasks//EN";
+// Content lines are limited to 75 octets, continuations start with a space
+const MAX_LINE_OCTETS: usize = 75;
+
+// Calendar apps differ in what they show: reminders apps read VTODOs, most calendars only VEVENTs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarComponent {
+    #[default]
+    Todo,
+    Event,
+}
+
+// Header of a calendar; `refresh_minutes` tells subscribing clients how often to poll
+#[derive(Debug, Clone)]
+pub struct CalendarInfo {
+    pub name: String,
+    pub refresh_minutes: Option<i64>,
+}
+
+// Tasks that never came from a calendar get a UID derived from their id
+pub fn task_uid(task_id: i32) -> String {
+    format!("task-{}@taskflow", task_id)
+}
+
+pub fn uid_of(task: &Task) -> String {
+    task.ical_uid.clone().unwrap_or_else(|| task_uid(task.id))
+}
+
+// Reverses `task_uid`
+pub fn task_id_from_uid(uid: &str) -> Option<i32> {
+    uid.strip_prefix("task-")?.strip_suffix("@taskflow")?.parse().ok()
+}
+
+// A VCALENDAR with one component per task; tasks without a due date are skipped for events
+pub fn write_calendar(info: &CalendarInfo, tasks: &[Task], component: CalendarComponent) -> String {
+    let mut out = String::new();
+    begin_calendar(&mut out);
+    push_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(&info.name)));
+    if let Some(minutes) = info.refresh_minutes {
+        push_line(&mut out, &format!("REFRESH-INTERVAL;VALUE=DURATION:PT{}M", minutes));
+        push_line(&mut out, &format!("X-PUBLISHED-TTL:PT{}M", minutes));
+    }
+    let uids = tasks.iter().map(|t| (t.id, uid_of(t))).collect::<HashMap<_, _>>();
+    for task in tasks {
+        match component {
+            CalendarComponent::Todo => write_todo(&mut out, task, &uids),
+            CalendarComponent::Event if task.due_date.is_some() => write_event(&mut out, task, &uids),
+            CalendarComponent::Event => {}
+        }
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+// A single to-do as stored in a CalDAV collection; `uids` resolves the parent's UID
+pub fn write_resource(task: &Task, uids: &HashMap<i32, String>) -> String {
+    let mut out = String::new();
+    begin_calendar(&mut out);
+    write_todo(&mut out, task, uids);
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+fn begin_calendar(out: &mut String) {
+    push_line(out, "BEGIN:VCALENDAR");
+    push_line(out, "VERSION:2.0");
+    push_line(out, &format!("PRODID:{}", PRODUCT_ID));
+    push_line(out, "CALSCALE:GREGORIAN");
+}
+
+fn write_todo(out: &mut String, task: &Task, uids: &HashMap<i32, String>) {
+    push_line(out, "BEGIN:VTODO");
+    write_common(out, task, uids);
+    if let Some(due) = due_value(task) {
+        // Recurring to-dos need a start for the rule to anchor on
+        if task.recurrence.is_some() {
+            push_line(out, &format!("DTSTART{}", due));
+        }
+        push_line(out, &format!("DUE{}", due));
+    }
+    push_line(out, &format!("STATUS:{}", todo_status(task.status)));
+    if let Some(completed_at) = task.completed_at {
+        push_line(out, &format!("COMPLETED:{}", format_utc(completed_at)));
+    }
+    if let Some(progress) = task.progress {
+        push_line(out, &format!("PERCENT-COMPLETE:{}", progress));
+    }
+    push_line(out, "END:VTODO");
+}
+
+fn write_event(out: &mut String, task: &Task, uids: &HashMap<i32, String>) {
+    push_line(out, "BEGIN:VEVENT");
+    write_common(out, task, uids);
+    if let Some(due) = due_value(task) {
+        // A date-time start without an end is an instant; a date start lasts the whole day
+        push_line(out, &format!("DTSTART{}", due));
+    }
+    let status = if task.status == TaskStatus::Cancelled { "CANCELLED" } else { "CONFIRMED" };
+    push_line(out, &format!("STATUS:{}", status));
+    push_line(out, "TRANSP:TRANSPARENT");
+    push_line(out, "END:VEVENT");
+}
+
+// `uids` maps task ids to UIDs for parents that are part of the same calendar
+fn write_common(out: &mut String, task: &Task, uids: &HashMap<i32, String>) {
+    push_line(out, &format!("UID:{}", uid_of(task)));
+    push_line(out, &format!("DTSTAMP:{}", format_utc(task.updated_at)));
+    push_line(out, &format!("CREATED:{}", format_utc(task.created_at)));
+    push_line(out, &format!("LAST-MODIFIED:{}", format_utc(task.updated_at)));
+    push_line(out, &format!("SUMMARY:{}", escape_text(&task.title)));
+    if let Some(description) = task.description.as_deref().filter(|d| !d.is_empty()) {
+        push_line(out, &format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(priority) = task.priority.and_then(priority_to_ical) {
+        push_line(out, &format!("PRIORITY:{}", priority));
+    }
+    if !task.tags.is_empty() {
+        let categories = task.tags.iter().map(|t| escape_text(t)).collect::<Vec<_>>().join(",");
+        push_line(out, &format!("CATEGORIES:{}", categories));
+    }
+    if let Some(parent_id) = task.parent_task {
+        let parent_uid = uids.get(&parent_id).cloned().unwrap_or_else(|| task_uid(parent_id));
+        push_line(out, &format!("RELATED-TO;RELTYPE=PARENT:{}", parent_uid));
+    }
+    if let Some(rule) = recurrence_rule(task) {
+        push_line(out, &format!("RRULE:{}", rule));
+    }
+}
+
+// `;VALUE=DATE:20240501` for date-only due dates, `:20240501T093000Z` otherwise
+fn due_value(task: &Task) -> Option<String> {
+    let due = task.due_date?;
+    Some(match all_day_date(task) {
+        Some(day) => format!(";VALUE=DATE:{}", format_date(day)),
+        None => format!(":{}", format_utc(due)),
+    })
+}
+
+fn all_day_date(task: &Task) -> Option<NaiveDate> {
+    let due = task.due_date?;
+    task.due_all_day.then(|| local_date(due, &task.due_zone(chrono_tz::UTC)))
+}
+
+// UNTIL takes the value type of the start: a date for date-only tasks, UTC otherwise
+pub fn recurrence_rule(task: &Task) -> Option<String> {
+    let frequency = match task.recurrence? {
+        Recurrence::Daily => "DAILY",
+        Recurrence::Weekly => "WEEKLY",
+        Recurrence::Monthly => "MONTHLY",
+        Recurrence::Yearly => "YEARLY",
+    };
+    let mut rule = format!("FREQ={}", frequency);
+    if let Some(end) = task.recurrence_end {
+        let until = match all_day_date(task) {
+            Some(_) => format_date(local_date(end, &task.due_zone(chrono_tz::UTC))),
+            None => format_utc(end),
+        };
+        rule.push_str(&format!(";UNTIL={}", until));
+    }
+    Some(rule)
+}
+
+// Task priorities run from 1 (low) upwards, iCalendar from 9 (low) to 1 (high)
+pub fn priority_to_ical(priority: i32) -> Option<u8> {
+    match priority {
+        p if p >= 4 => Some(1),
+        3 => Some(3),
+        2 => Some(5),
+        1 => Some(9),
+        _ => None,
+    }
+}
+
+fn todo_status(status: TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Completed => "COMPLETED",
+        TaskStatus::Cancelled => "CANCELLED",
+        TaskStatus::InProgress | TaskStatus::InReview => "IN-PROCESS",
+        TaskStatus::Backlog | TaskStatus::Pending | TaskStatus::Blocked => "NEEDS-ACTION",
+    }
+}
+
+pub fn format_utc(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+// Reverses `format_utc`
+pub fn parse_utc(value: &str) -> Option<i64> {
+    let local = value.strip_suffix('Z')?;
+    NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|at| at.and_utc().timestamp_millis())
+}
+
+fn format_date(day: NaiveDate) -> String {
+    day.format("%Y%m%d").to_string()
+}
+
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Folds the line at 75 octets without splitting a character, then ends it with CRLF
+fn push_line(out: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        let width = c.len_utf8();
+        if octets + width > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            octets = 1;
+        }
+        out.push(c);
+        octets += width;
+    }
+    out.push_str("\r\n");
+}
+
+// Task priorities from iCalendar's 1 (high) to 9 (low); 0 means undefined
+pub fn priority_from_ical(priority: u8) -> Option<i32> {
+    match priority {
+        1 | 2 => Some(4),
+        3 | 4 => Some(3),
+        5 => Some(2),
+        6..=9 => Some(1),
+        _ => None,
+    }
+}
+
+// A VTODO read from a calendar file, already mapped onto task values
+#[derive(Debug, Clone, Default)]
+pub struct ImportedTodo {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub due: Option<(DueDate, Tz)>,
+    pub priority: Option<i32>,
+    pub status: Option<TaskStatus>,
+    pub completed_at: Option<i64>,
+    pub recurrence: Option<Recurrence>,
+    pub recurrence_end: Option<i64>,
+    pub categories: Vec<String>,
+    pub parent_uid: Option<String>,
+    // Properties that could only be read in part, or not at all
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CalendarItem {
+    Todo(ImportedTodo),
+    // Any other top-level component; time zone definitions are not listed
+    Other { kind: String, uid: Option<String>, summary: Option<String> },
+}
+
+pub const MAX_IMPORT_ITEMS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportOutcome {
+    Created,
+    Updated,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportItem {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub outcome: ImportOutcome,
+    pub task_id: Option<i32>,
+    // Why the item was skipped
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub items: Vec<ImportItem>,
+}
+
+impl ImportReport {
+    pub fn push(&mut self, item: ImportItem) {
+        match item.outcome {
+            ImportOutcome::Created => self.created += 1,
+            ImportOutcome::Updated => self.updated += 1,
+            ImportOutcome::Skipped => self.skipped += 1,
+        }
+        self.items.push(item);
+    }
+
+    pub fn skip(&mut self, uid: Option<String>, summary: Option<String>, reason: &str) {
+        self.push(ImportItem {
+            uid,
+            summary,
+            outcome: ImportOutcome::Skipped,
+            task_id: None,
+            reason: Some(reason.to_string()),
+            warnings: Vec::new(),
+        });
+    }
+}
+
+struct ContentLine {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl ContentLine {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+// Reads the top-level components of a VCALENDAR. Date-times without a zone, and dates, are
+// placed in `default_zone`.
+pub fn parse_calendar(text: &str, default_zone: Tz) -> Result<Vec<CalendarItem>, String> {
+    let lines = unfold(text);
+    if !lines.first().is_some_and(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err("Not an iCalendar file: it must start with BEGIN:VCALENDAR".to_string());
+    }
+
+    let mut items = Vec::new();
+    let mut open = Vec::new();
+    let mut properties = Vec::new();
+    for line in lines.iter().filter_map(|line| parse_line(line)) {
+        match line.name.as_str() {
+            "BEGIN" => {
+                if open.len() == 1 {
+                    properties.clear();
+                }
+                open.push(line.value.trim().to_ascii_uppercase());
+            }
+            "END" => {
+                let kind = open.pop().ok_or("END without a matching BEGIN")?;
+                if open.len() == 1 {
+                    match kind.as_str() {
+                        "VTODO" => items.push(CalendarItem::Todo(read_todo(&properties, default_zone))),
+                        "VTIMEZONE" => {}
+                        _ => items.push(CalendarItem::Other {
+                            uid: find_value(&properties, "UID").map(str::to_string),
+                            summary: find_value(&properties, "SUMMARY").map(unescape_text),
+                            kind,
+                        }),
+                    }
+                }
+            }
+            // Properties of nested components such as VALARM are not needed
+            _ if open.len() == 2 => properties.push(line),
+            _ => {}
+        }
+    }
+    if !open.is_empty() {
+        return Err("The calendar ends before END:VCALENDAR".to_string());
+    }
+    Ok(items)
+}
+
+// Names and unescaped values of the properties of every VTODO in the calendar
+pub fn todo_properties(text: &str) -> Vec<(String, String)> {
+    let mut depth = Vec::new();
+    let mut properties = Vec::new();
+    for line in unfold(text).iter().filter_map(|line| parse_line(line)) {
+        match line.name.as_str() {
+            "BEGIN" => depth.push(line.value.trim().to_ascii_uppercase()),
+            "END" => {
+                depth.pop();
+            }
+            _ if depth.len() == 2 && depth[1] == "VTODO" => properties.push((line.name, unescape_text(line.value.trim()))),
+            _ => {}
+        }
+    }
+    properties
+}
+
+fn read_todo(properties: &[ContentLine], default_zone: Tz) -> ImportedTodo {
+    let mut todo = ImportedTodo::default();
+    let mut rule = None;
+    for property in properties {
+        let value = property.value.trim();
+        match property.name.as_str() {
+            "UID" => todo.uid = Some(value.to_string()).filter(|uid| !uid.is_empty()),
+            "SUMMARY" => todo.summary = Some(unescape_text(value)).filter(|s| !s.trim().is_empty()),
+            "DESCRIPTION" => todo.description = Some(unescape_text(value)).filter(|d| !d.trim().is_empty()),
+            "DUE" => todo.due = read_time(property, default_zone, &mut todo.warnings),
+            "PRIORITY" => match value.parse::<u8>() {
+                Ok(priority) => todo.priority = priority_from_ical(priority),
+                Err(_) => todo.warnings.push(format!("Ignored PRIORITY '{}'", value)),
+            },
+            "STATUS" => match value.to_ascii_uppercase().as_str() {
+                "NEEDS-ACTION" => todo.status = Some(TaskStatus::Pending),
+                "IN-PROCESS" => todo.status = Some(TaskStatus::InProgress),
+                "COMPLETED" => todo.status = Some(TaskStatus::Completed),
+                "CANCELLED" => todo.status = Some(TaskStatus::Cancelled),
+                other => todo.warnings.push(format!("Ignored STATUS '{}'", other)),
+            },
+            "COMPLETED" => {
+                todo.completed_at = read_time(property, default_zone, &mut todo.warnings).map(|(at, zone)| instant(at, &zone, false))
+            }
+            "RRULE" => rule = Some(value),
+            "CATEGORIES" => todo.categories.extend(
+                split_unescaped(value, ',')
+                    .into_iter()
+                    .map(|c| unescape_text(c.trim()))
+                    .filter(|c| !c.is_empty()),
+            ),
+            "RELATED-TO" => match property.param("RELTYPE").map(str::to_ascii_uppercase).as_deref() {
+                None | Some("PARENT") => todo.parent_uid = Some(value.to_string()).filter(|uid| !uid.is_empty()),
+                Some(other) => todo.warnings.push(format!("Ignored RELATED-TO of type {}", other)),
+            },
+            _ => {}
+        }
+    }
+    if let Some(rule) = rule {
+        read_rule(&mut todo, rule, default_zone);
+    }
+    todo
+}
+
+// Only FREQ and UNTIL have a counterpart on tasks; anything narrowing the rule is reported
+fn read_rule(todo: &mut ImportedTodo, rule: &str, default_zone: Tz) {
+    let zone = todo.due.as_ref().map(|(_, zone)| *zone).unwrap_or(default_zone);
+    let mut unsupported = Vec::new();
+    for part in rule.split(';').filter(|p| !p.is_empty()) {
+        let (key, value) = part.split_once('=').unwrap_or((part, ""));
+        match (key.to_ascii_uppercase().as_str(), value.to_ascii_uppercase().as_str()) {
+            ("FREQ", "DAILY") => todo.recurrence = Some(Recurrence::Daily),
+            ("FREQ", "WEEKLY") => todo.recurrence = Some(Recurrence::Weekly),
+            ("FREQ", "MONTHLY") => todo.recurrence = Some(Recurrence::Monthly),
+            ("FREQ", "YEARLY") => todo.recurrence = Some(Recurrence::Yearly),
+            ("UNTIL", until) => match parse_time(until, None, zone) {
+                Some((at, zone)) => todo.recurrence_end = Some(instant(at, &zone, true)),
+                None => unsupported.push(part),
+            },
+            ("INTERVAL", "1") | ("WKST", _) => {}
+            _ => unsupported.push(part),
+        }
+    }
+    if todo.recurrence.is_none() {
+        todo.recurrence_end = None;
+        todo.warnings.push(format!("Ignored RRULE '{}'", rule));
+    } else if !unsupported.is_empty() {
+        todo.warnings.push(format!("Ignored RRULE parts {}", unsupported.join(";")));
+    }
+}
+
+fn read_time(property: &ContentLine, default_zone: Tz, warnings: &mut Vec<String>) -> Option<(DueDate, Tz)> {
+    let zone = match property.param("TZID") {
+        Some(name) => match name.parse::<Tz>() {
+            Ok(zone) => Some(zone),
+            Err(_) => {
+                warnings.push(format!("Unknown time zone '{}' on {}; read as {}", name, property.name, default_zone.name()));
+                None
+            }
+        },
+        None => None,
+    };
+    let parsed = parse_time(property.value.trim(), zone, default_zone);
+    if parsed.is_none() {
+        warnings.push(format!("Ignored {} '{}'", property.name, property.value.trim()));
+    }
+    parsed
+}
+
+// `20240501`, `20240501T093000Z`, or a local `20240501T093000` read in `zone`
+fn parse_time(value: &str, zone: Option<Tz>, default_zone: Tz) -> Option<(DueDate, Tz)> {
+    if let Ok(day) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Some((DueDate::Date(day), zone.unwrap_or(default_zone)));
+    }
+    if value.ends_with('Z') {
+        let at = DateTime::from_timestamp_millis(parse_utc(value)?)?;
+        return Some((DueDate::DateTime(at.fixed_offset()), default_zone));
+    }
+    let zone = zone.unwrap_or(default_zone);
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((DueDate::DateTime(resolve_local(&zone, local).fixed_offset()), zone))
+}
+
+// A date stands for its start, or for its end when `end_of` is set
+fn instant(at: DueDate, zone: &Tz, end_of: bool) -> i64 {
+    match at {
+        DueDate::Date(day) if end_of => end_of_day(day, zone),
+        DueDate::Date(day) => start_of_day(day, zone),
+        DueDate::DateTime(at) => at.timestamp_millis(),
+    }
+}
+
+fn find_value<'a>(properties: &'a [ContentLine], name: &str) -> Option<&'a str> {
+    properties.iter().find(|p| p.name == name).map(|p| p.value.trim())
+}
+
+// Joins folded lines back together; accepts bare LF line endings as well
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match lines.last_mut() {
+            Some(last) if raw.starts_with([' ', '\t']) => last.push_str(&raw[1..]),
+            _ if raw.trim().is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+// `NAME;PARAM=value;PARAM="quoted:value":property value`
+fn parse_line(line: &str) -> Option<ContentLine> {
+    let mut quoted = false;
+    let split = line.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == ':' && !quoted).then_some(i)
+    })?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+    let mut parts = split_unquoted(head, ';').into_iter();
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|part| {
+            let (key, value) = part.split_once('=')?;
+            Some((key.trim().to_ascii_uppercase(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect();
+    Some(ContentLine { name, params, value: value.to_string() })
+}
+
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            parts.push(&value[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+// Splits on separators that are not escaped with a backslash
+fn split_unescaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == separator {
+            parts.push(&value[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::{America::New_York, Europe::Berlin, UTC};
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap().timestamp_millis()
+    }
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn calendar(lines: &[&str]) -> String {
+        let mut text = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n");
+        for line in lines {
+            text.push_str(line);
+            text.push_str("\r\n");
+        }
+        text.push_str("END:VCALENDAR\r\n");
+        text
+    }
+
+    fn todo(lines: &[&str]) -> ImportedTodo {
+        let mut body = vec!["BEGIN:VTODO"];
+        body.extend_from_slice(lines);
+        body.push("END:VTODO");
+        match parse_calendar(&calendar(&body), UTC).unwrap().pop() {
+            Some(CalendarItem::Todo(todo)) => todo,
+            other => panic!("expected a VTODO, got {:?}", other),
+        }
+    }
+
+    // What importing the exported resource gives back
+    fn reimport(task: &Task, default_zone: Tz) -> ImportedTodo {
+        let uids = HashMap::from([(3, "parent@example.com".to_string())]);
+        let exported = write_resource(task, &uids);
+        let mut items = parse_calendar(&exported, default_zone).unwrap();
+        assert_eq!(items.len(), 1);
+        match items.remove(0) {
+            CalendarItem::Todo(todo) => todo,
+            other => panic!("expected a VTODO, got {:?}", other),
+        }
+    }
+
+    fn sample_task() -> Task {
+        let mut task = Task::new(
+            "Renew passport, visa; and the \\ driving licence before the trip to Zürich".to_string(),
+            Some("Bring:\n- photos\n- old passport".to_string()),
+        );
+        task.id = 7;
+        task.created_at = utc(2024, 5, 1, 8, 0);
+        task.updated_at = utc(2024, 5, 2, 8, 0);
+        task.status = TaskStatus::InProgress;
+        task.priority = Some(3);
+        task.tags = vec!["travel".to_string(), "admin,paperwork".to_string()];
+        task.parent_task = Some(3);
+        task.recurrence = Some(Recurrence::Weekly);
+        task.activity_log.clear();
+        task
+    }
+
+    #[test]
+    fn exported_timed_todo_reimports_to_the_same_task() {
+        let mut task = sample_task();
+        let due = Utc.timestamp_millis_opt(utc(2024, 5, 20, 9, 30)).unwrap().fixed_offset();
+        task.set_due(Some(DueDate::DateTime(due)), UTC);
+        task.recurrence_end = Some(utc(2024, 8, 1, 0, 0));
+
+        let todo = reimport(&task, Berlin);
+        assert_eq!(todo.uid.as_deref(), Some("task-7@taskflow"));
+        assert_eq!(todo.summary.as_deref(), Some(task.title.as_str()));
+        assert_eq!(todo.description, task.description);
+        assert_eq!(todo.priority, task.priority);
+        assert_eq!(todo.status, Some(TaskStatus::InProgress));
+        assert_eq!(todo.categories, task.tags);
+        assert_eq!(todo.parent_uid.as_deref(), Some("parent@example.com"));
+        assert_eq!(todo.recurrence, task.recurrence);
+        assert_eq!(todo.recurrence_end, task.recurrence_end);
+        assert!(todo.warnings.is_empty(), "{:?}", todo.warnings);
+
+        let (imported_due, zone) = todo.due.unwrap();
+        let mut copy = Task::new(String::new(), None);
+        copy.set_due(Some(imported_due), zone);
+        assert_eq!(copy.due_date, task.due_date);
+        assert!(!copy.due_all_day);
+    }
+
+    #[test]
+    fn exported_all_day_todo_reimports_to_the_same_day() {
+        let mut task = sample_task();
+        task.set_due(Some(DueDate::Date(day(2024, 5, 20))), Berlin);
+        task.recurrence_end = Some(end_of_day(day(2024, 6, 30), &Berlin));
+
+        let exported = write_resource(&task, &HashMap::new());
+        assert!(exported.contains("\r\nDUE;VALUE=DATE:20240520\r\n"));
+        assert!(exported.contains("\r\nRRULE:FREQ=WEEKLY;UNTIL=20240630\r\n"));
+
+        let todo = reimport(&task, Berlin);
+        let (imported_due, zone) = todo.due.unwrap();
+        assert!(matches!(imported_due, DueDate::Date(d) if d == day(2024, 5, 20)));
+        let mut copy = Task::new(String::new(), None);
+        copy.set_due(Some(imported_due), zone);
+        assert_eq!(copy.due_date, task.due_date);
+        assert!(copy.due_all_day);
+        assert_eq!(todo.recurrence_end, task.recurrence_end);
+    }
+
+    #[test]
+    fn exported_completion_reimports() {
+        let mut task = sample_task();
+        task.status = TaskStatus::Completed;
+        task.completed_at = Some(utc(2024, 5, 3, 17, 45));
+
+        let todo = reimport(&task, UTC);
+        assert_eq!(todo.status, Some(TaskStatus::Completed));
+        assert_eq!(todo.completed_at, task.completed_at);
+    }
+
+    #[test]
+    fn priorities_survive_the_round_trip() {
+        for priority in 1..=4 {
+            assert_eq!(priority_to_ical(priority).and_then(priority_from_ical), Some(priority));
+        }
+        assert_eq!(priority_to_ical(0), None);
+        assert_eq!(priority_from_ical(0), None);
+    }
+
+    #[test]
+    fn long_lines_are_folded_and_unfolded() {
+        let mut task = sample_task();
+        task.title = "é".repeat(100);
+
+        let exported = write_resource(&task, &HashMap::new());
+        assert!(exported.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(exported.contains("\r\n é"));
+        assert_eq!(reimport(&task, UTC).summary, Some(task.title));
+    }
+
+    #[test]
+    fn unfolds_tabs_and_bare_line_feeds() {
+        let text = "BEGIN:VCALENDAR\nBEGIN:VTODO\nUID:fold\n  ed@example.com\nSUMMARY:Water\n\tthe plants\nEND:VTODO\nEND:VCALENDAR\n";
+        let Some(CalendarItem::Todo(todo)) = parse_calendar(text, UTC).unwrap().pop() else {
+            panic!("expected a VTODO");
+        };
+        assert_eq!(todo.uid.as_deref(), Some("fold ed@example.com"));
+        assert_eq!(todo.summary.as_deref(), Some("Waterthe plants"));
+    }
+
+    #[test]
+    fn unescapes_text_and_categories() {
+        let todo = todo(&[
+            "SUMMARY:Milk\\, eggs\\; bread\\nand a \\\\ backslash",
+            "DESCRIPTION:Line one\\NLine two",
+            "CATEGORIES:shopping,home\\,garden",
+            "CATEGORIES: errands ",
+        ]);
+        assert_eq!(todo.summary.as_deref(), Some("Milk, eggs; bread\nand a \\ backslash"));
+        assert_eq!(todo.description.as_deref(), Some("Line one\nLine two"));
+        assert_eq!(todo.categories, vec!["shopping", "home,garden", "errands"]);
+
+        let text = "a\\b;c,d\ne";
+        assert_eq!(unescape_text(&escape_text(text)), text);
+    }
+
+    #[test]
+    fn reads_due_dates_in_their_zone() {
+        let (due, zone) = todo(&["DUE;TZID=America/New_York:20240520T090000"]).due.unwrap();
+        assert_eq!(zone, New_York);
+        assert!(matches!(due, DueDate::DateTime(at) if at.timestamp_millis() == utc(2024, 5, 20, 13, 0)));
+
+        let (due, zone) = todo(&["DUE;TZID=\"Europe/Berlin\":20240520T090000"]).due.unwrap();
+        assert_eq!(zone, Berlin);
+        assert!(matches!(due, DueDate::DateTime(at) if at.timestamp_millis() == utc(2024, 5, 20, 7, 0)));
+
+        let (due, _) = todo(&["DUE:20240520T090000Z"]).due.unwrap();
+        assert!(matches!(due, DueDate::DateTime(at) if at.timestamp_millis() == utc(2024, 5, 20, 9, 0)));
+
+        let (due, zone) = todo(&["DUE;VALUE=DATE:20240520"]).due.unwrap();
+        assert_eq!(zone, UTC);
+        assert!(matches!(due, DueDate::Date(d) if d == day(2024, 5, 20)));
+    }
+
+    #[test]
+    fn unknown_zones_fall_back_with_a_warning() {
+        let todo = todo(&["DUE;TZID=Mars/Olympus_Mons:20240520T090000"]);
+        let (due, zone) = todo.due.unwrap();
+        assert_eq!(zone, UTC);
+        assert!(matches!(due, DueDate::DateTime(at) if at.timestamp_millis() == utc(2024, 5, 20, 9, 0)));
+        assert_eq!(todo.warnings, vec!["Unknown time zone 'Mars/Olympus_Mons' on DUE; read as UTC"]);
+    }
+
+    #[test]
+    fn reads_recurrence_rules() {
+        let monthly = todo(&["RRULE:FREQ=MONTHLY;INTERVAL=1;WKST=MO"]);
+        assert_eq!(monthly.recurrence, Some(Recurrence::Monthly));
+        assert!(monthly.warnings.is_empty());
+
+        let until = todo(&["DUE;TZID=America/New_York:20240520T090000", "RRULE:FREQ=DAILY;UNTIL=20240601"]);
+        assert_eq!(until.recurrence, Some(Recurrence::Daily));
+        assert_eq!(until.recurrence_end, Some(end_of_day(day(2024, 6, 1), &New_York)));
+
+        let narrowed = todo(&["RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5"]);
+        assert_eq!(narrowed.recurrence, Some(Recurrence::Weekly));
+        assert_eq!(narrowed.warnings, vec!["Ignored RRULE parts BYDAY=MO,WE;COUNT=5"]);
+
+        let hourly = todo(&["RRULE:FREQ=HOURLY;UNTIL=20240601T000000Z"]);
+        assert_eq!(hourly.recurrence, None);
+        assert_eq!(hourly.recurrence_end, None);
+        assert_eq!(hourly.warnings, vec!["Ignored RRULE 'FREQ=HOURLY;UNTIL=20240601T000000Z'"]);
+    }
+
+    #[test]
+    fn lists_other_components_and_skips_nested_ones() {
+        let items = parse_calendar(
+            &calendar(&[
+                "BEGIN:VTIMEZONE",
+                "TZID:Europe/Berlin",
+                "END:VTIMEZONE",
+                "BEGIN:VEVENT",
+                "UID:event@example.com",
+                "SUMMARY:Team lunch",
+                "END:VEVENT",
+                "BEGIN:VTODO",
+                "SUMMARY:Book a table",
+                "BEGIN:VALARM",
+                "DESCRIPTION:Reminder",
+                "END:VALARM",
+                "END:VTODO",
+            ]),
+            UTC,
+        )
+        .unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert!(matches!(&items[0], CalendarItem::Other { kind, uid, summary }
+            if kind == "VEVENT" && uid.as_deref() == Some("event@example.com") && summary.as_deref() == Some("Team lunch")));
+        assert!(matches!(&items[1], CalendarItem::Todo(todo) if todo.description.is_none() && todo.uid.is_none()));
+    }
+
+    #[test]
+    fn rejects_malformed_calendars() {
+        assert!(parse_calendar("", UTC).is_err());
+        assert!(parse_calendar("BEGIN:VTODO\r\nEND:VTODO\r\n", UTC).is_err());
+        assert_eq!(
+            parse_calendar("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:Cut off\r\n", UTC).unwrap_err(),
+            "The calendar ends before END:VCALENDAR"
+        );
+        assert_eq!(
+            parse_calendar("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\nEND:VCALENDAR\r\n", UTC).unwrap_err(),
+            "END without a matching BEGIN"
+        );
+    }
+
+    #[test]
+    fn reports_values_it_cannot_read() {
+        let todo = todo(&[
+            "this line has no colon",
+            "DUE:next tuesday",
+            "PRIORITY:high",
+            "STATUS:SNOOZED",
+            "RELATED-TO;RELTYPE=SIBLING:other@example.com",
+            "SUMMARY:   ",
+        ]);
+        assert!(todo.due.is_none());
+        assert!(todo.summary.is_none());
+        assert!(todo.parent_uid.is_none());
+        assert_eq!(
+            todo.warnings,
+            vec![
+                "Ignored DUE 'next tuesday'",
+                "Ignored PRIORITY 'high'",
+                "Ignored STATUS 'SNOOZED'",
+                "Ignored RELATED-TO of type SIBLING",
+            ]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::rfc3339;
+
+pub const MAX_LABEL_LENGTH: usize = 50;
+
+// Tags stored on tasks are label names; the label carries the presentation details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub id: i32,
+    pub name: String,
+    // `#rrggbb`
+    pub color: Option<String>,
+    pub description: Option<String>,
+    // Tasks outside the trash carrying the label
+    #[serde(default, skip_deserializing)]
+    pub usage_count: i64,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+// `Backend`, ` backend ` and `BACKEND` are all stored as `backend`; inner whitespace becomes
+// a single dash. Returns None when nothing is left.
+pub fn normalize_label(name: &str) -> Option<String> {
+    let normalized = name.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase();
+    (!normalized.is_empty()).then_some(normalized)
+}
+
+// Normalizes every tag and drops empty ones and duplicates, keeping the first occurrence
+pub fn normalize_labels(names: &[String]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::with_capacity(names.len());
+    for name in names.iter().filter_map(|n| normalize_label(n)) {
+        if !labels.contains(&name) {
+            labels.push(name);
+        }
+    }
+    labels
+}
+
+// Accepts `#rgb` and `#rrggbb` in any case and returns the long lowercase form
+pub fn normalize_color(color: &str) -> Option<String> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 => Some(format!("#{}", hex.chars().flat_map(|c| [c, c]).collect::<String>().to_lowercase())),
+        6 => Some(format!("#{}", hex.to_lowercase())),
+        _ => None,
+    }
+}
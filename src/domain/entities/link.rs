@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::task::TaskStatus;
+use crate::domain::time::rfc3339;
+
+// Stored direction of a link, read as "source <type> target"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkType {
+    Blocks,
+    // Symmetric; stored with the lower task id as the source
+    RelatesTo,
+    Duplicates,
+    Clones,
+}
+
+// A link as seen from one of its ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkRelation {
+    Blocks,
+    BlockedBy,
+    RelatesTo,
+    Duplicates,
+    DuplicatedBy,
+    Clones,
+    ClonedFrom,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskLink {
+    pub id: i64,
+    pub source_id: i32,
+    pub target_id: i32,
+    pub link_type: LinkType,
+    pub created_by: Option<i32>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+}
+
+// A link listed on a task, with enough of the other end to display it
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkedTask {
+    pub link_id: i64,
+    pub relation: LinkRelation,
+    pub task_id: i32,
+    pub reference: Option<String>,
+    pub title: String,
+    pub status: TaskStatus,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+}
+
+impl LinkRelation {
+    pub fn inverse(self) -> Self {
+        match self {
+            LinkRelation::Blocks => LinkRelation::BlockedBy,
+            LinkRelation::BlockedBy => LinkRelation::Blocks,
+            LinkRelation::RelatesTo => LinkRelation::RelatesTo,
+            LinkRelation::Duplicates => LinkRelation::DuplicatedBy,
+            LinkRelation::DuplicatedBy => LinkRelation::Duplicates,
+            LinkRelation::Clones => LinkRelation::ClonedFrom,
+            LinkRelation::ClonedFrom => LinkRelation::Clones,
+        }
+    }
+
+    // The stored type, and whether the task this relation is read from is its source
+    pub fn stored(self) -> (LinkType, bool) {
+        match self {
+            LinkRelation::Blocks => (LinkType::Blocks, true),
+            LinkRelation::BlockedBy => (LinkType::Blocks, false),
+            LinkRelation::RelatesTo => (LinkType::RelatesTo, true),
+            LinkRelation::Duplicates => (LinkType::Duplicates, true),
+            LinkRelation::DuplicatedBy => (LinkType::Duplicates, false),
+            LinkRelation::Clones => (LinkType::Clones, true),
+            LinkRelation::ClonedFrom => (LinkType::Clones, false),
+        }
+    }
+}
+
+impl TaskLink {
+    // Builds the link `task_id <relation> other_id`
+    pub fn new(task_id: i32, relation: LinkRelation, other_id: i32, created_by: Option<i32>, created_at: i64) -> Self {
+        let (link_type, is_source) = relation.stored();
+        let (source_id, target_id) = match link_type {
+            LinkType::RelatesTo => (task_id.min(other_id), task_id.max(other_id)),
+            _ if is_source => (task_id, other_id),
+            _ => (other_id, task_id),
+        };
+        TaskLink { id: 0, source_id, target_id, link_type, created_by, created_at }
+    }
+
+    pub fn other_end(&self, task_id: i32) -> i32 {
+        if self.source_id == task_id { self.target_id } else { self.source_id }
+    }
+
+    pub fn relation_for(&self, task_id: i32) -> LinkRelation {
+        let outgoing = self.source_id == task_id;
+        match self.link_type {
+            LinkType::Blocks if outgoing => LinkRelation::Blocks,
+            LinkType::Blocks => LinkRelation::BlockedBy,
+            LinkType::RelatesTo => LinkRelation::RelatesTo,
+            LinkType::Duplicates if outgoing => LinkRelation::Duplicates,
+            LinkType::Duplicates => LinkRelation::DuplicatedBy,
+            LinkType::Clones if outgoing => LinkRelation::Clones,
+            LinkType::Clones => LinkRelation::ClonedFrom,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::{rfc3339, rfc3339_option};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default, with = "rfc3339_option")]
+    pub start_date: Option<i64>,
+    #[serde(with = "rfc3339")]
+    pub due_date: i64,
+    pub task_ids: Vec<i32>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+// Cancelled tasks are left out of the percentage
+#[derive(Debug, Clone, Serialize)]
+pub struct MilestoneProgress {
+    pub milestone_id: i32,
+    pub total: usize,
+    pub completed: usize,
+    pub cancelled: usize,
+    pub open: usize,
+    pub percent: u8,
+    pub overdue: bool,
+}
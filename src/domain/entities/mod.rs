@@ -1,2 +1,25 @@
+pub mod activity;
+pub mod attachment;
+pub mod board;
+pub mod bulk;
+pub mod caldav;
+pub mod calendar_feed;
+pub mod clone;
+pub mod comment;
+pub mod custom_field;
+pub mod history;
+pub mod ical;
+pub mod label;
+pub mod link;
+pub mod milestone;
+pub mod notification;
+pub mod project;
+pub mod quick_add;
+pub mod reminder;
+pub mod sprint;
 pub mod task;
-pub mod user;
\ No newline at end of file
+pub mod template;
+pub mod time_tracking;
+pub mod trash;
+pub mod user;
+pub mod workflow;
\ No newline at end of file
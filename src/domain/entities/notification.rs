@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::domain::time::{rfc3339, rfc3339_option};
+
+// Users and tasks referenced in free text: `@alice`, `#42` or `#WEB-42`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Mentions {
+    pub usernames: Vec<String>,
+    pub task_ids: Vec<i32>,
+    pub task_references: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationKind {
+    Mentioned,
+    Commented,
+    StatusChanged,
+    Reassigned,
+    Reminder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: i64,
+    pub user_id: i32,
+    pub task_id: i32,
+    pub kind: NotificationKind,
+    pub actor_id: Option<i32>,
+    pub payload: Option<Value>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(default, with = "rfc3339_option")]
+    pub read_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watcher {
+    pub task_id: i32,
+    pub user_id: i32,
+    #[serde(with = "rfc3339")]
+    pub added_at: i64,
+}
+
+impl Mentions {
+    pub fn is_empty(&self) -> bool {
+        self.usernames.is_empty() && self.task_ids.is_empty() && self.task_references.is_empty()
+    }
+}
+
+// A marker only counts at the start of the text or after whitespace or punctuation, so
+// e-mail addresses and URL fragments are not taken for mentions
+pub fn parse_mentions(text: &str) -> Mentions {
+    let mut mentions = Mentions::default();
+    let chars = text.char_indices().collect::<Vec<_>>();
+    for (i, &(start, marker)) in chars.iter().enumerate() {
+        if marker != '@' && marker != '#' {
+            continue;
+        }
+        if i > 0 && is_word_char(chars[i - 1].1) {
+            continue;
+        }
+        let body = &text[start + 1..];
+        let end = body.find(|c: char| !is_word_char(c) && c != '-').unwrap_or(body.len());
+        let word = body[..end].trim_end_matches(['.', '-']);
+        if word.is_empty() {
+            continue;
+        }
+
+        if marker == '@' {
+            if !mentions.usernames.iter().any(|u| u.eq_ignore_ascii_case(word)) {
+                mentions.usernames.push(word.to_string());
+            }
+        } else if let Ok(id) = word.parse::<i32>() {
+            if !mentions.task_ids.contains(&id) {
+                mentions.task_ids.push(id);
+            }
+        } else if word.contains('-') && !mentions.task_references.iter().any(|r| r.eq_ignore_ascii_case(word)) {
+            mentions.task_references.push(word.to_string());
+        }
+    }
+    mentions
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
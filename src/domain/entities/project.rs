@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::{rfc3339, rfc3339_option};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProjectRole {
+    Admin,
+    Member,
+    Viewer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMember {
+    pub user_id: i32,
+    pub role: ProjectRole,
+    #[serde(with = "rfc3339")]
+    pub added_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: i32,
+    // Short uppercase prefix of task references, e.g. WEB in WEB-42; fixed once created
+    pub key: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub owner_id: i32,
+    pub members: Vec<ProjectMember>,
+    #[serde(default, with = "rfc3339_option")]
+    pub archived_at: Option<i64>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+impl Project {
+    pub fn new(key: String, name: String, description: Option<String>, owner_id: i32) -> Self {
+        let now = chrono::Utc::now().timestamp_millis();
+        Project {
+            id: 0,
+            key,
+            name,
+            description,
+            owner_id,
+            members: vec![ProjectMember { user_id: owner_id, role: ProjectRole::Admin, added_at: now }],
+            archived_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn member(&self, user_id: i32) -> Option<&ProjectMember> {
+        self.members.iter().find(|m| m.user_id == user_id)
+    }
+
+    pub fn is_member(&self, user_id: i32) -> bool {
+        user_id == self.owner_id || self.member(user_id).is_some()
+    }
+
+    // The owner and admins manage the project and its members
+    pub fn can_manage(&self, user_id: i32) -> bool {
+        user_id == self.owner_id || self.member(user_id).is_some_and(|m| m.role == ProjectRole::Admin)
+    }
+
+    // Viewers can read but not change tasks
+    pub fn can_edit_tasks(&self, user_id: i32) -> bool {
+        user_id == self.owner_id || self.member(user_id).is_some_and(|m| m.role != ProjectRole::Viewer)
+    }
+
+    pub fn add_member(&mut self, user_id: i32, role: ProjectRole) {
+        let now = chrono::Utc::now().timestamp_millis();
+        match self.members.iter_mut().find(|m| m.user_id == user_id) {
+            Some(member) => member.role = role,
+            None => self.members.push(ProjectMember { user_id, role, added_at: now }),
+        }
+        self.updated_at = now;
+    }
+
+    pub fn remove_member(&mut self, user_id: i32) {
+        self.members.retain(|m| m.user_id != user_id);
+        self.update_timestamp();
+    }
+
+    pub fn archive(&mut self) {
+        self.archived_at = Some(chrono::Utc::now().timestamp_millis());
+        self.update_timestamp();
+    }
+
+    pub fn unarchive(&mut self) {
+        self.archived_at = None;
+        self.update_timestamp();
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    // Human-readable task reference, e.g. WEB-42
+    pub fn task_reference(&self, number: i32) -> String {
+        format!("{}-{}", self.key, number)
+    }
+
+    fn update_timestamp(&mut self) {
+        self.updated_at = chrono::Utc::now().timestamp_millis();
+    }
+}
+
+// Splits a reference such as WEB-42 into its project key and task number
+pub fn parse_task_reference(reference: &str) -> Option<(String, i32)> {
+    let (key, number) = reference.rsplit_once('-')?;
+    let number = number.parse::<i32>().ok().filter(|n| *n > 0)?;
+    (!key.is_empty()).then(|| (key.to_ascii_uppercase(), number))
+}
@@ -0,0 +1,485 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::domain::entities::label::normalize_label;
+use crate::domain::entities::task::Recurrence;
+use crate::domain::time::{resolve_local, rfc3339_option};
+
+// Due time used for "tonight" when no explicit time is given
+const EVENING_HOUR: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum QuickAddField {
+    DueDate,
+    DueTime,
+    Tag,
+    Priority,
+    Assignee,
+    Recurrence,
+}
+
+// A fragment of the input and the field it was read into
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickAddMatch {
+    pub text: String,
+    pub field: QuickAddField,
+}
+
+// What a line of quick-add text means; everything not understood stays in the title
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuickAdd {
+    pub title: String,
+    #[serde(with = "rfc3339_option")]
+    pub due_date: Option<i64>,
+    // False when only a day was given; the task is then due at the end of that day
+    pub due_has_time: bool,
+    pub tags: Vec<String>,
+    pub priority: Option<i32>,
+    pub assignee: Option<String>,
+    pub recurrence: Option<Recurrence>,
+    pub matches: Vec<QuickAddMatch>,
+}
+
+// `Fix login bug tomorrow 5pm #backend !high @alice every week`. Relative dates are read in
+// the time zone of `now`; the first date, time, priority, assignee and recurrence win.
+pub fn parse_quick_add(text: &str, now: DateTime<Tz>) -> QuickAdd {
+    let tokens = text.split_whitespace().collect::<Vec<_>>();
+    let words = tokens
+        .iter()
+        .map(|t| t.trim_end_matches([',', ';']).to_lowercase())
+        .collect::<Vec<_>>();
+    let today = now.date_naive();
+
+    let mut parsed = QuickAdd::default();
+    let mut title = Vec::new();
+    let mut date: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut evening = false;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let rest = &words[i..];
+        let matched = match_symbol(&rest[0])
+            .map(|m| (1, m))
+            .or_else(|| match_recurrence(rest, today))
+            .or_else(|| with_preposition(rest, |r| match_date(r, now)))
+            .or_else(|| with_preposition(rest, match_time));
+
+        let Some((consumed, found)) = matched else {
+            title.push(tokens[i]);
+            i += 1;
+            continue;
+        };
+        let fragment = tokens[i..i + consumed].join(" ");
+        let field = match found {
+            Found::Tag(tag) => {
+                if !parsed.tags.contains(&tag) {
+                    parsed.tags.push(tag);
+                }
+                Some(QuickAddField::Tag)
+            }
+            Found::Priority(priority) if parsed.priority.is_none() => {
+                parsed.priority = Some(priority);
+                Some(QuickAddField::Priority)
+            }
+            Found::Assignee(username) if parsed.assignee.is_none() => {
+                parsed.assignee = Some(username);
+                Some(QuickAddField::Assignee)
+            }
+            Found::Recurrence(recurrence, day) if parsed.recurrence.is_none() => {
+                parsed.recurrence = Some(recurrence);
+                if date.is_none() {
+                    date = day;
+                }
+                Some(QuickAddField::Recurrence)
+            }
+            Found::Date(day, is_evening) if date.is_none() => {
+                date = Some(day);
+                evening = is_evening;
+                Some(QuickAddField::DueDate)
+            }
+            Found::DateTime(at) if date.is_none() && time.is_none() => {
+                date = Some(at.date());
+                time = Some(at.time());
+                Some(QuickAddField::DueDate)
+            }
+            Found::Time(at) if time.is_none() => {
+                time = Some(at);
+                Some(QuickAddField::DueTime)
+            }
+            _ => None,
+        };
+        match field {
+            Some(field) => parsed.matches.push(QuickAddMatch { text: fragment, field }),
+            None => title.extend(&tokens[i..i + consumed]),
+        }
+        i += consumed;
+    }
+
+    if evening && time.is_none() {
+        time = NaiveTime::from_hms_opt(EVENING_HOUR, 0, 0);
+    }
+    let due = match (date, time) {
+        // A bare time means its next occurrence
+        (None, Some(at)) if at > now.time() => Some(today.and_time(at)),
+        (None, Some(at)) => Some((today + Duration::days(1)).and_time(at)),
+        (Some(day), Some(at)) => Some(day.and_time(at)),
+        (Some(day), None) => day.and_hms_milli_opt(23, 59, 59, 999),
+        (None, None) => None,
+    };
+    parsed.due_date = due.map(|local| resolve_local(&now.timezone(), local).timestamp_millis());
+    parsed.due_has_time = time.is_some();
+    parsed.title = title.join(" ");
+    parsed
+}
+
+enum Found {
+    Tag(String),
+    Priority(i32),
+    Assignee(String),
+    Recurrence(Recurrence, Option<NaiveDate>),
+    Date(NaiveDate, bool),
+    DateTime(NaiveDateTime),
+    Time(NaiveTime),
+}
+
+fn match_symbol(word: &str) -> Option<Found> {
+    let mut chars = word.chars();
+    let marker = chars.next()?;
+    let body = chars.as_str();
+    match marker {
+        // `#42` is a task reference, not a tag
+        '#' if !body.chars().all(|c| c.is_ascii_digit()) => normalize_label(body).map(Found::Tag),
+        '!' => parse_priority(body).map(Found::Priority),
+        '@' if !body.is_empty() && body.chars().all(|c| c.is_alphanumeric() || "_.-".contains(c)) => {
+            Some(Found::Assignee(body.to_string()))
+        }
+        _ => None,
+    }
+}
+
+fn parse_priority(word: &str) -> Option<i32> {
+    match word {
+        "low" => Some(1),
+        "medium" | "med" | "normal" => Some(2),
+        "high" => Some(3),
+        "urgent" | "critical" | "highest" => Some(4),
+        digits => digits.parse().ok().filter(|p| (1..=10).contains(p)),
+    }
+}
+
+// `on friday`, `due tomorrow`, `at 5pm`: a leading preposition only goes when what follows matches
+fn with_preposition<F>(words: &[String], matcher: F) -> Option<(usize, Found)>
+where
+    F: Fn(&[String]) -> Option<(usize, Found)>,
+{
+    if let Some(found) = matcher(words) {
+        return Some(found);
+    }
+    match words.first().map(String::as_str) {
+        Some("on" | "at" | "by" | "due" | "@") if words.len() > 1 => matcher(&words[1..]).map(|(n, found)| (n + 1, found)),
+        _ => None,
+    }
+}
+
+fn match_recurrence(words: &[String], today: NaiveDate) -> Option<(usize, Found)> {
+    let single = match words[0].as_str() {
+        "daily" => Some(Recurrence::Daily),
+        "weekly" => Some(Recurrence::Weekly),
+        "monthly" => Some(Recurrence::Monthly),
+        "yearly" | "annually" => Some(Recurrence::Yearly),
+        _ => None,
+    };
+    if let Some(recurrence) = single {
+        return Some((1, Found::Recurrence(recurrence, None)));
+    }
+    if words[0] != "every" || words.len() < 2 {
+        return None;
+    }
+    let found = match words[1].as_str() {
+        "day" => Found::Recurrence(Recurrence::Daily, None),
+        "week" => Found::Recurrence(Recurrence::Weekly, None),
+        "month" => Found::Recurrence(Recurrence::Monthly, None),
+        "year" => Found::Recurrence(Recurrence::Yearly, None),
+        other => {
+            let weekday = parse_weekday(other)?;
+            Found::Recurrence(Recurrence::Weekly, Some(upcoming(today, weekday, false)))
+        }
+    };
+    Some((2, found))
+}
+
+fn match_date(words: &[String], now: DateTime<Tz>) -> Option<(usize, Found)> {
+    let today = now.date_naive();
+    match words[0].as_str() {
+        "today" => return Some((1, Found::Date(today, false))),
+        "tonight" => return Some((1, Found::Date(today, true))),
+        "tomorrow" | "tmr" | "tmrw" => return Some((1, Found::Date(today + Duration::days(1), false))),
+        "next" if words.len() > 1 => {
+            let day = match words[1].as_str() {
+                "week" => upcoming(today, Weekday::Mon, true),
+                "month" => today.with_day(1)?.checked_add_months(Months::new(1))?,
+                other => upcoming(today, parse_weekday(other)?, true),
+            };
+            return Some((2, Found::Date(day, false)));
+        }
+        "in" if words.len() > 2 => {
+            let amount = words[1].parse::<u32>().ok()?;
+            let found = match words[2].trim_end_matches('s') {
+                "min" | "minute" => Found::DateTime(now.naive_local() + Duration::minutes(amount.into())),
+                "hour" => Found::DateTime(now.naive_local() + Duration::hours(amount.into())),
+                "day" => Found::Date(today + Duration::days(amount.into()), false),
+                "week" => Found::Date(today + Duration::weeks(amount.into()), false),
+                "month" => Found::Date(today.checked_add_months(Months::new(amount))?, false),
+                _ => return None,
+            };
+            return Some((3, found));
+        }
+        _ => {}
+    }
+    if let Some(weekday) = parse_weekday(&words[0]) {
+        return Some((1, Found::Date(upcoming(today, weekday, false), false)));
+    }
+    if let Ok(day) = NaiveDate::parse_from_str(&words[0], "%Y-%m-%d") {
+        return Some((1, Found::Date(day, false)));
+    }
+    match_month_day(words, today)
+}
+
+// `mar 15`, `march 15th 2025`, `15 march`; without a year the next such day is meant
+fn match_month_day(words: &[String], today: NaiveDate) -> Option<(usize, Found)> {
+    let second = words.get(1)?;
+    let (month, day) = match (parse_month(&words[0]), parse_day(&words[0])) {
+        (Some(month), _) => (month, parse_day(second)?),
+        (None, Some(day)) => (parse_month(second)?, day),
+        _ => return None,
+    };
+    if let Some(year) = words.get(2).and_then(|w| w.parse::<i32>().ok()).filter(|y| (1970..=9999).contains(y)) {
+        return NaiveDate::from_ymd_opt(year, month, day).map(|date| (3, Found::Date(date, false)));
+    }
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+    let date = match this_year {
+        Some(date) if date >= today => date,
+        _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day)?,
+    };
+    Some((2, Found::Date(date, false)))
+}
+
+fn match_time(words: &[String]) -> Option<(usize, Found)> {
+    match words[0].as_str() {
+        "noon" => return Some((1, Found::Time(NaiveTime::from_hms_opt(12, 0, 0)?))),
+        "midnight" => return Some((1, Found::Time(NaiveTime::from_hms_opt(23, 59, 0)?))),
+        _ => {}
+    }
+    // `5 pm`, `5:30 pm`
+    if let Some(suffix) = words.get(1).filter(|w| *w == "am" || *w == "pm") {
+        if let Some(time) = parse_clock(&format!("{}{}", words[0], suffix)) {
+            return Some((2, Found::Time(time)));
+        }
+    }
+    parse_clock(&words[0]).map(|time| (1, Found::Time(time)))
+}
+
+// `5pm`, `5:30am`, `17:00`; a bare number is not a time
+fn parse_clock(word: &str) -> Option<NaiveTime> {
+    let (digits, meridiem) = match word.strip_suffix("am").or_else(|| word.strip_suffix("a.m.")) {
+        Some(digits) => (digits, Some(false)),
+        None => match word.strip_suffix("pm").or_else(|| word.strip_suffix("p.m.")) {
+            Some(digits) => (digits, Some(true)),
+            None => (word, None),
+        },
+    };
+    let (hour, minute) = match digits.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        Some(_) => return None,
+        None if meridiem.is_some() => (digits.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january", "february", "march", "april", "may", "june",
+        "july", "august", "september", "october", "november", "december",
+    ];
+    MONTHS
+        .iter()
+        .position(|month| *month == word || (word.len() >= 3 && month.starts_with(word)))
+        .map(|index| index as u32 + 1)
+}
+
+// `15`, `15th`, `1st`
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+// The next `weekday` from `today`, which counts unless `skip_today` is set. With
+// `skip_today` and a Monday, `next week` means the Monday after.
+fn upcoming(today: NaiveDate, weekday: Weekday, skip_today: bool) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 && skip_today { 7 } else { ahead };
+    today + Duration::days(ahead.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Berlin;
+
+    // Wednesday, 15 May 2024, 10:00 in Berlin
+    fn now() -> DateTime<Tz> {
+        Berlin.with_ymd_and_hms(2024, 5, 15, 10, 0, 0).unwrap()
+    }
+
+    fn parse(text: &str) -> QuickAdd {
+        parse_quick_add(text, now())
+    }
+
+    fn berlin(date: &str, time: &str) -> Option<i64> {
+        let local = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S%.3f").unwrap();
+        Some(Berlin.from_local_datetime(&local).unwrap().timestamp_millis())
+    }
+
+    fn fields(parsed: &QuickAdd) -> Vec<QuickAddField> {
+        parsed.matches.iter().map(|m| m.field).collect()
+    }
+
+    #[test]
+    fn reads_every_kind_of_token() {
+        let parsed = parse("Fix login bug tomorrow 5pm #backend !high @alice every week");
+
+        assert_eq!(parsed.title, "Fix login bug");
+        assert_eq!(parsed.due_date, berlin("2024-05-16", "17:00:00.000"));
+        assert!(parsed.due_has_time);
+        assert_eq!(parsed.tags, vec!["backend"]);
+        assert_eq!(parsed.priority, Some(3));
+        assert_eq!(parsed.assignee.as_deref(), Some("alice"));
+        assert_eq!(parsed.recurrence, Some(Recurrence::Weekly));
+        assert_eq!(
+            fields(&parsed),
+            vec![
+                QuickAddField::DueDate,
+                QuickAddField::DueTime,
+                QuickAddField::Tag,
+                QuickAddField::Priority,
+                QuickAddField::Assignee,
+                QuickAddField::Recurrence,
+            ]
+        );
+    }
+
+    #[test]
+    fn a_day_without_time_is_due_at_its_end() {
+        let parsed = parse("Pay rent on friday");
+
+        assert_eq!(parsed.title, "Pay rent");
+        assert_eq!(parsed.due_date, berlin("2024-05-17", "23:59:59.999"));
+        assert!(!parsed.due_has_time);
+        assert_eq!(parsed.matches[0].text, "on friday");
+    }
+
+    #[test]
+    fn reads_relative_and_absolute_dates() {
+        let cases = [
+            ("today", "2024-05-15"),
+            ("wednesday", "2024-05-15"),
+            ("next wednesday", "2024-05-22"),
+            ("next week", "2024-05-20"),
+            ("next month", "2024-06-01"),
+            ("in 3 days", "2024-05-18"),
+            ("in 2 weeks", "2024-05-29"),
+            ("2024-07-01", "2024-07-01"),
+            ("15 june", "2024-06-15"),
+            ("march 3rd", "2025-03-03"),
+            ("dec 24 2026", "2026-12-24"),
+        ];
+        for (text, date) in cases {
+            let parsed = parse(&format!("Task {}", text));
+            assert_eq!(parsed.title, "Task", "{}", text);
+            assert_eq!(parsed.due_date, berlin(date, "23:59:59.999"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn reads_times() {
+        let cases = [
+            ("at noon", "2024-05-15", "12:00:00.000"),
+            ("5:30 pm", "2024-05-15", "17:30:00.000"),
+            // Already past today, so tomorrow
+            ("at 9am", "2024-05-16", "09:00:00.000"),
+            ("tonight", "2024-05-15", "20:00:00.000"),
+            ("in 90 minutes", "2024-05-15", "11:30:00.000"),
+            ("tomorrow at 17:45", "2024-05-16", "17:45:00.000"),
+        ];
+        for (text, date, time) in cases {
+            let parsed = parse(&format!("Call back {}", text));
+            assert_eq!(parsed.title, "Call back", "{}", text);
+            assert_eq!(parsed.due_date, berlin(date, time), "{}", text);
+            assert!(parsed.due_has_time, "{}", text);
+        }
+    }
+
+    #[test]
+    fn the_first_priority_and_assignee_win() {
+        let parsed = parse("Review !low @Bob !urgent @carol !5");
+
+        assert_eq!(parsed.priority, Some(1));
+        assert_eq!(parsed.assignee.as_deref(), Some("bob"));
+        assert_eq!(parsed.title, "Review !urgent @carol !5");
+        assert_eq!(parse("Review !7").priority, Some(7));
+    }
+
+    #[test]
+    fn tags_are_normalized_once_and_numbers_are_task_references() {
+        let parsed = parse("Deploy #Backend see #42 #backend #ops");
+
+        assert_eq!(parsed.tags, vec!["backend", "ops"]);
+        assert_eq!(parsed.title, "Deploy see #42");
+    }
+
+    #[test]
+    fn reads_recurrences() {
+        assert_eq!(parse("Water plants daily").recurrence, Some(Recurrence::Daily));
+        assert_eq!(parse("Report every month").recurrence, Some(Recurrence::Monthly));
+        assert_eq!(parse("Taxes yearly").recurrence, Some(Recurrence::Yearly));
+
+        let parsed = parse("Team sync every monday 9am");
+        assert_eq!(parsed.title, "Team sync");
+        assert_eq!(parsed.recurrence, Some(Recurrence::Weekly));
+        assert_eq!(parsed.due_date, berlin("2024-05-20", "09:00:00.000"));
+    }
+
+    #[test]
+    fn leaves_what_it_cannot_read_in_the_title() {
+        for text in ["!99", "!", "@", "13pm", "25:00", "feb 30", "in x days", "next year", "every now", "#42", "at home"] {
+            let parsed = parse(&format!("Odd {}", text));
+            assert_eq!(parsed.title, format!("Odd {}", text));
+            assert!(parsed.matches.is_empty(), "{}", text);
+            assert_eq!(parsed.due_date, None, "{}", text);
+        }
+        assert_eq!(parse("").title, "");
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::rfc3339;
+
+// Minutes before the due date: one day and one hour
+pub const DEFAULT_REMINDER_OFFSETS: [i64; 2] = [24 * 60, 60];
+pub const MAX_REMINDER_OFFSET_MINUTES: i64 = 7 * 24 * 60;
+pub const MAX_REMINDER_OFFSETS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderSettings {
+    pub user_id: i32,
+    pub offsets: Vec<i64>,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+impl ReminderSettings {
+    pub fn default_for(user_id: i32) -> Self {
+        ReminderSettings {
+            user_id,
+            offsets: DEFAULT_REMINDER_OFFSETS.to_vec(),
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReminderKind {
+    Upcoming { minutes_before: i64 },
+    Overdue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderEvent {
+    pub task_id: i32,
+    pub user_id: i32,
+    pub title: String,
+    #[serde(with = "rfc3339")]
+    pub due_date: i64,
+    pub kind: ReminderKind,
+    #[serde(with = "rfc3339")]
+    pub fired_at: i64,
+}
+
+impl ReminderEvent {
+    // Identifies the reminder independently of when it fired; a new due date yields a new key
+    pub fn dedup_key(&self) -> String {
+        let kind = match self.kind {
+            ReminderKind::Upcoming { minutes_before } => format!("before:{}", minutes_before),
+            ReminderKind::Overdue => "overdue".to_string(),
+        };
+        format!("reminder:{}:{}:{}:{}", self.task_id, self.user_id, self.due_date, kind)
+    }
+}
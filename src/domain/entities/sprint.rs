@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::{rfc3339, rfc3339_option};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SprintState {
+    Planned,
+    Active,
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sprint {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    pub goal: Option<String>,
+    #[serde(with = "rfc3339")]
+    pub start_date: i64,
+    #[serde(with = "rfc3339")]
+    pub end_date: i64,
+    pub state: SprintState,
+    pub task_ids: Vec<i32>,
+    #[serde(default, with = "rfc3339_option")]
+    pub started_at: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
+    pub closed_at: Option<i64>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+impl Sprint {
+    pub fn start(&mut self, now: i64) {
+        self.state = SprintState::Active;
+        self.started_at = Some(now);
+        self.updated_at = now;
+    }
+
+    pub fn close(&mut self, now: i64) {
+        self.state = SprintState::Closed;
+        self.closed_at = Some(now);
+        self.updated_at = now;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScopeChangeKind {
+    Added,
+    Removed,
+    // Unfinished work moved in from, or out to, another sprint when one closes
+    CarriedIn,
+    CarriedOut,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopeChange {
+    pub sprint_id: i32,
+    pub task_id: i32,
+    pub kind: ScopeChangeKind,
+    pub actor_id: Option<i32>,
+    #[serde(with = "rfc3339")]
+    pub changed_at: i64,
+    // True when the change happened after the sprint started
+    pub after_start: bool,
+}
+
+// Scope at start versus what was added, removed and finished since
+#[derive(Debug, Clone, Serialize)]
+pub struct SprintScope {
+    pub sprint_id: i32,
+    pub committed: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub current: usize,
+    pub completed: usize,
+    pub changes: Vec<ScopeChange>,
+}
+
+// Outcome of closing a sprint
+#[derive(Debug, Clone, Serialize)]
+pub struct SprintClosure {
+    pub sprint: Sprint,
+    pub completed: Vec<i32>,
+    pub carried_over: Vec<i32>,
+    pub carried_to: Option<i32>,
+}
@@ -1,15 +1,35 @@
-use std::cmp::PartialEq;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, FixedOffset, Months, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+use crate::domain::entities::activity::{ActivityContext, ActivityEvent, ActivityKind, ActivitySubject};
+use crate::domain::entities::comment::Comment;
+use crate::domain::entities::custom_field::{CustomFieldDefinition, CustomFieldTarget};
+use crate::domain::entities::label::normalize_label;
+use crate::domain::entities::link::LinkRelation;
+use crate::domain::entities::time_tracking::WorkLog;
+use crate::domain::errors::AppError;
+use crate::domain::time::{end_of_day, local_date, resolve_local, rfc3339, rfc3339_option, to_rfc3339};
+
+pub const MAX_CHECKLIST_ITEMS: usize = 100;
+pub const MAX_CHECKLIST_TEXT_LENGTH: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TaskStatus {
+    Backlog,
     Pending,
     InProgress,
+    Blocked,
+    InReview,
     Completed,
+    Cancelled,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Recurrence {
     Daily,
     Weekly,
@@ -29,58 +49,171 @@ pub struct Collaborator {
     pub role: Role,
 }
 
+// A lightweight step inside a task; ids are unique within the task only
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Comment {
-    pub user_id: i32,
-    pub comment: String,
-    pub timestamp: i64,
+pub struct ChecklistItem {
+    pub id: i32,
+    pub text: String,
+    pub checked: bool,
+    pub checked_by: Option<i32>,
+    #[serde(default, with = "rfc3339_option")]
+    pub checked_at: Option<i64>,
+}
+
+// A due date as clients send it: a calendar day (`2024-05-01`) or an RFC 3339 date-time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueDate {
+    Date(NaiveDate),
+    DateTime(DateTime<FixedOffset>),
+}
+
+impl FromStr for DueDate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(day) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(DueDate::Date(day));
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(DueDate::DateTime)
+            .map_err(|_| format!("'{}' is neither a date (YYYY-MM-DD) nor an RFC 3339 date-time", s))
+    }
+}
+
+impl<'de> Deserialize<'de> for DueDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: i32,
+    pub project_id: Option<i32>,
+    // Per-project sequence number; together with the project key it forms the reference, e.g. WEB-42
+    pub number: Option<i32>,
+    pub reference: Option<String>,
     pub title: String,
     pub description: Option<String>,
     pub status: TaskStatus,
+    #[serde(with = "rfc3339")]
     pub created_at: i64,
+    #[serde(with = "rfc3339")]
     pub updated_at: i64,
+    // For date-only due dates, the last millisecond of that day in `due_timezone`
+    #[serde(default, with = "rfc3339_option")]
     pub due_date: Option<i64>,
+    #[serde(default)]
+    pub due_all_day: bool,
+    // IANA zone the due date was set in; it decides which day a date-only due date falls on
+    #[serde(default)]
+    pub due_timezone: Option<String>,
+    #[serde(default, with = "rfc3339_option")]
+    pub overdue_at: Option<i64>,
     pub priority: Option<i32>,
     pub tags: Vec<String>,
-    pub subtasks: Vec<Task>,
+    pub subtasks: Vec<i32>,
     pub parent_task: Option<i32>,
     pub assigned_to: Option<i32>,
     pub assigned_by: Option<i32>,
+    #[serde(default, with = "rfc3339_option")]
     pub completed_at: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
     pub archived_at: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
     pub deleted_at: Option<i64>,
     pub recurrence: Option<Recurrence>,
+    #[serde(default, with = "rfc3339_option")]
     pub recurrence_end: Option<i64>,
     pub dependencies: Vec<i32>,
     pub collaborators: Vec<Collaborator>,
     pub progress: Option<u8>,
-    pub comments: Vec<Comment>,
-    pub activity_log: Vec<String>,
-    pub custom_fields: HashMap<String, String>,
+    pub original_estimate: Option<i64>,
+    // Minutes of work left; lowered as work is logged
+    pub remaining_estimate: Option<i64>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    // Derive progress from the checklist while the task has no subtasks
+    #[serde(default)]
+    pub checklist_progress: bool,
+    // UID of the iCalendar to-do the task was imported from or synced with
+    #[serde(default)]
+    pub ical_uid: Option<String>,
+    // Resource name a CalDAV client stored the task under, when it is not `<uid>.ics`
+    #[serde(default)]
+    pub caldav_name: Option<String>,
+    // Events recorded since the task was loaded; drained into the activity table on save
+    #[serde(skip)]
+    pub activity_log: Vec<ActivityEvent>,
+    #[serde(skip)]
+    pub activity_context: ActivityContext,
+    pub custom_fields: HashMap<String, Value>,
 }
 
-impl PartialEq for TaskStatus {
-    fn eq(&self, other: &Self) -> bool {
-        todo!()
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 7] = [
+        TaskStatus::Backlog,
+        TaskStatus::Pending,
+        TaskStatus::InProgress,
+        TaskStatus::Blocked,
+        TaskStatus::InReview,
+        TaskStatus::Completed,
+        TaskStatus::Cancelled,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Backlog => "Backlog",
+            TaskStatus::Pending => "Pending",
+            TaskStatus::InProgress => "InProgress",
+            TaskStatus::Blocked => "Blocked",
+            TaskStatus::InReview => "InReview",
+            TaskStatus::Completed => "Completed",
+            TaskStatus::Cancelled => "Cancelled",
+        }
+    }
+
+    // Closed statuses end the workflow; leaving one reopens the task
+    pub fn is_closed(&self) -> bool {
+        matches!(self, TaskStatus::Completed | TaskStatus::Cancelled)
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TaskStatus::ALL
+            .iter()
+            .find(|status| status.as_str() == s)
+            .copied()
+            .ok_or_else(|| format!("Unknown task status '{}'", s))
     }
 }
 
 impl Task {
     // Constructor for a new task
     pub fn new(title: String, description: Option<String>) -> Self {
-        Task {
+        let mut task = Task {
             id: 0,
+            project_id: None,
+            number: None,
+            reference: None,
             title,
             description,
             status: TaskStatus::Pending,
             created_at: chrono::Utc::now().timestamp_millis(),
             updated_at: chrono::Utc::now().timestamp_millis(),
             due_date: None,
+            due_all_day: false,
+            due_timezone: None,
+            overdue_at: None,
             priority: None,
             tags: Vec::new(),
             subtasks: Vec::new(),
@@ -95,73 +228,384 @@ impl Task {
             dependencies: Vec::new(),
             collaborators: Vec::new(),
             progress: None,
-            comments: Vec::new(),
+            original_estimate: None,
+            remaining_estimate: None,
+            checklist: Vec::new(),
+            checklist_progress: false,
+            ical_uid: None,
+            caldav_name: None,
             activity_log: Vec::new(),
+            activity_context: ActivityContext::default(),
             custom_fields: HashMap::new(),
-        }
+        };
+        task.record_activity(ActivityKind::Created, None, Some(json!(task.title)));
+        task
+    }
+
+    // Attributes every following event to this actor and request
+    pub fn set_activity_context(&mut self, context: ActivityContext) {
+        self.activity_context = context;
     }
 
     // Setters for various task attributes
+    pub fn set_title(&mut self, title: String) {
+        let previous = std::mem::replace(&mut self.title, title);
+        self.update_timestamp();
+        self.record_activity(ActivityKind::TitleChanged, Some(json!(previous)), Some(json!(self.title)));
+    }
+
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+        self.update_timestamp();
+        self.record_activity(ActivityKind::DescriptionChanged, None, None);
+    }
+
     pub fn set_status(&mut self, status: TaskStatus) {
+        let previous = self.status;
         self.status = status;
+        if status == TaskStatus::Completed {
+            if self.completed_at.is_none() {
+                self.completed_at = Some(chrono::Utc::now().timestamp_millis());
+            }
+        } else {
+            self.completed_at = None;
+        }
         self.update_timestamp();
-        self.log_activity(format!("Status set to {:?}", status));
+        let kind = if previous.is_closed() && !status.is_closed() {
+            ActivityKind::Reopened
+        } else {
+            ActivityKind::StatusChanged
+        };
+        self.record_activity(kind, Some(json!(previous)), Some(json!(status)));
     }
 
+    // An exact instant; the zone of an earlier due date is kept for day-boundary checks
     pub fn set_due_date(&mut self, due_date: Option<i64>) {
+        let timezone = due_date.and(self.due_timezone.clone());
+        self.replace_due(due_date, false, timezone);
+    }
+
+    pub fn set_due(&mut self, due: Option<DueDate>, timezone: Tz) {
+        let (due_date, all_day) = match due {
+            Some(DueDate::Date(day)) => (Some(end_of_day(day, &timezone)), true),
+            Some(DueDate::DateTime(at)) => (Some(at.timestamp_millis()), false),
+            None => (None, false),
+        };
+        self.replace_due(due_date, all_day, due.map(|_| timezone.name().to_string()));
+    }
+
+    fn replace_due(&mut self, due_date: Option<i64>, all_day: bool, timezone: Option<String>) {
+        let previous = self.due_json();
         self.due_date = due_date;
+        self.due_all_day = all_day;
+        self.due_timezone = timezone;
+        self.overdue_at = None;
         self.update_timestamp();
-        self.log_activity(format!("Due date set to {:?}", due_date));
+        self.record_activity(ActivityKind::DueDateChanged, Some(previous), Some(self.due_json()));
+    }
+
+    fn due_json(&self) -> Value {
+        match self.due_date {
+            Some(_) => json!({
+                "due_date": self.due_date.map(to_rfc3339),
+                "all_day": self.due_all_day,
+                "timezone": self.due_timezone,
+            }),
+            None => Value::Null,
+        }
+    }
+
+    // Zone the task's days are counted in; `fallback` when the due date carries none
+    pub fn due_zone(&self, fallback: Tz) -> Tz {
+        self.due_timezone
+            .as_deref()
+            .and_then(|name| name.parse::<Tz>().ok())
+            .unwrap_or(fallback)
+    }
+
+    // Day the task is due as seen by someone in `viewer`. A date-only due date is the same
+    // calendar day everywhere.
+    pub fn due_day(&self, viewer: Tz) -> Option<NaiveDate> {
+        let due = self.due_date?;
+        let zone = if self.due_all_day { self.due_zone(viewer) } else { viewer };
+        Some(local_date(due, &zone))
+    }
+
+    pub fn is_due_on(&self, day: NaiveDate, viewer: Tz) -> bool {
+        self.due_day(viewer) == Some(day)
+    }
+
+    // Due date of the next occurrence, stepped in the task's own zone so a daily 9:00 task
+    // stays at 9:00 across DST changes. None once the recurrence has ended.
+    pub fn next_due_date(&self) -> Option<i64> {
+        let recurrence = self.recurrence?;
+        let zone = self.due_zone(chrono_tz::UTC);
+        let local = DateTime::from_timestamp_millis(self.due_date?)?.with_timezone(&zone).naive_local();
+        let next = match recurrence {
+            Recurrence::Daily => local + Duration::days(1),
+            Recurrence::Weekly => local + Duration::weeks(1),
+            Recurrence::Monthly => local.checked_add_months(Months::new(1))?,
+            Recurrence::Yearly => local.checked_add_months(Months::new(12))?,
+        };
+        let next = if self.due_all_day {
+            end_of_day(next.date(), &zone)
+        } else {
+            resolve_local(&zone, next).timestamp_millis()
+        };
+        match self.recurrence_end {
+            Some(end) if local_date(next, &zone) > local_date(end, &zone) => None,
+            _ => Some(next),
+        }
     }
 
     pub fn set_priority(&mut self, priority: Option<i32>) {
+        let previous = self.priority;
         self.priority = priority;
         self.update_timestamp();
-        self.log_activity(format!("Priority set to {:?}", priority));
+        self.record_activity(ActivityKind::PriorityChanged, Some(json!(previous)), Some(json!(priority)));
     }
 
-    pub fn add_tag(&mut self, tag: String) {
+    // Tags are normalized label names; adding one the task already has is a no-op.
+    // Returns whether the tag was added.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let Some(tag) = normalize_label(tag) else { return false };
+        if self.tags.contains(&tag) {
+            return false;
+        }
+        self.update_timestamp();
+        self.record_activity(ActivityKind::TagAdded, None, Some(json!(tag)));
         self.tags.push(tag);
-        self.log_activity(format!("Tag '{}' added", tag));
+        true
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let Some(tag) = normalize_label(tag) else { return false };
+        let before = self.tags.len();
+        self.tags.retain(|t| *t != tag);
+        if self.tags.len() == before {
+            return false;
+        }
+        self.update_timestamp();
+        self.record_activity(ActivityKind::TagRemoved, Some(json!(tag)), None);
+        true
+    }
+
+    // Renames a tag in place, as when its label is renamed or merged. A task that already has
+    // `to` keeps it once, at the earlier of the two positions. Returns whether the tags changed.
+    pub fn replace_tag(&mut self, from: &str, to: &str) -> bool {
+        let Some(index) = self.tags.iter().position(|t| t == from) else { return false };
+        if from == to {
+            return false;
+        }
+        self.update_timestamp();
+        match self.tags.iter().position(|t| t == to) {
+            Some(existing) => {
+                self.tags[index.min(existing)] = to.to_string();
+                self.tags.remove(index.max(existing));
+                self.record_activity(ActivityKind::TagRemoved, Some(json!(from)), None);
+            }
+            None => {
+                self.tags[index] = to.to_string();
+                self.record_activity(ActivityKind::TagRenamed, Some(json!(from)), Some(json!(to)));
+            }
+        }
+        true
+    }
+
+    pub fn assign(&mut self, user_id: Option<i32>, assigned_by: Option<i32>) {
+        let previous = self.assigned_to;
+        self.assigned_to = user_id;
+        self.assigned_by = user_id.and(assigned_by);
+        self.update_timestamp();
+        self.record_activity(ActivityKind::AssigneeChanged, Some(json!(previous)), Some(json!(user_id)));
+    }
+
+    pub fn add_subtask(&mut self, subtask_id: i32, position: Option<usize>) {
+        if self.subtasks.contains(&subtask_id) {
+            return;
+        }
+        let position = position.unwrap_or(self.subtasks.len()).min(self.subtasks.len());
+        self.subtasks.insert(position, subtask_id);
+        self.update_timestamp();
+        self.record_activity(ActivityKind::SubtaskAdded, None, Some(json!(subtask_id)));
+    }
+
+    pub fn remove_subtask(&mut self, subtask_id: i32) -> bool {
+        let before = self.subtasks.len();
+        self.subtasks.retain(|&id| id != subtask_id);
+        if self.subtasks.len() == before {
+            return false;
+        }
+        self.update_timestamp();
+        self.record_activity(ActivityKind::SubtaskRemoved, Some(json!(subtask_id)), None);
+        true
+    }
+
+    pub fn move_subtask(&mut self, subtask_id: i32, position: usize) -> bool {
+        let Some(current) = self.subtasks.iter().position(|&id| id == subtask_id) else {
+            return false;
+        };
+        self.subtasks.remove(current);
+        let position = position.min(self.subtasks.len());
+        self.subtasks.insert(position, subtask_id);
+        self.update_timestamp();
+        self.record_activity(
+            ActivityKind::SubtaskMoved,
+            Some(json!({ "subtask_id": subtask_id, "position": current })),
+            Some(json!({ "subtask_id": subtask_id, "position": position })),
+        );
+        true
+    }
+
+    pub fn set_parent(&mut self, parent_task: Option<i32>) {
+        let previous = self.parent_task;
+        self.parent_task = parent_task;
+        self.update_timestamp();
+        self.record_activity(ActivityKind::ParentChanged, Some(json!(previous)), Some(json!(parent_task)));
+    }
+
+    // The task is renumbered in its new project when saved
+    pub fn move_to_project(&mut self, project_id: Option<i32>) {
+        let previous = json!({ "project_id": self.project_id, "reference": self.reference });
+        self.project_id = project_id;
+        self.number = None;
+        self.reference = None;
+        self.update_timestamp();
+        self.record_activity(ActivityKind::ProjectChanged, Some(previous), Some(json!({ "project_id": project_id })));
     }
 
-    pub fn add_subtask(&mut self, subtask: Task) {
-        self.subtasks.push(subtask);
-        self.update_progress();
-        self.log_activity("Subtask added".to_string());
+    pub fn set_original_estimate(&mut self, minutes: Option<i64>) {
+        let previous = self.original_estimate;
+        self.original_estimate = minutes;
+        self.update_timestamp();
+        self.record_activity(ActivityKind::EstimateChanged, Some(json!(previous)), Some(json!(minutes)));
     }
 
-    pub fn complete(&mut self) {
-        self.status = TaskStatus::Completed;
-        self.completed_at = Some(chrono::Utc::now().timestamp_millis());
+    pub fn set_remaining_estimate(&mut self, minutes: Option<i64>) {
+        let previous = self.remaining_estimate;
+        self.remaining_estimate = minutes;
         self.update_timestamp();
-        self.log_activity("Task completed".to_string());
+        self.record_activity(ActivityKind::RemainingEstimateChanged, Some(json!(previous)), Some(json!(minutes)));
+    }
+
+    // Records logged work and burns it down from the remaining estimate, starting from
+    // the original estimate when no remaining estimate was set yet
+    pub fn log_work(&mut self, log: &WorkLog) {
+        if let Some(remaining) = self.remaining_estimate.or(self.original_estimate) {
+            self.remaining_estimate = Some((remaining - log.minutes).max(0));
+        }
+        self.update_timestamp();
+        self.record_activity(
+            ActivityKind::WorkLogged,
+            None,
+            Some(json!({ "work_log_id": log.id, "user_id": log.user_id, "minutes": log.minutes })),
+        );
+    }
+
+    // Gives the minutes of a removed work log back to the remaining estimate
+    pub fn unlog_work(&mut self, log: &WorkLog) {
+        if let Some(remaining) = self.remaining_estimate {
+            self.remaining_estimate = Some(remaining + log.minutes);
+        }
+        self.update_timestamp();
+        self.record_activity(
+            ActivityKind::WorkLogRemoved,
+            Some(json!({ "work_log_id": log.id, "user_id": log.user_id, "minutes": log.minutes })),
+            None,
+        );
+    }
+
+    // Date-only due dates are stored as the end of their day, so the instant comparison
+    // already respects the task's zone
+    pub fn is_overdue(&self, now: i64) -> bool {
+        !self.status.is_closed() && self.due_date.is_some_and(|due| due <= now)
+    }
+
+    pub fn mark_overdue(&mut self, now: i64) {
+        if self.overdue_at.is_none() {
+            self.overdue_at = Some(now);
+            self.record_activity(ActivityKind::MarkedOverdue, None, Some(json!(self.due_date.map(to_rfc3339))));
+        }
+    }
+
+    // Users who should hear about this task: the assignee and every collaborator
+    pub fn reminder_recipients(&self) -> Vec<i32> {
+        let mut recipients = self.assigned_to.into_iter().collect::<Vec<_>>();
+        for collaborator in &self.collaborators {
+            if !recipients.contains(&collaborator.user_id) {
+                recipients.push(collaborator.user_id);
+            }
+        }
+        recipients
     }
 
     pub fn archive(&mut self) {
-        self.archived_at = Some(chrono::Utc::now().timestamp_millis());
+        self.archive_at(chrono::Utc::now().timestamp_millis());
+    }
+
+    // Subtasks trashed along with their parent share its timestamp, so they can be restored together
+    pub fn archive_at(&mut self, archived_at: i64) {
+        self.archived_at = Some(archived_at);
         self.update_timestamp();
-        self.log_activity("Task archived".to_string());
+        self.record_activity(ActivityKind::Archived, None, Some(json!(self.archived_at)));
     }
 
     pub fn delete(&mut self) {
-        self.deleted_at = Some(chrono::Utc::now().timestamp_millis());
+        self.delete_at(chrono::Utc::now().timestamp_millis());
+    }
+
+    pub fn delete_at(&mut self, deleted_at: i64) {
+        self.deleted_at = Some(deleted_at);
+        self.update_timestamp();
+        self.record_activity(ActivityKind::Deleted, None, Some(json!(self.deleted_at)));
+    }
+
+    pub fn restore(&mut self) {
+        let previous = json!({ "archived_at": self.archived_at, "deleted_at": self.deleted_at });
+        self.archived_at = None;
+        self.deleted_at = None;
         self.update_timestamp();
-        self.log_activity("Task deleted".to_string());
+        self.record_activity(ActivityKind::Restored, Some(previous), None);
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.archived_at.is_some() || self.deleted_at.is_some()
     }
 
     pub fn set_recurrence(&mut self, recurrence: Option<Recurrence>, recurrence_end: Option<i64>) {
+        let previous = json!({ "recurrence": self.recurrence, "recurrence_end": self.recurrence_end.map(to_rfc3339) });
         self.recurrence = recurrence;
         self.recurrence_end = recurrence_end;
-        self.log_activity(format!("Recurrence set to {:?}, ends at {:?}", recurrence, recurrence_end));
+        self.record_activity(
+            ActivityKind::RecurrenceChanged,
+            Some(previous),
+            Some(json!({ "recurrence": recurrence, "recurrence_end": recurrence_end.map(to_rfc3339) })),
+        );
     }
 
     pub fn add_dependency(&mut self, task_id: i32) {
         self.dependencies.push(task_id);
-        self.log_activity(format!("Dependency on task {} added", task_id));
+        self.record_activity(ActivityKind::DependencyAdded, None, Some(json!(task_id)));
+    }
+
+    // Blocked-by links are mirrored into `dependencies`
+    pub fn add_link(&mut self, relation: LinkRelation, task_id: i32) {
+        if relation == LinkRelation::BlockedBy && !self.dependencies.contains(&task_id) {
+            self.dependencies.push(task_id);
+        }
+        self.update_timestamp();
+        self.record_activity(ActivityKind::LinkAdded, None, Some(json!({ "relation": relation, "task_id": task_id })));
+    }
+
+    pub fn remove_link(&mut self, relation: LinkRelation, task_id: i32) {
+        if relation == LinkRelation::BlockedBy {
+            self.dependencies.retain(|id| *id != task_id);
+        }
+        self.update_timestamp();
+        self.record_activity(ActivityKind::LinkRemoved, Some(json!({ "relation": relation, "task_id": task_id })), None);
     }
 
-    pub fn can_be_completed(&self, other_tasks: &Vec<Task>) -> bool {
+    pub fn can_be_completed(&self, other_tasks: &[Task]) -> bool {
         for dep_id in &self.dependencies {
             if let Some(dep_task) = other_tasks.iter().find(|t| t.id == *dep_id) {
                 if dep_task.status != TaskStatus::Completed {
@@ -174,60 +618,237 @@ impl Task {
 
     pub fn add_collaborator(&mut self, user_id: i32, role: Role) {
         self.collaborators.push(Collaborator { user_id, role });
-        self.log_activity(format!("Collaborator {} added with role {:?}", user_id, role));
+        self.record_activity(ActivityKind::CollaboratorAdded, None, Some(json!({ "user_id": user_id, "role": role })));
     }
 
     pub fn remove_collaborator(&mut self, user_id: i32) {
         self.collaborators.retain(|c| c.user_id != user_id);
-        self.log_activity(format!("Collaborator {} removed", user_id));
+        self.record_activity(ActivityKind::CollaboratorRemoved, Some(json!(user_id)), None);
     }
 
     pub fn change_role(&mut self, user_id: i32, new_role: Role) {
         if let Some(collaborator) = self.collaborators.iter_mut().find(|c| c.user_id == user_id) {
+            let previous = collaborator.role;
             collaborator.role = new_role;
-            self.log_activity(format!("Role of collaborator {} changed to {:?}", user_id, new_role));
+            self.record_activity(
+                ActivityKind::CollaboratorRoleChanged,
+                Some(json!({ "user_id": user_id, "role": previous })),
+                Some(json!({ "user_id": user_id, "role": new_role })),
+            );
+        }
+    }
+
+    // Recomputes progress from the whole subtask tree; `subtree` must contain every descendant.
+    // Without subtasks progress comes from the checklist when the task opted in.
+    pub fn update_progress(&mut self, subtree: &HashMap<i32, Task>, weighted: bool) {
+        let progress = if !self.subtasks.is_empty() {
+            self.rollup_progress(subtree, weighted)
+        } else {
+            match self.checklist_completion().filter(|_| self.checklist_progress) {
+                Some(progress) => progress,
+                None => return,
+            }
+        };
+        if self.progress != Some(progress) {
+            let previous = self.progress.replace(progress);
+            self.record_activity(ActivityKind::ProgressChanged, Some(json!(previous)), Some(json!(progress)));
+        }
+    }
+
+    // Percentage of checked items; None for an empty checklist
+    pub fn checklist_completion(&self) -> Option<u8> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+        let checked = self.checklist.iter().filter(|item| item.checked).count();
+        Some((checked * 100 / self.checklist.len()) as u8)
+    }
+
+    pub fn add_checklist_item(&mut self, text: String, position: Option<usize>) -> &ChecklistItem {
+        let id = self.checklist.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+        let position = position.unwrap_or(self.checklist.len()).min(self.checklist.len());
+        self.record_activity(ActivityKind::ChecklistItemAdded, None, Some(json!({ "id": id, "text": text })));
+        self.checklist.insert(position, ChecklistItem { id, text, checked: false, checked_by: None, checked_at: None });
+        self.update_timestamp();
+        &self.checklist[position]
+    }
+
+    // Returns false when the item was already in that state
+    pub fn check_checklist_item(&mut self, item_id: i32, checked: bool, user_id: Option<i32>) -> Result<bool, AppError> {
+        let item = self.checklist_item_mut(item_id)?;
+        if item.checked == checked {
+            return Ok(false);
+        }
+        item.checked = checked;
+        item.checked_by = if checked { user_id } else { None };
+        item.checked_at = checked.then(|| chrono::Utc::now().timestamp_millis());
+        let text = item.text.clone();
+        let kind = if checked { ActivityKind::ChecklistItemChecked } else { ActivityKind::ChecklistItemUnchecked };
+        self.update_timestamp();
+        self.record_activity(kind, None, Some(json!({ "id": item_id, "text": text })));
+        Ok(true)
+    }
+
+    pub fn rename_checklist_item(&mut self, item_id: i32, text: String) -> Result<(), AppError> {
+        let item = self.checklist_item_mut(item_id)?;
+        if item.text == text {
+            return Ok(());
         }
+        let previous = std::mem::replace(&mut item.text, text.clone());
+        self.update_timestamp();
+        self.record_activity(
+            ActivityKind::ChecklistItemRenamed,
+            Some(json!({ "id": item_id, "text": previous })),
+            Some(json!({ "id": item_id, "text": text })),
+        );
+        Ok(())
     }
 
-    pub fn update_progress(&mut self) {
-        let total = self.subtasks.len();
-        if total > 0 {
-            let completed = self.subtasks.iter().filter(|t| t.status == TaskStatus::Completed).count();
-            self.progress = Some((completed as u8 * 100) / total as u8);
-            self.log_activity(format!("Progress updated to {}%", self.progress.unwrap()));
+    pub fn move_checklist_item(&mut self, item_id: i32, position: usize) -> Result<(), AppError> {
+        let from = self
+            .checklist
+            .iter()
+            .position(|item| item.id == item_id)
+            .ok_or_else(|| AppError::not_found("Checklist item", item_id))?;
+        let item = self.checklist.remove(from);
+        let to = position.min(self.checklist.len());
+        let text = item.text.clone();
+        self.checklist.insert(to, item);
+        if from == to {
+            return Ok(());
         }
+        self.update_timestamp();
+        self.record_activity(
+            ActivityKind::ChecklistItemMoved,
+            Some(json!({ "id": item_id, "text": text, "position": from })),
+            Some(json!({ "id": item_id, "text": text, "position": to })),
+        );
+        Ok(())
+    }
+
+    pub fn remove_checklist_item(&mut self, item_id: i32) -> Result<ChecklistItem, AppError> {
+        let index = self
+            .checklist
+            .iter()
+            .position(|item| item.id == item_id)
+            .ok_or_else(|| AppError::not_found("Checklist item", item_id))?;
+        let item = self.checklist.remove(index);
+        self.update_timestamp();
+        self.record_activity(ActivityKind::ChecklistItemRemoved, Some(json!({ "id": item.id, "text": item.text })), None);
+        Ok(item)
+    }
+
+    fn checklist_item_mut(&mut self, item_id: i32) -> Result<&mut ChecklistItem, AppError> {
+        self.checklist
+            .iter_mut()
+            .find(|item| item.id == item_id)
+            .ok_or_else(|| AppError::not_found("Checklist item", item_id))
+    }
+
+    pub fn rollup_progress(&self, subtree: &HashMap<i32, Task>, weighted: bool) -> u8 {
+        let mut visited = HashSet::from([self.id]);
+        rollup(self, subtree, weighted, &mut visited) as u8
+    }
+
+    // Own estimate, or the sum of the subtasks' estimates when the task has none
+    pub fn rollup_estimate(&self, subtree: &HashMap<i32, Task>) -> i64 {
+        let mut visited = HashSet::from([self.id]);
+        estimate(self, subtree, &mut visited, |t| t.original_estimate)
+    }
+
+    // Own remaining work, falling back to the original estimate, or the sum over the subtasks
+    pub fn rollup_remaining(&self, subtree: &HashMap<i32, Task>) -> i64 {
+        let mut visited = HashSet::from([self.id]);
+        estimate(self, subtree, &mut visited, |t| {
+            if t.status.is_closed() {
+                Some(0)
+            } else {
+                t.remaining_estimate.or(t.original_estimate)
+            }
+        })
     }
 
     pub fn set_progress(&mut self, progress: u8) {
         if progress <= 100 {
-            self.progress = Some(progress);
-            self.log_activity(format!("Progress set to {}%", progress));
+            let previous = self.progress.replace(progress);
+            self.record_activity(ActivityKind::ProgressChanged, Some(json!(previous)), Some(json!(progress)));
         }
     }
 
-    pub fn add_comment(&mut self, user_id: i32, comment: String) {
-        self.comments.push(Comment {
-            user_id,
-            comment: comment.clone(),
-            timestamp: chrono::Utc::now().timestamp_millis(),
-        });
-        self.log_activity(format!("Comment added by user {}: {}", user_id, comment));
+    // Comments live in their own table; the task only keeps the activity trail
+    pub fn add_comment(&mut self, comment: &Comment) {
+        self.record_activity(
+            ActivityKind::CommentAdded,
+            None,
+            Some(json!({ "comment_id": comment.id, "user_id": comment.user_id, "comment": comment.body })),
+        );
     }
 
-    pub fn log_activity(&mut self, activity: String) {
-        self.activity_log.push(format!(
-            "{} - {}",
-            chrono::Utc::now().to_rfc3339(),
-            activity
+    pub fn edit_comment(&mut self, comment: &Comment, previous: &str) {
+        self.record_activity(
+            ActivityKind::CommentEdited,
+            Some(json!({ "comment_id": comment.id, "comment": previous })),
+            Some(json!({ "comment_id": comment.id, "comment": comment.body })),
+        );
+    }
+
+    pub fn delete_comment(&mut self, comment: &Comment) {
+        self.record_activity(ActivityKind::CommentDeleted, Some(json!({ "comment_id": comment.id })), None);
+    }
+
+    pub fn record_activity(&mut self, kind: ActivityKind, old_value: Option<serde_json::Value>, new_value: Option<serde_json::Value>) {
+        self.activity_log.push(ActivityEvent::new(
+            ActivitySubject::Task,
+            self.id,
+            &self.activity_context,
+            kind,
+            old_value,
+            new_value,
         ));
     }
 
-    pub fn add_custom_field(&mut self, key: String, value: String) {
-        self.custom_fields.insert(key.clone(), value.clone());
-        self.log_activity(format!("Custom field '{}' set to '{}'", key, value));
+    // Validates `value` against the field definition and stores it in its normalized form
+    pub fn add_custom_field(&mut self, definition: &CustomFieldDefinition, value: Value) -> Result<(), AppError> {
+        if definition.target != CustomFieldTarget::Task {
+            return Err(AppError::validation_error(&definition.key, "Field is not defined for tasks"));
+        }
+        let value = definition
+            .normalize(&value)
+            .map_err(|message| AppError::validation_error(&definition.key, &message))?;
+        let previous = self.custom_fields.insert(definition.key.clone(), value.clone());
+        if previous.as_ref() != Some(&value) {
+            self.update_timestamp();
+            self.record_activity(
+                ActivityKind::CustomFieldSet,
+                Some(json!({ "key": definition.key, "value": previous })),
+                Some(json!({ "key": definition.key, "value": value })),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn remove_custom_field(&mut self, definition: &CustomFieldDefinition) -> Result<(), AppError> {
+        if definition.required {
+            return Err(AppError::validation_error(&definition.key, "Field is required and cannot be removed"));
+        }
+        self.drop_custom_field(&definition.key);
+        Ok(())
+    }
+
+    // Drops the value whether or not the field is required, as when its definition is deleted.
+    // Returns whether there was a value.
+    pub fn drop_custom_field(&mut self, key: &str) -> bool {
+        let Some(previous) = self.custom_fields.remove(key) else { return false };
+        self.update_timestamp();
+        self.record_activity(
+            ActivityKind::CustomFieldSet,
+            Some(json!({ "key": key, "value": previous })),
+            Some(json!({ "key": key, "value": null })),
+        );
+        true
     }
 
-    pub fn get_custom_field(&self, key: &str) -> Option<&String> {
+    pub fn get_custom_field(&self, key: &str) -> Option<&Value> {
         self.custom_fields.get(key)
     }
 
@@ -235,3 +856,337 @@ impl Task {
         self.updated_at = chrono::Utc::now().timestamp_millis();
     }
 }
+
+fn children<'a>(task: &Task, subtree: &'a HashMap<i32, Task>, visited: &mut HashSet<i32>) -> Vec<&'a Task> {
+    task.subtasks
+        .iter()
+        .filter_map(|id| subtree.get(id))
+        .filter(|child| child.status != TaskStatus::Cancelled && !child.is_trashed() && visited.insert(child.id))
+        .collect()
+}
+
+fn rollup(task: &Task, subtree: &HashMap<i32, Task>, weighted: bool, visited: &mut HashSet<i32>) -> u64 {
+    let children = children(task, subtree, visited);
+    if children.is_empty() {
+        return match task.status {
+            TaskStatus::Completed => 100,
+            _ => u64::from(task.progress.unwrap_or(0).min(100)),
+        };
+    }
+
+    let mut weights = children
+        .iter()
+        .map(|child| if weighted { estimate(child, subtree, &mut visited.clone(), |t| t.original_estimate).max(0) as u64 } else { 1 })
+        .collect::<Vec<_>>();
+    if weights.iter().all(|&w| w == 0) {
+        weights.iter_mut().for_each(|w| *w = 1);
+    }
+
+    let total: u64 = weights.iter().sum();
+    let done: u64 = children
+        .iter()
+        .zip(&weights)
+        .map(|(child, weight)| rollup(child, subtree, weighted, visited) * weight)
+        .sum();
+    done / total
+}
+
+fn estimate(task: &Task, subtree: &HashMap<i32, Task>, visited: &mut HashSet<i32>, own: fn(&Task) -> Option<i64>) -> i64 {
+    if let Some(minutes) = own(task) {
+        return minutes;
+    }
+    children(task, subtree, visited)
+        .into_iter()
+        .map(|child| estimate(child, subtree, visited, own))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::services::TaskService;
+    use crate::domain::entities::workflow::{Transition, Workflow};
+
+    fn task_with_items(texts: &[&str]) -> Task {
+        let mut task = Task::new("Pack for the trip".to_string(), None);
+        for text in texts {
+            task.add_checklist_item(text.to_string(), None);
+        }
+        task.activity_log.clear();
+        task
+    }
+
+    fn item_ids(task: &Task) -> Vec<i32> {
+        task.checklist.iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn adding_a_checklist_item_records_it() {
+        let mut task = task_with_items(&["Passport"]);
+        let item = task.add_checklist_item("Tickets".to_string(), Some(0)).clone();
+
+        assert_eq!(item.id, 2);
+        assert_eq!(item_ids(&task), vec![2, 1]);
+        let event = task.activity_log.last().unwrap();
+        assert_eq!(event.kind, ActivityKind::ChecklistItemAdded);
+        assert_eq!(event.new_value, Some(json!({ "id": 2, "text": "Tickets" })));
+    }
+
+    #[test]
+    fn toggling_a_checklist_item_records_each_change_once() {
+        let mut task = task_with_items(&["Passport"]);
+
+        assert!(task.check_checklist_item(1, true, Some(7)).unwrap());
+        assert!(!task.check_checklist_item(1, true, Some(7)).unwrap());
+        assert_eq!(task.checklist[0].checked_by, Some(7));
+        assert!(task.check_checklist_item(1, false, Some(7)).unwrap());
+        assert_eq!(task.checklist[0].checked_by, None);
+
+        let kinds = task.activity_log.iter().map(|event| event.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![ActivityKind::ChecklistItemChecked, ActivityKind::ChecklistItemUnchecked]);
+    }
+
+    #[test]
+    fn renaming_a_checklist_item_records_old_and_new_text() {
+        let mut task = task_with_items(&["Pasport"]);
+
+        task.rename_checklist_item(1, "Passport".to_string()).unwrap();
+        task.rename_checklist_item(1, "Passport".to_string()).unwrap();
+
+        assert_eq!(task.checklist[0].text, "Passport");
+        assert_eq!(task.activity_log.len(), 1);
+        let event = &task.activity_log[0];
+        assert_eq!(event.kind, ActivityKind::ChecklistItemRenamed);
+        assert_eq!(event.old_value, Some(json!({ "id": 1, "text": "Pasport" })));
+        assert_eq!(event.new_value, Some(json!({ "id": 1, "text": "Passport" })));
+    }
+
+    #[test]
+    fn moving_a_checklist_item_records_its_positions() {
+        let mut task = task_with_items(&["Passport", "Tickets", "Charger"]);
+
+        task.move_checklist_item(3, 0).unwrap();
+        task.move_checklist_item(3, 0).unwrap();
+        task.move_checklist_item(1, 10).unwrap();
+
+        assert_eq!(item_ids(&task), vec![3, 2, 1]);
+        let moves = task
+            .activity_log
+            .iter()
+            .map(|event| (event.kind, event.old_value.as_ref().unwrap()["position"].clone(), event.new_value.as_ref().unwrap()["position"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            moves,
+            vec![
+                (ActivityKind::ChecklistItemMoved, json!(2), json!(0)),
+                (ActivityKind::ChecklistItemMoved, json!(1), json!(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_checklist_items_are_not_found() {
+        let mut task = task_with_items(&["Passport"]);
+
+        assert!(task.rename_checklist_item(9, "Visa".to_string()).is_err());
+        assert!(task.move_checklist_item(9, 0).is_err());
+        assert!(task.activity_log.is_empty());
+    }
+
+    fn task_with_tags(tags: &[&str]) -> Task {
+        let mut task = Task::new("Plan the offsite".to_string(), None);
+        task.tags = tags.iter().map(|t| t.to_string()).collect();
+        task.activity_log.clear();
+        task
+    }
+
+    #[test]
+    fn replacing_a_tag_renames_it_in_place() {
+        let mut task = task_with_tags(&["travel", "q3", "budget"]);
+
+        assert!(task.replace_tag("q3", "q4"));
+        assert_eq!(task.tags, vec!["travel", "q4", "budget"]);
+        let event = task.activity_log.last().unwrap();
+        assert_eq!(event.kind, ActivityKind::TagRenamed);
+        assert_eq!((event.old_value.clone(), event.new_value.clone()), (Some(json!("q3")), Some(json!("q4"))));
+    }
+
+    #[test]
+    fn replacing_onto_an_existing_tag_keeps_it_once_at_the_earlier_position() {
+        let mut task = task_with_tags(&["travel", "budget", "costs"]);
+
+        assert!(task.replace_tag("costs", "travel"));
+        assert_eq!(task.tags, vec!["travel", "budget"]);
+        assert!(task.replace_tag("travel", "budget"));
+        assert_eq!(task.tags, vec!["budget"]);
+        let kinds = task.activity_log.iter().map(|event| event.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![ActivityKind::TagRemoved, ActivityKind::TagRemoved]);
+    }
+
+    #[test]
+    fn replacing_a_missing_tag_changes_nothing() {
+        let mut task = task_with_tags(&["travel"]);
+
+        assert!(!task.replace_tag("q3", "q4"));
+        assert!(!task.replace_tag("travel", "travel"));
+        assert_eq!(task.tags, vec!["travel"]);
+        assert!(task.activity_log.is_empty());
+    }
+
+    #[test]
+    fn dropping_a_custom_field_records_the_old_value() {
+        let mut task = task_with_tags(&[]);
+        task.custom_fields.insert("severity".to_string(), json!("high"));
+
+        assert!(task.drop_custom_field("severity"));
+        assert!(!task.drop_custom_field("severity"));
+        assert!(task.custom_fields.is_empty());
+        assert_eq!(task.activity_log.len(), 1);
+        let event = &task.activity_log[0];
+        assert_eq!(event.kind, ActivityKind::CustomFieldSet);
+        assert_eq!(event.old_value, Some(json!({ "key": "severity", "value": "high" })));
+        assert_eq!(event.new_value, Some(json!({ "key": "severity", "value": null })));
+    }
+
+    fn task_in_status(status: TaskStatus) -> Task {
+        let mut task = Task::new("Ship the release".to_string(), None);
+        task.status = status;
+        task.activity_log.clear();
+        task
+    }
+
+    #[test]
+    fn the_default_workflow_only_completes_started_tasks() {
+        let workflow = Workflow::default_for(None);
+
+        assert!(workflow.allows(TaskStatus::InProgress, TaskStatus::Completed));
+        assert!(workflow.allows(TaskStatus::InReview, TaskStatus::Completed));
+        assert!(!workflow.allows(TaskStatus::Pending, TaskStatus::Completed));
+        assert!(!workflow.allows(TaskStatus::Completed, TaskStatus::Cancelled));
+        assert_eq!(workflow.next_statuses(TaskStatus::Backlog), vec![TaskStatus::Pending, TaskStatus::Cancelled]);
+        assert!(TaskStatus::ALL.iter().all(|&status| workflow.has_status(status)));
+    }
+
+    #[test]
+    fn changing_status_follows_the_workflow() {
+        let workflow = Workflow::default_for(None);
+        let mut task = task_in_status(TaskStatus::InProgress);
+
+        TaskService::change_task_status(&mut task, &workflow, TaskStatus::Completed).unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert!(task.completed_at.is_some());
+
+        TaskService::change_task_status(&mut task, &workflow, TaskStatus::InProgress).unwrap();
+        assert_eq!(task.completed_at, None);
+        let kinds = task.activity_log.iter().map(|event| event.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![ActivityKind::StatusChanged, ActivityKind::Reopened]);
+    }
+
+    #[test]
+    fn changing_status_rejects_moves_the_workflow_does_not_allow() {
+        let workflow = Workflow::default_for(None);
+        let mut task = task_in_status(TaskStatus::Pending);
+
+        let same = TaskService::change_task_status(&mut task, &workflow, TaskStatus::Pending).unwrap_err();
+        assert!(same.to_string().contains("already Pending"), "{}", same);
+        let skipped = TaskService::change_task_status(&mut task, &workflow, TaskStatus::Completed).unwrap_err();
+        assert!(
+            skipped.to_string().contains("allowed next statuses: Backlog, InProgress, Cancelled"),
+            "{}",
+            skipped
+        );
+        assert!(TaskService::complete_task(&mut task, &workflow).is_err());
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert!(task.activity_log.is_empty());
+    }
+
+    #[test]
+    fn changing_status_rejects_statuses_outside_a_custom_workflow() {
+        let workflow = Workflow {
+            project_id: Some(1),
+            statuses: vec![TaskStatus::Pending, TaskStatus::Completed],
+            initial_status: TaskStatus::Pending,
+            transitions: vec![Transition { from: TaskStatus::Pending, to: TaskStatus::Completed }],
+            updated_at: 0,
+        };
+        let mut task = task_in_status(TaskStatus::Pending);
+
+        let error = TaskService::change_task_status(&mut task, &workflow, TaskStatus::InProgress).unwrap_err();
+        assert!(error.to_string().contains("not part of this workflow"), "{}", error);
+        let mut completed = task_in_status(TaskStatus::Completed);
+        let error = TaskService::change_task_status(&mut completed, &workflow, TaskStatus::Pending).unwrap_err();
+        assert!(error.to_string().contains("allowed next statuses: none"), "{}", error);
+
+        TaskService::complete_task(&mut task, &workflow).unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert!(TaskService::complete_task(&mut task, &workflow).is_err());
+    }
+
+    // Tasks 1..=n where each is the only subtask of the one before it
+    fn task_chain(n: i32) -> HashMap<i32, Task> {
+        (1..=n)
+            .map(|id| {
+                let mut task = Task::new(format!("Step {}", id), None);
+                task.id = id;
+                task.parent_task = (id > 1).then_some(id - 1);
+                task.subtasks = if id < n { vec![id + 1] } else { vec![] };
+                task.activity_log.clear();
+                (id, task)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rolling_up_progress_updates_each_ancestor_from_the_one_below() {
+        let mut subtree = task_chain(2);
+        let mut done = Task::new("Done".to_string(), None);
+        done.id = 3;
+        done.parent_task = Some(2);
+        done.status = TaskStatus::Completed;
+        let mut open = Task::new("Open".to_string(), None);
+        open.id = 4;
+        open.parent_task = Some(2);
+        subtree.get_mut(&2).unwrap().subtasks = vec![3, 4];
+        subtree.extend([(3, done), (4, open)]);
+
+        assert_eq!(TaskService::rollup_progress(&mut subtree, &[2, 1]), vec![2, 1]);
+        assert_eq!((subtree[&2].progress, subtree[&1].progress), (Some(50), Some(50)));
+        assert_eq!(subtree[&1].activity_log.last().unwrap().kind, ActivityKind::ProgressChanged);
+
+        // Nothing changed since, so nothing needs saving
+        assert!(TaskService::rollup_progress(&mut subtree, &[2, 1]).is_empty());
+    }
+
+    #[test]
+    fn rolling_up_progress_skips_ancestors_that_did_not_change() {
+        let mut subtree = task_chain(3);
+        subtree.get_mut(&3).unwrap().status = TaskStatus::Completed;
+        subtree.get_mut(&1).unwrap().progress = Some(100);
+
+        assert_eq!(TaskService::rollup_progress(&mut subtree, &[2, 1]), vec![2]);
+        assert!(subtree[&1].activity_log.is_empty());
+    }
+
+    #[test]
+    fn rolling_up_progress_reaches_the_top_of_a_deep_hierarchy() {
+        let mut subtree = task_chain(500);
+        subtree.get_mut(&500).unwrap().status = TaskStatus::Completed;
+        let ancestors = (1..500).rev().collect::<Vec<_>>();
+
+        assert_eq!(TaskService::rollup_progress(&mut subtree, &ancestors), ancestors);
+        assert!(subtree.values().filter(|t| t.id < 500).all(|t| t.progress == Some(100)));
+    }
+
+    #[test]
+    fn rolling_up_progress_terminates_on_a_cycle() {
+        // 1 and 2 are each other's subtask; the ancestor query then repeats them
+        let mut subtree = task_chain(3);
+        subtree.get_mut(&1).unwrap().parent_task = Some(2);
+        subtree.get_mut(&2).unwrap().subtasks = vec![1, 3];
+        subtree.get_mut(&3).unwrap().status = TaskStatus::Completed;
+
+        let changed = TaskService::rollup_progress(&mut subtree, &[2, 1, 2, 1, 2]);
+        assert_eq!(changed, vec![2, 1]);
+        assert!(subtree[&1].progress.is_some() && subtree[&2].progress.is_some());
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::domain::entities::task::Collaborator;
+use crate::domain::time::rfc3339;
+
+pub const MAX_TEMPLATE_DEPTH: usize = 5;
+pub const MAX_TEMPLATE_TASKS: usize = 200;
+
+// One task of a template; due dates are given in days after the instantiation start date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateTask {
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub custom_fields: HashMap<String, Value>,
+    #[serde(default)]
+    pub collaborators: Vec<Collaborator>,
+    pub due_in_days: Option<i64>,
+    #[serde(default)]
+    pub subtasks: Vec<TemplateTask>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTemplate {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub root: TemplateTask,
+    // Placeholders callers have to fill in when instantiating
+    #[serde(default, skip_deserializing)]
+    pub variables: Vec<String>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+// A template task with variables substituted, listed parents first
+#[derive(Debug, Clone)]
+pub struct PlannedTask {
+    pub parent: Option<usize>,
+    pub task: TemplateTask,
+    pub due_date: Option<i64>,
+}
+
+impl TemplateTask {
+    // Depth-first walk, parents before their subtasks
+    pub fn walk(&self) -> Vec<(usize, &TemplateTask)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(1, self)];
+        while let Some((depth, node)) = stack.pop() {
+            nodes.push((depth, node));
+            stack.extend(node.subtasks.iter().rev().map(|s| (depth + 1, s)));
+        }
+        nodes
+    }
+
+    // Names of every `{{variable}}` used in text fields of this task and its subtasks
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut variables = BTreeSet::new();
+        for (_, node) in self.walk() {
+            for text in node.texts() {
+                variables.extend(placeholders(text));
+            }
+        }
+        variables
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        let mut texts = vec![self.title.as_str()];
+        texts.extend(self.description.as_deref());
+        texts.extend(self.tags.iter().map(String::as_str));
+        texts.extend(self.custom_fields.values().filter_map(Value::as_str));
+        texts
+    }
+
+    // Copy of this task alone, without subtasks, with every placeholder replaced
+    pub fn substitute(&self, values: &HashMap<String, String>) -> TemplateTask {
+        let replace = |text: &str| substitute(text, values);
+        TemplateTask {
+            title: replace(&self.title),
+            description: self.description.as_deref().map(replace),
+            tags: self.tags.iter().map(|t| replace(t)).collect(),
+            priority: self.priority,
+            custom_fields: self
+                .custom_fields
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(text) => Value::String(replace(text)),
+                        other => other.clone(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+            collaborators: self.collaborators.clone(),
+            due_in_days: self.due_in_days,
+            subtasks: Vec::new(),
+        }
+    }
+}
+
+fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else { break };
+        names.push(rest[start + 2..start + 2 + end].trim().to_string());
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+// Unknown placeholders are left as they are; callers check for missing values first
+pub fn substitute(text: &str, values: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else { break };
+        let name = rest[start + 2..start + 2 + end].trim();
+        output.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
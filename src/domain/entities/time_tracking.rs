@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::rfc3339;
+
+// A single manual entry may not exceed one day
+pub const MAX_WORK_LOG_MINUTES: i64 = 24 * 60;
+pub const MAX_REPORT_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkLogSource {
+    Manual,
+    Timer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkLog {
+    pub id: i64,
+    pub task_id: i32,
+    pub user_id: i32,
+    #[serde(with = "rfc3339")]
+    pub started_at: i64,
+    pub minutes: i64,
+    pub note: Option<String>,
+    pub source: WorkLogSource,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveTimer {
+    pub user_id: i32,
+    pub task_id: i32,
+    #[serde(with = "rfc3339")]
+    pub started_at: i64,
+}
+
+impl ActiveTimer {
+    // Rounded to the nearest minute; a started timer always yields at least one
+    pub fn elapsed_minutes(&self, now: i64) -> i64 {
+        ((now - self.started_at + 30_000) / 60_000).max(1)
+    }
+
+    pub fn into_work_log(self, now: i64, note: Option<String>) -> WorkLog {
+        WorkLog {
+            id: 0,
+            task_id: self.task_id,
+            user_id: self.user_id,
+            started_at: self.started_at,
+            minutes: self.elapsed_minutes(now),
+            note,
+            source: WorkLogSource::Timer,
+            created_at: now,
+        }
+    }
+}
+
+// Estimates and time spent on a task, alone and including its subtasks
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeSummary {
+    pub task_id: i32,
+    pub original_estimate: Option<i64>,
+    pub remaining_estimate: Option<i64>,
+    pub time_spent: i64,
+    pub total_original_estimate: i64,
+    pub total_remaining_estimate: i64,
+    pub total_time_spent: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGrouping {
+    User,
+    Task,
+    Tag,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeReportRow {
+    pub key: String,
+    pub minutes: i64,
+    pub entries: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeReport {
+    #[serde(with = "rfc3339")]
+    pub from: i64,
+    #[serde(with = "rfc3339")]
+    pub to: i64,
+    pub group_by: ReportGrouping,
+    pub total_minutes: i64,
+    pub rows: Vec<TimeReportRow>,
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::time::rfc3339_option;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrashState {
+    Archived,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedUser {
+    pub id: i32,
+    pub username: String,
+    #[serde(default, with = "rfc3339_option")]
+    pub archived_at: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
+    pub deleted_at: Option<i64>,
+}
+
+// How long trashed entities are kept before the retention job removes them for good.
+// Archived entities are kept forever unless `archived_days` is set.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    pub deleted_days: i64,
+    pub archived_days: Option<i64>,
+}
+
+impl RetentionPolicy {
+    pub fn deleted_cutoff(&self, now: i64) -> i64 {
+        now - self.deleted_days * DAY_MS
+    }
+
+    pub fn archived_cutoff(&self, now: i64) -> Option<i64> {
+        self.archived_days.map(|days| now - days * DAY_MS)
+    }
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            deleted_days: 30,
+            archived_days: None,
+        }
+    }
+}
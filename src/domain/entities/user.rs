@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
+use crate::domain::entities::activity::{ActivityContext, ActivityEvent, ActivityKind, ActivitySubject};
+use crate::domain::entities::custom_field::{CustomFieldDefinition, CustomFieldTarget};
+use crate::domain::errors::AppError;
+use crate::domain::time::{rfc3339, rfc3339_option};
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Role {
     Admin,
@@ -19,14 +25,25 @@ pub struct User {
     pub image: Option<String>,
     pub followers: Vec<i32>,
     pub following: Vec<i32>,
+    #[serde(with = "rfc3339")]
     pub created_at: i64,
+    #[serde(with = "rfc3339")]
     pub updated_at: i64,
+    #[serde(default, with = "rfc3339_option")]
     pub archived_at: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
     pub deleted_at: Option<i64>,
     pub role: Role,
     pub is_verified: bool,
-    pub activity_log: Vec<String>,
-    pub custom_fields: HashMap<String, String>,
+    // IANA zone the user's days are counted in: due today, overdue, relative quick-add dates
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    // Events recorded since the user was loaded; drained into the activity table on save
+    #[serde(skip)]
+    pub activity_log: Vec<ActivityEvent>,
+    #[serde(skip)]
+    pub activity_context: ActivityContext,
+    pub custom_fields: HashMap<String, Value>,
 }
 
 impl User {
@@ -48,117 +65,171 @@ impl User {
             deleted_at: None,
             role,
             is_verified: false,
+            timezone: default_timezone(),
             activity_log: Vec::new(),
+            activity_context: ActivityContext::default(),
             custom_fields: HashMap::new(),
         }
     }
 
+    // Attributes every following event to this actor and request
+    pub fn set_activity_context(&mut self, context: ActivityContext) {
+        self.activity_context = context;
+    }
+
     pub fn set_name(&mut self, name: String) {
-        self.name = Some(name);
+        let previous = self.name.replace(name);
         self.update_timestamp();
-        self.log_activity(format!("Name set to {}", name));
+        self.record_activity(ActivityKind::NameChanged, Some(json!(previous)), Some(json!(self.name)));
     }
 
     pub fn set_surname(&mut self, surname: String) {
-        self.surname = Some(surname);
+        let previous = self.surname.replace(surname);
         self.update_timestamp();
-        self.log_activity(format!("Surname set to {}", surname));
+        self.record_activity(ActivityKind::SurnameChanged, Some(json!(previous)), Some(json!(self.surname)));
     }
 
     pub fn set_email(&mut self, email: String) {
-        self.email = Some(email);
+        let previous = self.email.replace(email);
         self.update_timestamp();
-        self.log_activity(format!("Email set to {}", email));
+        self.record_activity(ActivityKind::EmailChanged, Some(json!(previous)), Some(json!(self.email)));
     }
 
     pub fn set_bio(&mut self, bio: String) {
         self.bio = Some(bio);
         self.update_timestamp();
-        self.log_activity("Bio updated".to_string());
+        self.record_activity(ActivityKind::BioChanged, None, None);
     }
 
     pub fn set_image(&mut self, image: String) {
         self.image = Some(image);
         self.update_timestamp();
-        self.log_activity("Profile image updated".to_string());
+        self.record_activity(ActivityKind::ImageChanged, None, Some(json!(self.image)));
     }
 
     pub fn add_follower(&mut self, follower_id: i32) {
         if !self.followers.contains(&follower_id) {
             self.followers.push(follower_id);
-            self.log_activity(format!("User {} followed", follower_id));
+            self.record_activity(ActivityKind::FollowerAdded, None, Some(json!(follower_id)));
         }
     }
 
     pub fn remove_follower(&mut self, follower_id: i32) {
         if self.followers.contains(&follower_id) {
             self.followers.retain(|&id| id != follower_id);
-            self.log_activity(format!("User {} unfollowed", follower_id));
+            self.record_activity(ActivityKind::FollowerRemoved, Some(json!(follower_id)), None);
         }
     }
 
     pub fn add_following(&mut self, following_id: i32) {
         if !self.following.contains(&following_id) {
             self.following.push(following_id);
-            self.log_activity(format!("Started following user {}", following_id));
+            self.record_activity(ActivityKind::FollowingAdded, None, Some(json!(following_id)));
         }
     }
 
     pub fn remove_following(&mut self, following_id: i32) {
         if self.following.contains(&following_id) {
             self.following.retain(|&id| id != following_id);
-            self.log_activity(format!("Stopped following user {}", following_id));
+            self.record_activity(ActivityKind::FollowingRemoved, Some(json!(following_id)), None);
         }
     }
 
     pub fn update_password(&mut self, new_password_hash: String) {
         self.password_hash = new_password_hash;
         self.update_timestamp();
-        self.log_activity("Password updated".to_string());
+        self.record_activity(ActivityKind::PasswordChanged, None, None);
     }
 
     pub fn set_role(&mut self, role: Role) {
+        let previous = self.role;
         self.role = role;
         self.update_timestamp();
-        self.log_activity(format!("Role set to {:?}", role));
+        self.record_activity(ActivityKind::RoleChanged, Some(json!(previous)), Some(json!(role)));
     }
 
     pub fn set_verification_status(&mut self, is_verified: bool) {
+        let previous = self.is_verified;
         self.is_verified = is_verified;
         self.update_timestamp();
-        self.log_activity(format!("Verification status set to {}", is_verified));
+        self.record_activity(ActivityKind::VerificationChanged, Some(json!(previous)), Some(json!(is_verified)));
     }
 
     pub fn archive_user(&mut self) {
         self.archived_at = Some(chrono::Utc::now().timestamp_millis());
         self.update_timestamp();
-        self.log_activity("User archived".to_string());
+        self.record_activity(ActivityKind::Archived, None, Some(json!(self.archived_at)));
     }
 
     pub fn delete_user(&mut self) {
         self.deleted_at = Some(chrono::Utc::now().timestamp_millis());
         self.update_timestamp();
-        self.log_activity("User deleted".to_string());
+        self.record_activity(ActivityKind::Deleted, None, Some(json!(self.deleted_at)));
+    }
+
+    pub fn restore_user(&mut self) {
+        let previous = json!({ "archived_at": self.archived_at, "deleted_at": self.deleted_at });
+        self.archived_at = None;
+        self.deleted_at = None;
+        self.update_timestamp();
+        self.record_activity(ActivityKind::Restored, Some(previous), None);
+    }
+
+    // Validates `value` against the field definition and stores it in its normalized form
+    pub fn add_custom_field(&mut self, definition: &CustomFieldDefinition, value: Value) -> Result<(), AppError> {
+        if definition.target != CustomFieldTarget::User {
+            return Err(AppError::validation_error(&definition.key, "Field is not defined for users"));
+        }
+        let value = definition
+            .normalize(&value)
+            .map_err(|message| AppError::validation_error(&definition.key, &message))?;
+        let previous = self.custom_fields.insert(definition.key.clone(), value.clone());
+        if previous.as_ref() != Some(&value) {
+            self.update_timestamp();
+            self.record_activity(
+                ActivityKind::CustomFieldSet,
+                Some(json!({ "key": definition.key, "value": previous })),
+                Some(json!({ "key": definition.key, "value": value })),
+            );
+        }
+        Ok(())
     }
 
-    pub fn add_custom_field(&mut self, key: String, value: String) {
-        self.custom_fields.insert(key.clone(), value.clone());
-        self.log_activity(format!("Custom field '{}' set to '{}'", key, value));
+    pub fn remove_custom_field(&mut self, definition: &CustomFieldDefinition) -> Result<(), AppError> {
+        if definition.required {
+            return Err(AppError::validation_error(&definition.key, "Field is required and cannot be removed"));
+        }
+        if let Some(previous) = self.custom_fields.remove(&definition.key) {
+            self.update_timestamp();
+            self.record_activity(
+                ActivityKind::CustomFieldSet,
+                Some(json!({ "key": definition.key, "value": previous })),
+                Some(json!({ "key": definition.key, "value": null })),
+            );
+        }
+        Ok(())
     }
 
-    pub fn get_custom_field(&self, key: &str) -> Option<&String> {
+    pub fn get_custom_field(&self, key: &str) -> Option<&Value> {
         self.custom_fields.get(key)
     }
 
-    pub fn log_activity(&mut self, activity: String) {
-        self.activity_log.push(format!(
-            "{} - {}",
-            chrono::Utc::now().to_rfc3339(),
-            activity
+    pub fn record_activity(&mut self, kind: ActivityKind, old_value: Option<serde_json::Value>, new_value: Option<serde_json::Value>) {
+        self.activity_log.push(ActivityEvent::new(
+            ActivitySubject::User,
+            self.id,
+            &self.activity_context,
+            kind,
+            old_value,
+            new_value,
         ));
     }
 
     pub fn update_timestamp(&mut self) {
         self.updated_at = chrono::Utc::now().timestamp_millis();
     }
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
 }
\ No newline at end of file
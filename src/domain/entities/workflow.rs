@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::task::TaskStatus;
+use crate::domain::time::rfc3339;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: TaskStatus,
+    pub to: TaskStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    pub project_id: Option<i32>,
+    pub statuses: Vec<TaskStatus>,
+    pub initial_status: TaskStatus,
+    pub transitions: Vec<Transition>,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+}
+
+impl Workflow {
+    // Workflow used by tasks outside a project or whose project has not defined its own
+    pub fn default_for(project_id: Option<i32>) -> Self {
+        use TaskStatus::*;
+
+        let transitions = [
+            (Backlog, Pending),
+            (Backlog, Cancelled),
+            (Pending, Backlog),
+            (Pending, InProgress),
+            (Pending, Cancelled),
+            (InProgress, Pending),
+            (InProgress, Blocked),
+            (InProgress, InReview),
+            (InProgress, Completed),
+            (InProgress, Cancelled),
+            (Blocked, InProgress),
+            (Blocked, Cancelled),
+            (InReview, InProgress),
+            (InReview, Completed),
+            (Completed, InProgress),
+            (Cancelled, Backlog),
+            (Cancelled, Pending),
+        ]
+        .into_iter()
+        .map(|(from, to)| Transition { from, to })
+        .collect();
+
+        Workflow {
+            project_id,
+            statuses: TaskStatus::ALL.to_vec(),
+            initial_status: Pending,
+            transitions,
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    pub fn has_status(&self, status: TaskStatus) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    pub fn allows(&self, from: TaskStatus, to: TaskStatus) -> bool {
+        self.transitions.iter().any(|t| t.from == from && t.to == to)
+    }
+
+    pub fn next_statuses(&self, from: TaskStatus) -> Vec<TaskStatus> {
+        self.transitions
+            .iter()
+            .filter(|t| t.from == from)
+            .map(|t| t.to)
+            .collect()
+    }
+}
@@ -1,2 +1,4 @@
 pub mod errors;
-pub mod entities;
\ No newline at end of file
+pub mod entities;
+pub mod pagination;
+pub mod time;
\ No newline at end of file
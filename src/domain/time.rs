@@ -0,0 +1,113 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{de, Deserialize, Deserializer, Serializer};
+use std::fmt;
+
+use crate::domain::errors::AppError;
+
+// Timestamps are stored as milliseconds since the epoch and exchanged as RFC 3339 in UTC,
+// e.g. `2024-05-01T09:30:00.000Z`
+
+pub fn parse_time_zone(name: &str) -> Result<Tz, AppError> {
+    name.parse::<Tz>()
+        .map_err(|_| AppError::validation_error("timezone", &format!("Unknown time zone '{}'", name)))
+}
+
+pub fn to_rfc3339(millis: i64) -> String {
+    match Utc.timestamp_millis_opt(millis) {
+        LocalResult::Single(at) => at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        _ => millis.to_string(),
+    }
+}
+
+pub fn parse_rfc3339(value: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.timestamp_millis())
+        .map_err(|_| format!("'{}' is not an RFC 3339 date-time", value))
+}
+
+// Local times skipped by a DST change move forward an hour; repeated ones take the first
+pub fn resolve_local(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => at,
+        LocalResult::None => resolve_local(tz, local + Duration::hours(1)),
+    }
+}
+
+// Calendar day of the instant as seen in the zone
+pub fn local_date(millis: i64, tz: &Tz) -> NaiveDate {
+    tz.timestamp_millis_opt(millis)
+        .single()
+        .map(|at| at.date_naive())
+        .unwrap_or_default()
+}
+
+pub fn start_of_day(date: NaiveDate, tz: &Tz) -> i64 {
+    resolve_local(tz, date.and_time(NaiveTime::MIN)).timestamp_millis()
+}
+
+// Last millisecond of the day, so `due <= now` turns true once the day is over
+pub fn end_of_day(date: NaiveDate, tz: &Tz) -> i64 {
+    start_of_day(date + Duration::days(1), tz) - 1
+}
+
+// Accepts RFC 3339 strings and, for values written before the switch, plain milliseconds
+struct Millis(i64);
+
+impl<'de> Deserialize<'de> for Millis {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MillisVisitor;
+
+        impl de::Visitor<'_> for MillisVisitor {
+            type Value = Millis;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC 3339 date-time")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Millis, E> {
+                Ok(Millis(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Millis, E> {
+                i64::try_from(value).map(Millis).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Millis, E> {
+                match value.parse::<i64>() {
+                    Ok(millis) => Ok(Millis(millis)),
+                    Err(_) => parse_rfc3339(value).map(Millis).map_err(E::custom),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(MillisVisitor)
+    }
+}
+
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(millis: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_rfc3339(*millis))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        Millis::deserialize(deserializer).map(|m| m.0)
+    }
+}
+
+pub mod rfc3339_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(millis: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match millis {
+            Some(millis) => serializer.serialize_some(&to_rfc3339(*millis)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+        Option::<Millis>::deserialize(deserializer).map(|m| m.map(|m| m.0))
+    }
+}
@@ -0,0 +1,144 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use serde::Deserialize;
+use crate::domain::entities::activity::{ActivityEvent, ActivityKind, ActivitySubject};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use crate::domain::time::rfc3339_option;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ActivityRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Corrupted activity row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<ActivityRepoError> for AppError {
+    fn from(err: ActivityRepoError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ActivityFilter {
+    pub kind: Option<ActivityKind>,
+    pub actor_id: Option<i32>,
+    #[serde(default, with = "rfc3339_option")]
+    pub since: Option<i64>,
+    #[serde(default, with = "rfc3339_option")]
+    pub until: Option<i64>,
+}
+
+pub struct ActivityRepository;
+
+impl ActivityRepository {
+    pub async fn insert_events(client: &impl GenericClient, events: &[ActivityEvent]) -> Result<(), ActivityRepoError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let statement = client
+            .prepare_cached(
+                "INSERT INTO activity_events (subject_type, subject_id, actor_id, kind, old_value, new_value, correlation_id, timestamp) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            )
+            .await
+            .map_err(ActivityRepoError::DatabaseError)?;
+
+        for event in events {
+            client
+                .execute(
+                    &statement,
+                    &[
+                        &to_db_name(&event.subject)?,
+                        &event.subject_id,
+                        &event.actor_id,
+                        &to_db_name(&event.kind)?,
+                        &event.old_value.as_ref().map(Json),
+                        &event.new_value.as_ref().map(Json),
+                        &event.correlation_id,
+                        &event.timestamp,
+                    ],
+                )
+                .await
+                .map_err(ActivityRepoError::DatabaseError)?;
+        }
+        Ok(())
+    }
+
+    // Newest events first
+    pub async fn find_events(
+        client: &impl GenericClient,
+        subject: ActivitySubject,
+        subject_id: i32,
+        filter: &ActivityFilter,
+        page: &PageRequest,
+    ) -> Result<Page<ActivityEvent>, ActivityRepoError> {
+        let subject = to_db_name(&subject)?;
+        let kind = filter.kind.as_ref().map(to_db_name).transpose()?;
+        let conditions = "subject_type = $1 AND subject_id = $2 \
+            AND ($3::TEXT IS NULL OR kind = $3) AND ($4::INT IS NULL OR actor_id = $4) \
+            AND ($5::BIGINT IS NULL OR timestamp >= $5) AND ($6::BIGINT IS NULL OR timestamp < $6)";
+
+        let total: i64 = client
+            .query_one(
+                &format!("SELECT COUNT(*) FROM activity_events WHERE {}", conditions),
+                &[&subject, &subject_id, &kind, &filter.actor_id, &filter.since, &filter.until],
+            )
+            .await
+            .map_err(ActivityRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT id, subject_type, subject_id, actor_id, kind, old_value, new_value, correlation_id, timestamp \
+                     FROM activity_events WHERE {} ORDER BY timestamp DESC, id DESC LIMIT $7 OFFSET $8",
+                    conditions
+                ),
+                &[&subject, &subject_id, &kind, &filter.actor_id, &filter.since, &filter.until, &page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(ActivityRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_event).collect::<Result<_, _>>()?,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+}
+
+fn row_to_event(row: &Row) -> Result<ActivityEvent, ActivityRepoError> {
+    let subject: String = row.get("subject_type");
+    let kind: String = row.get("kind");
+    let old_value: Option<Json<serde_json::Value>> = row.get("old_value");
+    let new_value: Option<Json<serde_json::Value>> = row.get("new_value");
+
+    Ok(ActivityEvent {
+        id: row.get("id"),
+        subject: from_db_name(&subject)?,
+        subject_id: row.get("subject_id"),
+        actor_id: row.get("actor_id"),
+        kind: from_db_name(&kind)?,
+        old_value: old_value.map(|Json(v)| v),
+        new_value: new_value.map(|Json(v)| v),
+        correlation_id: row.get("correlation_id"),
+        timestamp: row.get("timestamp"),
+    })
+}
+
+// Unit enum variants are stored under their serde names
+fn to_db_name<T: serde::Serialize>(value: &T) -> Result<String, ActivityRepoError> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => Ok(name),
+        _ => Err(ActivityRepoError::InvalidRow("Activity enums must serialize to strings".to_string())),
+    }
+}
+
+fn from_db_name<T: for<'de> Deserialize<'de>>(name: &str) -> Result<T, ActivityRepoError> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| ActivityRepoError::InvalidRow(format!("Unknown activity value '{}'", name)))
+}
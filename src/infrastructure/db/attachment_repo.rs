@@ -0,0 +1,171 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use crate::domain::entities::attachment::Attachment;
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AttachmentRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Attachment with ID {0} not found")]
+    AttachmentNotFound(i64),
+}
+
+impl From<AttachmentRepoError> for AppError {
+    fn from(err: AttachmentRepoError) -> Self {
+        match err {
+            AttachmentRepoError::AttachmentNotFound(id) => AppError::not_found("Attachment", id as i32),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const ATTACHMENT_COLUMNS: &str = "id, task_id, filename, content_type, size, checksum, storage_key, uploaded_by, created_at";
+
+pub struct AttachmentRepository;
+
+impl AttachmentRepository {
+    pub async fn find_attachments(client: &impl GenericClient, task_id: i32) -> Result<Vec<Attachment>, AttachmentRepoError> {
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM task_attachments WHERE task_id = $1 ORDER BY created_at, id", ATTACHMENT_COLUMNS),
+                &[&task_id],
+            )
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(row_to_attachment).collect())
+    }
+
+    pub async fn find_attachment(client: &impl GenericClient, task_id: i32, attachment_id: i64) -> Result<Attachment, AttachmentRepoError> {
+        let row = client
+            .query_opt(
+                &format!("SELECT {} FROM task_attachments WHERE id = $1 AND task_id = $2", ATTACHMENT_COLUMNS),
+                &[&attachment_id, &task_id],
+            )
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+
+        row.as_ref().map(row_to_attachment).ok_or(AttachmentRepoError::AttachmentNotFound(attachment_id))
+    }
+
+    pub async fn count_attachments(client: &impl GenericClient, task_id: i32) -> Result<usize, AttachmentRepoError> {
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM task_attachments WHERE task_id = $1", &[&task_id])
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?
+            .get(0);
+
+        Ok(count as usize)
+    }
+
+    pub async fn insert_attachment(client: &impl GenericClient, attachment: &Attachment) -> Result<Attachment, AttachmentRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO task_attachments (task_id, filename, content_type, size, checksum, storage_key, uploaded_by, created_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {}",
+                    ATTACHMENT_COLUMNS
+                ),
+                &[
+                    &attachment.task_id,
+                    &attachment.filename,
+                    &attachment.content_type,
+                    &attachment.size,
+                    &attachment.checksum,
+                    &attachment.storage_key,
+                    &attachment.uploaded_by,
+                    &attachment.created_at,
+                ],
+            )
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+
+        Ok(row_to_attachment(&row))
+    }
+
+    // Copies the metadata of every attachment of `from_task`; the copies share the stored
+    // content, which is only queued for removal once no row refers to it
+    pub async fn copy_attachments(client: &impl GenericClient, from_task: i32, to_task: i32, now: i64) -> Result<u64, AttachmentRepoError> {
+        client
+            .execute(
+                "INSERT INTO task_attachments (task_id, filename, content_type, size, checksum, storage_key, uploaded_by, created_at) \
+                 SELECT $2, filename, content_type, size, checksum, storage_key, uploaded_by, $3 \
+                 FROM task_attachments WHERE task_id = $1 ORDER BY created_at, id",
+                &[&from_task, &to_task, &now],
+            )
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)
+    }
+
+    // The content is queued for removal from storage by a trigger on the table
+    pub async fn delete_attachment(client: &impl GenericClient, attachment_id: i64) -> Result<(), AttachmentRepoError> {
+        let deleted = client
+            .execute("DELETE FROM task_attachments WHERE id = $1", &[&attachment_id])
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            return Err(AttachmentRepoError::AttachmentNotFound(attachment_id));
+        }
+        Ok(())
+    }
+
+    // Queued before new content is written, so content whose rows never commit is still removed
+    pub async fn queue_pending_deletions(client: &impl GenericClient, storage_keys: &[String], now: i64) -> Result<(), AttachmentRepoError> {
+        client
+            .execute(
+                "INSERT INTO attachment_deletions (storage_key, queued_at) SELECT UNNEST($1::TEXT[]), $2 \
+                 ON CONFLICT (storage_key) DO NOTHING",
+                &[&storage_keys, &now],
+            )
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+        Ok(())
+    }
+
+    // Storage keys whose metadata is gone, including attachments removed with a purged task.
+    // Only keys queued before `queued_before` are returned so uploads still in flight keep their content.
+    pub async fn find_pending_deletions(client: &impl GenericClient, queued_before: i64, limit: i64) -> Result<Vec<String>, AttachmentRepoError> {
+        let rows = client
+            .query(
+                "SELECT storage_key FROM attachment_deletions WHERE queued_at < $1 ORDER BY queued_at LIMIT $2",
+                &[&queued_before, &limit],
+            )
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    pub async fn is_pending_deletion(client: &impl GenericClient, storage_key: &str) -> Result<bool, AttachmentRepoError> {
+        let row = client
+            .query_one("SELECT EXISTS (SELECT 1 FROM attachment_deletions WHERE storage_key = $1)", &[&storage_key])
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+        Ok(row.get(0))
+    }
+
+    pub async fn clear_pending_deletions(client: &impl GenericClient, storage_keys: &[String]) -> Result<(), AttachmentRepoError> {
+        client
+            .execute("DELETE FROM attachment_deletions WHERE storage_key = ANY($1)", &[&storage_keys])
+            .await
+            .map_err(AttachmentRepoError::DatabaseError)?;
+        Ok(())
+    }
+}
+
+fn row_to_attachment(row: &Row) -> Attachment {
+    Attachment {
+        id: row.get("id"),
+        task_id: row.get("task_id"),
+        filename: row.get("filename"),
+        content_type: row.get("content_type"),
+        size: row.get("size"),
+        checksum: row.get("checksum"),
+        storage_key: row.get("storage_key"),
+        uploaded_by: row.get("uploaded_by"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,180 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use std::collections::HashMap;
+use crate::domain::entities::board::{Board, Swimlane};
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BoardRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Board with ID {0} not found")]
+    BoardNotFound(i32),
+    #[error("Corrupted board row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<BoardRepoError> for AppError {
+    fn from(err: BoardRepoError) -> Self {
+        match err {
+            BoardRepoError::BoardNotFound(id) => AppError::not_found("Board", id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const BOARD_COLUMNS: &str = "id, project_id, name, columns, swimlane, created_at, updated_at";
+
+pub struct BoardRepository;
+
+impl BoardRepository {
+    pub async fn find_board(client: &impl GenericClient, board_id: i32) -> Result<Board, BoardRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM boards WHERE id = $1", BOARD_COLUMNS), &[&board_id])
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_board(&row),
+            None => Err(BoardRepoError::BoardNotFound(board_id)),
+        }
+    }
+
+    // Locks the board so concurrent moves see each other's WIP counts
+    pub async fn lock_board(client: &impl GenericClient, board_id: i32) -> Result<Board, BoardRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM boards WHERE id = $1 FOR UPDATE", BOARD_COLUMNS), &[&board_id])
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_board(&row),
+            None => Err(BoardRepoError::BoardNotFound(board_id)),
+        }
+    }
+
+    pub async fn find_boards(client: &impl GenericClient, project_id: i32) -> Result<Vec<Board>, BoardRepoError> {
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM boards WHERE project_id = $1 ORDER BY name, id", BOARD_COLUMNS),
+                &[&project_id],
+            )
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_board).collect()
+    }
+
+    pub async fn create_board(client: &impl GenericClient, board: &Board) -> Result<Board, BoardRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO boards (project_id, name, columns, swimlane, created_at, updated_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+                    BOARD_COLUMNS
+                ),
+                &[
+                    &board.project_id,
+                    &board.name,
+                    &Json(&board.columns),
+                    &board.swimlane.map(swimlane_to_str),
+                    &board.created_at,
+                    &board.updated_at,
+                ],
+            )
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        row_to_board(&row)
+    }
+
+    pub async fn update_board(client: &impl GenericClient, board: &Board) -> Result<(), BoardRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE boards SET name = $2, columns = $3, swimlane = $4, updated_at = $5 WHERE id = $1",
+                &[
+                    &board.id,
+                    &board.name,
+                    &Json(&board.columns),
+                    &board.swimlane.map(swimlane_to_str),
+                    &board.updated_at,
+                ],
+            )
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            return Err(BoardRepoError::BoardNotFound(board.id));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_board(client: &impl GenericClient, board_id: i32) -> Result<(), BoardRepoError> {
+        let deleted = client
+            .execute("DELETE FROM boards WHERE id = $1", &[&board_id])
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            return Err(BoardRepoError::BoardNotFound(board_id));
+        }
+        Ok(())
+    }
+
+    pub async fn find_ranks(client: &impl GenericClient, board_id: i32) -> Result<HashMap<i32, i64>, BoardRepoError> {
+        let rows = client
+            .query("SELECT task_id, rank FROM board_cards WHERE board_id = $1", &[&board_id])
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    // Ranks the given cards in order; other columns keep their ranks
+    pub async fn save_card_order(client: &impl GenericClient, board_id: i32, task_ids: &[i32]) -> Result<(), BoardRepoError> {
+        let ranks = (0..task_ids.len() as i64).collect::<Vec<_>>();
+        client
+            .execute(
+                "INSERT INTO board_cards (board_id, task_id, rank) \
+                 SELECT $1, * FROM unnest($2::INT[], $3::BIGINT[]) \
+                 ON CONFLICT (board_id, task_id) DO UPDATE SET rank = EXCLUDED.rank",
+                &[&board_id, &task_ids, &ranks],
+            )
+            .await
+            .map_err(BoardRepoError::DatabaseError)?;
+        Ok(())
+    }
+}
+
+fn row_to_board(row: &Row) -> Result<Board, BoardRepoError> {
+    let Json(columns) = row.get("columns");
+    let swimlane: Option<String> = row.get("swimlane");
+
+    Ok(Board {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        name: row.get("name"),
+        columns,
+        swimlane: swimlane.as_deref().map(swimlane_from_str).transpose()?,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn swimlane_to_str(swimlane: Swimlane) -> &'static str {
+    match swimlane {
+        Swimlane::Assignee => "Assignee",
+        Swimlane::Priority => "Priority",
+        Swimlane::Tag => "Tag",
+    }
+}
+
+fn swimlane_from_str(swimlane: &str) -> Result<Swimlane, BoardRepoError> {
+    match swimlane {
+        "Assignee" => Ok(Swimlane::Assignee),
+        "Priority" => Ok(Swimlane::Priority),
+        "Tag" => Ok(Swimlane::Tag),
+        other => Err(BoardRepoError::InvalidRow(format!("Unknown swimlane '{}'", other))),
+    }
+}
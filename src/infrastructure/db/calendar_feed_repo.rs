@@ -0,0 +1,81 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use crate::domain::entities::calendar_feed::CalendarFeed;
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CalendarFeedRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("User {0} has no calendar feed")]
+    FeedNotFound(i32),
+}
+
+impl From<CalendarFeedRepoError> for AppError {
+    fn from(err: CalendarFeedRepoError) -> Self {
+        match err {
+            CalendarFeedRepoError::FeedNotFound(user_id) => AppError::not_found("Calendar feed of user", user_id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+pub struct CalendarFeedRepository;
+
+impl CalendarFeedRepository {
+    pub async fn find_feed(client: &impl GenericClient, user_id: i32) -> Result<CalendarFeed, CalendarFeedRepoError> {
+        let row = client
+            .query_opt("SELECT user_id, token, created_at FROM calendar_feeds WHERE user_id = $1", &[&user_id])
+            .await
+            .map_err(CalendarFeedRepoError::DatabaseError)?;
+
+        row.as_ref().map(row_to_feed).ok_or(CalendarFeedRepoError::FeedNotFound(user_id))
+    }
+
+    pub async fn find_feed_by_token(client: &impl GenericClient, token: &str) -> Result<Option<CalendarFeed>, CalendarFeedRepoError> {
+        let row = client
+            .query_opt("SELECT user_id, token, created_at FROM calendar_feeds WHERE token = $1", &[&token])
+            .await
+            .map_err(CalendarFeedRepoError::DatabaseError)?;
+
+        Ok(row.as_ref().map(row_to_feed))
+    }
+
+    // Issues a fresh token, creating the feed on first use; the previous address stops working
+    pub async fn rotate_feed(client: &impl GenericClient, user_id: i32, now: i64) -> Result<CalendarFeed, CalendarFeedRepoError> {
+        let row = client
+            .query_one(
+                "INSERT INTO calendar_feeds (user_id, token, created_at) \
+                 VALUES ($1, replace(gen_random_uuid()::TEXT || gen_random_uuid()::TEXT, '-', ''), $2) \
+                 ON CONFLICT (user_id) DO UPDATE SET token = EXCLUDED.token, created_at = EXCLUDED.created_at \
+                 RETURNING user_id, token, created_at",
+                &[&user_id, &now],
+            )
+            .await
+            .map_err(CalendarFeedRepoError::DatabaseError)?;
+
+        Ok(row_to_feed(&row))
+    }
+
+    pub async fn delete_feed(client: &impl GenericClient, user_id: i32) -> Result<(), CalendarFeedRepoError> {
+        let result = client
+            .execute("DELETE FROM calendar_feeds WHERE user_id = $1", &[&user_id])
+            .await
+            .map_err(CalendarFeedRepoError::DatabaseError)?;
+
+        if result == 0 {
+            Err(CalendarFeedRepoError::FeedNotFound(user_id))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn row_to_feed(row: &Row) -> CalendarFeed {
+    CalendarFeed {
+        user_id: row.get(0),
+        token: row.get(1),
+        created_at: row.get(2),
+    }
+}
@@ -0,0 +1,207 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use std::collections::HashMap;
+use crate::domain::entities::comment::{Comment, Reaction};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CommentRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Comment with ID {0} not found")]
+    CommentNotFound(i64),
+}
+
+impl From<CommentRepoError> for AppError {
+    fn from(err: CommentRepoError) -> Self {
+        match err {
+            CommentRepoError::CommentNotFound(id) => AppError::not_found("Comment", id as i32),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const COMMENT_COLUMNS: &str = "id, task_id, parent_id, user_id, body, created_at, edited_at, deleted_at, edits";
+
+pub struct CommentRepository;
+
+impl CommentRepository {
+    pub async fn find_comment(client: &impl GenericClient, comment_id: i64) -> Result<Comment, CommentRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM task_comments WHERE id = $1", COMMENT_COLUMNS), &[&comment_id])
+            .await
+            .map_err(CommentRepoError::DatabaseError)?
+            .ok_or(CommentRepoError::CommentNotFound(comment_id))?;
+
+        let mut comments = vec![row_to_comment(&row)];
+        Self::load_reactions(client, &mut comments).await?;
+        Ok(comments.remove(0))
+    }
+
+    // Top-level comments of one page, oldest first, followed by every reply beneath them
+    pub async fn find_comments(client: &impl GenericClient, task_id: i32, page: &PageRequest) -> Result<Page<Comment>, CommentRepoError> {
+        let total: i64 = client
+            .query_one("SELECT COUNT(*) FROM task_comments WHERE task_id = $1 AND parent_id IS NULL", &[&task_id])
+            .await
+            .map_err(CommentRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "WITH RECURSIVE roots AS ( \
+                         SELECT id FROM task_comments WHERE task_id = $1 AND parent_id IS NULL \
+                         ORDER BY created_at, id LIMIT $2 OFFSET $3 \
+                     ), thread AS ( \
+                         SELECT id FROM roots \
+                         UNION ALL \
+                         SELECT c.id FROM task_comments c JOIN thread t ON c.parent_id = t.id \
+                     ) \
+                     SELECT {} FROM task_comments WHERE id IN (SELECT id FROM thread) ORDER BY created_at, id",
+                    COMMENT_COLUMNS
+                ),
+                &[&task_id, &page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?;
+
+        let mut comments = rows.iter().map(row_to_comment).collect::<Vec<_>>();
+        Self::load_reactions(client, &mut comments).await?;
+        Ok(Page {
+            items: comments,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    // Number of comments from the top of the thread down to this one, inclusive
+    pub async fn find_depth(client: &impl GenericClient, comment_id: i64) -> Result<usize, CommentRepoError> {
+        let depth: i64 = client
+            .query_one(
+                "WITH RECURSIVE ancestors AS ( \
+                     SELECT id, parent_id FROM task_comments WHERE id = $1 \
+                     UNION ALL \
+                     SELECT c.id, c.parent_id FROM task_comments c JOIN ancestors a ON c.id = a.parent_id \
+                 ) \
+                 SELECT COUNT(*) FROM ancestors",
+                &[&comment_id],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?
+            .get(0);
+
+        Ok(depth as usize)
+    }
+
+    pub async fn insert_comment(client: &impl GenericClient, comment: &Comment) -> Result<Comment, CommentRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO task_comments (task_id, parent_id, user_id, body, created_at) \
+                     VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+                    COMMENT_COLUMNS
+                ),
+                &[&comment.task_id, &comment.parent_id, &comment.user_id, &comment.body, &comment.created_at],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?;
+
+        Ok(row_to_comment(&row))
+    }
+
+    pub async fn update_comment(client: &impl GenericClient, comment: &Comment) -> Result<(), CommentRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE task_comments SET body = $2, edited_at = $3, deleted_at = $4, edits = $5 WHERE id = $1",
+                &[&comment.id, &comment.body, &comment.edited_at, &comment.deleted_at, &Json(&comment.edits)],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            return Err(CommentRepoError::CommentNotFound(comment.id));
+        }
+        if comment.is_deleted() {
+            client
+                .execute("DELETE FROM comment_reactions WHERE comment_id = $1", &[&comment.id])
+                .await
+                .map_err(CommentRepoError::DatabaseError)?;
+        }
+        Ok(())
+    }
+
+    // Returns false when the user had already reacted with this emoji
+    pub async fn add_reaction(client: &impl GenericClient, comment_id: i64, user_id: i32, emoji: &str, created_at: i64) -> Result<bool, CommentRepoError> {
+        let added = client
+            .execute(
+                "INSERT INTO comment_reactions (comment_id, user_id, emoji, created_at) VALUES ($1, $2, $3, $4) \
+                 ON CONFLICT DO NOTHING",
+                &[&comment_id, &user_id, &emoji, &created_at],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?;
+
+        Ok(added > 0)
+    }
+
+    pub async fn remove_reaction(client: &impl GenericClient, comment_id: i64, user_id: i32, emoji: &str) -> Result<bool, CommentRepoError> {
+        let removed = client
+            .execute(
+                "DELETE FROM comment_reactions WHERE comment_id = $1 AND user_id = $2 AND emoji = $3",
+                &[&comment_id, &user_id, &emoji],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?;
+
+        Ok(removed > 0)
+    }
+
+    // Reactions are grouped by emoji in the order they were first used
+    async fn load_reactions(client: &impl GenericClient, comments: &mut [Comment]) -> Result<(), CommentRepoError> {
+        let ids = comments.iter().map(|c| c.id).collect::<Vec<_>>();
+        let rows = client
+            .query(
+                "SELECT comment_id, emoji, user_id FROM comment_reactions WHERE comment_id = ANY($1) \
+                 ORDER BY created_at, user_id",
+                &[&ids],
+            )
+            .await
+            .map_err(CommentRepoError::DatabaseError)?;
+
+        let mut reactions: HashMap<i64, Vec<Reaction>> = HashMap::new();
+        for row in &rows {
+            let emoji: String = row.get(1);
+            let entry = reactions.entry(row.get(0)).or_default();
+            match entry.iter_mut().find(|r| r.emoji == emoji) {
+                Some(reaction) => reaction.user_ids.push(row.get(2)),
+                None => entry.push(Reaction { emoji, user_ids: vec![row.get(2)] }),
+            }
+        }
+        for comment in comments.iter_mut() {
+            comment.reactions = reactions.remove(&comment.id).unwrap_or_default();
+        }
+        Ok(())
+    }
+}
+
+fn row_to_comment(row: &Row) -> Comment {
+    let Json(edits) = row.get("edits");
+    let mut comment = Comment {
+        id: row.get("id"),
+        task_id: row.get("task_id"),
+        parent_id: row.get("parent_id"),
+        user_id: row.get("user_id"),
+        body: row.get("body"),
+        created_at: row.get("created_at"),
+        edited_at: row.get("edited_at"),
+        deleted_at: row.get("deleted_at"),
+        edits,
+        reactions: Vec::new(),
+        replies: Vec::new(),
+    };
+    comment.redact();
+    comment
+}
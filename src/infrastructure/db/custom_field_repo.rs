@@ -0,0 +1,189 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use serde::Deserialize;
+use crate::domain::entities::custom_field::{CustomFieldDefinition, CustomFieldTarget};
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CustomFieldRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Custom field with ID {0} not found")]
+    DefinitionNotFound(i32),
+    #[error("Custom field '{0}' already exists")]
+    DuplicateKey(String),
+    #[error("Corrupted custom field row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<CustomFieldRepoError> for AppError {
+    fn from(err: CustomFieldRepoError) -> Self {
+        match err {
+            CustomFieldRepoError::DefinitionNotFound(id) => AppError::not_found("Custom field", id),
+            CustomFieldRepoError::DuplicateKey(key) => {
+                AppError::validation_error("key", &format!("Custom field '{}' already exists", key))
+            }
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const DEFINITION_COLUMNS: &str = "id, target, key, name, field_type, required, allowed_values, created_at, updated_at";
+
+pub struct CustomFieldRepository;
+
+impl CustomFieldRepository {
+    pub async fn find_definitions(
+        client: &impl GenericClient,
+        target: Option<CustomFieldTarget>,
+    ) -> Result<Vec<CustomFieldDefinition>, CustomFieldRepoError> {
+        let target = target.as_ref().map(to_db_name).transpose()?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM custom_field_definitions WHERE ($1::TEXT IS NULL OR target = $1) ORDER BY target, key",
+                    DEFINITION_COLUMNS
+                ),
+                &[&target],
+            )
+            .await
+            .map_err(CustomFieldRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_definition).collect()
+    }
+
+    pub async fn find_definition(client: &impl GenericClient, id: i32) -> Result<CustomFieldDefinition, CustomFieldRepoError> {
+        let row = client
+            .query_opt(
+                &format!("SELECT {} FROM custom_field_definitions WHERE id = $1", DEFINITION_COLUMNS),
+                &[&id],
+            )
+            .await
+            .map_err(CustomFieldRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_definition(&row),
+            None => Err(CustomFieldRepoError::DefinitionNotFound(id)),
+        }
+    }
+
+    pub async fn create_definition(
+        client: &impl GenericClient,
+        definition: &CustomFieldDefinition,
+    ) -> Result<CustomFieldDefinition, CustomFieldRepoError> {
+        let row = client
+            .query_opt(
+                &format!(
+                    "INSERT INTO custom_field_definitions \
+                     (target, key, name, field_type, required, allowed_values, created_at, updated_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+                     ON CONFLICT (target, key) DO NOTHING RETURNING {}",
+                    DEFINITION_COLUMNS
+                ),
+                &[
+                    &to_db_name(&definition.target)?,
+                    &definition.key,
+                    &definition.name,
+                    &to_db_name(&definition.field_type)?,
+                    &definition.required,
+                    &definition.allowed_values,
+                    &definition.created_at,
+                    &definition.updated_at,
+                ],
+            )
+            .await
+            .map_err(CustomFieldRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_definition(&row),
+            None => Err(CustomFieldRepoError::DuplicateKey(definition.key.clone())),
+        }
+    }
+
+    pub async fn update_definition(client: &impl GenericClient, definition: &CustomFieldDefinition) -> Result<(), CustomFieldRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE custom_field_definitions SET name = $2, required = $3, allowed_values = $4, updated_at = $5 \
+                 WHERE id = $1",
+                &[
+                    &definition.id,
+                    &definition.name,
+                    &definition.required,
+                    &definition.allowed_values,
+                    &definition.updated_at,
+                ],
+            )
+            .await
+            .map_err(CustomFieldRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            Err(CustomFieldRepoError::DefinitionNotFound(definition.id))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Values stored under the key stay behind; callers drop them from tasks first
+    pub async fn delete_definition(client: &impl GenericClient, id: i32) -> Result<(), CustomFieldRepoError> {
+        let deleted = client
+            .execute("DELETE FROM custom_field_definitions WHERE id = $1", &[&id])
+            .await
+            .map_err(CustomFieldRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            Err(CustomFieldRepoError::DefinitionNotFound(id))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Values stored under options that an update is about to drop
+    pub async fn count_tasks_using(
+        client: &impl GenericClient,
+        key: &str,
+        options: &[String],
+    ) -> Result<i64, CustomFieldRepoError> {
+        let row = client
+            .query_one(
+                "SELECT COUNT(*) FROM tasks WHERE custom_fields ? $1::TEXT AND ( \
+                     custom_fields ->> $1::TEXT = ANY($2) \
+                     OR (jsonb_typeof(custom_fields -> $1::TEXT) = 'array' \
+                         AND custom_fields -> $1::TEXT ?| $2) \
+                 )",
+                &[&key, &options],
+            )
+            .await
+            .map_err(CustomFieldRepoError::DatabaseError)?;
+        Ok(row.get(0))
+    }
+}
+
+fn row_to_definition(row: &Row) -> Result<CustomFieldDefinition, CustomFieldRepoError> {
+    let target: String = row.get("target");
+    let field_type: String = row.get("field_type");
+
+    Ok(CustomFieldDefinition {
+        id: row.get("id"),
+        target: from_db_name(&target)?,
+        key: row.get("key"),
+        name: row.get("name"),
+        field_type: from_db_name(&field_type)?,
+        required: row.get("required"),
+        allowed_values: row.get("allowed_values"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn to_db_name<T: serde::Serialize>(value: &T) -> Result<String, CustomFieldRepoError> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => Ok(name),
+        _ => Err(CustomFieldRepoError::InvalidRow("Custom field enums must serialize to strings".to_string())),
+    }
+}
+
+fn from_db_name<T: for<'de> Deserialize<'de>>(name: &str) -> Result<T, CustomFieldRepoError> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| CustomFieldRepoError::InvalidRow(format!("Unknown custom field value '{}'", name)))
+}
@@ -0,0 +1,98 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use crate::domain::entities::activity::ActivityContext;
+use crate::domain::entities::history::{FieldChange, TaskVersion};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HistoryRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+}
+
+impl From<HistoryRepoError> for AppError {
+    fn from(err: HistoryRepoError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+pub struct HistoryRepository;
+
+impl HistoryRepository {
+    pub async fn insert_version(
+        client: &impl GenericClient,
+        task_id: i32,
+        changed_at: i64,
+        context: &ActivityContext,
+        changes: &[FieldChange],
+    ) -> Result<(), HistoryRepoError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        client
+            .execute(
+                "INSERT INTO task_versions (task_id, version, changed_at, actor_id, correlation_id, changes) \
+                 VALUES ($1, (SELECT COALESCE(MAX(version), 0) + 1 FROM task_versions WHERE task_id = $1), $2, $3, $4, $5)",
+                &[&task_id, &changed_at, &context.actor_id, &context.correlation_id, &Json(changes)],
+            )
+            .await
+            .map_err(HistoryRepoError::DatabaseError)?;
+        Ok(())
+    }
+
+    pub async fn find_versions(client: &impl GenericClient, task_id: i32) -> Result<Vec<TaskVersion>, HistoryRepoError> {
+        let rows = client
+            .query(
+                "SELECT task_id, version, changed_at, actor_id, correlation_id, changes FROM task_versions \
+                 WHERE task_id = $1 ORDER BY version",
+                &[&task_id],
+            )
+            .await
+            .map_err(HistoryRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(row_to_version).collect())
+    }
+
+    // Newest versions first
+    pub async fn find_versions_page(
+        client: &impl GenericClient,
+        task_id: i32,
+        page: &PageRequest,
+    ) -> Result<Page<TaskVersion>, HistoryRepoError> {
+        let total: i64 = client
+            .query_one("SELECT COUNT(*) FROM task_versions WHERE task_id = $1", &[&task_id])
+            .await
+            .map_err(HistoryRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                "SELECT task_id, version, changed_at, actor_id, correlation_id, changes FROM task_versions \
+                 WHERE task_id = $1 ORDER BY version DESC LIMIT $2 OFFSET $3",
+                &[&task_id, &page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(HistoryRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_version).collect(),
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+}
+
+fn row_to_version(row: &Row) -> TaskVersion {
+    let Json(changes) = row.get("changes");
+    TaskVersion {
+        task_id: row.get("task_id"),
+        version: row.get("version"),
+        changed_at: row.get("changed_at"),
+        actor_id: row.get("actor_id"),
+        correlation_id: row.get("correlation_id"),
+        changes,
+    }
+}
@@ -0,0 +1,154 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{error::SqlState, Error as PgError, Row};
+use crate::domain::entities::label::Label;
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LabelRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Label with ID {0} not found")]
+    LabelNotFound(i32),
+    #[error("Label '{0}' already exists")]
+    DuplicateName(String),
+}
+
+impl From<LabelRepoError> for AppError {
+    fn from(err: LabelRepoError) -> Self {
+        match err {
+            LabelRepoError::LabelNotFound(id) => AppError::not_found("Label", id),
+            LabelRepoError::DuplicateName(name) => AppError::validation_error(
+                "name",
+                &format!("Label '{}' already exists; merge the labels instead", name),
+            ),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+// Usage only counts live tasks; the GIN index on tasks.tags serves the containment check
+const LABEL_COLUMNS: &str = "l.id, l.name, l.color, l.description, l.created_at, l.updated_at, \
+    (SELECT COUNT(*) FROM tasks t WHERE t.tags @> ARRAY[l.name] AND t.archived_at IS NULL AND t.deleted_at IS NULL) AS usage_count";
+
+pub struct LabelRepository;
+
+impl LabelRepository {
+    pub async fn find_labels(client: &impl GenericClient, page: &PageRequest) -> Result<Page<Label>, LabelRepoError> {
+        let total: i64 = client
+            .query_one("SELECT COUNT(*) FROM labels", &[])
+            .await
+            .map_err(LabelRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM labels l ORDER BY l.name LIMIT $1 OFFSET $2", LABEL_COLUMNS),
+                &[&page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(LabelRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_label).collect(),
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    // Labels starting with `query` come first, then those containing it; most used first
+    pub async fn suggest_labels(client: &impl GenericClient, query: &str, limit: i64) -> Result<Vec<Label>, LabelRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM labels l WHERE strpos(l.name, $1) > 0 \
+                     ORDER BY starts_with(l.name, $1) DESC, usage_count DESC, l.name LIMIT $2",
+                    LABEL_COLUMNS
+                ),
+                &[&query, &limit],
+            )
+            .await
+            .map_err(LabelRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(row_to_label).collect())
+    }
+
+    pub async fn find_label(client: &impl GenericClient, id: i32) -> Result<Label, LabelRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM labels l WHERE l.id = $1", LABEL_COLUMNS), &[&id])
+            .await
+            .map_err(LabelRepoError::DatabaseError)?;
+
+        row.map(|row| row_to_label(&row)).ok_or(LabelRepoError::LabelNotFound(id))
+    }
+
+    // Locks the labels so concurrent renames and merges apply one after the other
+    pub async fn find_labels_for_update(client: &impl GenericClient, ids: &[i32]) -> Result<Vec<Label>, LabelRepoError> {
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM labels l WHERE l.id = ANY($1) ORDER BY l.id FOR UPDATE", LABEL_COLUMNS),
+                &[&ids],
+            )
+            .await
+            .map_err(LabelRepoError::DatabaseError)?;
+
+        let labels = rows.iter().map(row_to_label).collect::<Vec<_>>();
+        if let Some(missing) = ids.iter().find(|id| !labels.iter().any(|l| l.id == **id)) {
+            return Err(LabelRepoError::LabelNotFound(*missing));
+        }
+        Ok(labels)
+    }
+
+    pub async fn create_label(client: &impl GenericClient, label: &Label) -> Result<Label, LabelRepoError> {
+        let row = client
+            .query_opt(
+                "INSERT INTO labels (name, color, description, created_at, updated_at) VALUES ($1, $2, $3, $4, $5) \
+                 ON CONFLICT (name) DO NOTHING RETURNING id",
+                &[&label.name, &label.color, &label.description, &label.created_at, &label.updated_at],
+            )
+            .await
+            .map_err(LabelRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => Self::find_label(client, row.get(0)).await,
+            None => Err(LabelRepoError::DuplicateName(label.name.clone())),
+        }
+    }
+
+    // Tasks keep the old name until the caller retags them in the same transaction
+    pub async fn update_label(client: &impl GenericClient, label: &Label) -> Result<(), LabelRepoError> {
+        client
+            .execute(
+                "UPDATE labels SET name = $2, color = $3, description = $4, updated_at = $5 WHERE id = $1",
+                &[&label.id, &label.name, &label.color, &label.description, &label.updated_at],
+            )
+            .await
+            .map_err(|e| match e.code() {
+                Some(code) if *code == SqlState::UNIQUE_VIOLATION => LabelRepoError::DuplicateName(label.name.clone()),
+                _ => LabelRepoError::DatabaseError(e),
+            })?;
+        Ok(())
+    }
+
+    pub async fn delete_labels(client: &impl GenericClient, label_ids: &[i32]) -> Result<(), LabelRepoError> {
+        client
+            .execute("DELETE FROM labels WHERE id = ANY($1)", &[&label_ids])
+            .await
+            .map_err(LabelRepoError::DatabaseError)?;
+        Ok(())
+    }
+}
+
+fn row_to_label(row: &Row) -> Label {
+    Label {
+        id: row.get("id"),
+        name: row.get("name"),
+        color: row.get("color"),
+        description: row.get("description"),
+        usage_count: row.get("usage_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
@@ -0,0 +1,139 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use crate::domain::entities::link::{LinkType, TaskLink};
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LinkRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Link with ID {0} not found")]
+    LinkNotFound(i64),
+    #[error("Corrupted link row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<LinkRepoError> for AppError {
+    fn from(err: LinkRepoError) -> Self {
+        match err {
+            LinkRepoError::LinkNotFound(id) => AppError::not_found("Link", id as i32),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const LINK_COLUMNS: &str = "id, source_id, target_id, link_type, created_by, created_at";
+
+pub struct LinkRepository;
+
+impl LinkRepository {
+    // Links with the task at either end, oldest first
+    pub async fn find_links(client: &impl GenericClient, task_id: i32) -> Result<Vec<TaskLink>, LinkRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM task_links WHERE source_id = $1 OR target_id = $1 ORDER BY created_at, id",
+                    LINK_COLUMNS
+                ),
+                &[&task_id],
+            )
+            .await
+            .map_err(LinkRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_link).collect()
+    }
+
+    pub async fn find_link(client: &impl GenericClient, task_id: i32, link_id: i64) -> Result<TaskLink, LinkRepoError> {
+        let row = client
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM task_links WHERE id = $1 AND (source_id = $2 OR target_id = $2)",
+                    LINK_COLUMNS
+                ),
+                &[&link_id, &task_id],
+            )
+            .await
+            .map_err(LinkRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_link(&row),
+            None => Err(LinkRepoError::LinkNotFound(link_id)),
+        }
+    }
+
+    // Whether `from` already blocks `to`, directly or through other tasks
+    pub async fn blocks_transitively(client: &impl GenericClient, from: i32, to: i32) -> Result<bool, LinkRepoError> {
+        let row = client
+            .query_one(
+                "WITH RECURSIVE blocked(id) AS ( \
+                     SELECT target_id FROM task_links WHERE source_id = $1 AND link_type = 'Blocks' \
+                     UNION \
+                     SELECT l.target_id FROM task_links l JOIN blocked b ON l.source_id = b.id WHERE l.link_type = 'Blocks' \
+                 ) SELECT EXISTS (SELECT 1 FROM blocked WHERE id = $2)",
+                &[&from, &to],
+            )
+            .await
+            .map_err(LinkRepoError::DatabaseError)?;
+        Ok(row.get(0))
+    }
+
+    pub async fn insert_link(client: &impl GenericClient, link: &TaskLink) -> Result<TaskLink, LinkRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO task_links (source_id, target_id, link_type, created_by, created_at) \
+                     VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+                    LINK_COLUMNS
+                ),
+                &[&link.source_id, &link.target_id, &link_type_to_str(link.link_type), &link.created_by, &link.created_at],
+            )
+            .await
+            .map_err(LinkRepoError::DatabaseError)?;
+
+        row_to_link(&row)
+    }
+
+    pub async fn delete_link(client: &impl GenericClient, link_id: i64) -> Result<(), LinkRepoError> {
+        let deleted = client
+            .execute("DELETE FROM task_links WHERE id = $1", &[&link_id])
+            .await
+            .map_err(LinkRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            return Err(LinkRepoError::LinkNotFound(link_id));
+        }
+        Ok(())
+    }
+}
+
+fn row_to_link(row: &Row) -> Result<TaskLink, LinkRepoError> {
+    let link_type: String = row.get("link_type");
+    Ok(TaskLink {
+        id: row.get("id"),
+        source_id: row.get("source_id"),
+        target_id: row.get("target_id"),
+        link_type: link_type_from_str(&link_type)?,
+        created_by: row.get("created_by"),
+        created_at: row.get("created_at"),
+    })
+}
+
+fn link_type_to_str(link_type: LinkType) -> &'static str {
+    match link_type {
+        LinkType::Blocks => "Blocks",
+        LinkType::RelatesTo => "RelatesTo",
+        LinkType::Duplicates => "Duplicates",
+        LinkType::Clones => "Clones",
+    }
+}
+
+fn link_type_from_str(link_type: &str) -> Result<LinkType, LinkRepoError> {
+    match link_type {
+        "Blocks" => Ok(LinkType::Blocks),
+        "RelatesTo" => Ok(LinkType::RelatesTo),
+        "Duplicates" => Ok(LinkType::Duplicates),
+        "Clones" => Ok(LinkType::Clones),
+        other => Err(LinkRepoError::InvalidRow(format!("Unknown link type '{}'", other))),
+    }
+}
@@ -0,0 +1,151 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use crate::domain::entities::milestone::Milestone;
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MilestoneRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Milestone with ID {0} not found")]
+    MilestoneNotFound(i32),
+}
+
+impl From<MilestoneRepoError> for AppError {
+    fn from(err: MilestoneRepoError) -> Self {
+        match err {
+            MilestoneRepoError::MilestoneNotFound(id) => AppError::not_found("Milestone", id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const MILESTONE_COLUMNS: &str = "id, project_id, name, description, start_date, due_date, created_at, updated_at, \
+    ARRAY(SELECT mt.task_id FROM milestone_tasks mt WHERE mt.milestone_id = milestones.id ORDER BY mt.task_id) AS task_ids";
+
+pub struct MilestoneRepository;
+
+impl MilestoneRepository {
+    pub async fn find_milestone(client: &impl GenericClient, milestone_id: i32) -> Result<Milestone, MilestoneRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM milestones WHERE id = $1", MILESTONE_COLUMNS), &[&milestone_id])
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => Ok(row_to_milestone(&row)),
+            None => Err(MilestoneRepoError::MilestoneNotFound(milestone_id)),
+        }
+    }
+
+    // Soonest due first
+    pub async fn find_milestones(client: &impl GenericClient, project_id: i32) -> Result<Vec<Milestone>, MilestoneRepoError> {
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM milestones WHERE project_id = $1 ORDER BY due_date, id", MILESTONE_COLUMNS),
+                &[&project_id],
+            )
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(row_to_milestone).collect())
+    }
+
+    pub async fn create_milestone(client: &impl GenericClient, milestone: &Milestone) -> Result<Milestone, MilestoneRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO milestones (project_id, name, description, start_date, due_date, created_at, updated_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {}",
+                    MILESTONE_COLUMNS
+                ),
+                &[
+                    &milestone.project_id,
+                    &milestone.name,
+                    &milestone.description,
+                    &milestone.start_date,
+                    &milestone.due_date,
+                    &milestone.created_at,
+                    &milestone.updated_at,
+                ],
+            )
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+
+        Ok(row_to_milestone(&row))
+    }
+
+    pub async fn update_milestone(client: &impl GenericClient, milestone: &Milestone) -> Result<(), MilestoneRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE milestones SET name = $2, description = $3, start_date = $4, due_date = $5, updated_at = $6 \
+                 WHERE id = $1",
+                &[
+                    &milestone.id,
+                    &milestone.name,
+                    &milestone.description,
+                    &milestone.start_date,
+                    &milestone.due_date,
+                    &milestone.updated_at,
+                ],
+            )
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            return Err(MilestoneRepoError::MilestoneNotFound(milestone.id));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_milestone(client: &impl GenericClient, milestone_id: i32) -> Result<(), MilestoneRepoError> {
+        let deleted = client
+            .execute("DELETE FROM milestones WHERE id = $1", &[&milestone_id])
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            return Err(MilestoneRepoError::MilestoneNotFound(milestone_id));
+        }
+        Ok(())
+    }
+
+    // A task belongs to at most one milestone, so adding it here moves it from any other
+    pub async fn add_task(client: &impl GenericClient, milestone_id: i32, task_id: i32) -> Result<(), MilestoneRepoError> {
+        client
+            .execute(
+                "INSERT INTO milestone_tasks (milestone_id, task_id) VALUES ($1, $2) \
+                 ON CONFLICT (task_id) DO UPDATE SET milestone_id = EXCLUDED.milestone_id",
+                &[&milestone_id, &task_id],
+            )
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+        Ok(())
+    }
+
+    pub async fn remove_task(client: &impl GenericClient, milestone_id: i32, task_id: i32) -> Result<bool, MilestoneRepoError> {
+        let deleted = client
+            .execute(
+                "DELETE FROM milestone_tasks WHERE milestone_id = $1 AND task_id = $2",
+                &[&milestone_id, &task_id],
+            )
+            .await
+            .map_err(MilestoneRepoError::DatabaseError)?;
+        Ok(deleted > 0)
+    }
+}
+
+fn row_to_milestone(row: &Row) -> Milestone {
+    Milestone {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        name: row.get("name"),
+        description: row.get("description"),
+        start_date: row.get("start_date"),
+        due_date: row.get("due_date"),
+        task_ids: row.get("task_ids"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
@@ -1,4 +1,23 @@
+pub mod activity_repo;
+pub mod attachment_repo;
+pub mod board_repo;
+pub mod calendar_feed_repo;
+pub mod comment_repo;
+pub mod custom_field_repo;
+pub mod history_repo;
+pub mod label_repo;
+pub mod link_repo;
+pub mod milestone_repo;
+pub mod notification_repo;
+pub mod project_repo;
+pub mod reminder_repo;
+pub mod sprint_repo;
+pub mod task_repo;
+pub mod template_repo;
+pub mod time_repo;
 pub mod user_repo;
+pub mod watcher_repo;
+pub mod workflow_repo;
 
 use deadpool_postgres::{Config, ManagerConfig, Pool, RecyclingMethod, Runtime};
 use std::env;
@@ -7,6 +26,7 @@ use thiserror::Error;
 use deadpool::managed::PoolError as DeadpoolError;
 use tokio_postgres::Error as PgError;
 use std::str::FromStr;
+use crate::domain::errors::AppError;
 
 pub type DbPool = Pool;
 
@@ -22,6 +42,16 @@ pub enum DbError {
     InvalidUrl,
 }
 
+impl From<DbError> for AppError {
+    fn from(err: DbError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+pub async fn get_client(pool: &DbPool) -> Result<deadpool_postgres::Client, DbError> {
+    Ok(pool.get().await?)
+}
+
 pub async fn init_db() -> Result<DbPool, DbError> {
     let mut cfg = Config::new();
 
@@ -0,0 +1,129 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use crate::domain::entities::notification::{Notification, NotificationKind};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum NotificationRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Corrupted notification row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<NotificationRepoError> for AppError {
+    fn from(err: NotificationRepoError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+const NOTIFICATION_COLUMNS: &str = "id, user_id, task_id, kind, actor_id, payload, created_at, read_at";
+
+pub struct NotificationRepository;
+
+impl NotificationRepository {
+    pub async fn insert_notifications(client: &impl GenericClient, notifications: &[Notification]) -> Result<(), NotificationRepoError> {
+        for notification in notifications {
+            client
+                .execute(
+                    "INSERT INTO notifications (user_id, task_id, kind, actor_id, payload, created_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6)",
+                    &[
+                        &notification.user_id,
+                        &notification.task_id,
+                        &kind_to_str(notification.kind),
+                        &notification.actor_id,
+                        &notification.payload.as_ref().map(Json),
+                        &notification.created_at,
+                    ],
+                )
+                .await
+                .map_err(NotificationRepoError::DatabaseError)?;
+        }
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn find_notifications(
+        client: &impl GenericClient,
+        user_id: i32,
+        unread_only: bool,
+        page: &PageRequest,
+    ) -> Result<Page<Notification>, NotificationRepoError> {
+        let conditions = "user_id = $1 AND (NOT $2 OR read_at IS NULL)";
+        let total: i64 = client
+            .query_one(&format!("SELECT COUNT(*) FROM notifications WHERE {}", conditions), &[&user_id, &unread_only])
+            .await
+            .map_err(NotificationRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM notifications WHERE {} ORDER BY created_at DESC, id DESC LIMIT $3 OFFSET $4",
+                    NOTIFICATION_COLUMNS, conditions
+                ),
+                &[&user_id, &unread_only, &page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(NotificationRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_notification).collect::<Result<_, _>>()?,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    // Marks the given notifications, or all of them when `ids` is None, as read
+    pub async fn mark_read(client: &impl GenericClient, user_id: i32, ids: Option<&[i64]>, read_at: i64) -> Result<u64, NotificationRepoError> {
+        client
+            .execute(
+                "UPDATE notifications SET read_at = $3 \
+                 WHERE user_id = $1 AND read_at IS NULL AND ($2::BIGINT[] IS NULL OR id = ANY($2))",
+                &[&user_id, &ids, &read_at],
+            )
+            .await
+            .map_err(NotificationRepoError::DatabaseError)
+    }
+}
+
+fn row_to_notification(row: &Row) -> Result<Notification, NotificationRepoError> {
+    let kind: String = row.get("kind");
+    let payload: Option<Json<serde_json::Value>> = row.get("payload");
+
+    Ok(Notification {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        task_id: row.get("task_id"),
+        kind: kind_from_str(&kind)?,
+        actor_id: row.get("actor_id"),
+        payload: payload.map(|Json(value)| value),
+        created_at: row.get("created_at"),
+        read_at: row.get("read_at"),
+    })
+}
+
+fn kind_to_str(kind: NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::Mentioned => "Mentioned",
+        NotificationKind::Commented => "Commented",
+        NotificationKind::StatusChanged => "StatusChanged",
+        NotificationKind::Reassigned => "Reassigned",
+        NotificationKind::Reminder => "Reminder",
+    }
+}
+
+fn kind_from_str(kind: &str) -> Result<NotificationKind, NotificationRepoError> {
+    match kind {
+        "Mentioned" => Ok(NotificationKind::Mentioned),
+        "Commented" => Ok(NotificationKind::Commented),
+        "StatusChanged" => Ok(NotificationKind::StatusChanged),
+        "Reassigned" => Ok(NotificationKind::Reassigned),
+        "Reminder" => Ok(NotificationKind::Reminder),
+        other => Err(NotificationRepoError::InvalidRow(format!("Unknown notification kind '{}'", other))),
+    }
+}
@@ -0,0 +1,233 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::domain::entities::project::{Project, ProjectMember, ProjectRole};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProjectRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Project with ID {0} not found")]
+    ProjectNotFound(i32),
+    #[error("Project key '{0}' is already taken")]
+    DuplicateKey(String),
+    #[error("Corrupted project row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<ProjectRepoError> for AppError {
+    fn from(err: ProjectRepoError) -> Self {
+        match err {
+            ProjectRepoError::ProjectNotFound(id) => AppError::not_found("Project", id),
+            ProjectRepoError::DuplicateKey(key) => {
+                AppError::validation_error("key", &format!("Project key '{}' is already taken", key))
+            }
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProjectFilter {
+    pub member_id: Option<i32>,
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+const PROJECT_COLUMNS: &str = "id, key, name, description, owner_id, archived_at, created_at, updated_at";
+
+pub struct ProjectRepository;
+
+impl ProjectRepository {
+    pub async fn find_project(client: &impl GenericClient, project_id: i32) -> Result<Project, ProjectRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM projects WHERE id = $1", PROJECT_COLUMNS), &[&project_id])
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?
+            .ok_or(ProjectRepoError::ProjectNotFound(project_id))?;
+
+        let mut members = Self::find_members(client, &[project_id]).await?;
+        Ok(row_to_project(&row, members.remove(&project_id).unwrap_or_default()))
+    }
+
+    // Ordered by key; the owner counts as a member
+    pub async fn find_projects(
+        client: &impl GenericClient,
+        filter: &ProjectFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Project>, ProjectRepoError> {
+        let conditions = "($1 OR archived_at IS NULL) AND ($2::INT IS NULL OR owner_id = $2 \
+            OR EXISTS (SELECT 1 FROM project_members m WHERE m.project_id = projects.id AND m.user_id = $2))";
+
+        let total: i64 = client
+            .query_one(
+                &format!("SELECT COUNT(*) FROM projects WHERE {}", conditions),
+                &[&filter.include_archived, &filter.member_id],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM projects WHERE {} ORDER BY key LIMIT $3 OFFSET $4",
+                    PROJECT_COLUMNS, conditions
+                ),
+                &[&filter.include_archived, &filter.member_id, &page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?;
+
+        let ids = rows.iter().map(|row| row.get("id")).collect::<Vec<i32>>();
+        let mut members = Self::find_members(client, &ids).await?;
+        Ok(Page {
+            items: rows
+                .iter()
+                .map(|row| {
+                    let id: i32 = row.get("id");
+                    row_to_project(row, members.remove(&id).unwrap_or_default())
+                })
+                .collect(),
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    pub async fn create_project(client: &impl GenericClient, project: &Project) -> Result<Project, ProjectRepoError> {
+        let row = client
+            .query_opt(
+                &format!(
+                    "INSERT INTO projects (key, name, description, owner_id, archived_at, created_at, updated_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (key) DO NOTHING RETURNING {}",
+                    PROJECT_COLUMNS
+                ),
+                &[
+                    &project.key,
+                    &project.name,
+                    &project.description,
+                    &project.owner_id,
+                    &project.archived_at,
+                    &project.created_at,
+                    &project.updated_at,
+                ],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?
+            .ok_or_else(|| ProjectRepoError::DuplicateKey(project.key.clone()))?;
+
+        let created = row_to_project(&row, project.members.clone());
+        Self::save_members(client, &created).await?;
+        Ok(created)
+    }
+
+    // The key is immutable and not written back
+    pub async fn update_project(client: &impl GenericClient, project: &Project) -> Result<(), ProjectRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE projects SET name = $2, description = $3, owner_id = $4, archived_at = $5, updated_at = $6 \
+                 WHERE id = $1",
+                &[
+                    &project.id,
+                    &project.name,
+                    &project.description,
+                    &project.owner_id,
+                    &project.archived_at,
+                    &project.updated_at,
+                ],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            return Err(ProjectRepoError::ProjectNotFound(project.id));
+        }
+        Ok(())
+    }
+
+    // Replaces the stored membership with `project.members`
+    pub async fn save_members(client: &impl GenericClient, project: &Project) -> Result<(), ProjectRepoError> {
+        let user_ids = project.members.iter().map(|m| m.user_id).collect::<Vec<_>>();
+        let roles = project.members.iter().map(|m| role_to_str(m.role)).collect::<Vec<_>>();
+        let added_at = project.members.iter().map(|m| m.added_at).collect::<Vec<_>>();
+
+        client
+            .execute(
+                "DELETE FROM project_members WHERE project_id = $1 AND NOT (user_id = ANY($2))",
+                &[&project.id, &user_ids],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?;
+        client
+            .execute(
+                "INSERT INTO project_members (project_id, user_id, role, added_at) \
+                 SELECT $1, * FROM unnest($2::INT[], $3::TEXT[], $4::BIGINT[]) \
+                 ON CONFLICT (project_id, user_id) DO UPDATE SET role = EXCLUDED.role",
+                &[&project.id, &user_ids, &roles, &added_at],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?;
+        Ok(())
+    }
+
+    async fn find_members(
+        client: &impl GenericClient,
+        project_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<ProjectMember>>, ProjectRepoError> {
+        let rows = client
+            .query(
+                "SELECT project_id, user_id, role, added_at FROM project_members \
+                 WHERE project_id = ANY($1) ORDER BY added_at, user_id",
+                &[&project_ids],
+            )
+            .await
+            .map_err(ProjectRepoError::DatabaseError)?;
+
+        let mut members: HashMap<i32, Vec<ProjectMember>> = HashMap::new();
+        for row in &rows {
+            let role: String = row.get(2);
+            members.entry(row.get(0)).or_default().push(ProjectMember {
+                user_id: row.get(1),
+                role: role_from_str(&role)?,
+                added_at: row.get(3),
+            });
+        }
+        Ok(members)
+    }
+}
+
+fn row_to_project(row: &Row, members: Vec<ProjectMember>) -> Project {
+    Project {
+        id: row.get("id"),
+        key: row.get("key"),
+        name: row.get("name"),
+        description: row.get("description"),
+        owner_id: row.get("owner_id"),
+        members,
+        archived_at: row.get("archived_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn role_to_str(role: ProjectRole) -> &'static str {
+    match role {
+        ProjectRole::Admin => "Admin",
+        ProjectRole::Member => "Member",
+        ProjectRole::Viewer => "Viewer",
+    }
+}
+
+fn role_from_str(role: &str) -> Result<ProjectRole, ProjectRepoError> {
+    match role {
+        "Admin" => Ok(ProjectRole::Admin),
+        "Member" => Ok(ProjectRole::Member),
+        "Viewer" => Ok(ProjectRole::Viewer),
+        other => Err(ProjectRepoError::InvalidRow(format!("Unknown project role '{}'", other))),
+    }
+}
@@ -0,0 +1,56 @@
+use tokio_postgres::{Client, Error as PgError};
+use std::collections::HashMap;
+use crate::domain::entities::reminder::ReminderSettings;
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReminderRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+}
+
+impl From<ReminderRepoError> for AppError {
+    fn from(err: ReminderRepoError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+pub struct ReminderRepository;
+
+impl ReminderRepository {
+    // Users without stored settings are absent from the map and get the defaults
+    pub async fn find_settings(client: &Client, user_ids: &[i32]) -> Result<HashMap<i32, ReminderSettings>, ReminderRepoError> {
+        let rows = client
+            .query(
+                "SELECT user_id, offsets, updated_at FROM reminder_settings WHERE user_id = ANY($1)",
+                &[&user_ids],
+            )
+            .await
+            .map_err(ReminderRepoError::DatabaseError)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let settings = ReminderSettings {
+                    user_id: row.get(0),
+                    offsets: row.get(1),
+                    updated_at: row.get(2),
+                };
+                (settings.user_id, settings)
+            })
+            .collect())
+    }
+
+    pub async fn save_settings(client: &Client, settings: &ReminderSettings) -> Result<(), ReminderRepoError> {
+        client
+            .execute(
+                "INSERT INTO reminder_settings (user_id, offsets, updated_at) VALUES ($1, $2, $3) \
+                 ON CONFLICT (user_id) DO UPDATE SET offsets = EXCLUDED.offsets, updated_at = EXCLUDED.updated_at",
+                &[&settings.user_id, &settings.offsets, &settings.updated_at],
+            )
+            .await
+            .map_err(ReminderRepoError::DatabaseError)?;
+        Ok(())
+    }
+}
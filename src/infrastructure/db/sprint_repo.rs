@@ -0,0 +1,269 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use crate::domain::entities::sprint::{ScopeChange, ScopeChangeKind, Sprint, SprintState};
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SprintRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Sprint with ID {0} not found")]
+    SprintNotFound(i32),
+    #[error("Corrupted sprint row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<SprintRepoError> for AppError {
+    fn from(err: SprintRepoError) -> Self {
+        match err {
+            SprintRepoError::SprintNotFound(id) => AppError::not_found("Sprint", id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const SPRINT_COLUMNS: &str = "id, project_id, name, goal, start_date, end_date, state, started_at, closed_at, \
+    created_at, updated_at, \
+    ARRAY(SELECT st.task_id FROM sprint_tasks st WHERE st.sprint_id = sprints.id ORDER BY st.added_at, st.task_id) AS task_ids";
+
+pub struct SprintRepository;
+
+impl SprintRepository {
+    pub async fn find_sprint(client: &impl GenericClient, sprint_id: i32) -> Result<Sprint, SprintRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM sprints WHERE id = $1", SPRINT_COLUMNS), &[&sprint_id])
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_sprint(&row),
+            None => Err(SprintRepoError::SprintNotFound(sprint_id)),
+        }
+    }
+
+    // Serializes scope changes and state transitions of one sprint
+    pub async fn lock_sprint(client: &impl GenericClient, sprint_id: i32) -> Result<Sprint, SprintRepoError> {
+        client
+            .query_opt("SELECT id FROM sprints WHERE id = $1 FOR UPDATE", &[&sprint_id])
+            .await
+            .map_err(SprintRepoError::DatabaseError)?
+            .ok_or(SprintRepoError::SprintNotFound(sprint_id))?;
+        Self::find_sprint(client, sprint_id).await
+    }
+
+    // Newest first
+    pub async fn find_sprints(
+        client: &impl GenericClient,
+        project_id: i32,
+        state: Option<SprintState>,
+    ) -> Result<Vec<Sprint>, SprintRepoError> {
+        let state = state.map(state_to_str);
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM sprints WHERE project_id = $1 AND ($2::TEXT IS NULL OR state = $2) \
+                     ORDER BY start_date DESC, id DESC",
+                    SPRINT_COLUMNS
+                ),
+                &[&project_id, &state],
+            )
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_sprint).collect()
+    }
+
+    pub async fn find_active_sprint(client: &impl GenericClient, project_id: i32) -> Result<Option<Sprint>, SprintRepoError> {
+        Ok(Self::find_sprints(client, project_id, Some(SprintState::Active)).await?.into_iter().next())
+    }
+
+    // The sprint that is not yet closed and holds the task, if any
+    pub async fn find_open_sprint_of_task(client: &impl GenericClient, task_id: i32) -> Result<Option<i32>, SprintRepoError> {
+        let row = client
+            .query_opt(
+                "SELECT s.id FROM sprints s JOIN sprint_tasks st ON st.sprint_id = s.id \
+                 WHERE st.task_id = $1 AND s.state <> 'Closed'",
+                &[&task_id],
+            )
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    pub async fn create_sprint(client: &impl GenericClient, sprint: &Sprint) -> Result<Sprint, SprintRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO sprints (project_id, name, goal, start_date, end_date, state, started_at, closed_at, \
+                     created_at, updated_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING {}",
+                    SPRINT_COLUMNS
+                ),
+                &[
+                    &sprint.project_id,
+                    &sprint.name,
+                    &sprint.goal,
+                    &sprint.start_date,
+                    &sprint.end_date,
+                    &state_to_str(sprint.state),
+                    &sprint.started_at,
+                    &sprint.closed_at,
+                    &sprint.created_at,
+                    &sprint.updated_at,
+                ],
+            )
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+
+        row_to_sprint(&row)
+    }
+
+    // Saves the sprint itself; membership changes go through add_task and remove_task
+    pub async fn update_sprint(client: &impl GenericClient, sprint: &Sprint) -> Result<(), SprintRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE sprints SET name = $2, goal = $3, start_date = $4, end_date = $5, state = $6, \
+                 started_at = $7, closed_at = $8, updated_at = $9 WHERE id = $1",
+                &[
+                    &sprint.id,
+                    &sprint.name,
+                    &sprint.goal,
+                    &sprint.start_date,
+                    &sprint.end_date,
+                    &state_to_str(sprint.state),
+                    &sprint.started_at,
+                    &sprint.closed_at,
+                    &sprint.updated_at,
+                ],
+            )
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            return Err(SprintRepoError::SprintNotFound(sprint.id));
+        }
+        Ok(())
+    }
+
+    pub async fn add_task(client: &impl GenericClient, sprint_id: i32, task_id: i32, added_at: i64) -> Result<bool, SprintRepoError> {
+        let inserted = client
+            .execute(
+                "INSERT INTO sprint_tasks (sprint_id, task_id, added_at) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+                &[&sprint_id, &task_id, &added_at],
+            )
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+        Ok(inserted > 0)
+    }
+
+    pub async fn remove_task(client: &impl GenericClient, sprint_id: i32, task_id: i32) -> Result<bool, SprintRepoError> {
+        let deleted = client
+            .execute("DELETE FROM sprint_tasks WHERE sprint_id = $1 AND task_id = $2", &[&sprint_id, &task_id])
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+        Ok(deleted > 0)
+    }
+
+    pub async fn record_scope_changes(client: &impl GenericClient, changes: &[ScopeChange]) -> Result<(), SprintRepoError> {
+        for change in changes {
+            client
+                .execute(
+                    "INSERT INTO sprint_scope_changes (sprint_id, task_id, kind, actor_id, changed_at, after_start) \
+                     VALUES ($1, $2, $3, $4, $5, $6)",
+                    &[
+                        &change.sprint_id,
+                        &change.task_id,
+                        &kind_to_str(change.kind),
+                        &change.actor_id,
+                        &change.changed_at,
+                        &change.after_start,
+                    ],
+                )
+                .await
+                .map_err(SprintRepoError::DatabaseError)?;
+        }
+        Ok(())
+    }
+
+    // Oldest first
+    pub async fn find_scope_changes(client: &impl GenericClient, sprint_id: i32) -> Result<Vec<ScopeChange>, SprintRepoError> {
+        let rows = client
+            .query(
+                "SELECT sprint_id, task_id, kind, actor_id, changed_at, after_start FROM sprint_scope_changes \
+                 WHERE sprint_id = $1 ORDER BY changed_at, id",
+                &[&sprint_id],
+            )
+            .await
+            .map_err(SprintRepoError::DatabaseError)?;
+
+        rows.iter()
+            .map(|row| {
+                let kind: String = row.get(2);
+                Ok(ScopeChange {
+                    sprint_id: row.get(0),
+                    task_id: row.get(1),
+                    kind: kind_from_str(&kind)?,
+                    actor_id: row.get(3),
+                    changed_at: row.get(4),
+                    after_start: row.get(5),
+                })
+            })
+            .collect()
+    }
+}
+
+fn row_to_sprint(row: &Row) -> Result<Sprint, SprintRepoError> {
+    let state: String = row.get("state");
+
+    Ok(Sprint {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        name: row.get("name"),
+        goal: row.get("goal"),
+        start_date: row.get("start_date"),
+        end_date: row.get("end_date"),
+        state: state_from_str(&state)?,
+        task_ids: row.get("task_ids"),
+        started_at: row.get("started_at"),
+        closed_at: row.get("closed_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn state_to_str(state: SprintState) -> &'static str {
+    match state {
+        SprintState::Planned => "Planned",
+        SprintState::Active => "Active",
+        SprintState::Closed => "Closed",
+    }
+}
+
+fn state_from_str(state: &str) -> Result<SprintState, SprintRepoError> {
+    match state {
+        "Planned" => Ok(SprintState::Planned),
+        "Active" => Ok(SprintState::Active),
+        "Closed" => Ok(SprintState::Closed),
+        other => Err(SprintRepoError::InvalidRow(format!("Unknown sprint state '{}'", other))),
+    }
+}
+
+fn kind_to_str(kind: ScopeChangeKind) -> &'static str {
+    match kind {
+        ScopeChangeKind::Added => "Added",
+        ScopeChangeKind::Removed => "Removed",
+        ScopeChangeKind::CarriedIn => "CarriedIn",
+        ScopeChangeKind::CarriedOut => "CarriedOut",
+    }
+}
+
+fn kind_from_str(kind: &str) -> Result<ScopeChangeKind, SprintRepoError> {
+    match kind {
+        "Added" => Ok(ScopeChangeKind::Added),
+        "Removed" => Ok(ScopeChangeKind::Removed),
+        "CarriedIn" => Ok(ScopeChangeKind::CarriedIn),
+        "CarriedOut" => Ok(ScopeChangeKind::CarriedOut),
+        other => Err(SprintRepoError::InvalidRow(format!("Unknown scope change '{}'", other))),
+    }
+}
@@ -0,0 +1,770 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use crate::domain::entities::activity::ActivityContext;
+use crate::domain::entities::caldav::TaskCollection;
+use crate::domain::entities::history::{diff_tasks, FieldChange};
+use crate::domain::entities::task::{Recurrence, Task, TaskStatus};
+use crate::domain::entities::trash::TrashState;
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use crate::infrastructure::db::activity_repo::{ActivityRepoError, ActivityRepository};
+use crate::infrastructure::db::history_repo::{HistoryRepoError, HistoryRepository};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TaskRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Task with ID {0} not found")]
+    TaskNotFound(i32),
+    #[error("Project with ID {0} not found")]
+    ProjectNotFound(i32),
+    #[error("Corrupted task row: {0}")]
+    InvalidRow(String),
+    #[error("Failed to record activity: {0}")]
+    ActivityError(#[from] ActivityRepoError),
+    #[error("Failed to record history: {0}")]
+    HistoryError(#[from] HistoryRepoError),
+}
+
+impl From<TaskRepoError> for AppError {
+    fn from(err: TaskRepoError) -> Self {
+        match err {
+            TaskRepoError::TaskNotFound(id) => AppError::not_found("Task", id),
+            TaskRepoError::ProjectNotFound(id) => AppError::not_found("Project", id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+// Listing criteria for live tasks; custom field values are already normalized by their definitions
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    pub project_id: Option<i32>,
+    pub status: Option<TaskStatus>,
+    pub assigned_to: Option<i32>,
+    pub custom_field: Option<(String, serde_json::Value)>,
+    pub sort_by_field: Option<String>,
+    pub descending: bool,
+    pub due_on: Option<DueDay>,
+    // Open tasks whose due date has passed at this instant
+    pub overdue_as_of: Option<i64>,
+}
+
+// A calendar day of the viewer. Date-only due dates match on their own day wherever they were
+// set; timed ones when they fall within [start, end).
+#[derive(Debug, Clone)]
+pub struct DueDay {
+    pub date: NaiveDate,
+    pub start: i64,
+    pub end: i64,
+}
+
+// Live tasks with a due date for calendar exports
+#[derive(Debug, Clone, Default)]
+pub struct CalendarFilter {
+    pub project_id: Option<i32>,
+    pub tag: Option<String>,
+    // Tasks the user is assigned to or collaborates on
+    pub user_id: Option<i32>,
+    pub include_closed: bool,
+    pub limit: i64,
+}
+
+// Live tasks of a CalDAV collection; $1 is the project and $2 the assignee
+const COLLECTION_MEMBER: &str = "archived_at IS NULL AND deleted_at IS NULL \
+    AND ($1::INT IS NULL OR project_id = $1) AND ($2::INT IS NULL OR assigned_to = $2)";
+
+const TASK_COLUMNS: &str = "id, project_id, title, description, status, created_at, updated_at, due_date, \
+    priority, tags, parent_task, assigned_to, assigned_by, completed_at, archived_at, deleted_at, \
+    recurrence, recurrence_end, dependencies, collaborators, progress, original_estimate, \
+    custom_fields, overdue_at, remaining_estimate, number, checklist, checklist_progress, due_all_day, due_timezone, ical_uid, caldav_name, \
+    (SELECT p.key || '-' || tasks.number FROM projects p WHERE p.id = tasks.project_id) AS reference, \
+    ARRAY(SELECT c.id FROM tasks c WHERE c.parent_task = tasks.id ORDER BY c.position, c.id) AS subtasks";
+
+pub struct TaskRepository;
+
+impl TaskRepository {
+    pub async fn find_task_by_id(client: &impl GenericClient, task_id: i32) -> Result<Task, TaskRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM tasks WHERE id = $1", TASK_COLUMNS), &[&task_id])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_task(&row),
+            None => Err(TaskRepoError::TaskNotFound(task_id)),
+        }
+    }
+
+    // Archived and deleted tasks are left out. A multi-select field matches when it contains the value.
+    pub async fn find_tasks(client: &impl GenericClient, filter: &TaskFilter, page: &PageRequest) -> Result<Page<Task>, TaskRepoError> {
+        let status = filter.status.map(|s| s.as_str());
+        let (field_key, field_value) = match &filter.custom_field {
+            Some((key, value)) => (Some(key.as_str()), Some(Json(value))),
+            None => (None, None),
+        };
+        let conditions = "archived_at IS NULL AND deleted_at IS NULL \
+            AND ($1::TEXT IS NULL OR status = $1) AND ($2::INT IS NULL OR assigned_to = $2) \
+            AND ($3::TEXT IS NULL OR custom_fields -> $3::TEXT @> $4::JSONB) \
+            AND ($5::INT IS NULL OR project_id = $5) \
+            AND ($6::TEXT IS NULL OR CASE WHEN due_all_day \
+                THEN (to_timestamp(due_date / 1000.0) AT TIME ZONE COALESCE(due_timezone, 'UTC'))::DATE = $6::TEXT::DATE \
+                ELSE due_date >= $7 AND due_date < $8 END) \
+            AND ($9::BIGINT IS NULL OR (due_date <= $9 AND status NOT IN ('Completed', 'Cancelled')))";
+        let due_on = filter.due_on.as_ref().map(|day| day.date.to_string());
+        let (due_start, due_end) = match &filter.due_on {
+            Some(day) => (Some(day.start), Some(day.end)),
+            None => (None, None),
+        };
+        let direction = if filter.descending { "DESC" } else { "ASC" };
+
+        let total: i64 = client
+            .query_one(
+                &format!("SELECT COUNT(*) FROM tasks WHERE {}", conditions),
+                &[
+                    &status,
+                    &filter.assigned_to,
+                    &field_key,
+                    &field_value,
+                    &filter.project_id,
+                    &due_on,
+                    &due_start,
+                    &due_end,
+                    &filter.overdue_as_of,
+                ],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM tasks WHERE {} \
+                     ORDER BY custom_fields -> $10::TEXT {dir} NULLS LAST, created_at {dir}, id {dir} \
+                     LIMIT $11 OFFSET $12",
+                    TASK_COLUMNS,
+                    conditions,
+                    dir = direction
+                ),
+                &[
+                    &status,
+                    &filter.assigned_to,
+                    &field_key,
+                    &field_value,
+                    &filter.project_id,
+                    &due_on,
+                    &due_start,
+                    &due_end,
+                    &filter.overdue_as_of,
+                    &filter.sort_by_field,
+                    &page.limit(),
+                    &page.offset(),
+                ],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_task).collect::<Result<_, _>>()?,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    // Soonest due first
+    pub async fn find_calendar_tasks(client: &impl GenericClient, filter: &CalendarFilter) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM tasks WHERE due_date IS NOT NULL AND archived_at IS NULL AND deleted_at IS NULL \
+                     AND ($1::INT IS NULL OR project_id = $1) AND ($2::TEXT IS NULL OR tags @> ARRAY[$2::TEXT]) \
+                     AND ($3::INT IS NULL OR assigned_to = $3 \
+                          OR collaborators @> jsonb_build_array(jsonb_build_object('user_id', $3::INT))) \
+                     AND ($4 OR status NOT IN ('Completed', 'Cancelled')) \
+                     ORDER BY due_date, id LIMIT $5",
+                    TASK_COLUMNS
+                ),
+                &[&filter.project_id, &filter.tag, &filter.user_id, &filter.include_closed, &filter.limit],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Only tasks written after the `since` write sequence, when given
+    pub async fn find_collection_tasks(
+        client: &impl GenericClient,
+        collection: TaskCollection,
+        since: Option<i64>,
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM tasks WHERE {} AND ($3::BIGINT IS NULL OR sync_seq > $3) ORDER BY id",
+                    TASK_COLUMNS, COLLECTION_MEMBER
+                ),
+                &[&collection.project_id(), &collection.assignee_id(), &since],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // The collection's task stored under the resource name, see `caldav::resource_name`
+    pub async fn find_collection_resource(
+        client: &impl GenericClient,
+        collection: TaskCollection,
+        name: &str,
+    ) -> Result<Option<Task>, TaskRepoError> {
+        let row = client
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM tasks WHERE {} AND (caldav_name = $3 OR (caldav_name IS NULL \
+                     AND COALESCE(ical_uid, 'task-' || id || '@taskflow') || '.ics' = $3))",
+                    TASK_COLUMNS, COLLECTION_MEMBER
+                ),
+                &[&collection.project_id(), &collection.assignee_id(), &name],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        row.as_ref().map(row_to_task).transpose()
+    }
+
+    // Resource names of tasks that left the collection after the write sequence and have not
+    // come back since
+    pub async fn find_collection_removals(
+        client: &impl GenericClient,
+        collection: TaskCollection,
+        since: i64,
+    ) -> Result<Vec<String>, TaskRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT DISTINCT resource_name FROM task_tombstones b WHERE sync_seq > $3 \
+                     AND ($1::INT IS NULL OR b.project_id = $1) AND ($2::INT IS NULL OR b.assigned_to = $2) \
+                     AND NOT EXISTS (SELECT 1 FROM tasks t WHERE t.id = b.task_id AND {}) \
+                     ORDER BY resource_name",
+                    COLLECTION_MEMBER
+                ),
+                &[&collection.project_id(), &collection.assignee_id(), &since],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // Latest write that touched the collection, including tasks leaving it; 0 when there is none
+    pub async fn find_collection_sync_seq(client: &impl GenericClient, collection: TaskCollection) -> Result<i64, TaskRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "SELECT GREATEST( \
+                         (SELECT MAX(sync_seq) FROM tasks WHERE {}), \
+                         (SELECT MAX(sync_seq) FROM task_tombstones \
+                          WHERE ($1::INT IS NULL OR project_id = $1) AND ($2::INT IS NULL OR assigned_to = $2)), \
+                         0)",
+                    COLLECTION_MEMBER
+                ),
+                &[&collection.project_id(), &collection.assignee_id()],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        Ok(row.get(0))
+    }
+
+    // Trashed tasks included, so callers can tell a removed task from an unknown UID
+    pub async fn find_tasks_by_ical_uids(client: &impl GenericClient, uids: &[String]) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(&format!("SELECT {} FROM tasks WHERE ical_uid = ANY($1)", TASK_COLUMNS), &[&uids])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Unknown ids are skipped
+    pub async fn find_tasks_by_ids(client: &impl GenericClient, task_ids: &[i32]) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(&format!("SELECT {} FROM tasks WHERE id = ANY($1) ORDER BY id", TASK_COLUMNS), &[&task_ids])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Every task carrying any of the tags, trashed ones included, locked for a rewrite
+    pub async fn find_tasks_with_tags_for_update(client: &impl GenericClient, tags: &[String]) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(&format!("SELECT {} FROM tasks WHERE tags && $1 ORDER BY id FOR UPDATE", TASK_COLUMNS), &[&tags])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Every task with a value under the custom field, trashed ones included, locked for a rewrite
+    pub async fn find_tasks_with_custom_field_for_update(client: &impl GenericClient, key: &str) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(&format!("SELECT {} FROM tasks WHERE custom_fields ? $1::TEXT ORDER BY id FOR UPDATE", TASK_COLUMNS), &[&key])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Live tasks of the project in any of the statuses, unordered
+    pub async fn find_tasks_in_statuses(
+        client: &impl GenericClient,
+        project_id: i32,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<Task>, TaskRepoError> {
+        let statuses = statuses.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM tasks WHERE project_id = $1 AND status = ANY($2) \
+                     AND archived_at IS NULL AND deleted_at IS NULL",
+                    TASK_COLUMNS
+                ),
+                &[&project_id, &statuses],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Inserts the task and its pending activity, which is re-attributed to the new id
+    pub async fn create_task(client: &impl GenericClient, task: &mut Task) -> Result<Task, TaskRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "WITH allocated AS ( \
+                         UPDATE projects SET task_counter = task_counter + 1 WHERE id = $1 RETURNING task_counter \
+                     ) \
+                     INSERT INTO tasks (project_id, title, description, status, created_at, updated_at, due_date, \
+                     priority, tags, parent_task, assigned_to, assigned_by, completed_at, archived_at, deleted_at, \
+                     recurrence, recurrence_end, dependencies, collaborators, progress, original_estimate, \
+                     custom_fields, overdue_at, remaining_estimate, number, position, checklist, checklist_progress, \
+                     due_all_day, due_timezone, ical_uid, caldav_name) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, \
+                     (SELECT task_counter FROM allocated), (SELECT COUNT(*) FROM tasks WHERE parent_task = $10), $25, $26, $27, $28, $29, $30) \
+                     RETURNING {}",
+                    TASK_COLUMNS
+                ),
+                &[
+                    &task.project_id,
+                    &task.title,
+                    &task.description,
+                    &task.status.as_str(),
+                    &task.created_at,
+                    &task.updated_at,
+                    &task.due_date,
+                    &task.priority,
+                    &task.tags,
+                    &task.parent_task,
+                    &task.assigned_to,
+                    &task.assigned_by,
+                    &task.completed_at,
+                    &task.archived_at,
+                    &task.deleted_at,
+                    &task.recurrence.map(recurrence_to_str),
+                    &task.recurrence_end,
+                    &task.dependencies,
+                    &Json(&task.collaborators),
+                    &task.progress.map(i16::from),
+                    &task.original_estimate,
+                    &Json(&task.custom_fields),
+                    &task.overdue_at,
+                    &task.remaining_estimate,
+                    &Json(&task.checklist),
+                    &task.checklist_progress,
+                    &task.due_all_day,
+                    &task.due_timezone,
+                    &task.ical_uid,
+                    &task.caldav_name,
+                ],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        let mut created = row_to_task(&row)?;
+        let mut events = std::mem::take(&mut task.activity_log);
+        events.iter_mut().for_each(|event| event.subject_id = created.id);
+        ActivityRepository::insert_events(client, &events).await?;
+        let changes = diff_tasks(&Task::new(String::new(), None), &created);
+        HistoryRepository::insert_version(client, created.id, created.created_at, &task.activity_context, &changes).await?;
+        created.activity_context = task.activity_context.clone();
+        Ok(created)
+    }
+
+    // Saves the task, records the field-level diff as a new version and drains its pending
+    // activity into the activity table
+    pub async fn update_task(client: &impl GenericClient, task: &mut Task) -> Result<(), TaskRepoError> {
+        let previous = client
+            .query_opt(&format!("SELECT {} FROM tasks WHERE id = $1 FOR UPDATE", TASK_COLUMNS), &[&task.id])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?
+            .ok_or(TaskRepoError::TaskNotFound(task.id))?;
+        let previous = row_to_task(&previous)?;
+        if previous.project_id != task.project_id {
+            let (number, reference) = Self::allocate_task_number(client, task.project_id).await?;
+            task.number = number;
+            task.reference = reference;
+        }
+
+        let result = client
+            .execute(
+                "UPDATE tasks SET project_id = $2, title = $3, description = $4, status = $5, updated_at = $6, \
+                 due_date = $7, priority = $8, tags = $9, parent_task = $10, assigned_to = $11, assigned_by = $12, \
+                 completed_at = $13, archived_at = $14, deleted_at = $15, recurrence = $16, recurrence_end = $17, \
+                 dependencies = $18, collaborators = $19, progress = $20, original_estimate = $21, \
+                 custom_fields = $22, overdue_at = $23, remaining_estimate = $24, number = $25, checklist = $26, \
+                 checklist_progress = $27, due_all_day = $28, due_timezone = $29, \
+                 ical_uid = $30, caldav_name = $31 WHERE id = $1",
+                &[
+                    &task.id,
+                    &task.project_id,
+                    &task.title,
+                    &task.description,
+                    &task.status.as_str(),
+                    &task.updated_at,
+                    &task.due_date,
+                    &task.priority,
+                    &task.tags,
+                    &task.parent_task,
+                    &task.assigned_to,
+                    &task.assigned_by,
+                    &task.completed_at,
+                    &task.archived_at,
+                    &task.deleted_at,
+                    &task.recurrence.map(recurrence_to_str),
+                    &task.recurrence_end,
+                    &task.dependencies,
+                    &Json(&task.collaborators),
+                    &task.progress.map(i16::from),
+                    &task.original_estimate,
+                    &Json(&task.custom_fields),
+                    &task.overdue_at,
+                    &task.remaining_estimate,
+                    &task.number,
+                    &Json(&task.checklist),
+                    &task.checklist_progress,
+                    &task.due_all_day,
+                    &task.due_timezone,
+                    &task.ical_uid,
+                    &task.caldav_name,
+                ],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        if result == 0 {
+            return Err(TaskRepoError::TaskNotFound(task.id));
+        }
+        let changes = diff_tasks(&previous, task);
+        let changed_at = chrono::Utc::now().timestamp_millis();
+        HistoryRepository::insert_version(client, task.id, changed_at, &task.activity_context, &changes).await?;
+        ActivityRepository::insert_events(client, &std::mem::take(&mut task.activity_log)).await?;
+        Ok(())
+    }
+
+    // Open tasks due before `until`, skipping those already flagged overdue
+    pub async fn find_tasks_due_before(client: &impl GenericClient, until: i64, now: i64) -> Result<Vec<Task>, TaskRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM tasks \
+                     WHERE due_date IS NOT NULL AND due_date <= $1 AND (due_date > $2 OR overdue_at IS NULL) \
+                     AND status NOT IN ('Completed', 'Cancelled') AND archived_at IS NULL AND deleted_at IS NULL \
+                     ORDER BY due_date",
+                    TASK_COLUMNS
+                ),
+                &[&until, &now],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter().map(row_to_task).collect()
+    }
+
+    // Returns false when another instance already flagged the task
+    pub async fn mark_overdue(client: &impl GenericClient, task: &mut Task) -> Result<bool, TaskRepoError> {
+        let result = client
+            .execute(
+                "UPDATE tasks SET overdue_at = $2 WHERE id = $1 AND overdue_at IS NULL",
+                &[&task.id, &task.overdue_at],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+        let events = std::mem::take(&mut task.activity_log);
+        if result == 0 {
+            return Ok(false);
+        }
+        let changes = [FieldChange {
+            field: "overdue_at".to_string(),
+            old_value: serde_json::Value::Null,
+            new_value: serde_json::json!(task.overdue_at),
+        }];
+        let changed_at = task.overdue_at.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+        HistoryRepository::insert_version(client, task.id, changed_at, &task.activity_context, &changes).await?;
+        ActivityRepository::insert_events(client, &events).await?;
+        Ok(true)
+    }
+
+    // Newest first; a task both archived and deleted is listed as deleted
+    pub async fn find_trashed_tasks(client: &impl GenericClient, state: TrashState, page: &PageRequest) -> Result<Page<Task>, TaskRepoError> {
+        let (filter, order_by) = match state {
+            TrashState::Archived => ("archived_at IS NOT NULL AND deleted_at IS NULL", "archived_at"),
+            TrashState::Deleted => ("deleted_at IS NOT NULL", "deleted_at"),
+        };
+
+        let total: i64 = client
+            .query_one(&format!("SELECT COUNT(*) FROM tasks WHERE {}", filter), &[])
+            .await
+            .map_err(TaskRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM tasks WHERE {} ORDER BY {} DESC, id DESC LIMIT $1 OFFSET $2",
+                    TASK_COLUMNS, filter, order_by
+                ),
+                &[&page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_task).collect::<Result<_, _>>()?,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    // Trashed tasks whose retention period is over
+    pub async fn find_expired_trash(
+        client: &impl GenericClient,
+        deleted_before: i64,
+        archived_before: Option<i64>,
+    ) -> Result<Vec<i32>, TaskRepoError> {
+        let rows = client
+            .query(
+                "SELECT id FROM tasks WHERE deleted_at < $1 OR ($2::BIGINT IS NOT NULL AND archived_at < $2)",
+                &[&deleted_before, &archived_before],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // Hard-deletes the tasks with their whole subtrees, history and activity. Returns the purged ids.
+    // Run it inside a transaction so a failure cannot leave dangling links or activity behind.
+    pub async fn purge_tasks(client: &impl GenericClient, task_ids: &[i32]) -> Result<Vec<i32>, TaskRepoError> {
+        let rows = client
+            .query(
+                "WITH RECURSIVE doomed(id) AS ( \
+                     SELECT id FROM tasks WHERE id = ANY($1) \
+                     UNION \
+                     SELECT t.id FROM tasks t JOIN doomed d ON t.parent_task = d.id \
+                 ) DELETE FROM tasks WHERE id IN (SELECT id FROM doomed) RETURNING id",
+                &[&task_ids],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+        let purged = rows.iter().map(|row| row.get(0)).collect::<Vec<i32>>();
+
+        // Links go with their tasks; the blocked-by ids mirrored on survivors have to follow
+        client
+            .execute(
+                "UPDATE tasks SET dependencies = ARRAY(SELECT d FROM unnest(dependencies) AS d WHERE d <> ALL($1)) \
+                 WHERE dependencies && $1",
+                &[&purged],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        client
+            .execute(
+                "DELETE FROM activity_events WHERE subject_type = 'Task' AND subject_id = ANY($1)",
+                &[&purged],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+        Ok(purged)
+    }
+
+    // Next number in the project's sequence with the matching reference; none outside a project
+    async fn allocate_task_number(
+        client: &impl GenericClient,
+        project_id: Option<i32>,
+    ) -> Result<(Option<i32>, Option<String>), TaskRepoError> {
+        let Some(project_id) = project_id else {
+            return Ok((None, None));
+        };
+        let row = client
+            .query_opt(
+                "UPDATE projects SET task_counter = task_counter + 1 WHERE id = $1 RETURNING task_counter, key",
+                &[&project_id],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?
+            .ok_or(TaskRepoError::ProjectNotFound(project_id))?;
+
+        let number: i32 = row.get(0);
+        let key: String = row.get(1);
+        Ok((Some(number), Some(format!("{}-{}", key, number))))
+    }
+
+    pub async fn find_task_by_reference(client: &impl GenericClient, key: &str, number: i32) -> Result<Option<Task>, TaskRepoError> {
+        let row = client
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM tasks WHERE number = $2 AND project_id = (SELECT id FROM projects WHERE key = $1)",
+                    TASK_COLUMNS
+                ),
+                &[&key, &number],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        row.as_ref().map(row_to_task).transpose()
+    }
+
+    // Persists the order of `task.subtasks` as the position of each child row
+    pub async fn save_subtask_order(client: &impl GenericClient, task: &Task) -> Result<(), TaskRepoError> {
+        let positions = (0..task.subtasks.len() as i32).collect::<Vec<_>>();
+        client
+            .execute(
+                "UPDATE tasks SET position = o.position \
+                 FROM UNNEST($2::INT[], $3::INT[]) AS o(id, position) \
+                 WHERE tasks.id = o.id AND tasks.parent_task = $1",
+                &[&task.id, &task.subtasks, &positions],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+        Ok(())
+    }
+
+    // Ids of every ancestor of the task, nearest parent first
+    pub async fn find_ancestor_ids(client: &impl GenericClient, task_id: i32) -> Result<Vec<i32>, TaskRepoError> {
+        let rows = client
+            .query(
+                "WITH RECURSIVE ancestors(id, depth) AS ( \
+                     SELECT parent_task, 1 FROM tasks WHERE id = $1 AND parent_task IS NOT NULL \
+                     UNION \
+                     SELECT t.parent_task, a.depth + 1 FROM tasks t JOIN ancestors a ON t.id = a.id \
+                     WHERE t.parent_task IS NOT NULL AND a.depth < 1000 \
+                 ) SELECT id FROM ancestors ORDER BY depth",
+                &[&task_id],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // The task itself and all of its descendants, keyed by id
+    pub async fn find_subtree(client: &impl GenericClient, task_id: i32) -> Result<HashMap<i32, Task>, TaskRepoError> {
+        let rows = client
+            .query(
+                &format!(
+                    "WITH RECURSIVE subtree(id) AS ( \
+                         SELECT id FROM tasks WHERE id = $1 \
+                         UNION \
+                         SELECT t.id FROM tasks t JOIN subtree s ON t.parent_task = s.id \
+                     ) SELECT {} FROM tasks WHERE id IN (SELECT id FROM subtree)",
+                    TASK_COLUMNS
+                ),
+                &[&task_id],
+            )
+            .await
+            .map_err(TaskRepoError::DatabaseError)?;
+
+        rows.iter()
+            .map(|row| row_to_task(row).map(|task| (task.id, task)))
+            .collect()
+    }
+}
+
+fn row_to_task(row: &Row) -> Result<Task, TaskRepoError> {
+    let status: String = row.get("status");
+    let status = status.parse::<TaskStatus>().map_err(TaskRepoError::InvalidRow)?;
+    let recurrence: Option<String> = row.get("recurrence");
+    let recurrence = recurrence.as_deref().map(recurrence_from_str).transpose()?;
+    let progress: Option<i16> = row.get("progress");
+    let Json(collaborators) = row.get("collaborators");
+    let Json(custom_fields) = row.get("custom_fields");
+    let Json(checklist) = row.get("checklist");
+
+    Ok(Task {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        number: row.get("number"),
+        reference: row.get("reference"),
+        title: row.get("title"),
+        description: row.get("description"),
+        status,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        due_date: row.get("due_date"),
+        due_all_day: row.get("due_all_day"),
+        due_timezone: row.get("due_timezone"),
+        priority: row.get("priority"),
+        tags: row.get("tags"),
+        subtasks: row.get("subtasks"),
+        parent_task: row.get("parent_task"),
+        assigned_to: row.get("assigned_to"),
+        assigned_by: row.get("assigned_by"),
+        completed_at: row.get("completed_at"),
+        archived_at: row.get("archived_at"),
+        deleted_at: row.get("deleted_at"),
+        recurrence,
+        recurrence_end: row.get("recurrence_end"),
+        dependencies: row.get("dependencies"),
+        collaborators,
+        progress: progress.map(|p| p.clamp(0, 100) as u8),
+        original_estimate: row.get("original_estimate"),
+        remaining_estimate: row.get("remaining_estimate"),
+        checklist,
+        checklist_progress: row.get("checklist_progress"),
+        ical_uid: row.get("ical_uid"),
+        caldav_name: row.get("caldav_name"),
+        overdue_at: row.get("overdue_at"),
+        activity_log: Vec::new(),
+        activity_context: ActivityContext::default(),
+        custom_fields,
+    })
+}
+
+fn recurrence_to_str(recurrence: Recurrence) -> &'static str {
+    match recurrence {
+        Recurrence::Daily => "Daily",
+        Recurrence::Weekly => "Weekly",
+        Recurrence::Monthly => "Monthly",
+        Recurrence::Yearly => "Yearly",
+    }
+}
+
+fn recurrence_from_str(value: &str) -> Result<Recurrence, TaskRepoError> {
+    match value {
+        "Daily" => Ok(Recurrence::Daily),
+        "Weekly" => Ok(Recurrence::Weekly),
+        "Monthly" => Ok(Recurrence::Monthly),
+        "Yearly" => Ok(Recurrence::Yearly),
+        other => Err(TaskRepoError::InvalidRow(format!("Unknown recurrence '{}'", other))),
+    }
+}
@@ -0,0 +1,119 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use crate::domain::entities::template::{TaskTemplate, TemplateTask};
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TemplateRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Template with ID {0} not found")]
+    TemplateNotFound(i32),
+}
+
+impl From<TemplateRepoError> for AppError {
+    fn from(err: TemplateRepoError) -> Self {
+        match err {
+            TemplateRepoError::TemplateNotFound(id) => AppError::not_found("Template", id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, description, root, created_at, updated_at";
+
+pub struct TemplateRepository;
+
+impl TemplateRepository {
+    pub async fn find_template(client: &impl GenericClient, template_id: i32) -> Result<TaskTemplate, TemplateRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM task_templates WHERE id = $1", TEMPLATE_COLUMNS), &[&template_id])
+            .await
+            .map_err(TemplateRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => Ok(row_to_template(&row)),
+            None => Err(TemplateRepoError::TemplateNotFound(template_id)),
+        }
+    }
+
+    pub async fn find_templates(client: &impl GenericClient) -> Result<Vec<TaskTemplate>, TemplateRepoError> {
+        let rows = client
+            .query(&format!("SELECT {} FROM task_templates ORDER BY name, id", TEMPLATE_COLUMNS), &[])
+            .await
+            .map_err(TemplateRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(row_to_template).collect())
+    }
+
+    pub async fn create_template(client: &impl GenericClient, template: &TaskTemplate) -> Result<TaskTemplate, TemplateRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO task_templates (name, description, root, created_at, updated_at) \
+                     VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+                    TEMPLATE_COLUMNS
+                ),
+                &[
+                    &template.name,
+                    &template.description,
+                    &Json(&template.root),
+                    &template.created_at,
+                    &template.updated_at,
+                ],
+            )
+            .await
+            .map_err(TemplateRepoError::DatabaseError)?;
+
+        Ok(row_to_template(&row))
+    }
+
+    pub async fn update_template(client: &impl GenericClient, template: &TaskTemplate) -> Result<(), TemplateRepoError> {
+        let updated = client
+            .execute(
+                "UPDATE task_templates SET name = $2, description = $3, root = $4, updated_at = $5 WHERE id = $1",
+                &[
+                    &template.id,
+                    &template.name,
+                    &template.description,
+                    &Json(&template.root),
+                    &template.updated_at,
+                ],
+            )
+            .await
+            .map_err(TemplateRepoError::DatabaseError)?;
+
+        if updated == 0 {
+            return Err(TemplateRepoError::TemplateNotFound(template.id));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_template(client: &impl GenericClient, template_id: i32) -> Result<(), TemplateRepoError> {
+        let deleted = client
+            .execute("DELETE FROM task_templates WHERE id = $1", &[&template_id])
+            .await
+            .map_err(TemplateRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            return Err(TemplateRepoError::TemplateNotFound(template_id));
+        }
+        Ok(())
+    }
+}
+
+fn row_to_template(row: &Row) -> TaskTemplate {
+    let Json(root) = row.get::<_, Json<TemplateTask>>("root");
+    let variables = root.variables().into_iter().collect();
+
+    TaskTemplate {
+        id: row.get("id"),
+        name: row.get("name"),
+        description: row.get("description"),
+        root,
+        variables,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
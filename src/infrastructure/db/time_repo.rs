@@ -0,0 +1,244 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use std::collections::HashMap;
+use crate::domain::entities::time_tracking::{ActiveTimer, ReportGrouping, TimeReportRow, WorkLog, WorkLogSource};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TimeRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Work log with ID {0} not found")]
+    WorkLogNotFound(i64),
+    #[error("A timer is already running on task {0}")]
+    TimerRunning(i32),
+    #[error("Corrupted work log row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<TimeRepoError> for AppError {
+    fn from(err: TimeRepoError) -> Self {
+        match err {
+            TimeRepoError::WorkLogNotFound(id) => AppError::not_found("Work log", id as i32),
+            TimeRepoError::TimerRunning(task_id) => {
+                AppError::validation_error("timer", &format!("A timer is already running on task {}", task_id))
+            }
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const WORK_LOG_COLUMNS: &str = "id, task_id, user_id, started_at, minutes, note, source, created_at";
+
+pub struct TimeRepository;
+
+impl TimeRepository {
+    pub async fn find_active_timer(client: &impl GenericClient, user_id: i32) -> Result<Option<ActiveTimer>, TimeRepoError> {
+        let row = client
+            .query_opt("SELECT user_id, task_id, started_at FROM active_timers WHERE user_id = $1", &[&user_id])
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        Ok(row.map(|row| ActiveTimer {
+            user_id: row.get(0),
+            task_id: row.get(1),
+            started_at: row.get(2),
+        }))
+    }
+
+    // A user has at most one running timer, enforced by the primary key
+    pub async fn start_timer(client: &impl GenericClient, timer: &ActiveTimer) -> Result<(), TimeRepoError> {
+        let inserted = client
+            .execute(
+                "INSERT INTO active_timers (user_id, task_id, started_at) VALUES ($1, $2, $3) \
+                 ON CONFLICT (user_id) DO NOTHING",
+                &[&timer.user_id, &timer.task_id, &timer.started_at],
+            )
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        if inserted == 0 {
+            let running = Self::find_active_timer(client, timer.user_id).await?;
+            return Err(TimeRepoError::TimerRunning(running.map_or(timer.task_id, |t| t.task_id)));
+        }
+        Ok(())
+    }
+
+    // Stops the user's timer if it runs on `task_id`, returning it
+    pub async fn take_active_timer(
+        client: &impl GenericClient,
+        user_id: i32,
+        task_id: i32,
+    ) -> Result<Option<ActiveTimer>, TimeRepoError> {
+        let row = client
+            .query_opt(
+                "DELETE FROM active_timers WHERE user_id = $1 AND task_id = $2 RETURNING user_id, task_id, started_at",
+                &[&user_id, &task_id],
+            )
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        Ok(row.map(|row| ActiveTimer {
+            user_id: row.get(0),
+            task_id: row.get(1),
+            started_at: row.get(2),
+        }))
+    }
+
+    pub async fn insert_work_log(client: &impl GenericClient, log: &WorkLog) -> Result<WorkLog, TimeRepoError> {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO work_logs (task_id, user_id, started_at, minutes, note, source, created_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {}",
+                    WORK_LOG_COLUMNS
+                ),
+                &[
+                    &log.task_id,
+                    &log.user_id,
+                    &log.started_at,
+                    &log.minutes,
+                    &log.note,
+                    &source_to_str(log.source),
+                    &log.created_at,
+                ],
+            )
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        row_to_work_log(&row)
+    }
+
+    pub async fn find_work_log(client: &impl GenericClient, id: i64) -> Result<WorkLog, TimeRepoError> {
+        let row = client
+            .query_opt(&format!("SELECT {} FROM work_logs WHERE id = $1", WORK_LOG_COLUMNS), &[&id])
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        match row {
+            Some(row) => row_to_work_log(&row),
+            None => Err(TimeRepoError::WorkLogNotFound(id)),
+        }
+    }
+
+    // Newest first
+    pub async fn find_work_logs(client: &impl GenericClient, task_id: i32, page: &PageRequest) -> Result<Page<WorkLog>, TimeRepoError> {
+        let total: i64 = client
+            .query_one("SELECT COUNT(*) FROM work_logs WHERE task_id = $1", &[&task_id])
+            .await
+            .map_err(TimeRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} FROM work_logs WHERE task_id = $1 ORDER BY started_at DESC, id DESC LIMIT $2 OFFSET $3",
+                    WORK_LOG_COLUMNS
+                ),
+                &[&task_id, &page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_work_log).collect::<Result<_, _>>()?,
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    pub async fn delete_work_log(client: &impl GenericClient, id: i64) -> Result<(), TimeRepoError> {
+        let deleted = client
+            .execute("DELETE FROM work_logs WHERE id = $1", &[&id])
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        if deleted == 0 {
+            Err(TimeRepoError::WorkLogNotFound(id))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Minutes logged per task; tasks without work are absent
+    pub async fn time_spent_by_task(client: &impl GenericClient, task_ids: &[i32]) -> Result<HashMap<i32, i64>, TimeRepoError> {
+        let rows = client
+            .query(
+                "SELECT task_id, SUM(minutes)::BIGINT FROM work_logs WHERE task_id = ANY($1) GROUP BY task_id",
+                &[&task_ids],
+            )
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    // Work started within [from, to), grouped and largest first. Grouping by tag counts a
+    // log once for every tag of its task and leaves out logs on untagged tasks.
+    pub async fn report(
+        client: &impl GenericClient,
+        from: i64,
+        to: i64,
+        grouping: ReportGrouping,
+        user_id: Option<i32>,
+    ) -> Result<Vec<TimeReportRow>, TimeRepoError> {
+        let (key, joins) = match grouping {
+            ReportGrouping::User => ("w.user_id::TEXT", ""),
+            ReportGrouping::Task => ("w.task_id::TEXT", ""),
+            ReportGrouping::Tag => ("tag", "JOIN tasks t ON t.id = w.task_id CROSS JOIN LATERAL unnest(t.tags) AS tag"),
+        };
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {key} AS key, SUM(w.minutes)::BIGINT AS minutes, COUNT(*) AS entries \
+                     FROM work_logs w {joins} \
+                     WHERE w.started_at >= $1 AND w.started_at < $2 AND ($3::INT IS NULL OR w.user_id = $3) \
+                     GROUP BY {key} ORDER BY minutes DESC, key",
+                    key = key,
+                    joins = joins
+                ),
+                &[&from, &to, &user_id],
+            )
+            .await
+            .map_err(TimeRepoError::DatabaseError)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| TimeReportRow {
+                key: row.get("key"),
+                minutes: row.get("minutes"),
+                entries: row.get("entries"),
+            })
+            .collect())
+    }
+}
+
+fn row_to_work_log(row: &Row) -> Result<WorkLog, TimeRepoError> {
+    let source: String = row.get("source");
+    let source = match source.as_str() {
+        "Manual" => WorkLogSource::Manual,
+        "Timer" => WorkLogSource::Timer,
+        other => return Err(TimeRepoError::InvalidRow(format!("Unknown work log source '{}'", other))),
+    };
+
+    Ok(WorkLog {
+        id: row.get("id"),
+        task_id: row.get("task_id"),
+        user_id: row.get("user_id"),
+        started_at: row.get("started_at"),
+        minutes: row.get("minutes"),
+        note: row.get("note"),
+        source,
+        created_at: row.get("created_at"),
+    })
+}
+
+fn source_to_str(source: WorkLogSource) -> &'static str {
+    match source {
+        WorkLogSource::Manual => "Manual",
+        WorkLogSource::Timer => "Timer",
+    }
+}
@@ -1,5 +1,12 @@
-use tokio_postgres::{Client, Error as PgError};
-use crate::domain::entities::user::User;
+use chrono_tz::Tz;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError, Row};
+use crate::domain::entities::activity::ActivityContext;
+use crate::domain::entities::trash::{TrashState, TrashedUser};
+use crate::domain::entities::user::{Role, User};
+use crate::domain::errors::AppError;
+use crate::domain::pagination::{Page, PageRequest};
+use crate::infrastructure::db::activity_repo::{ActivityRepoError, ActivityRepository};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,45 +15,129 @@ pub enum UserRepoError {
     DatabaseError(#[from] PgError),
     #[error("User with ID {0} not found")]
     UserNotFound(i32),
+    #[error("Corrupted user row: {0}")]
+    InvalidRow(String),
+    #[error("Failed to record activity: {0}")]
+    ActivityError(#[from] ActivityRepoError),
 }
 
+impl From<UserRepoError> for AppError {
+    fn from(err: UserRepoError) -> Self {
+        match err {
+            UserRepoError::UserNotFound(id) => AppError::not_found("User", id),
+            other => AppError::database_error(other),
+        }
+    }
+}
+
+const USER_COLUMNS: &str = "id, username, password_hash, name, surname, email, bio, image, followers, following, \
+    created_at, updated_at, archived_at, deleted_at, role, is_verified, timezone, custom_fields";
+
 pub struct UserRepository;
 
 impl UserRepository {
-    pub async fn find_user_by_id(client: &Client, user_id: i32) -> Result<User, UserRepoError> {
+    pub async fn find_user_by_id(client: &impl GenericClient, user_id: i32) -> Result<User, UserRepoError> {
         let row = client
-            .query_opt("SELECT id, username, password_hash FROM users WHERE id = $1", &[&user_id])
+            .query_opt(&format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS), &[&user_id])
             .await
             .map_err(UserRepoError::DatabaseError)?;
 
-        if let Some(row) = row {
-            Ok(User {
-                id: row.get(0),
-                username: row.get(1),
-                password_hash: row.get(2),
-            })
-        } else {
-            Err(UserRepoError::UserNotFound(user_id))
+        match row {
+            Some(row) => row_to_user(&row),
+            None => Err(UserRepoError::UserNotFound(user_id)),
         }
     }
 
-    pub async fn create_user(client: &Client, username: &str, password_hash: &str) -> Result<User, UserRepoError> {
+    // Ids of live users with any of the given usernames, compared case-insensitively
+    pub async fn find_user_ids_by_usernames(client: &impl GenericClient, usernames: &[String]) -> Result<Vec<i32>, UserRepoError> {
+        let usernames = usernames.iter().map(|u| u.to_lowercase()).collect::<Vec<_>>();
+        let rows = client
+            .query(
+                "SELECT id FROM users WHERE LOWER(username) = ANY($1) AND archived_at IS NULL AND deleted_at IS NULL",
+                &[&usernames],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // Unknown users and unparsable stored names fall back to UTC
+    pub async fn find_time_zone(client: &impl GenericClient, user_id: i32) -> Result<Tz, UserRepoError> {
+        let row = client
+            .query_opt("SELECT timezone FROM users WHERE id = $1", &[&user_id])
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        Ok(row
+            .and_then(|row| row.get::<_, String>(0).parse::<Tz>().ok())
+            .unwrap_or(chrono_tz::UTC))
+    }
+
+    // Returns the zone it replaced
+    pub async fn set_time_zone(client: &impl GenericClient, user_id: i32, timezone: Tz, updated_at: i64) -> Result<String, UserRepoError> {
+        let row = client
+            .query_opt(
+                "UPDATE users u SET timezone = $2, updated_at = $3 \
+                 FROM (SELECT id, timezone FROM users WHERE id = $1 FOR UPDATE) previous \
+                 WHERE u.id = previous.id RETURNING previous.timezone",
+                &[&user_id, &timezone.name(), &updated_at],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        row.map(|row| row.get(0)).ok_or(UserRepoError::UserNotFound(user_id))
+    }
+
+    pub async fn create_user(client: &impl GenericClient, username: &str, password_hash: &str) -> Result<User, UserRepoError> {
         let row = client
             .query_one(
-                "INSERT INTO users (username, password_hash) VALUES ($1, $2) RETURNING id, username, password_hash",
+                &format!("INSERT INTO users (username, password_hash) VALUES ($1, $2) RETURNING {}", USER_COLUMNS),
                 &[&username, &password_hash],
             )
             .await
             .map_err(UserRepoError::DatabaseError)?;
 
-        Ok(User {
-            id: row.get(0),
-            username: row.get(1),
-            password_hash: row.get(2),
-        })
+        row_to_user(&row)
+    }
+
+    // Saves the user and drains its pending activity into the activity table
+    pub async fn update_user(client: &impl GenericClient, user: &mut User) -> Result<(), UserRepoError> {
+        let result = client
+            .execute(
+                "UPDATE users SET password_hash = $2, name = $3, surname = $4, email = $5, bio = $6, image = $7, \
+                 followers = $8, following = $9, updated_at = $10, archived_at = $11, deleted_at = $12, role = $13, \
+                 is_verified = $14, timezone = $15, custom_fields = $16 WHERE id = $1",
+                &[
+                    &user.id,
+                    &user.password_hash,
+                    &user.name,
+                    &user.surname,
+                    &user.email,
+                    &user.bio,
+                    &user.image,
+                    &user.followers,
+                    &user.following,
+                    &user.updated_at,
+                    &user.archived_at,
+                    &user.deleted_at,
+                    &role_to_str(user.role),
+                    &user.is_verified,
+                    &user.timezone,
+                    &Json(&user.custom_fields),
+                ],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        if result == 0 {
+            return Err(UserRepoError::UserNotFound(user.id));
+        }
+        ActivityRepository::insert_events(client, &std::mem::take(&mut user.activity_log)).await?;
+        Ok(())
     }
 
-    pub async fn delete_user(client: &Client, user_id: i32) -> Result<(), UserRepoError> {
+    pub async fn delete_user(client: &impl GenericClient, user_id: i32) -> Result<(), UserRepoError> {
         let result = client
             .execute("DELETE FROM users WHERE id = $1", &[&user_id])
             .await
@@ -58,4 +149,166 @@ impl UserRepository {
             Ok(())
         }
     }
+
+    pub async fn find_trashed_user(client: &impl GenericClient, user_id: i32) -> Result<TrashedUser, UserRepoError> {
+        let row = client
+            .query_opt(
+                "SELECT id, username, archived_at, deleted_at FROM users \
+                 WHERE id = $1 AND (archived_at IS NOT NULL OR deleted_at IS NOT NULL)",
+                &[&user_id],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        row.as_ref().map(row_to_trashed_user).ok_or(UserRepoError::UserNotFound(user_id))
+    }
+
+    // Newest first; a user both archived and deleted is listed as deleted
+    pub async fn find_trashed_users(client: &impl GenericClient, state: TrashState, page: &PageRequest) -> Result<Page<TrashedUser>, UserRepoError> {
+        let (filter, order_by) = match state {
+            TrashState::Archived => ("archived_at IS NOT NULL AND deleted_at IS NULL", "archived_at"),
+            TrashState::Deleted => ("deleted_at IS NOT NULL", "deleted_at"),
+        };
+
+        let total: i64 = client
+            .query_one(&format!("SELECT COUNT(*) FROM users WHERE {}", filter), &[])
+            .await
+            .map_err(UserRepoError::DatabaseError)?
+            .get(0);
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT id, username, archived_at, deleted_at FROM users WHERE {} \
+                     ORDER BY {} DESC, id DESC LIMIT $1 OFFSET $2",
+                    filter, order_by
+                ),
+                &[&page.limit(), &page.offset()],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        Ok(Page {
+            items: rows.iter().map(row_to_trashed_user).collect(),
+            total,
+            limit: page.limit(),
+            offset: page.offset(),
+        })
+    }
+
+    pub async fn restore_user(client: &impl GenericClient, user_id: i32, updated_at: i64) -> Result<(), UserRepoError> {
+        let result = client
+            .execute(
+                "UPDATE users SET archived_at = NULL, deleted_at = NULL, updated_at = $2 \
+                 WHERE id = $1 AND (archived_at IS NOT NULL OR deleted_at IS NOT NULL)",
+                &[&user_id, &updated_at],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+
+        if result == 0 {
+            Err(UserRepoError::UserNotFound(user_id))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub async fn purge_user(client: &impl GenericClient, user_id: i32) -> Result<(), UserRepoError> {
+        let purged = Self::purge_where(client, "id = $1 AND (archived_at IS NOT NULL OR deleted_at IS NOT NULL)", &[&user_id]).await?;
+        if purged.is_empty() {
+            Err(UserRepoError::UserNotFound(user_id))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Hard-deletes trashed users whose retention period is over, with their activity
+    pub async fn purge_expired_users(
+        client: &impl GenericClient,
+        deleted_before: i64,
+        archived_before: Option<i64>,
+    ) -> Result<Vec<i32>, UserRepoError> {
+        Self::purge_where(
+            client,
+            "deleted_at < $1 OR ($2::BIGINT IS NOT NULL AND archived_at < $2)",
+            &[&deleted_before, &archived_before],
+        )
+        .await
+    }
+
+    async fn purge_where(
+        client: &impl GenericClient,
+        condition: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Vec<i32>, UserRepoError> {
+        let rows = client
+            .query(&format!("DELETE FROM users WHERE {} RETURNING id", condition), params)
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+        let purged = rows.iter().map(|row| row.get(0)).collect::<Vec<i32>>();
+
+        client
+            .execute(
+                "DELETE FROM activity_events WHERE subject_type = 'User' AND subject_id = ANY($1)",
+                &[&purged],
+            )
+            .await
+            .map_err(UserRepoError::DatabaseError)?;
+        Ok(purged)
+    }
+}
+
+fn row_to_user(row: &Row) -> Result<User, UserRepoError> {
+    let role: String = row.get("role");
+    let created_at: i64 = row.get("created_at");
+    // Users never changed since updated_at was added have none
+    let updated_at: Option<i64> = row.get("updated_at");
+    let Json(custom_fields) = row.get("custom_fields");
+
+    Ok(User {
+        id: row.get("id"),
+        username: row.get("username"),
+        password_hash: row.get("password_hash"),
+        name: row.get("name"),
+        surname: row.get("surname"),
+        email: row.get("email"),
+        bio: row.get("bio"),
+        image: row.get("image"),
+        followers: row.get("followers"),
+        following: row.get("following"),
+        created_at,
+        updated_at: updated_at.unwrap_or(created_at),
+        archived_at: row.get("archived_at"),
+        deleted_at: row.get("deleted_at"),
+        role: role_from_str(&role)?,
+        is_verified: row.get("is_verified"),
+        timezone: row.get("timezone"),
+        activity_log: Vec::new(),
+        activity_context: ActivityContext::default(),
+        custom_fields,
+    })
+}
+
+fn role_to_str(role: Role) -> &'static str {
+    match role {
+        Role::Admin => "Admin",
+        Role::Regular => "Regular",
+    }
+}
+
+fn role_from_str(role: &str) -> Result<Role, UserRepoError> {
+    match role {
+        "Admin" => Ok(Role::Admin),
+        "Regular" => Ok(Role::Regular),
+        other => Err(UserRepoError::InvalidRow(format!("Unknown role '{}'", other))),
+    }
+}
+
+fn row_to_trashed_user(row: &Row) -> TrashedUser {
+    TrashedUser {
+        id: row.get(0),
+        username: row.get(1),
+        archived_at: row.get(2),
+        deleted_at: row.get(3),
+    }
 }
@@ -0,0 +1,74 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error as PgError, Row};
+use crate::domain::entities::notification::Watcher;
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WatcherRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+}
+
+impl From<WatcherRepoError> for AppError {
+    fn from(err: WatcherRepoError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+pub struct WatcherRepository;
+
+impl WatcherRepository {
+    pub async fn find_watchers(client: &impl GenericClient, task_id: i32) -> Result<Vec<Watcher>, WatcherRepoError> {
+        let rows = client
+            .query(
+                "SELECT task_id, user_id, added_at FROM task_watchers WHERE task_id = $1 ORDER BY added_at, user_id",
+                &[&task_id],
+            )
+            .await
+            .map_err(WatcherRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(row_to_watcher).collect())
+    }
+
+    pub async fn find_watcher_ids(client: &impl GenericClient, task_id: i32) -> Result<Vec<i32>, WatcherRepoError> {
+        let rows = client
+            .query("SELECT user_id FROM task_watchers WHERE task_id = $1", &[&task_id])
+            .await
+            .map_err(WatcherRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // Returns the users that were not watching yet
+    pub async fn add_watchers(client: &impl GenericClient, task_id: i32, user_ids: &[i32], added_at: i64) -> Result<Vec<i32>, WatcherRepoError> {
+        let rows = client
+            .query(
+                "INSERT INTO task_watchers (task_id, user_id, added_at) \
+                 SELECT $1, user_id, $3 FROM unnest($2::INT[]) AS user_id \
+                 ON CONFLICT DO NOTHING RETURNING user_id",
+                &[&task_id, &user_ids, &added_at],
+            )
+            .await
+            .map_err(WatcherRepoError::DatabaseError)?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    pub async fn remove_watcher(client: &impl GenericClient, task_id: i32, user_id: i32) -> Result<bool, WatcherRepoError> {
+        let removed = client
+            .execute("DELETE FROM task_watchers WHERE task_id = $1 AND user_id = $2", &[&task_id, &user_id])
+            .await
+            .map_err(WatcherRepoError::DatabaseError)?;
+
+        Ok(removed > 0)
+    }
+}
+
+fn row_to_watcher(row: &Row) -> Watcher {
+    Watcher {
+        task_id: row.get(0),
+        user_id: row.get(1),
+        added_at: row.get(2),
+    }
+}
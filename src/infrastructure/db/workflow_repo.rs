@@ -0,0 +1,81 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{types::Json, Error as PgError};
+use crate::domain::entities::task::TaskStatus;
+use crate::domain::entities::workflow::Workflow;
+use crate::domain::errors::AppError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WorkflowRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] PgError),
+    #[error("Corrupted workflow row: {0}")]
+    InvalidRow(String),
+}
+
+impl From<WorkflowRepoError> for AppError {
+    fn from(err: WorkflowRepoError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+pub struct WorkflowRepository;
+
+impl WorkflowRepository {
+    // Falls back to the default workflow when the project has not defined one
+    pub async fn find_for_project(client: &impl GenericClient, project_id: Option<i32>) -> Result<Workflow, WorkflowRepoError> {
+        let Some(id) = project_id else {
+            return Ok(Workflow::default_for(None));
+        };
+        let row = client
+            .query_opt(
+                "SELECT project_id, statuses, initial_status, transitions, updated_at FROM workflows WHERE project_id = $1",
+                &[&id],
+            )
+            .await
+            .map_err(WorkflowRepoError::DatabaseError)?;
+
+        let Some(row) = row else {
+            return Ok(Workflow::default_for(project_id));
+        };
+
+        let statuses: Vec<String> = row.get(1);
+        let statuses = statuses
+            .iter()
+            .map(|s| s.parse::<TaskStatus>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(WorkflowRepoError::InvalidRow)?;
+        let initial_status: String = row.get(2);
+        let Json(transitions) = row.get(3);
+
+        Ok(Workflow {
+            project_id: Some(row.get(0)),
+            statuses,
+            initial_status: initial_status.parse().map_err(WorkflowRepoError::InvalidRow)?,
+            transitions,
+            updated_at: row.get(4),
+        })
+    }
+
+    pub async fn save_workflow(client: &impl GenericClient, workflow: &Workflow) -> Result<(), WorkflowRepoError> {
+        let statuses = workflow.statuses.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        client
+            .execute(
+                "INSERT INTO workflows (project_id, statuses, initial_status, transitions, updated_at) \
+                 VALUES ($1, $2, $3, $4, $5) \
+                 ON CONFLICT (project_id) DO UPDATE SET statuses = EXCLUDED.statuses, \
+                 initial_status = EXCLUDED.initial_status, transitions = EXCLUDED.transitions, \
+                 updated_at = EXCLUDED.updated_at",
+                &[
+                    &workflow.project_id,
+                    &statuses,
+                    &workflow.initial_status.as_str(),
+                    &Json(&workflow.transitions),
+                    &workflow.updated_at,
+                ],
+            )
+            .await
+            .map_err(WorkflowRepoError::DatabaseError)?;
+        Ok(())
+    }
+}
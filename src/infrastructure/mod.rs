@@ -1,2 +1,3 @@
 pub mod db;
-pub mod redis;
\ No newline at end of file
+pub mod redis;
+pub mod storage;
\ No newline at end of file
@@ -1 +1,15 @@
-pub mod redis_service;
\ No newline at end of file
+pub mod redis_service;
+
+use bb8_redis::{bb8::Pool, RedisConnectionManager};
+use std::env;
+
+use redis_service::RedisServiceError;
+
+pub type RedisPool = Pool<RedisConnectionManager>;
+
+pub async fn init_redis() -> Result<RedisPool, RedisServiceError> {
+    let redis_url = env::var("REDIS_URL")?;
+    let manager = RedisConnectionManager::new(redis_url)?;
+    let pool = Pool::builder().build(manager).await?;
+    Ok(pool)
+}
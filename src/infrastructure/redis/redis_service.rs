@@ -1,10 +1,12 @@
 use bb8_redis::{bb8::Pool, RedisConnectionManager};
-use redis::{AsyncCommands, RedisError};
+use redis::{AsyncCommands, ExistenceCheck, RedisError, SetExpiry, SetOptions};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 
 use bb8_redis::bb8::RunError;
+use crate::domain::errors::AppError;
 
 #[derive(Debug, Error)]
 pub enum RedisServiceError {
@@ -12,6 +14,14 @@ pub enum RedisServiceError {
     ConnectionError(#[from] RunError<RedisError>),
     #[error("Redis command error: {0}")]
     CommandError(#[from] RedisError),
+    #[error("Environment variable error: {0}")]
+    EnvError(#[from] std::env::VarError),
+}
+
+impl From<RedisServiceError> for AppError {
+    fn from(err: RedisServiceError) -> Self {
+        AppError::database_error(err)
+    }
 }
 
 pub struct RedisService<T> {
@@ -33,13 +43,13 @@ impl<T> RedisService<T> {
         V: Serialize,
     {
         let mut conn = self.pool.get().await.expect("Failed to get connection");
-        let serialized_value = serde_json::to_string(&value).map_err(|e| {
+        let serialized_value = serde_json::to_string(&value).map_err(|_| {
             RedisServiceError::CommandError(redis::RedisError::from((
                 redis::ErrorKind::TypeError,
                 "Serialization error",
             )))
         })?;
-        conn.set(key.as_ref(), serialized_value).await?;
+        conn.set::<_, _, ()>(key.as_ref(), serialized_value).await?;
         Ok(())
     }
 
@@ -51,7 +61,7 @@ impl<T> RedisService<T> {
         let mut conn = self.pool.get().await.expect("Failed to get connection");
         let result: Option<String> = conn.get(key.as_ref()).await?;
         if let Some(data) = result {
-            let deserialized_value = serde_json::from_str(&data).map_err(|e| {
+            let deserialized_value = serde_json::from_str(&data).map_err(|_| {
                 RedisServiceError::CommandError(redis::RedisError::from((
                     redis::ErrorKind::TypeError,
                     "Deserialization error",
@@ -68,10 +78,63 @@ impl<T> RedisService<T> {
         K: AsRef<str>,
     {
         let mut conn = self.pool.get().await.expect("Failed to get connection");
-        conn.del(key.as_ref()).await?;
+        conn.del::<_, ()>(key.as_ref()).await?;
+        Ok(())
+    }
+
+    // Atomically claims `key` for `ttl_secs`; returns false if someone else holds it
+    pub async fn set_if_absent<K>(&self, key: K, ttl_secs: u64) -> Result<bool, RedisServiceError>
+    where
+        K: AsRef<str>,
+    {
+        let mut conn = self.pool.get().await?;
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::EX(ttl_secs));
+        let result: Option<String> = conn.set_options(key.as_ref(), 1, options).await?;
+        Ok(result.is_some())
+    }
+
+    pub async fn push_value<K>(&self, key: K, value: &T) -> Result<(), RedisServiceError>
+    where
+        K: AsRef<str>,
+        T: Serialize,
+    {
+        let mut conn = self.pool.get().await?;
+        let serialized_value = serde_json::to_string(value).map_err(|_| {
+            RedisServiceError::CommandError(redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "Serialization error",
+            )))
+        })?;
+        conn.rpush::<_, _, ()>(key.as_ref(), serialized_value).await?;
         Ok(())
     }
 
+    // Removes up to `count` values from the head of the list; values that no longer decode are dropped
+    pub async fn pop_values<K>(&self, key: K, count: usize) -> Result<Vec<T>, RedisServiceError>
+    where
+        K: AsRef<str>,
+        T: for<'de> Deserialize<'de>,
+    {
+        let Some(count) = NonZeroUsize::new(count) else {
+            return Ok(Vec::new());
+        };
+        let mut conn = self.pool.get().await?;
+        let values: Option<Vec<String>> = conn.lpop(key.as_ref(), Some(count)).await?;
+        Ok(values
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|value| match serde_json::from_str(value) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    tracing::warn!("Dropping undecodable entry of {}: {}", key.as_ref(), err);
+                    None
+                }
+            })
+            .collect())
+    }
+
     pub async fn key_exists<K>(&self, key: K) -> Result<bool, RedisServiceError>
     where
         K: AsRef<str>,
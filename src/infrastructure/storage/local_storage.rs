@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use axum::body::Bytes;
+use std::{io::ErrorKind, path::PathBuf};
+
+use super::{validate_key, BlobStorage, StorageError};
+
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl BlobStorage for LocalStorage {
+    // Writes to a temporary file first so readers never see partial content
+    async fn put(&self, key: &str, content: Bytes, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, &content).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(content) => Ok(Bytes::from(content)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(StorageError::NotFound(key.to_string())),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("taskflow-{}-{}-{}", name, std::process::id(), nanos))
+    }
+
+    #[tokio::test]
+    async fn putting_getting_and_deleting_round_trips() {
+        let root = temp_root("round-trip");
+        let storage = LocalStorage::new(&root);
+        let key = "tasks/1/1700000000000-abc";
+
+        storage.put(key, Bytes::from_static(b"hello"), "text/plain").await.unwrap();
+        assert_eq!(storage.get(key).await.unwrap(), Bytes::from_static(b"hello"));
+        assert!(!root.join("tasks/1/1700000000000-abc.partial").exists());
+
+        storage.put(key, Bytes::from_static(b"replaced"), "text/plain").await.unwrap();
+        assert_eq!(storage.get(key).await.unwrap(), Bytes::from_static(b"replaced"));
+
+        storage.delete(key).await.unwrap();
+        assert!(matches!(storage.get(key).await, Err(StorageError::NotFound(_))));
+        // Deleting again is not an error
+        storage.delete(key).await.unwrap();
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keys_cannot_leave_the_root() {
+        let root = temp_root("invalid-key");
+        let storage = LocalStorage::new(&root);
+
+        for key in ["../outside", "/etc/passwd", "tasks//1", "tasks/./1", ""] {
+            let result = storage.put(key, Bytes::from_static(b"x"), "text/plain").await;
+            assert!(matches!(result, Err(StorageError::InvalidKey(_))), "{}", key);
+        }
+        assert!(!root.exists());
+    }
+}
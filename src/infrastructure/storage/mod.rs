@@ -0,0 +1,78 @@
+pub mod local_storage;
+pub mod s3_storage;
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use sha2::{Digest, Sha256};
+use std::{env, sync::Arc};
+use thiserror::Error;
+use crate::domain::errors::AppError;
+
+use local_storage::LocalStorage;
+use s3_storage::S3Storage;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Object {0} not found")]
+    NotFound(String),
+    #[error("Invalid storage key {0}")]
+    InvalidKey(String),
+    #[error("Storage I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Storage request failed: {0}")]
+    Request(String),
+    #[error("Environment variable error: {0}")]
+    EnvError(#[from] env::VarError),
+}
+
+impl From<StorageError> for AppError {
+    fn from(err: StorageError) -> Self {
+        AppError::database_error(err)
+    }
+}
+
+// Where attachment content lives; metadata stays in Postgres
+#[async_trait]
+pub trait BlobStorage: Send + Sync {
+    async fn put(&self, key: &str, content: Bytes, content_type: &str) -> Result<(), StorageError>;
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError>;
+    // Deleting a missing object succeeds, so cleanup can be retried
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+pub type SharedStorage = Arc<dyn BlobStorage>;
+
+// STORAGE_BACKEND selects `local` (the default) or `s3`
+pub fn init_storage() -> Result<SharedStorage, StorageError> {
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("s3") => Ok(Arc::new(S3Storage::from_env()?)),
+        _ => {
+            let root = env::var("LOCAL_STORAGE_DIR").unwrap_or_else(|_| "./data/attachments".to_string());
+            Ok(Arc::new(LocalStorage::new(root)))
+        }
+    }
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    to_hex(&Sha256::digest(content))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Keys are relative paths made of plain segments
+pub(crate) fn validate_key(key: &str) -> Result<(), StorageError> {
+    let valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(StorageError::InvalidKey(key.to_string()))
+    }
+}
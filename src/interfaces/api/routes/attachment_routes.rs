@@ -0,0 +1,234 @@
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Multipart, Path},
+    http::{header, HeaderValue, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use crate::{
+    application::services::{AttachmentService, ProjectService},
+    domain::{
+        entities::{
+            activity::{ActivityContext, ActivityKind},
+            attachment::{Attachment, AttachmentLimits},
+        },
+        errors::AppError,
+    },
+    infrastructure::{
+        db::{attachment_repo::AttachmentRepository, get_client, project_repo::ProjectRepository, task_repo::TaskRepository, DbPool},
+        storage::{sha256_hex, SharedStorage},
+    },
+};
+use serde_json::json;
+
+pub fn attachment_routes(limits: &AttachmentLimits) -> Router {
+    Router::new()
+        .route(
+            "/tasks/:id/attachments",
+            // Per-file and per-task limits are enforced while reading the body
+            get(list_attachments).post(upload_attachments).layer(DefaultBodyLimit::max(limits.max_request_bytes)),
+        )
+        .route("/tasks/:id/attachments/:attachment_id", get(download_attachment).delete(delete_attachment))
+}
+
+async fn list_attachments(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<Attachment>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    Ok(Json(AttachmentRepository::find_attachments(&client, id).await?))
+}
+
+struct Upload {
+    filename: String,
+    content_type: String,
+    content: Bytes,
+}
+
+// Every file part of the form is attached; other parts are ignored. Limits are checked as
+// the parts arrive so an oversized request is rejected before it is fully read.
+async fn read_uploads(multipart: &mut Multipart, limits: &AttachmentLimits, existing: usize) -> Result<Vec<Upload>, AppError> {
+    let mut uploads = Vec::new();
+    let mut total = 0;
+    loop {
+        let next = multipart.next_field().await.map_err(|e| e.to_string());
+        let Some(mut field) = next.map_err(|e| AppError::invalid_input(&e))? else { break };
+        let Some(filename) = field.file_name().map(str::to_string) else { continue };
+        let content_type = field.content_type().unwrap_or("application/octet-stream").to_string();
+        if existing + uploads.len() >= limits.max_per_task {
+            return Err(AppError::validation_error(
+                "file",
+                &format!("Tasks can hold at most {} attachments", limits.max_per_task),
+            ));
+        }
+        if !limits.allows_type(&content_type) {
+            return Err(AppError::validation_error(
+                "file",
+                &format!("Files of type {} cannot be attached", content_type),
+            ));
+        }
+
+        let mut content = Vec::new();
+        loop {
+            let next = field.chunk().await.map_err(|e| e.to_string());
+            let Some(chunk) = next.map_err(|e| AppError::invalid_input(&e))? else { break };
+            if content.len() + chunk.len() > limits.max_bytes {
+                return Err(AppError::validation_error(
+                    "file",
+                    &format!("{} exceeds the {} byte limit", filename, limits.max_bytes),
+                ));
+            }
+            total += chunk.len();
+            if total > limits.max_request_bytes {
+                return Err(AppError::validation_error(
+                    "file",
+                    &format!("The upload exceeds the {} byte limit", limits.max_request_bytes),
+                ));
+            }
+            content.extend_from_slice(&chunk);
+        }
+        uploads.push(Upload { filename, content_type, content: Bytes::from(content) });
+    }
+    if uploads.is_empty() {
+        return Err(AppError::validation_error("file", "The form contains no files"));
+    }
+    Ok(uploads)
+}
+
+async fn upload_attachments(
+    Extension(pool): Extension<DbPool>,
+    Extension(storage): Extension<SharedStorage>,
+    Extension(limits): Extension<AttachmentLimits>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<Attachment>>), AppError> {
+    let mut client = get_client(&pool).await?;
+
+    // Checked before the body is read; repeated below inside the transaction
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    if let Some(project_id) = task.project_id {
+        let project = ProjectRepository::find_project(&client, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    let existing = AttachmentRepository::count_attachments(&client, id).await?;
+    let uploads = read_uploads(&mut multipart, &limits, existing).await?;
+
+    let mut pending = Vec::with_capacity(uploads.len());
+    for upload in uploads {
+        let created_at = chrono::Utc::now().timestamp_millis();
+        let checksum = sha256_hex(&upload.content);
+        let storage_key = Attachment::storage_key_for(id, created_at, &checksum);
+        pending.push((upload, storage_key, checksum, created_at));
+    }
+
+    // The keys are queued for deletion before the content is written and cleared once the rows
+    // are committed, so content of a failed upload is removed by the retention job
+    let storage_keys: Vec<String> = pending.iter().map(|(_, key, _, _)| key.clone()).collect();
+    AttachmentRepository::queue_pending_deletions(&client, &storage_keys, chrono::Utc::now().timestamp_millis()).await?;
+    for (upload, storage_key, _, _) in &pending {
+        storage.put(storage_key, upload.content.clone(), &upload.content_type).await?;
+    }
+
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let mut task = TaskRepository::find_task_by_id(&tx, id).await?;
+    task.set_activity_context(context.clone());
+    if let Some(project_id) = task.project_id {
+        let project = ProjectRepository::find_project(&tx, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    let mut existing = AttachmentRepository::count_attachments(&tx, id).await?;
+
+    let mut attachments = Vec::with_capacity(pending.len());
+    for (upload, storage_key, checksum, created_at) in pending {
+        let filename = AttachmentService::validate_upload(
+            &task,
+            &limits,
+            existing,
+            &upload.filename,
+            &upload.content_type,
+            upload.content.len(),
+        )?;
+        let attachment = Attachment {
+            id: 0,
+            task_id: id,
+            filename,
+            content_type: upload.content_type,
+            size: upload.content.len() as i64,
+            checksum,
+            storage_key,
+            uploaded_by: context.actor_id,
+            created_at,
+        };
+        let attachment = AttachmentRepository::insert_attachment(&tx, &attachment).await?;
+        task.record_activity(
+            ActivityKind::AttachmentAdded,
+            None,
+            Some(json!({ "attachment_id": attachment.id, "filename": attachment.filename })),
+        );
+        attachments.push(attachment);
+        existing += 1;
+    }
+    TaskRepository::update_task(&tx, &mut task).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+
+    AttachmentRepository::clear_pending_deletions(&client, &storage_keys).await?;
+    Ok((StatusCode::CREATED, Json(attachments)))
+}
+
+async fn download_attachment(
+    Extension(pool): Extension<DbPool>,
+    Extension(storage): Extension<SharedStorage>,
+    Path((id, attachment_id)): Path<(i32, i64)>,
+) -> Result<impl IntoResponse, AppError> {
+    let client = get_client(&pool).await?;
+    let attachment = AttachmentRepository::find_attachment(&client, id, attachment_id).await?;
+    let content = storage.get(&attachment.storage_key).await?;
+
+    let content_type = HeaderValue::from_str(&attachment.content_type)
+        .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream"));
+    let disposition = HeaderValue::from_bytes(format!("attachment; filename=\"{}\"", attachment.filename).as_bytes())
+        .unwrap_or_else(|_| HeaderValue::from_static("attachment"));
+    let etag = HeaderValue::from_str(&format!("\"{}\"", attachment.checksum)).map_err(AppError::database_error)?;
+    Ok((
+        [(header::CONTENT_TYPE, content_type), (header::CONTENT_DISPOSITION, disposition), (header::ETAG, etag)],
+        content,
+    ))
+}
+
+async fn delete_attachment(
+    Extension(pool): Extension<DbPool>,
+    Extension(storage): Extension<SharedStorage>,
+    Path((id, attachment_id)): Path<(i32, i64)>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let attachment = AttachmentRepository::find_attachment(&tx, id, attachment_id).await?;
+    let mut task = TaskRepository::find_task_by_id(&tx, id).await?;
+    task.set_activity_context(context.clone());
+    if let Some(project_id) = task.project_id {
+        let project = ProjectRepository::find_project(&tx, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    AttachmentRepository::delete_attachment(&tx, attachment_id).await?;
+    task.record_activity(
+        ActivityKind::AttachmentRemoved,
+        Some(json!({ "attachment_id": attachment.id, "filename": attachment.filename })),
+        None,
+    );
+    TaskRepository::update_task(&tx, &mut task).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+
+    // Content still shared with a clone is not queued. On failure the key stays queued and
+    // the retention job retries.
+    if AttachmentRepository::is_pending_deletion(&client, &attachment.storage_key).await?
+        && storage.delete(&attachment.storage_key).await.is_ok()
+    {
+        AttachmentRepository::clear_pending_deletions(&client, &[attachment.storage_key]).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
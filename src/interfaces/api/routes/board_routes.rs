@@ -0,0 +1,183 @@
+use axum::{extract::Path, routing::{get, put}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::{BoardService, ProjectService, TaskService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            board::{Board, BoardColumn, BoardView, Swimlane},
+            task::{Task, TaskStatus},
+        },
+        errors::AppError,
+    },
+    infrastructure::db::{
+        board_repo::BoardRepository,
+        get_client,
+        project_repo::ProjectRepository,
+        task_repo::TaskRepository,
+        workflow_repo::WorkflowRepository,
+        DbPool,
+    },
+    interfaces::api::routes::{task_routes::rollup_ancestors, watcher_routes::notify_watchers},
+};
+use serde::Deserialize;
+
+pub fn board_routes() -> Router {
+    Router::new()
+        .route("/projects/:id/boards", get(list_boards).post(create_board))
+        .route("/boards/:id", get(get_board).put(update_board).delete(delete_board))
+        .route("/boards/:id/view", get(get_board_view))
+        .route("/boards/:id/cards/:task_id", put(move_card))
+}
+
+async fn list_boards(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<Board>>, AppError> {
+    let client = get_client(&pool).await?;
+    ProjectRepository::find_project(&client, project_id).await?;
+    Ok(Json(BoardRepository::find_boards(&client, project_id).await?))
+}
+
+#[derive(Deserialize)]
+struct BoardRequest {
+    name: String,
+    columns: Vec<BoardColumn>,
+    swimlane: Option<Swimlane>,
+}
+
+async fn create_board(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<BoardRequest>,
+) -> Result<(StatusCode, Json<Board>), AppError> {
+    let client = get_client(&pool).await?;
+    let project = ProjectRepository::find_project(&client, project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut board = Board {
+        id: 0,
+        project_id,
+        name: payload.name,
+        columns: payload.columns,
+        swimlane: payload.swimlane,
+        created_at: now,
+        updated_at: now,
+    };
+    let workflow = WorkflowRepository::find_for_project(&client, Some(project_id)).await?;
+    BoardService::validate_board(&mut board, &workflow)?;
+    let board = BoardRepository::create_board(&client, &board).await?;
+    Ok((StatusCode::CREATED, Json(board)))
+}
+
+async fn get_board(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Board>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(BoardRepository::find_board(&client, id).await?))
+}
+
+async fn update_board(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<BoardRequest>,
+) -> Result<Json<Board>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut board = BoardRepository::find_board(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, board.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+
+    board.name = payload.name;
+    board.columns = payload.columns;
+    board.swimlane = payload.swimlane;
+    board.updated_at = chrono::Utc::now().timestamp_millis();
+    let workflow = WorkflowRepository::find_for_project(&client, Some(board.project_id)).await?;
+    BoardService::validate_board(&mut board, &workflow)?;
+    BoardRepository::update_board(&client, &board).await?;
+    Ok(Json(board))
+}
+
+async fn delete_board(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let client = get_client(&pool).await?;
+    let board = BoardRepository::find_board(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, board.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    BoardRepository::delete_board(&client, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// The whole board in one call: column summaries and the ordered cards of every lane
+async fn get_board_view(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<BoardView>, AppError> {
+    let client = get_client(&pool).await?;
+    let board = BoardRepository::find_board(&client, id).await?;
+    let tasks = TaskRepository::find_tasks_in_statuses(&client, board.project_id, &board.statuses()).await?;
+    let ranks = BoardRepository::find_ranks(&client, id).await?;
+    Ok(Json(BoardService::build_view(&board, tasks, &ranks)))
+}
+
+#[derive(Deserialize)]
+struct MoveCardRequest {
+    column: String,
+    // Needed only when the column groups several statuses
+    status: Option<TaskStatus>,
+    position: Option<usize>,
+}
+
+// Moves the card to a column, changing the task status through the project workflow
+async fn move_card(
+    Extension(pool): Extension<DbPool>,
+    Path((id, task_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+    Json(payload): Json<MoveCardRequest>,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let board = BoardRepository::lock_board(&tx, id).await?;
+    let project = ProjectRepository::find_project(&tx, board.project_id).await?;
+    ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    let mut task = TaskRepository::find_task_by_id(&tx, task_id).await?;
+    if task.project_id != Some(board.project_id) || task.is_trashed() {
+        return Err(AppError::validation_error("task_id", &format!("Task {} is not on this board", task_id)));
+    }
+    task.set_activity_context(context);
+
+    let column = board
+        .column_named(&payload.column)
+        .ok_or_else(|| AppError::validation_error("column", &format!("Board has no column {}", payload.column)))?;
+    let status = BoardService::target_status(&board, column, task.status, payload.status)?;
+
+    let mut column_tasks =
+        TaskRepository::find_tasks_in_statuses(&tx, board.project_id, &board.columns[column].statuses).await?;
+    if board.column_of(task.status) != Some(column) {
+        BoardService::check_wip_limit(&board, column, column_tasks.len())?;
+    }
+
+    if status != task.status {
+        let workflow = WorkflowRepository::find_for_project(&tx, task.project_id).await?;
+        TaskService::change_task_status(&mut task, &workflow, status)?;
+        let events = task.activity_log.clone();
+        TaskRepository::update_task(&tx, &mut task).await?;
+        rollup_ancestors(&tx, task.id).await?;
+        notify_watchers(&tx, &task, &events, &[]).await?;
+    }
+
+    let ranks = BoardRepository::find_ranks(&tx, id).await?;
+    BoardService::sort_cards(&mut column_tasks, &ranks);
+    let order = column_tasks.iter().map(|t| t.id).collect::<Vec<_>>();
+    let order = BoardService::place_card(order, task.id, payload.position);
+    BoardRepository::save_card_order(&tx, id, &order).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(task))
+}
@@ -0,0 +1,910 @@
+use axum::{
+    extract::Path,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::any,
+    Extension, Router,
+};
+use chrono_tz::Tz;
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{ProjectService, TaskService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            caldav::{
+                collection_path, decode_segment, home_path, parse_sync_token, resource_name, resource_path, sync_token,
+                CompFilter, PropFilter, TaskCollection, TextMatch, TimeRange, CALDAV_ROOT,
+            },
+            custom_field::CustomFieldTarget,
+            ical::{parse_calendar, parse_utc, todo_properties, uid_of, write_resource, CalendarItem, ImportedTodo},
+            task::Task,
+            trash::TrashState,
+        },
+        errors::AppError,
+        pagination::{PageRequest, MAX_PAGE_SIZE},
+    },
+    infrastructure::{
+        db::{
+            custom_field_repo::CustomFieldRepository,
+            get_client,
+            project_repo::{ProjectFilter, ProjectRepoError, ProjectRepository},
+            task_repo::TaskRepository,
+            workflow_repo::WorkflowRepository,
+            DbPool,
+        },
+        storage::sha256_hex,
+    },
+    interfaces::{
+        api::routes::{
+            calendar_routes::{find_by_uids, link_parent},
+            task_routes::{rollup_ancestors, viewer_time_zone},
+            watcher_routes::notify_watchers,
+        },
+        http::dav::{dav_error, escape_xml, href, parse_xml, MultiStatus, XmlElement, XmlName, CALDAV, CALENDARSERVER, DAV},
+    },
+};
+use std::collections::{HashMap, HashSet};
+
+// CalDAV (RFC 4791) access to tasks, with collection sync (RFC 6578). Every user's principal
+// is also their calendar home, holding `tasks` for what is assigned to them and
+// `project-<id>` for each project they belong to. Tasks are VTODO resources named after
+// their UID. Like the rest of the API, requests are authenticated upstream, which passes
+// the user on in x-user-id.
+
+const DAV_CAPABILITIES: &str = "1, 3, calendar-access";
+const COLLECTION_METHODS: &str = "OPTIONS, PROPFIND, REPORT";
+const RESOURCE_METHODS: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND";
+const RESOURCE_CONTENT_TYPE: &str = "text/calendar; charset=utf-8; component=VTODO";
+
+pub fn caldav_routes() -> Router {
+    Router::new()
+        .route("/.well-known/caldav", any(well_known))
+        .route("/caldav", any(root_request))
+        .route("/caldav/", any(root_request))
+        .route("/caldav/:user_id", any(home_request))
+        .route("/caldav/:user_id/", any(home_request))
+        .route("/caldav/:user_id/:calendar", any(collection_request))
+        .route("/caldav/:user_id/:calendar/", any(collection_request))
+        .route("/caldav/:user_id/:calendar/:resource", any(resource_request))
+}
+
+async fn well_known() -> Response {
+    (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, CALDAV_ROOT)]).into_response()
+}
+
+// Lets clients discover the user's principal
+async fn root_request(method: Method, context: ActivityContext, body: String) -> Result<Response, AppError> {
+    let Some(actor_id) = context.actor_id else { return Ok(unauthorized()) };
+    match method.as_str() {
+        "OPTIONS" => Ok(options(COLLECTION_METHODS)),
+        "PROPFIND" => {
+            let Some(request) = prop_request(&body) else { return Ok(StatusCode::BAD_REQUEST.into_response()) };
+            let mut found = principal_props(actor_id, actor_id);
+            found.retain(|(name, _)| !name.is(DAV, "principal-URL"));
+            let mut status = MultiStatus::default();
+            respond(&mut status, CALDAV_ROOT, found, &request);
+            Ok(status.into_response(None))
+        }
+        _ => Ok(not_allowed(COLLECTION_METHODS)),
+    }
+}
+
+async fn home_request(
+    Extension(pool): Extension<DbPool>,
+    method: Method,
+    headers: HeaderMap,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+    body: String,
+) -> Result<Response, AppError> {
+    let Some(actor_id) = context.actor_id else { return Ok(unauthorized()) };
+    ensure_own_home(actor_id, user_id)?;
+    match method.as_str() {
+        "OPTIONS" => Ok(options(COLLECTION_METHODS)),
+        "PROPFIND" => {
+            let Some(request) = prop_request(&body) else { return Ok(StatusCode::BAD_REQUEST.into_response()) };
+            let mut status = MultiStatus::default();
+            respond(&mut status, &home_path(user_id), principal_props(actor_id, user_id), &request);
+            if depth(&headers) > 0 {
+                let client = get_client(&pool).await?;
+                let calendars = user_calendars(&client, user_id).await?;
+                for calendar in calendars {
+                    let token = sync_token(TaskRepository::find_collection_sync_seq(&client, calendar.collection).await?);
+                    let path = collection_path(user_id, calendar.collection);
+                    respond(&mut status, &path, collection_props(&calendar, actor_id, &token), &request);
+                }
+            }
+            Ok(status.into_response(None))
+        }
+        _ => Ok(not_allowed(COLLECTION_METHODS)),
+    }
+}
+
+async fn collection_request(
+    Extension(pool): Extension<DbPool>,
+    method: Method,
+    headers: HeaderMap,
+    context: ActivityContext,
+    Path((user_id, name)): Path<(i32, String)>,
+    body: String,
+) -> Result<Response, AppError> {
+    let Some(actor_id) = context.actor_id else { return Ok(unauthorized()) };
+    ensure_own_home(actor_id, user_id)?;
+    let client = get_client(&pool).await?;
+    let Some(calendar) = find_calendar(&client, actor_id, user_id, &name).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    match method.as_str() {
+        "OPTIONS" => Ok(options(COLLECTION_METHODS)),
+        "PROPFIND" => {
+            let Some(request) = prop_request(&body) else { return Ok(StatusCode::BAD_REQUEST.into_response()) };
+            let token = sync_token(TaskRepository::find_collection_sync_seq(&client, calendar.collection).await?);
+            let mut status = MultiStatus::default();
+            respond(&mut status, &collection_path(user_id, calendar.collection), collection_props(&calendar, actor_id, &token), &request);
+            if depth(&headers) > 0 {
+                let tasks = TaskRepository::find_collection_tasks(&client, calendar.collection, None).await?;
+                for resource in render(&client, tasks).await? {
+                    respond(&mut status, &resource.path(&calendar), resource.props(), &request);
+                }
+            }
+            Ok(status.into_response(None))
+        }
+        "REPORT" => report(&client, &calendar, &body).await,
+        _ => Ok(not_allowed(COLLECTION_METHODS)),
+    }
+}
+
+async fn resource_request(
+    Extension(pool): Extension<DbPool>,
+    method: Method,
+    headers: HeaderMap,
+    context: ActivityContext,
+    Path((user_id, name, resource)): Path<(i32, String, String)>,
+    body: String,
+) -> Result<Response, AppError> {
+    let Some(actor_id) = context.actor_id else { return Ok(unauthorized()) };
+    ensure_own_home(actor_id, user_id)?;
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let Some(calendar) = find_calendar(&tx, actor_id, user_id, &name).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let current = match TaskRepository::find_collection_resource(&tx, calendar.collection, &resource).await? {
+        Some(task) => render(&tx, vec![task]).await?.pop(),
+        None => None,
+    };
+
+    let response = match method.as_str() {
+        "OPTIONS" => options(RESOURCE_METHODS),
+        "GET" | "HEAD" => match current {
+            Some(current) if etag_listed(&headers, header::IF_NONE_MATCH, &current.etag) => {
+                (StatusCode::NOT_MODIFIED, [(header::ETAG, current.etag)]).into_response()
+            }
+            Some(current) => (
+                [(header::CONTENT_TYPE, RESOURCE_CONTENT_TYPE.to_string()), (header::ETAG, current.etag)],
+                current.body,
+            )
+                .into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+        "PROPFIND" => match current {
+            Some(current) => {
+                let Some(request) = prop_request(&body) else { return Ok(StatusCode::BAD_REQUEST.into_response()) };
+                let mut status = MultiStatus::default();
+                respond(&mut status, &current.path(&calendar), current.props(), &request);
+                status.into_response(None)
+            }
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+        "PUT" | "DELETE" => {
+            if !calendar.writable {
+                return Err(AppError::forbidden(actor_id, &format!("change tasks in calendar {}", calendar.name)));
+            }
+            if !preconditions_hold(&headers, current.as_ref()) {
+                return Ok(StatusCode::PRECONDITION_FAILED.into_response());
+            }
+            let response = if method == Method::PUT {
+                put_resource(&tx, &calendar, &resource, current.map(|c| c.task), &context, &body).await?
+            } else {
+                match current {
+                    Some(current) => delete_resource(&tx, current.task, &context).await?,
+                    None => StatusCode::NOT_FOUND.into_response(),
+                }
+            };
+            tx.commit().await.map_err(AppError::database_error)?;
+            response
+        }
+        _ => not_allowed(RESOURCE_METHODS),
+    };
+    Ok(response)
+}
+
+// Creates or updates the task from the single VTODO in the body. The stored resource is
+// rendered from the task, so no ETag is returned and clients fetch it again.
+async fn put_resource(
+    client: &impl GenericClient,
+    calendar: &Calendar,
+    resource: &str,
+    current: Option<Task>,
+    context: &ActivityContext,
+    body: &str,
+) -> Result<Response, AppError> {
+    let timezone = viewer_time_zone(client, None, context.actor_id).await?;
+    let (todo, uid) = match single_todo(body, timezone) {
+        Ok(read) => read,
+        Err(condition) => return Ok(dav_error(StatusCode::FORBIDDEN, CALDAV, condition)),
+    };
+
+    // Clients cannot show why a status change was refused; the next sync shows the stored one
+    let mut warnings = Vec::new();
+    let (task, status) = match current {
+        Some(mut task) => {
+            if uid_of(&task) != uid {
+                return Ok(dav_error(StatusCode::FORBIDDEN, CALDAV, "no-uid-conflict"));
+            }
+            task.set_activity_context(context.clone());
+            let workflow = WorkflowRepository::find_for_project(client, task.project_id).await?;
+            if TaskService::apply_imported_todo(&mut task, &todo, &workflow, &mut warnings) {
+                let events = task.activity_log.clone();
+                TaskRepository::update_task(client, &mut task).await?;
+                rollup_ancestors(client, task.id).await?;
+                notify_watchers(client, &task, &events, &[]).await?;
+            }
+            (task, StatusCode::NO_CONTENT)
+        }
+        None => {
+            if !find_by_uids(client, std::slice::from_ref(&uid)).await?.is_empty() {
+                return Ok(dav_error(StatusCode::FORBIDDEN, CALDAV, "no-uid-conflict"));
+            }
+            let Some(summary) = todo.summary.clone() else {
+                return Ok(dav_error(StatusCode::FORBIDDEN, CALDAV, "valid-calendar-object-resource"));
+            };
+            let workflow = WorkflowRepository::find_for_project(client, calendar.collection.project_id()).await?;
+            let mut task = TaskService::create_task(summary, None, &workflow)?;
+            task.set_activity_context(context.clone());
+            task.project_id = calendar.collection.project_id();
+            if let Some(assignee_id) = calendar.collection.assignee_id() {
+                task.assign(Some(assignee_id), context.actor_id);
+            }
+            task.ical_uid = Some(uid.clone());
+            task.caldav_name = Some(resource.to_string()).filter(|r| *r != format!("{}.ics", uid));
+            TaskService::apply_imported_todo(&mut task, &todo, &workflow, &mut warnings);
+            let definitions = CustomFieldRepository::find_definitions(client, Some(CustomFieldTarget::Task)).await?;
+            TaskService::set_custom_fields(&mut task, &definitions, HashMap::new())?;
+            let events = task.activity_log.clone();
+            let task = TaskRepository::create_task(client, &mut task).await?;
+            notify_watchers(client, &task, &events, &[]).await?;
+            (task, StatusCode::CREATED)
+        }
+    };
+
+    if let Some(parent_uid) = todo.parent_uid {
+        let mut parents = find_by_uids(client, std::slice::from_ref(&parent_uid)).await?;
+        if let Some(parent) = parents.remove(&parent_uid) {
+            link_parent(client, task.id, parent.id, context).await?;
+        }
+    }
+    Ok(status.into_response())
+}
+
+// The single VTODO of a PUT body with its UID, or the CalDAV precondition it fails
+fn single_todo(body: &str, timezone: Tz) -> Result<(ImportedTodo, String), &'static str> {
+    let items = parse_calendar(body, timezone).map_err(|_| "valid-calendar-data")?;
+    let mut todos = Vec::new();
+    for item in items {
+        match item {
+            CalendarItem::Todo(todo) => todos.push(todo),
+            CalendarItem::Other { .. } => return Err("supported-calendar-component"),
+        }
+    }
+    let (Some(todo), None) = (todos.pop(), todos.pop()) else { return Err("valid-calendar-object-resource") };
+    let uid = todo.uid.clone().ok_or("valid-calendar-object-resource")?;
+    Ok((todo, uid))
+}
+
+// Moves the task and its subtasks to the trash, where they can still be restored
+async fn delete_resource(client: &impl GenericClient, task: Task, context: &ActivityContext) -> Result<Response, AppError> {
+    let mut subtree = TaskRepository::find_subtree(client, task.id).await?;
+    subtree.values_mut().for_each(|t| t.set_activity_context(context.clone()));
+    let changed = TaskService::trash_subtree(&mut subtree, task.id, TrashState::Deleted)?;
+    for changed_id in changed {
+        if let Some(task) = subtree.get_mut(&changed_id) {
+            TaskRepository::update_task(client, task).await?;
+        }
+    }
+    rollup_ancestors(client, task.id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn report(client: &impl GenericClient, calendar: &Calendar, body: &str) -> Result<Response, AppError> {
+    let Ok(root) = parse_xml(body) else { return Ok(StatusCode::BAD_REQUEST.into_response()) };
+    let request = props_of(&root);
+    let mut status = MultiStatus::default();
+
+    if root.is(CALDAV, "calendar-query") {
+        let filter = root
+            .child(CALDAV, "filter")
+            .and_then(|f| f.child(CALDAV, "comp-filter"))
+            .map(comp_filter);
+        let tasks = TaskRepository::find_collection_tasks(client, calendar.collection, None).await?;
+        for resource in render(client, tasks).await? {
+            let matches = filter
+                .as_ref()
+                .is_none_or(|f| f.matches(&resource.task, &todo_properties(&resource.body)));
+            if matches {
+                respond(&mut status, &resource.path(calendar), resource.props(), &request);
+            }
+        }
+        return Ok(status.into_response(None));
+    }
+
+    if root.is(CALDAV, "calendar-multiget") {
+        for requested in root.children.iter().filter(|c| c.is(DAV, "href")) {
+            let path = requested.text.trim();
+            let name = path.rsplit('/').next().and_then(decode_segment).unwrap_or_default();
+            let task = match path.contains(&collection_path(calendar.user_id, calendar.collection)) {
+                true => TaskRepository::find_collection_resource(client, calendar.collection, &name).await?,
+                false => None,
+            };
+            match task {
+                Some(task) => {
+                    let resource = render(client, vec![task]).await?.remove(0);
+                    respond(&mut status, path, resource.props(), &request);
+                }
+                None => status.not_found(path),
+            }
+        }
+        return Ok(status.into_response(None));
+    }
+
+    if root.is(DAV, "sync-collection") {
+        let since = match requested_sync(&root) {
+            Ok(since) => since,
+            Err(condition) => return Ok(dav_error(StatusCode::FORBIDDEN, DAV, condition)),
+        };
+        // Read before the changes, so a write racing this report is sent again next time
+        let token = sync_token(TaskRepository::find_collection_sync_seq(client, calendar.collection).await?);
+        let tasks = TaskRepository::find_collection_tasks(client, calendar.collection, since).await?;
+        let resources = render(client, tasks).await?;
+        let removals = match since {
+            Some(since) => TaskRepository::find_collection_removals(client, calendar.collection, since).await?,
+            None => Vec::new(),
+        };
+        write_sync(&mut status, calendar, &resources, &removals, &request);
+        return Ok(status.into_response(Some(&token)));
+    }
+
+    Ok(dav_error(StatusCode::FORBIDDEN, DAV, "supported-report"))
+}
+
+// The sequence a sync-collection report continues from, None for an initial sync, or the
+// precondition an unknown token fails
+fn requested_sync(root: &XmlElement) -> Result<Option<i64>, &'static str> {
+    match root.child(DAV, "sync-token").map(|t| t.text.trim()).filter(|t| !t.is_empty()) {
+        Some(token) => parse_sync_token(token).map(Some).ok_or("valid-sync-token"),
+        None => Ok(None),
+    }
+}
+
+// Changed resources with their properties; removed ones, by their old name, as 404s
+fn write_sync(status: &mut MultiStatus, calendar: &Calendar, resources: &[Resource], removals: &[String], request: &PropRequest) {
+    for resource in resources {
+        respond(status, &resource.path(calendar), resource.props(), request);
+    }
+    for name in removals {
+        status.not_found(&resource_path(calendar.user_id, calendar.collection, name));
+    }
+}
+
+// A collection as seen by the requesting user
+struct Calendar {
+    user_id: i32,
+    collection: TaskCollection,
+    name: String,
+    description: Option<String>,
+    writable: bool,
+}
+
+// The user's own tasks first, then every live project they belong to
+async fn user_calendars(client: &impl GenericClient, user_id: i32) -> Result<Vec<Calendar>, AppError> {
+    let filter = ProjectFilter { member_id: Some(user_id), include_archived: false };
+    let page = PageRequest { limit: Some(MAX_PAGE_SIZE), offset: None };
+    let projects = ProjectRepository::find_projects(client, &filter, &page).await?;
+    let mut calendars = vec![own_calendar(user_id)];
+    calendars.extend(projects.items.into_iter().map(|project| Calendar {
+        user_id,
+        collection: TaskCollection::Project(project.id),
+        writable: project.can_edit_tasks(user_id),
+        name: project.name,
+        description: project.description,
+    }));
+    Ok(calendars)
+}
+
+fn own_calendar(user_id: i32) -> Calendar {
+    Calendar {
+        user_id,
+        collection: TaskCollection::Assigned(user_id),
+        name: "My tasks".to_string(),
+        description: None,
+        writable: true,
+    }
+}
+
+async fn find_calendar(client: &impl GenericClient, actor_id: i32, user_id: i32, name: &str) -> Result<Option<Calendar>, AppError> {
+    let project_id = match TaskCollection::parse(user_id, name) {
+        Some(TaskCollection::Project(project_id)) => project_id,
+        Some(TaskCollection::Assigned(_)) => return Ok(Some(own_calendar(user_id))),
+        None => return Ok(None),
+    };
+    let project = match ProjectRepository::find_project(client, project_id).await {
+        Ok(project) => project,
+        Err(ProjectRepoError::ProjectNotFound(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if !project.is_member(actor_id) {
+        return Err(AppError::forbidden(actor_id, &format!("read tasks of project {}", project.key)));
+    }
+    let writable = ProjectService::ensure_can_edit_tasks(&project, Some(actor_id)).is_ok();
+    Ok(Some(Calendar {
+        user_id,
+        collection: TaskCollection::Project(project_id),
+        name: project.name,
+        description: project.description,
+        writable,
+    }))
+}
+
+// A task rendered as a calendar object, with the strong ETag of exactly that body
+struct Resource {
+    task: Task,
+    body: String,
+    etag: String,
+}
+
+impl Resource {
+    fn path(&self, calendar: &Calendar) -> String {
+        resource_path(calendar.user_id, calendar.collection, &resource_name(&self.task))
+    }
+
+    fn props(&self) -> Vec<(XmlName, String)> {
+        let last_modified = chrono::DateTime::from_timestamp_millis(self.task.updated_at)
+            .unwrap_or_default()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        vec![
+            (XmlName::new(DAV, "getetag"), escape_xml(&self.etag)),
+            (XmlName::new(DAV, "getcontenttype"), RESOURCE_CONTENT_TYPE.to_string()),
+            (XmlName::new(DAV, "getlastmodified"), last_modified),
+            (XmlName::new(DAV, "resourcetype"), String::new()),
+            (XmlName::new(CALDAV, "calendar-data"), escape_xml(&self.body)),
+        ]
+    }
+}
+
+// Parents outside the batch are looked up so RELATED-TO carries their real UID
+async fn render(client: &impl GenericClient, tasks: Vec<Task>) -> Result<Vec<Resource>, AppError> {
+    let mut uids = tasks.iter().map(|t| (t.id, uid_of(t))).collect::<HashMap<_, _>>();
+    let parent_ids = tasks
+        .iter()
+        .filter_map(|t| t.parent_task)
+        .filter(|id| !uids.contains_key(id))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for parent in TaskRepository::find_tasks_by_ids(client, &parent_ids).await? {
+        uids.insert(parent.id, uid_of(&parent));
+    }
+    Ok(tasks
+        .into_iter()
+        .map(|task| {
+            let body = write_resource(&task, &uids);
+            let etag = format!("\"{}\"", sha256_hex(body.as_bytes()));
+            Resource { task, body, etag }
+        })
+        .collect())
+}
+
+fn principal_props(actor_id: i32, user_id: i32) -> Vec<(XmlName, String)> {
+    vec![
+        (XmlName::new(DAV, "resourcetype"), "<d:collection/><d:principal/>".to_string()),
+        (XmlName::new(DAV, "displayname"), "Tasks".to_string()),
+        (XmlName::new(DAV, "current-user-principal"), href(&home_path(actor_id))),
+        (XmlName::new(DAV, "principal-URL"), href(&home_path(user_id))),
+        (XmlName::new(CALDAV, "calendar-home-set"), href(&home_path(user_id))),
+    ]
+}
+
+fn collection_props(calendar: &Calendar, actor_id: i32, token: &str) -> Vec<(XmlName, String)> {
+    let mut privileges = vec!["read"];
+    if calendar.writable {
+        privileges.extend(["write", "write-content", "bind", "unbind"]);
+    }
+    let privileges = privileges
+        .iter()
+        .map(|p| format!("<d:privilege><d:{}/></d:privilege>", p))
+        .collect::<String>();
+    let reports = [("c", "calendar-query"), ("c", "calendar-multiget"), ("d", "sync-collection")]
+        .iter()
+        .map(|(prefix, report)| format!("<d:supported-report><d:report><{}:{}/></d:report></d:supported-report>", prefix, report))
+        .collect::<String>();
+
+    let mut props = vec![
+        (XmlName::new(DAV, "resourcetype"), "<d:collection/><c:calendar/>".to_string()),
+        (XmlName::new(DAV, "displayname"), escape_xml(&calendar.name)),
+        (XmlName::new(DAV, "owner"), href(&home_path(calendar.user_id))),
+        (XmlName::new(DAV, "current-user-principal"), href(&home_path(actor_id))),
+        (XmlName::new(DAV, "current-user-privilege-set"), privileges),
+        (XmlName::new(DAV, "supported-report-set"), reports),
+        (XmlName::new(DAV, "sync-token"), escape_xml(token)),
+        (XmlName::new(CALENDARSERVER, "getctag"), escape_xml(token)),
+        (XmlName::new(CALDAV, "supported-calendar-component-set"), "<c:comp name=\"VTODO\"/>".to_string()),
+        (
+            XmlName::new(CALDAV, "supported-calendar-data"),
+            "<c:calendar-data content-type=\"text/calendar\" version=\"2.0\"/>".to_string(),
+        ),
+    ];
+    if let Some(description) = &calendar.description {
+        props.push((XmlName::new(CALDAV, "calendar-description"), escape_xml(description)));
+    }
+    props
+}
+
+enum PropRequest {
+    All,
+    Names,
+    Props(Vec<XmlName>),
+}
+
+// An empty PROPFIND body asks for all properties. None when the body is not valid XML.
+fn prop_request(body: &str) -> Option<PropRequest> {
+    if body.trim().is_empty() {
+        return Some(PropRequest::All);
+    }
+    parse_xml(body).ok().map(|root| props_of(&root))
+}
+
+fn props_of(root: &XmlElement) -> PropRequest {
+    if root.child(DAV, "propname").is_some() {
+        return PropRequest::Names;
+    }
+    match root.child(DAV, "prop") {
+        Some(prop) => PropRequest::Props(prop.children.iter().map(|c| c.name.clone()).collect()),
+        None => PropRequest::All,
+    }
+}
+
+// Calendar data is only sent when asked for by name, as RFC 4791 recommends
+fn respond(status: &mut MultiStatus, path: &str, available: Vec<(XmlName, String)>, request: &PropRequest) {
+    match request {
+        PropRequest::All => {
+            let found = available.into_iter().filter(|(name, _)| !name.is(CALDAV, "calendar-data")).collect::<Vec<_>>();
+            status.response(path, &found, &[]);
+        }
+        PropRequest::Names => {
+            let names = available.into_iter().map(|(name, _)| (name, String::new())).collect::<Vec<_>>();
+            status.response(path, &names, &[]);
+        }
+        PropRequest::Props(requested) => {
+            let mut available = available.into_iter().collect::<HashMap<_, _>>();
+            let (found, missing): (Vec<_>, Vec<_>) = requested.iter().partition(|name| available.contains_key(name));
+            let found = found
+                .into_iter()
+                .filter_map(|name| available.remove_entry(name))
+                .collect::<Vec<_>>();
+            let missing = missing.into_iter().cloned().collect::<Vec<_>>();
+            status.response(path, &found, &missing);
+        }
+    }
+}
+
+fn comp_filter(element: &XmlElement) -> CompFilter {
+    CompFilter {
+        name: element.attribute("name").unwrap_or_default().to_ascii_uppercase(),
+        is_not_defined: element.child(CALDAV, "is-not-defined").is_some(),
+        time_range: element.child(CALDAV, "time-range").map(|range| TimeRange {
+            start: range.attribute("start").and_then(parse_utc),
+            end: range.attribute("end").and_then(parse_utc),
+        }),
+        props: element
+            .children
+            .iter()
+            .filter(|c| c.is(CALDAV, "prop-filter"))
+            .map(prop_filter)
+            .collect(),
+        comps: element
+            .children
+            .iter()
+            .filter(|c| c.is(CALDAV, "comp-filter"))
+            .map(comp_filter)
+            .collect(),
+    }
+}
+
+fn prop_filter(element: &XmlElement) -> PropFilter {
+    PropFilter {
+        name: element.attribute("name").unwrap_or_default().to_ascii_uppercase(),
+        is_not_defined: element.child(CALDAV, "is-not-defined").is_some(),
+        text_match: element.child(CALDAV, "text-match").map(|text| TextMatch {
+            text: text.text.clone(),
+            negate: text.attribute("negate-condition") == Some("yes"),
+            case_sensitive: text.attribute("collation") == Some("i;octet"),
+        }),
+    }
+}
+
+fn ensure_own_home(actor_id: i32, user_id: i32) -> Result<(), AppError> {
+    if actor_id != user_id {
+        return Err(AppError::forbidden(actor_id, "access another user's calendars"));
+    }
+    Ok(())
+}
+
+// Asks the client for credentials, which the authenticating proxy in front checks
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Basic realm=\"taskflow\"")]).into_response()
+}
+
+fn options(methods: &'static str) -> Response {
+    (
+        StatusCode::OK,
+        [(header::ALLOW, HeaderValue::from_static(methods)), (header::HeaderName::from_static("dav"), HeaderValue::from_static(DAV_CAPABILITIES))],
+    )
+        .into_response()
+}
+
+fn not_allowed(methods: &'static str) -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, methods)]).into_response()
+}
+
+// Depth 0 is the target alone; 1 and infinity both list its members
+fn depth(headers: &HeaderMap) -> u8 {
+    match headers.get("depth").and_then(|v| v.to_str().ok()).map(str::trim) {
+        Some("0") => 0,
+        _ => 1,
+    }
+}
+
+fn etag_listed(headers: &HeaderMap, name: header::HeaderName, etag: &str) -> bool {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').map(str::trim).any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag))
+}
+
+// If-Match guards against overwriting someone else's edit, If-None-Match: * against
+// creating over an existing resource
+fn preconditions_hold(headers: &HeaderMap, current: Option<&Resource>) -> bool {
+    let if_match = headers.contains_key(header::IF_MATCH);
+    let if_none_match = headers.contains_key(header::IF_NONE_MATCH);
+    match current {
+        Some(current) => {
+            (!if_match || etag_listed(headers, header::IF_MATCH, &current.etag))
+                && !(if_none_match && etag_listed(headers, header::IF_NONE_MATCH, &current.etag))
+        }
+        None => !if_match,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::UTC;
+
+    const TODO: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:0B1C@example.com\r\n\
+                        SUMMARY:Renew passport\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+    fn resource(id: i32, title: &str) -> Resource {
+        let mut task = Task::new(title.to_string(), None);
+        task.id = id;
+        let body = write_resource(&task, &HashMap::new());
+        let etag = format!("\"{}\"", sha256_hex(body.as_bytes()));
+        Resource { task, body, etag }
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    async fn body_of(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    async fn multistatus(write: impl FnOnce(&mut MultiStatus), sync_token: Option<&str>) -> String {
+        let mut status = MultiStatus::default();
+        write(&mut status);
+        let response = status.into_response(sync_token);
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        body_of(response).await
+    }
+
+    #[test]
+    fn if_match_guards_against_overwriting_other_edits() {
+        let current = resource(12, "Renew passport");
+        let stale = "\"0000\"";
+
+        assert!(preconditions_hold(&headers(&[]), Some(&current)));
+        assert!(preconditions_hold(&headers(&[(header::IF_MATCH, &current.etag)]), Some(&current)));
+        assert!(preconditions_hold(&headers(&[(header::IF_MATCH, &format!("{}, W/{}", stale, current.etag))]), Some(&current)));
+        assert!(preconditions_hold(&headers(&[(header::IF_MATCH, "*")]), Some(&current)));
+        assert!(!preconditions_hold(&headers(&[(header::IF_MATCH, stale)]), Some(&current)));
+        assert!(!preconditions_hold(&headers(&[(header::IF_MATCH, "*")]), None));
+    }
+
+    #[test]
+    fn if_none_match_guards_against_creating_over_a_resource() {
+        let current = resource(12, "Renew passport");
+
+        assert!(preconditions_hold(&headers(&[(header::IF_NONE_MATCH, "*")]), None));
+        assert!(!preconditions_hold(&headers(&[(header::IF_NONE_MATCH, "*")]), Some(&current)));
+        assert!(!preconditions_hold(&headers(&[(header::IF_NONE_MATCH, &current.etag)]), Some(&current)));
+        assert!(preconditions_hold(&headers(&[(header::IF_NONE_MATCH, "\"0000\"")]), Some(&current)));
+        assert!(etag_listed(&headers(&[(header::IF_NONE_MATCH, &current.etag)]), header::IF_NONE_MATCH, &current.etag));
+    }
+
+    #[test]
+    fn depth_zero_is_the_target_alone() {
+        let depth_of = |value: &str| depth(&headers(&[(header::HeaderName::from_static("depth"), value)]));
+
+        assert_eq!(depth_of("0"), 0);
+        assert_eq!(depth_of(" 0 "), 0);
+        assert_eq!(depth_of("1"), 1);
+        assert_eq!(depth_of("infinity"), 1);
+        assert_eq!(depth(&HeaderMap::new()), 1);
+    }
+
+    #[test]
+    fn propfind_bodies_select_the_properties() {
+        assert!(matches!(prop_request(""), Some(PropRequest::All)));
+        assert!(matches!(prop_request("<d:allprop xmlns:d=\"DAV:\"/>"), Some(PropRequest::All)));
+        assert!(matches!(prop_request("<d:propfind xmlns:d=\"DAV:\"><d:propname/></d:propfind>"), Some(PropRequest::Names)));
+        assert!(prop_request("<d:propfind xmlns:d=\"DAV:\">").is_none());
+
+        let request = prop_request("<d:propfind xmlns:d=\"DAV:\"><d:prop><d:getetag/><d:displayname/></d:prop></d:propfind>");
+        match request {
+            Some(PropRequest::Props(names)) => assert_eq!(names, vec![XmlName::new(DAV, "getetag"), XmlName::new(DAV, "displayname")]),
+            _ => panic!("expected named properties"),
+        }
+    }
+
+    #[tokio::test]
+    async fn allprop_leaves_out_calendar_data() {
+        let resource = resource(12, "Renew passport");
+        let body = multistatus(|status| respond(status, "/caldav/4/tasks/a.ics", resource.props(), &PropRequest::All), None).await;
+
+        assert!(body.contains(&format!("<d:getetag>{}</d:getetag>", escape_xml(&resource.etag))));
+        assert!(!body.contains("calendar-data"));
+        assert!(!body.contains("404 Not Found"));
+    }
+
+    #[tokio::test]
+    async fn requested_properties_are_found_or_reported_missing() {
+        let resource = resource(12, "Renew passport");
+        let request = PropRequest::Props(vec![XmlName::new(CALDAV, "calendar-data"), XmlName::new(DAV, "displayname")]);
+        let body = multistatus(|status| respond(status, "/caldav/4/tasks/a.ics", resource.props(), &request), None).await;
+
+        assert!(body.contains("<c:calendar-data>BEGIN:VCALENDAR"));
+        assert!(body.contains("SUMMARY:Renew passport"));
+        assert!(body.contains("<d:prop><d:displayname/></d:prop><d:status>HTTP/1.1 404 Not Found</d:status>"));
+        assert!(!body.contains("getetag"));
+    }
+
+    #[tokio::test]
+    async fn propname_lists_names_without_values() {
+        let resource = resource(12, "Renew passport");
+        let body = multistatus(|status| respond(status, "/caldav/4/tasks/a.ics", resource.props(), &PropRequest::Names), None).await;
+
+        assert!(body.contains("<d:getetag/>"));
+        assert!(body.contains("<c:calendar-data/>"));
+        assert!(!body.contains("BEGIN:VCALENDAR"));
+    }
+
+    #[test]
+    fn calendar_query_filters_are_read_from_the_report() {
+        let root = parse_xml(
+            "<c:calendar-query xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\">\
+             <d:prop><d:getetag/></d:prop><c:filter><c:comp-filter name=\"VCALENDAR\"><c:comp-filter name=\"vtodo\">\
+             <c:time-range start=\"20240501T000000Z\" end=\"20240601T000000Z\"/>\
+             <c:prop-filter name=\"summary\">\
+             <c:text-match collation=\"i;octet\" negate-condition=\"yes\">Draft</c:text-match></c:prop-filter>\
+             <c:prop-filter name=\"COMPLETED\"><c:is-not-defined/></c:prop-filter>\
+             </c:comp-filter></c:comp-filter></c:filter></c:calendar-query>",
+        )
+        .unwrap();
+        let filter = comp_filter(root.child(CALDAV, "filter").unwrap().child(CALDAV, "comp-filter").unwrap());
+
+        let todo = &filter.comps[0];
+        assert_eq!(todo.name, "VTODO");
+        let range = todo.time_range.unwrap();
+        assert_eq!(range.start, parse_utc("20240501T000000Z"));
+        assert_eq!(range.end, parse_utc("20240601T000000Z"));
+        let text = todo.props[0].text_match.as_ref().unwrap();
+        assert_eq!((todo.props[0].name.as_str(), text.text.as_str(), text.negate, text.case_sensitive), ("SUMMARY", "Draft", true, true));
+        assert!(todo.props[1].is_not_defined);
+
+        let mut task = resource(12, "Renew passport").task;
+        task.due_date = parse_utc("20240515T090000Z");
+        assert!(filter.matches(&task, &[("SUMMARY".to_string(), "Renew passport".to_string())]));
+        assert!(!filter.matches(&task, &[("SUMMARY".to_string(), "Draft".to_string())]));
+        task.due_date = parse_utc("20240615T090000Z");
+        assert!(!filter.matches(&task, &[("SUMMARY".to_string(), "Renew passport".to_string())]));
+    }
+
+    #[test]
+    fn put_bodies_must_hold_a_single_vtodo_with_a_uid() {
+        let (todo, uid) = single_todo(TODO, UTC).unwrap();
+        assert_eq!(uid, "0B1C@example.com");
+        assert_eq!(todo.summary.as_deref(), Some("Renew passport"));
+
+        let event = TODO.replace("VTODO", "VEVENT");
+        let twice = TODO.replace("END:VTODO\r\n", "END:VTODO\r\nBEGIN:VTODO\r\nUID:other@example.com\r\nEND:VTODO\r\n");
+        let without_uid = TODO.replace("UID:0B1C@example.com\r\n", "");
+        assert_eq!(single_todo("not a calendar", UTC).err(), Some("valid-calendar-data"));
+        assert_eq!(single_todo(&event, UTC).err(), Some("supported-calendar-component"));
+        assert_eq!(single_todo(&twice, UTC).err(), Some("valid-calendar-object-resource"));
+        assert_eq!(single_todo(&without_uid, UTC).err(), Some("valid-calendar-object-resource"));
+    }
+
+    #[test]
+    fn sync_reports_continue_from_their_token() {
+        let report = |token: &str| {
+            parse_xml(&format!("<d:sync-collection xmlns:d=\"DAV:\"><d:sync-token>{}</d:sync-token></d:sync-collection>", token))
+                .unwrap()
+        };
+
+        assert_eq!(requested_sync(&report("")).ok(), Some(None));
+        assert_eq!(requested_sync(&parse_xml("<d:sync-collection xmlns:d=\"DAV:\"/>").unwrap()).ok(), Some(None));
+        assert_eq!(requested_sync(&report(&sync_token(381))).ok(), Some(Some(381)));
+
+        assert_eq!(requested_sync(&report("https://example.com/sync/381")).err(), Some("valid-sync-token"));
+    }
+
+    #[tokio::test]
+    async fn sync_deltas_list_changes_and_tasks_that_left_the_collection() {
+        let calendar = own_calendar(4);
+        let changed = resource(12, "Renew passport");
+        let removals = vec!["task-13@taskflow.ics".to_string(), "Moved away.ics".to_string()];
+        let request = PropRequest::Props(vec![XmlName::new(DAV, "getetag")]);
+        let token = sync_token(390);
+        let changes = std::slice::from_ref(&changed);
+        let body = multistatus(|status| write_sync(status, &calendar, changes, &removals, &request), Some(&token)).await;
+
+        assert!(body.contains(&format!(
+            "<d:response><d:href>/caldav/4/tasks/task-12@taskflow.ics</d:href><d:propstat><d:prop><d:getetag>{}</d:getetag>",
+            escape_xml(&changed.etag)
+        )));
+        for path in ["/caldav/4/tasks/task-13@taskflow.ics", "/caldav/4/tasks/Moved%20away.ics"] {
+            assert!(body.contains(&format!(
+                "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                path
+            )));
+        }
+        assert!(body.ends_with("<d:sync-token>https://taskflow/ns/sync/390</d:sync-token></d:multistatus>"));
+    }
+
+    #[tokio::test]
+    async fn the_root_points_clients_at_their_principal() {
+        let context = ActivityContext { actor_id: Some(4), ..Default::default() };
+        let response = root_request(Method::from_bytes(b"PROPFIND").unwrap(), context.clone(), String::new()).await.unwrap();
+        let body = body_of(response).await;
+        assert!(body.contains("<d:current-user-principal><d:href>/caldav/4/</d:href></d:current-user-principal>"));
+        assert!(!body.contains("principal-URL"));
+
+        let response = root_request(Method::DELETE, context.clone(), String::new()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[header::ALLOW], COLLECTION_METHODS);
+
+        let response = root_request(Method::from_bytes(b"PROPFIND").unwrap(), context, "<d:propfind".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let anonymous = ActivityContext::default();
+        let response = root_request(Method::OPTIONS, anonymous, String::new()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,439 @@
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{ProjectService, TaskService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            calendar_feed::{CalendarFeed, FEED_REFRESH_MINUTES, MAX_CALENDAR_TASKS},
+            custom_field::CustomFieldTarget,
+            ical::{
+                parse_calendar, task_id_from_uid, write_calendar, CalendarComponent, CalendarInfo, CalendarItem,
+                ImportItem, ImportOutcome, ImportReport, MAX_IMPORT_ITEMS,
+            },
+            label::normalize_label,
+            task::Task,
+            workflow::Workflow,
+        },
+        errors::AppError,
+    },
+    infrastructure::{
+        db::{
+            calendar_feed_repo::CalendarFeedRepository,
+            custom_field_repo::CustomFieldRepository,
+            get_client,
+            project_repo::ProjectRepository,
+            task_repo::{CalendarFilter, TaskRepository},
+            workflow_repo::WorkflowRepository,
+            DbPool,
+        },
+        storage::sha256_hex,
+    },
+    interfaces::api::routes::task_routes::viewer_time_zone,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+pub fn calendar_routes() -> Router {
+    Router::new()
+        .route("/tasks/export.ics", get(export_calendar))
+        .route("/tasks/import.ics", post(import_calendar))
+        .route("/users/:id/calendar-feed", get(get_feed).delete(revoke_feed))
+        .route("/users/:id/calendar-feed/rotate", post(rotate_feed))
+        .route("/calendar/feeds/:token", get(serve_feed))
+}
+
+#[derive(Deserialize)]
+struct CalendarQuery {
+    project_id: Option<i32>,
+    tag: Option<String>,
+    #[serde(default)]
+    component: CalendarComponent,
+    // Completed and cancelled tasks are left out unless asked for
+    #[serde(default)]
+    include_closed: bool,
+}
+
+impl CalendarQuery {
+    fn filter(self, user_id: Option<i32>) -> (CalendarFilter, CalendarComponent) {
+        let filter = CalendarFilter {
+            project_id: self.project_id,
+            tag: self.tag.as_deref().and_then(normalize_label),
+            user_id,
+            include_closed: self.include_closed,
+            limit: MAX_CALENDAR_TASKS,
+        };
+        (filter, self.component)
+    }
+}
+
+// One-off download of every matching task with a due date
+async fn export_calendar(
+    Extension(pool): Extension<DbPool>,
+    headers: HeaderMap,
+    Query(query): Query<CalendarQuery>,
+) -> Result<Response, AppError> {
+    let client = get_client(&pool).await?;
+    let (filter, component) = query.filter(None);
+    let tasks = TaskRepository::find_calendar_tasks(&client, &filter).await?;
+    let info = CalendarInfo { name: "Tasks".to_string(), refresh_minutes: None };
+    Ok(calendar_response(
+        &headers,
+        write_calendar(&info, &tasks, component),
+        &tasks,
+        "private, no-cache",
+        "attachment; filename=\"tasks.ics\"",
+    ))
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    // Project for newly created tasks; existing tasks stay where they are
+    project_id: Option<i32>,
+    // Zone for floating times and dates; defaults to the importer's own zone
+    timezone: Option<String>,
+}
+
+// Creates or updates a task for every VTODO in the body. Items are matched by UID: first the
+// UID a task was imported with, then the UID this server exports (`task-<id>@taskflow`), so a
+// calendar exported earlier can be edited elsewhere and imported back.
+async fn import_calendar(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<ImportReport>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    if let Some(project_id) = query.project_id {
+        let project = ProjectRepository::find_project(&tx, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    let timezone = viewer_time_zone(&tx, query.timezone.as_deref(), context.actor_id).await?;
+    let items = parse_calendar(&body, timezone).map_err(|e| AppError::validation_error("calendar", &e))?;
+    if items.len() > MAX_IMPORT_ITEMS {
+        return Err(AppError::validation_error(
+            "calendar",
+            &format!("A calendar can hold at most {} items per import", MAX_IMPORT_ITEMS),
+        ));
+    }
+
+    let uids = items
+        .iter()
+        .filter_map(|item| match item {
+            CalendarItem::Todo(todo) => todo.uid.clone(),
+            CalendarItem::Other { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    let mut existing = find_by_uids(&tx, &uids).await?;
+    let definitions = CustomFieldRepository::find_definitions(&tx, Some(CustomFieldTarget::Task)).await?;
+    let mut workflows: HashMap<Option<i32>, Workflow> = HashMap::new();
+    let mut editable: HashMap<i32, bool> = HashMap::new();
+    if let Some(project_id) = query.project_id {
+        editable.insert(project_id, true);
+    }
+
+    let mut report = ImportReport::default();
+    let mut imported: HashMap<String, i32> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+    for item in items {
+        let todo = match item {
+            CalendarItem::Todo(todo) => todo,
+            CalendarItem::Other { kind, uid, summary } => {
+                report.skip(uid, summary, &format!("{} components are not imported", kind));
+                continue;
+            }
+        };
+        let Some(uid) = todo.uid.clone() else {
+            report.skip(None, todo.summary.clone(), "The VTODO has no UID");
+            continue;
+        };
+        if !seen.insert(uid.clone()) {
+            report.skip(Some(uid), todo.summary.clone(), "The UID appears more than once in the file");
+            continue;
+        }
+
+        let mut warnings = todo.warnings.clone();
+        let (task, outcome) = match existing.remove(&uid) {
+            Some(mut task) => {
+                if task.is_trashed() {
+                    report.skip(Some(uid), todo.summary.clone(), &format!("Task {} is in the trash", task.id));
+                    continue;
+                }
+                if let Some(project_id) = task.project_id {
+                    if !can_edit_project(&tx, &mut editable, project_id, context.actor_id).await? {
+                        report.skip(Some(uid), todo.summary.clone(), &format!("No permission to edit tasks in project {}", project_id));
+                        continue;
+                    }
+                }
+                task.set_activity_context(context.clone());
+                let workflow = workflow_for(&tx, &mut workflows, task.project_id).await?;
+                if !TaskService::apply_imported_todo(&mut task, &todo, workflow, &mut warnings) {
+                    imported.insert(uid.clone(), task.id);
+                    links.push((report.items.len(), task.id, todo.parent_uid.clone()));
+                    report.push(ImportItem {
+                        uid: Some(uid),
+                        summary: todo.summary.clone(),
+                        outcome: ImportOutcome::Skipped,
+                        task_id: Some(task.id),
+                        reason: Some("Unchanged".to_string()),
+                        warnings,
+                    });
+                    continue;
+                }
+                TaskRepository::update_task(&tx, &mut task).await?;
+                (task, ImportOutcome::Updated)
+            }
+            None => {
+                let Some(summary) = todo.summary.clone() else {
+                    report.skip(Some(uid), None, "The VTODO has no SUMMARY");
+                    continue;
+                };
+                let workflow = workflow_for(&tx, &mut workflows, query.project_id).await?;
+                let mut task = TaskService::create_task(summary, None, workflow)?;
+                task.set_activity_context(context.clone());
+                task.project_id = query.project_id;
+                task.ical_uid = Some(uid.clone());
+                TaskService::apply_imported_todo(&mut task, &todo, workflow, &mut warnings);
+                TaskService::set_custom_fields(&mut task, &definitions, HashMap::new())?;
+                (TaskRepository::create_task(&tx, &mut task).await?, ImportOutcome::Created)
+            }
+        };
+
+        imported.insert(uid.clone(), task.id);
+        links.push((report.items.len(), task.id, todo.parent_uid.clone()));
+        report.push(ImportItem {
+            uid: Some(uid),
+            summary: Some(task.title),
+            outcome,
+            task_id: Some(task.id),
+            reason: None,
+            warnings,
+        });
+    }
+
+    // Parents are linked once every item exists, so they may appear in any order. A parent may
+    // also be a task imported or exported earlier.
+    let missing = links
+        .iter()
+        .filter_map(|(_, _, parent_uid)| parent_uid.clone())
+        .filter(|uid| !imported.contains_key(uid))
+        .collect::<Vec<_>>();
+    for (uid, task) in find_by_uids(&tx, &missing).await? {
+        imported.insert(uid, task.id);
+    }
+    for (index, child_id, parent_uid) in links {
+        let Some(parent_uid) = parent_uid else { continue };
+        let Some(&parent_id) = imported.get(&parent_uid) else {
+            report.items[index].warnings.push(format!("Parent '{}' was not found", parent_uid));
+            continue;
+        };
+        if let Some(message) = link_parent(&tx, child_id, parent_id, &context).await? {
+            report.items[index].warnings.push(message);
+        }
+    }
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(report))
+}
+
+// Makes the task a subtask of the parent named by a calendar item, unless it already is one.
+// Returns why the link was refused, if it was.
+pub(crate) async fn link_parent(
+    client: &impl GenericClient,
+    child_id: i32,
+    parent_id: i32,
+    context: &ActivityContext,
+) -> Result<Option<String>, AppError> {
+    let mut child = TaskRepository::find_task_by_id(client, child_id).await?;
+    if child.parent_task == Some(parent_id) {
+        return Ok(None);
+    }
+    let mut parent = TaskRepository::find_task_by_id(client, parent_id).await?;
+    parent.set_activity_context(context.clone());
+    child.set_activity_context(context.clone());
+    let ancestors = TaskRepository::find_ancestor_ids(client, parent_id).await?;
+    let refused = match TaskService::add_subtask(&mut parent, &mut child, &ancestors, None) {
+        Ok(()) => None,
+        Err(AppError::ValidationError { message, .. }) => Some(message),
+        Err(e) => return Err(e),
+    };
+    if refused.is_some() {
+        return Ok(refused);
+    }
+    TaskRepository::update_task(client, &mut child).await?;
+    TaskRepository::update_task(client, &mut parent).await?;
+    TaskRepository::save_subtask_order(client, &parent).await?;
+    Ok(None)
+}
+
+// Tasks keyed by the UID they answer to, including trashed ones so they are not re-created
+pub(crate) async fn find_by_uids(client: &impl GenericClient, uids: &[String]) -> Result<HashMap<String, Task>, AppError> {
+    let mut found = HashMap::new();
+    for task in TaskRepository::find_tasks_by_ical_uids(client, uids).await? {
+        if let Some(uid) = task.ical_uid.clone() {
+            found.insert(uid, task);
+        }
+    }
+    let by_id = uids
+        .iter()
+        .filter(|uid| !found.contains_key(*uid))
+        .filter_map(|uid| task_id_from_uid(uid).map(|id| (id, uid.clone())))
+        .collect::<HashMap<_, _>>();
+    let ids = by_id.keys().copied().collect::<Vec<_>>();
+    for task in TaskRepository::find_tasks_by_ids(client, &ids).await? {
+        // A task imported under its own UID no longer answers to the generated one
+        if task.ical_uid.is_none() {
+            if let Some(uid) = by_id.get(&task.id) {
+                found.insert(uid.clone(), task);
+            }
+        }
+    }
+    Ok(found)
+}
+
+async fn workflow_for<'a>(
+    client: &impl GenericClient,
+    workflows: &'a mut HashMap<Option<i32>, Workflow>,
+    project_id: Option<i32>,
+) -> Result<&'a Workflow, AppError> {
+    if let Entry::Vacant(entry) = workflows.entry(project_id) {
+        entry.insert(WorkflowRepository::find_for_project(client, project_id).await?);
+    }
+    Ok(&workflows[&project_id])
+}
+
+async fn can_edit_project(
+    client: &impl GenericClient,
+    editable: &mut HashMap<i32, bool>,
+    project_id: i32,
+    actor_id: Option<i32>,
+) -> Result<bool, AppError> {
+    if let Some(&allowed) = editable.get(&project_id) {
+        return Ok(allowed);
+    }
+    let project = ProjectRepository::find_project(client, project_id).await?;
+    let allowed = ProjectService::ensure_can_edit_tasks(&project, actor_id).is_ok();
+    editable.insert(project_id, allowed);
+    Ok(allowed)
+}
+
+#[derive(Serialize)]
+struct FeedResponse {
+    url: String,
+    #[serde(flatten)]
+    feed: CalendarFeed,
+}
+
+impl From<CalendarFeed> for FeedResponse {
+    fn from(feed: CalendarFeed) -> Self {
+        FeedResponse { url: feed.path(), feed }
+    }
+}
+
+// The feed address is a secret, so only its owner may see or change it
+fn ensure_feed_owner(context: &ActivityContext, user_id: i32) -> Result<(), AppError> {
+    match context.actor_id {
+        Some(actor_id) if actor_id == user_id => Ok(()),
+        Some(actor_id) => Err(AppError::forbidden(actor_id, "manage another user's calendar feed")),
+        None => Err(AppError::permission_denied("Calendar feeds belong to a signed-in user")),
+    }
+}
+
+async fn get_feed(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+) -> Result<Json<FeedResponse>, AppError> {
+    ensure_feed_owner(&context, user_id)?;
+    let client = get_client(&pool).await?;
+    let feed = CalendarFeedRepository::find_feed(&client, user_id).await?;
+    Ok(Json(feed.into()))
+}
+
+async fn rotate_feed(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+) -> Result<Json<FeedResponse>, AppError> {
+    ensure_feed_owner(&context, user_id)?;
+    let client = get_client(&pool).await?;
+    let feed = CalendarFeedRepository::rotate_feed(&client, user_id, chrono::Utc::now().timestamp_millis()).await?;
+    Ok(Json(feed.into()))
+}
+
+async fn revoke_feed(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    ensure_feed_owner(&context, user_id)?;
+    let client = get_client(&pool).await?;
+    CalendarFeedRepository::delete_feed(&client, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Public: the token is the only credential. Unknown tokens get a bare 404 that says nothing
+// about which tokens exist.
+async fn serve_feed(
+    Extension(pool): Extension<DbPool>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+    Query(query): Query<CalendarQuery>,
+) -> Result<Response, AppError> {
+    let token = token.trim_end_matches(".ics");
+    let client = get_client(&pool).await?;
+    let Some(feed) = CalendarFeedRepository::find_feed_by_token(&client, token).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let (filter, component) = query.filter(Some(feed.user_id));
+    let tasks = TaskRepository::find_calendar_tasks(&client, &filter).await?;
+    let info = CalendarInfo { name: "My tasks".to_string(), refresh_minutes: Some(FEED_REFRESH_MINUTES) };
+    Ok(calendar_response(
+        &headers,
+        write_calendar(&info, &tasks, component),
+        &tasks,
+        "private, max-age=300",
+        "inline; filename=\"tasks.ics\"",
+    ))
+}
+
+// The calendar is rendered deterministically from the tasks, so its hash is a strong ETag
+// and a matching If-None-Match gets a 304 without the body
+fn calendar_response(headers: &HeaderMap, body: String, tasks: &[Task], cache_control: &'static str, disposition: &'static str) -> Response {
+    let etag = format!("\"{}\"", sha256_hex(body.as_bytes()));
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    let last_modified = tasks
+        .iter()
+        .map(|t| t.updated_at)
+        .max()
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .map(|at| at.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+    if let Some(value) = last_modified.and_then(|v| HeaderValue::from_str(&v).ok()) {
+        response_headers.insert(header::LAST_MODIFIED, value);
+    }
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').map(str::trim).any(|candidate| candidate == "*" || candidate == etag));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/calendar; charset=utf-8"));
+    response_headers.insert(header::CONTENT_DISPOSITION, HeaderValue::from_static(disposition));
+    (response_headers, body).into_response()
+}
@@ -0,0 +1,188 @@
+use axum::{extract::Path, routing::{get, post, put}, Extension, Router, Json, http::StatusCode};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{ProjectService, TaskService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            task::{ChecklistItem, Task},
+        },
+        errors::AppError,
+    },
+    infrastructure::db::{get_client, project_repo::ProjectRepository, task_repo::TaskRepository, DbPool},
+    interfaces::api::routes::task_routes::rollup_ancestors,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub fn checklist_routes() -> Router {
+    Router::new()
+        .route("/tasks/:id/checklist", get(list_items).post(add_item))
+        .route("/tasks/:id/checklist/progress", put(set_checklist_progress))
+        .route("/tasks/:id/checklist/:item_id", put(update_item).delete(remove_item))
+        .route("/tasks/:id/checklist/:item_id/toggle", post(toggle_item))
+        .route("/tasks/:id/checklist/:item_id/position", put(move_item))
+}
+
+async fn list_items(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<ChecklistItem>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(TaskRepository::find_task_by_id(&client, id).await?.checklist))
+}
+
+async fn load_task(client: &impl GenericClient, id: i32, context: &ActivityContext) -> Result<Task, AppError> {
+    let mut task = TaskRepository::find_task_by_id(client, id).await?;
+    task.set_activity_context(context.clone());
+    if let Some(project_id) = task.project_id {
+        let project = ProjectRepository::find_project(client, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    Ok(task)
+}
+
+// Checklist progress only applies without subtasks, so no subtree is needed here
+async fn save_task(client: &impl GenericClient, task: &mut Task) -> Result<(), AppError> {
+    task.update_progress(&HashMap::new(), false);
+    TaskRepository::update_task(client, task).await?;
+    rollup_ancestors(client, task.id).await
+}
+
+#[derive(Deserialize)]
+struct AddItemRequest {
+    text: String,
+    position: Option<usize>,
+}
+
+async fn add_item(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<AddItemRequest>,
+) -> Result<(StatusCode, Json<ChecklistItem>), AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = load_task(&tx, id, &context).await?;
+    let item = TaskService::add_checklist_item(&mut task, &payload.text, payload.position)?;
+    save_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((StatusCode::CREATED, Json(item)))
+}
+
+#[derive(Deserialize)]
+struct UpdateItemRequest {
+    text: Option<String>,
+    checked: Option<bool>,
+}
+
+async fn update_item(
+    Extension(pool): Extension<DbPool>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+    Json(payload): Json<UpdateItemRequest>,
+) -> Result<Json<ChecklistItem>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = load_task(&tx, id, &context).await?;
+    if let Some(text) = payload.text {
+        task.rename_checklist_item(item_id, TaskService::checklist_text(&text)?)?;
+    }
+    if let Some(checked) = payload.checked {
+        task.check_checklist_item(item_id, checked, context.actor_id)?;
+    }
+    let item = find_item(&task, item_id)?;
+    save_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(item))
+}
+
+async fn toggle_item(
+    Extension(pool): Extension<DbPool>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<Json<ChecklistItem>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = load_task(&tx, id, &context).await?;
+    let checked = find_item(&task, item_id)?.checked;
+    task.check_checklist_item(item_id, !checked, context.actor_id)?;
+    let item = find_item(&task, item_id)?;
+    save_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(item))
+}
+
+#[derive(Deserialize)]
+struct MoveItemRequest {
+    position: usize,
+}
+
+async fn move_item(
+    Extension(pool): Extension<DbPool>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+    Json(payload): Json<MoveItemRequest>,
+) -> Result<Json<Vec<ChecklistItem>>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = load_task(&tx, id, &context).await?;
+    task.move_checklist_item(item_id, payload.position)?;
+    TaskRepository::update_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(task.checklist))
+}
+
+async fn remove_item(
+    Extension(pool): Extension<DbPool>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = load_task(&tx, id, &context).await?;
+    task.remove_checklist_item(item_id)?;
+    save_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ChecklistProgressRequest {
+    enabled: bool,
+}
+
+async fn set_checklist_progress(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<ChecklistProgressRequest>,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = load_task(&tx, id, &context).await?;
+    task.checklist_progress = payload.enabled;
+    save_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(task))
+}
+
+fn find_item(task: &Task, item_id: i32) -> Result<ChecklistItem, AppError> {
+    task.checklist
+        .iter()
+        .find(|item| item.id == item_id)
+        .cloned()
+        .ok_or_else(|| AppError::not_found("Checklist item", item_id))
+}
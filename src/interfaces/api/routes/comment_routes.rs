@@ -0,0 +1,200 @@
+use axum::{extract::{Path, Query}, routing::{get, post, put}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::{CommentService, TaskService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            comment::{Comment, CommentEdit, CommentPolicy},
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+    },
+    infrastructure::db::{
+        comment_repo::CommentRepository,
+        get_client,
+        project_repo::ProjectRepository,
+        task_repo::TaskRepository,
+        DbPool,
+    },
+    interfaces::api::routes::watcher_routes::{notify_watchers, process_mentions},
+};
+use serde::Deserialize;
+
+pub fn comment_routes() -> Router {
+    Router::new()
+        .route("/tasks/:id/comments", get(list_comments).post(add_comment_to_task))
+        // Kept for clients of the original single-comment endpoint
+        .route("/tasks/:id/comment", post(add_comment_to_task))
+        .route("/comments/:id", get(get_comment).put(edit_comment).delete(delete_comment))
+        .route("/comments/:id/history", get(get_comment_history))
+        .route("/comments/:id/reactions/:emoji", put(add_reaction).delete(remove_reaction))
+}
+
+// Pages over top-level comments; replies are nested under their parents
+async fn list_comments(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Comment>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    let page = CommentRepository::find_comments(&client, id, &page).await?;
+    Ok(Json(Page {
+        items: CommentService::build_threads(page.items),
+        total: page.total,
+        limit: page.limit,
+        offset: page.offset,
+    }))
+}
+
+#[derive(Deserialize)]
+struct CommentRequest {
+    // Defaults to the acting user
+    user_id: Option<i32>,
+    comment: String,
+    parent_id: Option<i64>,
+}
+
+async fn add_comment_to_task(
+    Extension(pool): Extension<DbPool>,
+    Extension(policy): Extension<CommentPolicy>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<CommentRequest>,
+) -> Result<(StatusCode, Json<Comment>), AppError> {
+    let user_id = payload
+        .user_id
+        .or(context.actor_id)
+        .ok_or_else(|| AppError::invalid_input("user_id or x-user-id is required to comment"))?;
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = TaskRepository::find_task_by_id(&tx, id).await?;
+    task.set_activity_context(context.clone());
+    let parent = match payload.parent_id {
+        Some(parent_id) => {
+            let parent = CommentRepository::find_comment(&tx, parent_id).await?;
+            let depth = CommentRepository::find_depth(&tx, parent_id).await?;
+            Some((parent, depth))
+        }
+        None => None,
+    };
+    let mut comment = Comment::new(task.id, payload.parent_id, user_id, payload.comment);
+    TaskService::add_comment_to_task(&task, &policy, &mut comment, parent.as_ref().map(|(p, depth)| (p, *depth)))?;
+    let comment = CommentRepository::insert_comment(&tx, &comment).await?;
+    task.add_comment(&comment);
+    let events = task.activity_log.clone();
+    TaskRepository::update_task(&tx, &mut task).await?;
+
+    // Mentioned users already get their own notification for this comment
+    let mentioned = process_mentions(&tx, &task, &comment.body, &context).await?;
+    notify_watchers(&tx, &task, &events, &mentioned).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+async fn get_comment(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i64>,
+) -> Result<Json<Comment>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(CommentRepository::find_comment(&client, id).await?))
+}
+
+#[derive(Deserialize)]
+struct EditCommentRequest {
+    comment: String,
+}
+
+async fn edit_comment(
+    Extension(pool): Extension<DbPool>,
+    Extension(policy): Extension<CommentPolicy>,
+    Path(id): Path<i64>,
+    context: ActivityContext,
+    Json(payload): Json<EditCommentRequest>,
+) -> Result<Json<Comment>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut comment = CommentRepository::find_comment(&tx, id).await?;
+    let previous = comment.body.clone();
+    let now = chrono::Utc::now().timestamp_millis();
+    CommentService::edit_comment(&mut comment, &policy, context.actor_id, &payload.comment, now)?;
+    CommentRepository::update_comment(&tx, &comment).await?;
+
+    let mut task = TaskRepository::find_task_by_id(&tx, comment.task_id).await?;
+    task.set_activity_context(context);
+    task.edit_comment(&comment, &previous);
+    TaskRepository::update_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(comment))
+}
+
+async fn delete_comment(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i64>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut comment = CommentRepository::find_comment(&tx, id).await?;
+    let mut task = TaskRepository::find_task_by_id(&tx, comment.task_id).await?;
+    let can_moderate = match (task.project_id, context.actor_id) {
+        (Some(project_id), Some(actor_id)) => ProjectRepository::find_project(&tx, project_id).await?.can_manage(actor_id),
+        _ => false,
+    };
+    CommentService::delete_comment(&mut comment, context.actor_id, can_moderate, chrono::Utc::now().timestamp_millis())?;
+    CommentRepository::update_comment(&tx, &comment).await?;
+
+    task.set_activity_context(context);
+    task.delete_comment(&comment);
+    TaskRepository::update_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_comment_history(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<CommentEdit>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(CommentRepository::find_comment(&client, id).await?.edits))
+}
+
+// Reactions are always made by the acting user
+fn reacting_user(context: &ActivityContext) -> Result<i32, AppError> {
+    context
+        .actor_id
+        .ok_or_else(|| AppError::invalid_input("x-user-id is required to react to a comment"))
+}
+
+async fn add_reaction(
+    Extension(pool): Extension<DbPool>,
+    Path((id, emoji)): Path<(i64, String)>,
+    context: ActivityContext,
+) -> Result<Json<Comment>, AppError> {
+    let user_id = reacting_user(&context)?;
+    let client = get_client(&pool).await?;
+    let comment = CommentRepository::find_comment(&client, id).await?;
+    let emoji = CommentService::validate_reaction(&comment, &emoji)?;
+    CommentRepository::add_reaction(&client, id, user_id, &emoji, chrono::Utc::now().timestamp_millis()).await?;
+    Ok(Json(CommentRepository::find_comment(&client, id).await?))
+}
+
+async fn remove_reaction(
+    Extension(pool): Extension<DbPool>,
+    Path((id, emoji)): Path<(i64, String)>,
+    context: ActivityContext,
+) -> Result<Json<Comment>, AppError> {
+    let user_id = reacting_user(&context)?;
+    let client = get_client(&pool).await?;
+    CommentRepository::find_comment(&client, id).await?;
+    if !CommentRepository::remove_reaction(&client, id, user_id, emoji.trim()).await? {
+        return Err(AppError::validation_error("emoji", &format!("No {} reaction from user {}", emoji, user_id)));
+    }
+    Ok(Json(CommentRepository::find_comment(&client, id).await?))
+}
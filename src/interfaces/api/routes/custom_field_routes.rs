@@ -0,0 +1,144 @@
+use axum::{extract::{Path, Query}, routing::get, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::CustomFieldService,
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            custom_field::{CustomFieldDefinition, CustomFieldTarget, CustomFieldType},
+        },
+        errors::AppError,
+    },
+    infrastructure::db::{custom_field_repo::CustomFieldRepository, get_client, task_repo::TaskRepository, DbPool},
+};
+use serde::Deserialize;
+
+pub fn custom_field_routes() -> Router {
+    Router::new()
+        .route("/custom-fields", get(list_definitions).post(create_definition))
+        .route(
+            "/custom-fields/:id",
+            get(get_definition).put(update_definition).delete(delete_definition),
+        )
+}
+
+#[derive(Deserialize)]
+struct DefinitionQuery {
+    target: Option<CustomFieldTarget>,
+}
+
+async fn list_definitions(
+    Extension(pool): Extension<DbPool>,
+    Query(query): Query<DefinitionQuery>,
+) -> Result<Json<Vec<CustomFieldDefinition>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(CustomFieldRepository::find_definitions(&client, query.target).await?))
+}
+
+async fn get_definition(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<CustomFieldDefinition>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(CustomFieldRepository::find_definition(&client, id).await?))
+}
+
+#[derive(Deserialize)]
+struct DefinitionRequest {
+    target: CustomFieldTarget,
+    key: String,
+    name: String,
+    field_type: CustomFieldType,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    allowed_values: Vec<String>,
+}
+
+async fn create_definition(
+    Extension(pool): Extension<DbPool>,
+    Json(payload): Json<DefinitionRequest>,
+) -> Result<(StatusCode, Json<CustomFieldDefinition>), AppError> {
+    let client = get_client(&pool).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut definition = CustomFieldDefinition {
+        id: 0,
+        target: payload.target,
+        key: payload.key,
+        name: payload.name,
+        field_type: payload.field_type,
+        required: payload.required,
+        allowed_values: payload.allowed_values,
+        created_at: now,
+        updated_at: now,
+    };
+    CustomFieldService::validate_definition(&mut definition)?;
+    let definition = CustomFieldRepository::create_definition(&client, &definition).await?;
+    Ok((StatusCode::CREATED, Json(definition)))
+}
+
+async fn update_definition(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Json(payload): Json<DefinitionRequest>,
+) -> Result<Json<CustomFieldDefinition>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let existing = CustomFieldRepository::find_definition(&tx, id).await?;
+    let mut definition = CustomFieldDefinition {
+        target: payload.target,
+        key: payload.key,
+        name: payload.name,
+        field_type: payload.field_type,
+        required: payload.required,
+        allowed_values: payload.allowed_values,
+        updated_at: chrono::Utc::now().timestamp_millis(),
+        ..existing.clone()
+    };
+    CustomFieldService::validate_update(&existing, &mut definition)?;
+
+    // Options still stored on tasks cannot be withdrawn
+    let dropped = existing
+        .allowed_values
+        .iter()
+        .filter(|v| !definition.allowed_values.contains(v))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !dropped.is_empty() && existing.target == CustomFieldTarget::Task {
+        let in_use = CustomFieldRepository::count_tasks_using(&tx, &existing.key, &dropped).await?;
+        if in_use > 0 {
+            return Err(AppError::validation_error(
+                "allowed_values",
+                &format!("{} tasks still use one of: {}", in_use, dropped.join(", ")),
+            ));
+        }
+    }
+
+    CustomFieldRepository::update_definition(&tx, &definition).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(definition))
+}
+
+// Removes the definition along with every task value stored under its key; each task is
+// saved through the task repository so the removal is kept in its history and activity
+async fn delete_definition(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let definition = CustomFieldRepository::find_definition(&tx, id).await?;
+    if definition.target == CustomFieldTarget::Task {
+        let mut tasks = TaskRepository::find_tasks_with_custom_field_for_update(&tx, &definition.key).await?;
+        for task in &mut tasks {
+            task.set_activity_context(context.clone());
+            if task.drop_custom_field(&definition.key) {
+                TaskRepository::update_task(&tx, task).await?;
+            }
+        }
+    }
+    CustomFieldRepository::delete_definition(&tx, id).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
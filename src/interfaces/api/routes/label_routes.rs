@@ -0,0 +1,196 @@
+use axum::{extract::{Path, Query}, routing::{get, post}, Extension, Router, Json, http::StatusCode};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::LabelService,
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            label::{normalize_label, Label},
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+    },
+    infrastructure::db::{get_client, label_repo::LabelRepository, task_repo::TaskRepository, DbPool},
+};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_SUGGESTIONS: i64 = 10;
+const MAX_SUGGESTIONS: i64 = 50;
+
+pub fn label_routes() -> Router {
+    Router::new()
+        .route("/labels", get(list_labels).post(create_label))
+        .route("/labels/suggest", get(suggest_labels))
+        .route("/labels/merge", post(merge_labels))
+        .route("/labels/:id", get(get_label).put(update_label).delete(delete_label))
+}
+
+async fn list_labels(
+    Extension(pool): Extension<DbPool>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Label>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(LabelRepository::find_labels(&client, &page).await?))
+}
+
+#[derive(Deserialize)]
+struct SuggestQuery {
+    #[serde(default)]
+    q: String,
+    limit: Option<i64>,
+}
+
+async fn suggest_labels(
+    Extension(pool): Extension<DbPool>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Json<Vec<Label>>, AppError> {
+    // Typed input is matched the way it would be stored
+    let prefix = normalize_label(&query.q).unwrap_or_default();
+    let limit = query.limit.unwrap_or(DEFAULT_SUGGESTIONS).clamp(1, MAX_SUGGESTIONS);
+    let client = get_client(&pool).await?;
+    Ok(Json(LabelRepository::suggest_labels(&client, &prefix, limit).await?))
+}
+
+async fn get_label(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Label>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(LabelRepository::find_label(&client, id).await?))
+}
+
+#[derive(Deserialize)]
+struct LabelRequest {
+    name: String,
+    color: Option<String>,
+    description: Option<String>,
+}
+
+async fn create_label(
+    Extension(pool): Extension<DbPool>,
+    Json(payload): Json<LabelRequest>,
+) -> Result<(StatusCode, Json<Label>), AppError> {
+    let client = get_client(&pool).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut label = Label {
+        id: 0,
+        name: payload.name,
+        color: payload.color,
+        description: payload.description,
+        usage_count: 0,
+        created_at: now,
+        updated_at: now,
+    };
+    LabelService::validate_label(&mut label)?;
+    let label = LabelRepository::create_label(&client, &label).await?;
+    Ok((StatusCode::CREATED, Json(label)))
+}
+
+// Renaming rewrites the tag on every task; renaming onto an existing label is refused in
+// favour of an explicit merge
+async fn update_label(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(id): Path<i32>,
+    Json(payload): Json<LabelRequest>,
+) -> Result<Json<Label>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let existing = LabelRepository::find_labels_for_update(&tx, &[id]).await?.remove(0);
+    let mut label = Label {
+        name: payload.name,
+        color: payload.color,
+        description: payload.description,
+        updated_at: chrono::Utc::now().timestamp_millis(),
+        ..existing.clone()
+    };
+    LabelService::validate_label(&mut label)?;
+    LabelRepository::update_label(&tx, &label).await?;
+    if label.name != existing.name {
+        retag_tasks(&tx, &[existing.name], Some(&label.name), &context).await?;
+    }
+    let label = LabelRepository::find_label(&tx, id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(label))
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+    sources: Vec<i32>,
+    target: i32,
+}
+
+#[derive(Serialize)]
+struct MergeResult {
+    label: Label,
+    merged: Vec<String>,
+    tasks_updated: u64,
+}
+
+async fn merge_labels(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Json(payload): Json<MergeRequest>,
+) -> Result<Json<MergeResult>, AppError> {
+    LabelService::validate_merge(&payload.sources, payload.target)?;
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut ids = payload.sources.clone();
+    ids.push(payload.target);
+    ids.sort_unstable();
+    ids.dedup();
+    let (targets, sources): (Vec<Label>, Vec<Label>) = LabelRepository::find_labels_for_update(&tx, &ids)
+        .await?
+        .into_iter()
+        .partition(|l| l.id == payload.target);
+    let target = &targets[0];
+
+    let source_ids = sources.iter().map(|l| l.id).collect::<Vec<_>>();
+    let merged = sources.into_iter().map(|l| l.name).collect::<Vec<_>>();
+    let tasks_updated = retag_tasks(&tx, &merged, Some(&target.name), &context).await?;
+    LabelRepository::delete_labels(&tx, &source_ids).await?;
+    let label = LabelRepository::find_label(&tx, target.id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(MergeResult { label, merged, tasks_updated }))
+}
+
+// Removes the label from every task before dropping it
+async fn delete_label(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let label = LabelRepository::find_labels_for_update(&tx, &[id]).await?.remove(0);
+    retag_tasks(&tx, &[label.name], None, &context).await?;
+    LabelRepository::delete_labels(&tx, &[label.id]).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Renames the tags, or drops them when `to` is None, saving each task through the task
+// repository so the change is kept in its history and activity. Returns how many changed.
+async fn retag_tasks(client: &impl GenericClient, from: &[String], to: Option<&str>, context: &ActivityContext) -> Result<u64, AppError> {
+    let mut tasks = TaskRepository::find_tasks_with_tags_for_update(client, from).await?;
+    let mut updated = 0;
+    for task in &mut tasks {
+        task.set_activity_context(context.clone());
+        let mut changed = false;
+        for tag in from {
+            changed |= match to {
+                Some(to) => task.replace_tag(tag, to),
+                None => task.remove_tag(tag),
+            };
+        }
+        if changed {
+            TaskRepository::update_task(client, task).await?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
@@ -0,0 +1,145 @@
+use axum::{extract::Path, routing::{delete, get, post}, Extension, Router, Json, http::StatusCode};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{LinkService, ProjectService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            link::{LinkRelation, LinkedTask},
+            task::Task,
+        },
+        errors::AppError,
+    },
+    infrastructure::db::{
+        get_client,
+        link_repo::LinkRepository,
+        project_repo::ProjectRepository,
+        task_repo::TaskRepository,
+        workflow_repo::WorkflowRepository,
+        DbPool,
+    },
+    interfaces::api::routes::{task_routes::rollup_ancestors, watcher_routes::notify_watchers},
+};
+use serde::Deserialize;
+
+pub fn link_routes() -> Router {
+    Router::new()
+        .route("/tasks/:id/links", get(list_links).post(create_link))
+        .route("/tasks/:id/links/:link_id", delete(delete_link))
+        .route("/tasks/:id/close-as-duplicate", post(close_as_duplicate))
+}
+
+async fn list_links(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<LinkedTask>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    let links = LinkRepository::find_links(&client, id).await?;
+    let other_ids = links.iter().map(|l| l.other_end(id)).collect::<Vec<_>>();
+    let others = TaskRepository::find_tasks_by_ids(&client, &other_ids).await?;
+    Ok(Json(LinkService::linked_tasks(id, &links, &others)))
+}
+
+// Both ends of a link change, so the actor needs edit rights on each
+async fn load_pair(client: &impl GenericClient, id: i32, other_id: i32, context: &ActivityContext) -> Result<(Task, Task), AppError> {
+    let mut task = TaskRepository::find_task_by_id(client, id).await?;
+    let mut other = TaskRepository::find_task_by_id(client, other_id).await?;
+    for linked in [&mut task, &mut other] {
+        linked.set_activity_context(context.clone());
+        if let Some(project_id) = linked.project_id {
+            let project = ProjectRepository::find_project(client, project_id).await?;
+            ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+        }
+    }
+    Ok((task, other))
+}
+
+#[derive(Deserialize)]
+struct LinkRequest {
+    relation: LinkRelation,
+    task_id: i32,
+}
+
+async fn create_link(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<LinkRequest>,
+) -> Result<(StatusCode, Json<LinkedTask>), AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let (mut task, mut other) = load_pair(&tx, id, payload.task_id, &context).await?;
+    let blocking = match payload.relation {
+        LinkRelation::Blocks => Some((task.id, other.id)),
+        LinkRelation::BlockedBy => Some((other.id, task.id)),
+        _ => None,
+    };
+    if let Some((blocker, blocked)) = blocking {
+        if LinkRepository::blocks_transitively(&tx, blocked, blocker).await? {
+            return Err(AppError::validation_error(
+                "relation",
+                &format!("Task {} already blocks task {}; the link would create a cycle", blocked, blocker),
+            ));
+        }
+    }
+
+    let existing = LinkRepository::find_links(&tx, id).await?;
+    let link = LinkService::link_tasks(&mut task, payload.relation, &mut other, &existing, context.actor_id)?;
+    let link = LinkRepository::insert_link(&tx, &link).await?;
+    TaskRepository::update_task(&tx, &mut task).await?;
+    TaskRepository::update_task(&tx, &mut other).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    let linked = LinkService::linked_tasks(id, &[link], &[other]).remove(0);
+    Ok((StatusCode::CREATED, Json(linked)))
+}
+
+async fn delete_link(
+    Extension(pool): Extension<DbPool>,
+    Path((id, link_id)): Path<(i32, i64)>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let link = LinkRepository::find_link(&tx, id, link_id).await?;
+    let (mut task, mut other) = load_pair(&tx, id, link.other_end(id), &context).await?;
+    LinkService::unlink_tasks(&mut task, &mut other, &link);
+    LinkRepository::delete_link(&tx, link_id).await?;
+    TaskRepository::update_task(&tx, &mut task).await?;
+    TaskRepository::update_task(&tx, &mut other).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct DuplicateRequest {
+    duplicate_of: i32,
+}
+
+async fn close_as_duplicate(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<DuplicateRequest>,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let (mut task, mut original) = load_pair(&tx, id, payload.duplicate_of, &context).await?;
+    let existing = LinkRepository::find_links(&tx, id).await?;
+    let workflow = WorkflowRepository::find_for_project(&tx, task.project_id).await?;
+    let link = LinkService::close_as_duplicate(&mut task, &mut original, &existing, &workflow, context.actor_id)?;
+    LinkRepository::insert_link(&tx, &link).await?;
+    let events = task.activity_log.clone();
+    TaskRepository::update_task(&tx, &mut task).await?;
+    TaskRepository::update_task(&tx, &mut original).await?;
+    rollup_ancestors(&tx, task.id).await?;
+    notify_watchers(&tx, &task, &events, &[]).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(task))
+}
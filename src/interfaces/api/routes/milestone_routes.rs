@@ -0,0 +1,171 @@
+use axum::{extract::Path, routing::{delete, get, post}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::{MilestoneService, ProjectService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            milestone::{Milestone, MilestoneProgress},
+        },
+        errors::AppError,
+        time::{rfc3339, rfc3339_option},
+    },
+    infrastructure::db::{
+        get_client,
+        milestone_repo::MilestoneRepository,
+        project_repo::ProjectRepository,
+        task_repo::TaskRepository,
+        DbPool,
+    },
+};
+use serde::Deserialize;
+
+pub fn milestone_routes() -> Router {
+    Router::new()
+        .route("/projects/:id/milestones", get(list_milestones).post(create_milestone))
+        .route("/milestones/:id", get(get_milestone).put(update_milestone).delete(delete_milestone))
+        .route("/milestones/:id/tasks", post(add_task))
+        .route("/milestones/:id/tasks/:task_id", delete(remove_task))
+        .route("/milestones/:id/progress", get(get_progress))
+}
+
+async fn list_milestones(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<Milestone>>, AppError> {
+    let client = get_client(&pool).await?;
+    ProjectRepository::find_project(&client, project_id).await?;
+    Ok(Json(MilestoneRepository::find_milestones(&client, project_id).await?))
+}
+
+#[derive(Deserialize)]
+struct MilestoneRequest {
+    name: String,
+    description: Option<String>,
+    #[serde(default, with = "rfc3339_option")]
+    start_date: Option<i64>,
+    #[serde(with = "rfc3339")]
+    due_date: i64,
+}
+
+async fn create_milestone(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<MilestoneRequest>,
+) -> Result<(StatusCode, Json<Milestone>), AppError> {
+    let client = get_client(&pool).await?;
+    let project = ProjectRepository::find_project(&client, project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut milestone = Milestone {
+        id: 0,
+        project_id,
+        name: payload.name,
+        description: payload.description,
+        start_date: payload.start_date,
+        due_date: payload.due_date,
+        task_ids: Vec::new(),
+        created_at: now,
+        updated_at: now,
+    };
+    MilestoneService::validate_milestone(&mut milestone)?;
+    let milestone = MilestoneRepository::create_milestone(&client, &milestone).await?;
+    Ok((StatusCode::CREATED, Json(milestone)))
+}
+
+async fn get_milestone(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Milestone>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(MilestoneRepository::find_milestone(&client, id).await?))
+}
+
+async fn update_milestone(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<MilestoneRequest>,
+) -> Result<Json<Milestone>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut milestone = MilestoneRepository::find_milestone(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, milestone.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+
+    milestone.name = payload.name;
+    milestone.description = payload.description;
+    milestone.start_date = payload.start_date;
+    milestone.due_date = payload.due_date;
+    milestone.updated_at = chrono::Utc::now().timestamp_millis();
+    MilestoneService::validate_milestone(&mut milestone)?;
+    MilestoneRepository::update_milestone(&client, &milestone).await?;
+    Ok(Json(milestone))
+}
+
+async fn delete_milestone(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let client = get_client(&pool).await?;
+    let milestone = MilestoneRepository::find_milestone(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, milestone.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    MilestoneRepository::delete_milestone(&client, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct MilestoneTaskRequest {
+    task_id: i32,
+}
+
+async fn add_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<MilestoneTaskRequest>,
+) -> Result<Json<Milestone>, AppError> {
+    let client = get_client(&pool).await?;
+    let milestone = MilestoneRepository::find_milestone(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, milestone.project_id).await?;
+    ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+
+    let task = TaskRepository::find_task_by_id(&client, payload.task_id).await?;
+    if task.project_id != Some(milestone.project_id) {
+        return Err(AppError::validation_error(
+            "task_id",
+            &format!("Task {} does not belong to this project", task.id),
+        ));
+    }
+    MilestoneRepository::add_task(&client, id, task.id).await?;
+    Ok(Json(MilestoneRepository::find_milestone(&client, id).await?))
+}
+
+async fn remove_task(
+    Extension(pool): Extension<DbPool>,
+    Path((id, task_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<Json<Milestone>, AppError> {
+    let client = get_client(&pool).await?;
+    let milestone = MilestoneRepository::find_milestone(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, milestone.project_id).await?;
+    ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+
+    if !MilestoneRepository::remove_task(&client, id, task_id).await? {
+        return Err(AppError::validation_error("task_id", &format!("Task {} is not in this milestone", task_id)));
+    }
+    Ok(Json(MilestoneRepository::find_milestone(&client, id).await?))
+}
+
+async fn get_progress(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<MilestoneProgress>, AppError> {
+    let client = get_client(&pool).await?;
+    let milestone = MilestoneRepository::find_milestone(&client, id).await?;
+    let tasks = TaskRepository::find_tasks_by_ids(&client, &milestone.task_ids).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+    Ok(Json(MilestoneService::progress(&milestone, &tasks, now)))
+}
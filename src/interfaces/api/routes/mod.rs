@@ -1,2 +1,19 @@
 pub mod user_routes;
-pub mod task_routes;
\ No newline at end of file
+pub mod task_routes;
+pub mod trash_routes;
+pub mod workflow_routes;
+pub mod custom_field_routes;
+pub mod time_routes;
+pub mod project_routes;
+pub mod board_routes;
+pub mod sprint_routes;
+pub mod milestone_routes;
+pub mod template_routes;
+pub mod watcher_routes;
+pub mod comment_routes;
+pub mod attachment_routes;
+pub mod label_routes;
+pub mod link_routes;
+pub mod checklist_routes;
+pub mod calendar_routes;
+pub mod caldav_routes;
\ No newline at end of file
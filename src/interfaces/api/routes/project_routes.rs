@@ -0,0 +1,209 @@
+use axum::{extract::{Path, Query}, routing::{get, put}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::ProjectService,
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            project::{Project, ProjectMember, ProjectRole},
+            task::Task,
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+    },
+    infrastructure::db::{
+        get_client,
+        project_repo::{ProjectFilter, ProjectRepository},
+        task_repo::TaskRepository,
+        DbPool,
+    },
+    interfaces::api::routes::task_routes::{task_filter, TaskListQuery},
+};
+use serde::Deserialize;
+
+pub fn project_routes() -> Router {
+    Router::new()
+        .route("/projects", get(list_projects).post(create_project))
+        .route("/projects/:id", get(get_project).put(update_project))
+        .route("/projects/:id/archive", put(archive_project))
+        .route("/projects/:id/unarchive", put(unarchive_project))
+        .route("/projects/:id/members", get(list_members).post(add_member))
+        .route("/projects/:id/members/:user_id", put(change_member_role).delete(remove_member))
+        .route("/projects/:id/tasks", get(list_project_tasks))
+}
+
+async fn list_projects(
+    Extension(pool): Extension<DbPool>,
+    Query(filter): Query<ProjectFilter>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Project>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(ProjectRepository::find_projects(&client, &filter, &page).await?))
+}
+
+#[derive(Deserialize)]
+struct CreateProjectRequest {
+    key: String,
+    name: String,
+    description: Option<String>,
+}
+
+// The acting user becomes the owner
+async fn create_project(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Json(payload): Json<CreateProjectRequest>,
+) -> Result<(StatusCode, Json<Project>), AppError> {
+    let owner_id = context
+        .actor_id
+        .ok_or_else(|| AppError::invalid_input("x-user-id is required to create a project"))?;
+    let mut project = Project::new(payload.key, payload.name, payload.description, owner_id);
+    ProjectService::validate_project(&mut project)?;
+
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let project = ProjectRepository::create_project(&tx, &project).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((StatusCode::CREATED, Json(project)))
+}
+
+async fn get_project(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Project>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(ProjectRepository::find_project(&client, id).await?))
+}
+
+#[derive(Deserialize)]
+struct UpdateProjectRequest {
+    name: String,
+    description: Option<String>,
+}
+
+async fn update_project(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<UpdateProjectRequest>,
+) -> Result<Json<Project>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut project = ProjectRepository::find_project(&client, id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    project.name = payload.name;
+    project.description = payload.description;
+    project.updated_at = chrono::Utc::now().timestamp_millis();
+    ProjectService::validate_project(&mut project)?;
+    ProjectRepository::update_project(&client, &project).await?;
+    Ok(Json(project))
+}
+
+async fn archive_project(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Project>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut project = ProjectRepository::find_project(&client, id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    ProjectService::archive_project(&mut project)?;
+    ProjectRepository::update_project(&client, &project).await?;
+    Ok(Json(project))
+}
+
+async fn unarchive_project(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Project>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut project = ProjectRepository::find_project(&client, id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    ProjectService::unarchive_project(&mut project)?;
+    ProjectRepository::update_project(&client, &project).await?;
+    Ok(Json(project))
+}
+
+async fn list_members(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<ProjectMember>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(ProjectRepository::find_project(&client, id).await?.members))
+}
+
+#[derive(Deserialize)]
+struct MemberRequest {
+    user_id: i32,
+    role: ProjectRole,
+}
+
+async fn add_member(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<MemberRequest>,
+) -> Result<Json<Vec<ProjectMember>>, AppError> {
+    update_members(&pool, id, context, |project| {
+        ProjectService::add_member(project, payload.user_id, payload.role)
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct RoleRequest {
+    role: ProjectRole,
+}
+
+async fn change_member_role(
+    Extension(pool): Extension<DbPool>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+    Json(payload): Json<RoleRequest>,
+) -> Result<Json<Vec<ProjectMember>>, AppError> {
+    update_members(&pool, id, context, |project| {
+        if project.member(user_id).is_none() {
+            return Err(AppError::not_found("Project member", user_id));
+        }
+        ProjectService::add_member(project, user_id, payload.role)
+    })
+    .await
+}
+
+async fn remove_member(
+    Extension(pool): Extension<DbPool>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<Json<Vec<ProjectMember>>, AppError> {
+    update_members(&pool, id, context, |project| ProjectService::remove_member(project, user_id)).await
+}
+
+async fn update_members<F>(pool: &DbPool, id: i32, context: ActivityContext, change: F) -> Result<Json<Vec<ProjectMember>>, AppError>
+where
+    F: FnOnce(&mut Project) -> Result<(), AppError>,
+{
+    let mut client = get_client(pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut project = ProjectRepository::find_project(&tx, id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    change(&mut project)?;
+    ProjectRepository::save_members(&tx, &project).await?;
+    ProjectRepository::update_project(&tx, &project).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(project.members))
+}
+
+async fn list_project_tasks(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(id): Path<i32>,
+    Query(mut query): Query<TaskListQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Task>>, AppError> {
+    let client = get_client(&pool).await?;
+    ProjectRepository::find_project(&client, id).await?;
+    query.project_id = Some(id);
+    let filter = task_filter(&client, query, context.actor_id).await?;
+    Ok(Json(TaskRepository::find_tasks(&client, &filter, &page).await?))
+}
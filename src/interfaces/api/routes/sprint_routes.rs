@@ -0,0 +1,297 @@
+use axum::{extract::{Path, Query}, routing::{delete, get, post}, Extension, Router, Json, http::StatusCode};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{ProjectService, SprintService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            sprint::{ScopeChange, ScopeChangeKind, Sprint, SprintClosure, SprintScope, SprintState},
+            task::TaskStatus,
+        },
+        errors::AppError,
+        time::rfc3339,
+    },
+    infrastructure::db::{
+        get_client,
+        project_repo::ProjectRepository,
+        sprint_repo::SprintRepository,
+        task_repo::TaskRepository,
+        DbPool,
+    },
+};
+use serde::Deserialize;
+
+pub fn sprint_routes() -> Router {
+    Router::new()
+        .route("/projects/:id/sprints", get(list_sprints).post(create_sprint))
+        .route("/sprints/:id", get(get_sprint).put(update_sprint))
+        .route("/sprints/:id/tasks", post(add_task))
+        .route("/sprints/:id/tasks/:task_id", delete(remove_task))
+        .route("/sprints/:id/start", post(start_sprint))
+        .route("/sprints/:id/close", post(close_sprint))
+        .route("/sprints/:id/scope", get(get_scope))
+}
+
+#[derive(Deserialize)]
+struct SprintQuery {
+    state: Option<SprintState>,
+}
+
+async fn list_sprints(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+    Query(query): Query<SprintQuery>,
+) -> Result<Json<Vec<Sprint>>, AppError> {
+    let client = get_client(&pool).await?;
+    ProjectRepository::find_project(&client, project_id).await?;
+    Ok(Json(SprintRepository::find_sprints(&client, project_id, query.state).await?))
+}
+
+#[derive(Deserialize)]
+struct SprintRequest {
+    name: String,
+    goal: Option<String>,
+    #[serde(with = "rfc3339")]
+    start_date: i64,
+    #[serde(with = "rfc3339")]
+    end_date: i64,
+}
+
+async fn create_sprint(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<SprintRequest>,
+) -> Result<(StatusCode, Json<Sprint>), AppError> {
+    let client = get_client(&pool).await?;
+    let project = ProjectRepository::find_project(&client, project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut sprint = Sprint {
+        id: 0,
+        project_id,
+        name: payload.name,
+        goal: payload.goal,
+        start_date: payload.start_date,
+        end_date: payload.end_date,
+        state: SprintState::Planned,
+        task_ids: Vec::new(),
+        started_at: None,
+        closed_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    SprintService::validate_sprint(&mut sprint)?;
+    let sprint = SprintRepository::create_sprint(&client, &sprint).await?;
+    Ok((StatusCode::CREATED, Json(sprint)))
+}
+
+async fn get_sprint(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Sprint>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(SprintRepository::find_sprint(&client, id).await?))
+}
+
+async fn update_sprint(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<SprintRequest>,
+) -> Result<Json<Sprint>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut sprint = SprintRepository::find_sprint(&client, id).await?;
+    let project = ProjectRepository::find_project(&client, sprint.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    SprintService::ensure_open(&sprint)?;
+
+    sprint.name = payload.name;
+    sprint.goal = payload.goal;
+    sprint.start_date = payload.start_date;
+    sprint.end_date = payload.end_date;
+    sprint.updated_at = chrono::Utc::now().timestamp_millis();
+    SprintService::validate_sprint(&mut sprint)?;
+    SprintRepository::update_sprint(&client, &sprint).await?;
+    Ok(Json(sprint))
+}
+
+#[derive(Deserialize)]
+struct SprintTaskRequest {
+    task_id: i32,
+}
+
+async fn add_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<SprintTaskRequest>,
+) -> Result<Json<Sprint>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let sprint = SprintRepository::lock_sprint(&tx, id).await?;
+    let project = ProjectRepository::find_project(&tx, sprint.project_id).await?;
+    ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    SprintService::ensure_open(&sprint)?;
+
+    let task = TaskRepository::find_task_by_id(&tx, payload.task_id).await?;
+    if task.project_id != Some(sprint.project_id) || task.is_trashed() {
+        return Err(AppError::validation_error(
+            "task_id",
+            &format!("Task {} is not a live task of this project", task.id),
+        ));
+    }
+    match SprintRepository::find_open_sprint_of_task(&tx, task.id).await? {
+        Some(other) if other != sprint.id => {
+            return Err(AppError::validation_error(
+                "task_id",
+                &format!("Task {} is already planned in sprint {}", task.id, other),
+            ));
+        }
+        _ => {}
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    if SprintRepository::add_task(&tx, sprint.id, task.id, now).await? {
+        let change = scope_change(&sprint, task.id, ScopeChangeKind::Added, &context, now);
+        SprintRepository::record_scope_changes(&tx, &[change]).await?;
+    }
+
+    let sprint = SprintRepository::find_sprint(&tx, id).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(sprint))
+}
+
+async fn remove_task(
+    Extension(pool): Extension<DbPool>,
+    Path((id, task_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<Json<Sprint>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let sprint = SprintRepository::lock_sprint(&tx, id).await?;
+    let project = ProjectRepository::find_project(&tx, sprint.project_id).await?;
+    ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    SprintService::ensure_open(&sprint)?;
+
+    if !SprintRepository::remove_task(&tx, sprint.id, task_id).await? {
+        return Err(AppError::validation_error("task_id", &format!("Task {} is not in this sprint", task_id)));
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let change = scope_change(&sprint, task_id, ScopeChangeKind::Removed, &context, now);
+    SprintRepository::record_scope_changes(&tx, &[change]).await?;
+
+    let sprint = SprintRepository::find_sprint(&tx, id).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(sprint))
+}
+
+async fn start_sprint(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Sprint>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut sprint = SprintRepository::lock_sprint(&tx, id).await?;
+    let project = ProjectRepository::find_project(&tx, sprint.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    let active = SprintRepository::find_active_sprint(&tx, sprint.project_id).await?;
+    SprintService::start_sprint(&mut sprint, active.as_ref(), chrono::Utc::now().timestamp_millis())?;
+    SprintRepository::update_sprint(&tx, &sprint).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(sprint))
+}
+
+#[derive(Deserialize, Default)]
+struct CloseSprintRequest {
+    // Unfinished tasks go back to the backlog when no sprint is given
+    carry_over_to: Option<i32>,
+}
+
+async fn close_sprint(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    payload: Option<Json<CloseSprintRequest>>,
+) -> Result<Json<SprintClosure>, AppError> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut sprint = SprintRepository::lock_sprint(&tx, id).await?;
+    let project = ProjectRepository::find_project(&tx, sprint.project_id).await?;
+    ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    let target = match payload.carry_over_to {
+        Some(target_id) => Some(SprintRepository::lock_sprint(&tx, target_id).await?),
+        None => None,
+    };
+
+    let tasks = TaskRepository::find_tasks_by_ids(&tx, &sprint.task_ids).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let carried_over = SprintService::close_sprint(&mut sprint, &tasks, target.as_ref(), now)?;
+    SprintRepository::update_sprint(&tx, &sprint).await?;
+    carry_over(&tx, &sprint, target.as_ref(), &carried_over, &context, now).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    let completed = tasks
+        .iter()
+        .filter(|t| sprint.task_ids.contains(&t.id) && t.status == TaskStatus::Completed)
+        .map(|t| t.id)
+        .collect();
+    Ok(Json(SprintClosure {
+        sprint,
+        completed,
+        carried_over,
+        carried_to: payload.carry_over_to,
+    }))
+}
+
+// Moves unfinished tasks out of the closed sprint and into the target, recording both sides
+async fn carry_over(
+    client: &impl GenericClient,
+    closed: &Sprint,
+    target: Option<&Sprint>,
+    task_ids: &[i32],
+    context: &ActivityContext,
+    now: i64,
+) -> Result<(), AppError> {
+    let mut changes = Vec::new();
+    for &task_id in task_ids {
+        SprintRepository::remove_task(client, closed.id, task_id).await?;
+        changes.push(scope_change(closed, task_id, ScopeChangeKind::CarriedOut, context, now));
+        if let Some(target) = target {
+            SprintRepository::add_task(client, target.id, task_id, now).await?;
+            changes.push(scope_change(target, task_id, ScopeChangeKind::CarriedIn, context, now));
+        }
+    }
+    SprintRepository::record_scope_changes(client, &changes).await?;
+    Ok(())
+}
+
+fn scope_change(sprint: &Sprint, task_id: i32, kind: ScopeChangeKind, context: &ActivityContext, now: i64) -> ScopeChange {
+    ScopeChange {
+        sprint_id: sprint.id,
+        task_id,
+        kind,
+        actor_id: context.actor_id,
+        changed_at: now,
+        after_start: sprint.started_at.is_some(),
+    }
+}
+
+async fn get_scope(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<SprintScope>, AppError> {
+    let client = get_client(&pool).await?;
+    let sprint = SprintRepository::find_sprint(&client, id).await?;
+    let changes = SprintRepository::find_scope_changes(&client, id).await?;
+    let tasks = TaskRepository::find_tasks_by_ids(&client, &sprint.task_ids).await?;
+    Ok(Json(SprintService::scope(&sprint, changes, &tasks)))
+}
@@ -1,66 +1,914 @@
-use axum::{routing::{post, put}, Router, Json, http::StatusCode};
-use crate::{application::services::TaskService, domain::entities::task::{Task}};
-use serde::{Deserialize};
+use axum::{extract::{Path, Query}, routing::{get, post, put}, Extension, Router, Json, http::StatusCode};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{CustomFieldService, LinkService, ProjectService, TaskService},
+    domain::{
+        entities::{
+            activity::{ActivityContext, ActivityEvent, ActivitySubject},
+            bulk::{BulkItemResult, BulkMode, BulkOperation, BulkResult, MAX_BULK_TASKS},
+            clone::CloneOptions,
+            link::LinkRelation,
+            custom_field::CustomFieldTarget,
+            project::parse_task_reference,
+            quick_add::{parse_quick_add, QuickAdd},
+            history::{FieldChange, TaskVersion},
+            task::{DueDate, Task, TaskStatus},
+            trash::TrashState,
+            workflow::Workflow,
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+        time::{parse_time_zone, rfc3339, start_of_day},
+    },
+    infrastructure::db::{
+        activity_repo::{ActivityFilter, ActivityRepository},
+        attachment_repo::AttachmentRepository,
+        custom_field_repo::CustomFieldRepository,
+        get_client,
+        history_repo::HistoryRepository,
+        link_repo::LinkRepository,
+        project_repo::ProjectRepository,
+        task_repo::{DueDay, TaskFilter, TaskRepository},
+        user_repo::UserRepository,
+        watcher_repo::WatcherRepository,
+        workflow_repo::WorkflowRepository,
+        DbPool,
+    },
+    interfaces::api::routes::watcher_routes::{notify_watchers, process_mentions},
+};
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{hash_map::Entry, HashMap};
 
 pub fn task_routes() -> Router {
     Router::new()
-        .route("/tasks", post(create_task))
+        .route("/tasks", get(list_tasks).post(create_task))
+        .route("/tasks/ref/:reference", get(get_task_by_reference))
+        .route("/tasks/bulk", post(bulk_update_tasks))
+        .route("/tasks/quick", post(quick_add_task))
+        .route("/tasks/:id/status", put(change_task_status))
         .route("/tasks/:id/complete", put(complete_task))
+        .route("/tasks/:id/due", put(set_task_due))
         .route("/tasks/:id/archive", put(archive_task))
         .route("/tasks/:id/delete", put(delete_task))
-        .route("/tasks/:id/comment", post(add_comment_to_task))
         .route("/tasks/:id/subtask", post(add_subtask))
+        .route("/tasks/:id/clone", post(clone_task))
+        .route("/tasks/:id/parent", put(reparent_task).delete(detach_task))
+        .route("/tasks/:id/subtasks/:subtask_id/position", put(reorder_subtask))
+        .route("/tasks/:id/estimate", put(set_task_estimate))
+        .route("/tasks/:id/custom-fields/:key", put(set_custom_field).delete(remove_custom_field))
+        .route("/tasks/:id/progress", get(get_task_progress))
+        .route("/tasks/:id/activity", get(get_task_activity))
+        .route("/tasks/:id/history", get(get_task_history))
+        .route("/tasks/:id/history/diff", get(diff_task_versions))
+        .route("/tasks/:id/history/as-of", get(get_task_as_of))
 }
 
 #[derive(Deserialize)]
 struct CreateTaskRequest {
     title: String,
     description: Option<String>,
-    description0: Option<String>,
+    project_id: Option<i32>,
+    #[serde(default)]
+    custom_fields: HashMap<String, Value>,
 }
 
-async fn create_task(Json(payload): Json<CreateTaskRequest>) -> Result<Json<Task>, StatusCode> {
-    TaskService::create_task(payload.title, payload.description, payload.description0)
+async fn create_task(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Json(payload): Json<CreateTaskRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    if let Some(project_id) = payload.project_id {
+        let project = ProjectRepository::find_project(&client, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    let workflow = WorkflowRepository::find_for_project(&client, payload.project_id).await?;
+    let mut task = TaskService::create_task(payload.title, payload.description, &workflow)?;
+    task.set_activity_context(context);
+    task.project_id = payload.project_id;
+    let definitions = CustomFieldRepository::find_definitions(&client, Some(CustomFieldTarget::Task)).await?;
+    TaskService::set_custom_fields(&mut task, &definitions, payload.custom_fields)?;
+    let task = TaskRepository::create_task(&client, &mut task).await?;
+    if let Some(description) = &task.description {
+        process_mentions(&client, &task, description, &task.activity_context).await?;
+    }
+    Ok(Json(task))
+}
+
+#[derive(Deserialize)]
+struct QuickAddRequest {
+    text: String,
+    project_id: Option<i32>,
+    // IANA zone relative dates are read in; the requesting user's zone when omitted
+    timezone: Option<String>,
+    // Only report how the text would be read
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct QuickAddResponse {
+    interpretation: QuickAdd,
+    assignee_id: Option<i32>,
+    task: Option<Task>,
+}
+
+async fn quick_add_task(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Json(payload): Json<QuickAddRequest>,
+) -> Result<(StatusCode, Json<QuickAddResponse>), AppError> {
+    let client = get_client(&pool).await?;
+    let timezone = viewer_time_zone(&client, payload.timezone.as_deref(), context.actor_id).await?;
+    let interpretation = parse_quick_add(&payload.text, chrono::Utc::now().with_timezone(&timezone));
+    let assignee_id = match &interpretation.assignee {
+        Some(username) => UserRepository::find_user_ids_by_usernames(&client, std::slice::from_ref(username))
+            .await?
+            .first()
+            .copied(),
+        None => None,
+    };
+    if payload.dry_run {
+        return Ok((StatusCode::OK, Json(QuickAddResponse { interpretation, assignee_id, task: None })));
+    }
+
+    if let Some(project_id) = payload.project_id {
+        let project = ProjectRepository::find_project(&client, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    let workflow = WorkflowRepository::find_for_project(&client, payload.project_id).await?;
+    let mut task = TaskService::quick_add_task(&interpretation, timezone, assignee_id, context.actor_id, &workflow)?;
+    task.set_activity_context(context);
+    task.project_id = payload.project_id;
+    let definitions = CustomFieldRepository::find_definitions(&client, Some(CustomFieldTarget::Task)).await?;
+    TaskService::set_custom_fields(&mut task, &definitions, HashMap::new())?;
+    let task = TaskRepository::create_task(&client, &mut task).await?;
+    if let Some(assignee_id) = task.assigned_to {
+        WatcherRepository::add_watchers(&client, task.id, &[assignee_id], task.created_at).await?;
+    }
+    Ok((StatusCode::CREATED, Json(QuickAddResponse { interpretation, assignee_id, task: Some(task) })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TaskListQuery {
+    pub(crate) project_id: Option<i32>,
+    status: Option<TaskStatus>,
+    assigned_to: Option<i32>,
+    // Custom field filter: `field` must be given together with `value`
+    field: Option<String>,
+    value: Option<String>,
+    // Custom field key to sort by; creation time otherwise
+    sort_by: Option<String>,
+    order: Option<SortOrder>,
+    // `today`, `tomorrow` or a YYYY-MM-DD day, counted in `timezone`
+    due_on: Option<String>,
+    #[serde(default)]
+    overdue: bool,
+    // IANA zone for day filters; the requesting user's zone when omitted
+    timezone: Option<String>,
+}
+
+async fn list_tasks(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Query(query): Query<TaskListQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Task>>, AppError> {
+    let client = get_client(&pool).await?;
+    let filter = task_filter(&client, query, context.actor_id).await?;
+    Ok(Json(TaskRepository::find_tasks(&client, &filter, &page).await?))
+}
+
+// Resolves custom field filters and sort keys against their definitions, and day filters in
+// the zone of the request or of the acting user
+pub(crate) async fn task_filter(client: &impl GenericClient, query: TaskListQuery, actor_id: Option<i32>) -> Result<TaskFilter, AppError> {
+    let definitions = CustomFieldRepository::find_definitions(client, Some(CustomFieldTarget::Task)).await?;
+
+    let custom_field = match (query.field, query.value) {
+        (Some(key), Some(raw)) => {
+            let definition = CustomFieldService::find_definition(&definitions, CustomFieldTarget::Task, &key)?;
+            let value = definition
+                .normalize_filter(&raw)
+                .map_err(|message| AppError::validation_error("value", &message))?;
+            Some((key, value))
+        }
+        (None, None) => None,
+        _ => return Err(AppError::validation_error("field", "Filtering needs both field and value")),
+    };
+    if let Some(key) = &query.sort_by {
+        CustomFieldService::find_definition(&definitions, CustomFieldTarget::Task, key)?;
+    }
+    let due_on = match query.due_on.as_deref() {
+        Some(day) => {
+            let timezone = viewer_time_zone(client, query.timezone.as_deref(), actor_id).await?;
+            let today = chrono::Utc::now().with_timezone(&timezone).date_naive();
+            let date = match day {
+                "today" => today,
+                "tomorrow" => today + Duration::days(1),
+                other => NaiveDate::parse_from_str(other, "%Y-%m-%d")
+                    .map_err(|_| AppError::validation_error("due_on", "Use today, tomorrow or a YYYY-MM-DD date"))?,
+            };
+            Some(DueDay {
+                date,
+                start: start_of_day(date, &timezone),
+                end: start_of_day(date + Duration::days(1), &timezone),
+            })
+        }
+        None => None,
+    };
+
+    Ok(TaskFilter {
+        project_id: query.project_id,
+        status: query.status,
+        assigned_to: query.assigned_to,
+        custom_field,
+        sort_by_field: query.sort_by,
+        descending: matches!(query.order, Some(SortOrder::Desc)),
+        due_on,
+        overdue_as_of: query.overdue.then(|| chrono::Utc::now().timestamp_millis()),
+    })
+}
+
+// The zone named in the request, else the acting user's, else UTC
+pub(crate) async fn viewer_time_zone(client: &impl GenericClient, requested: Option<&str>, actor_id: Option<i32>) -> Result<Tz, AppError> {
+    match (requested, actor_id) {
+        (Some(name), _) => parse_time_zone(name),
+        (None, Some(user_id)) => Ok(UserRepository::find_time_zone(client, user_id).await?),
+        (None, None) => Ok(chrono_tz::UTC),
+    }
+}
+
+async fn get_task_by_reference(
+    Extension(pool): Extension<DbPool>,
+    Path(reference): Path<String>,
+) -> Result<Json<Task>, AppError> {
+    let (key, number) = parse_task_reference(&reference)
+        .ok_or_else(|| AppError::validation_error("reference", "Task references look like WEB-42"))?;
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_reference(&client, &key, number)
+        .await?
         .map(Json)
-        .map_err(|_| StatusCode::BAD_REQUEST)
+        .ok_or_else(|| AppError::not_found(&format!("Task in project {}", key), number))
+}
+
+#[derive(Deserialize)]
+struct StatusRequest {
+    status: TaskStatus,
+}
+
+async fn change_task_status(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<StatusRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    task.set_activity_context(context);
+    let workflow = WorkflowRepository::find_for_project(&client, task.project_id).await?;
+    TaskService::change_task_status(&mut task, &workflow, payload.status)?;
+    let events = task.activity_log.clone();
+    TaskRepository::update_task(&client, &mut task).await?;
+    rollup_ancestors(&client, task.id).await?;
+    notify_watchers(&client, &task, &events, &[]).await?;
+    Ok(Json(task))
 }
 
-async fn complete_task() -> Result<StatusCode, StatusCode> {
-    let mut task = Task::mock();
-    TaskService::complete_task(&mut task).map(|_| StatusCode::OK).map_err(|_| StatusCode::BAD_REQUEST)
+async fn complete_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    task.set_activity_context(context);
+    let workflow = WorkflowRepository::find_for_project(&client, task.project_id).await?;
+    TaskService::complete_task(&mut task, &workflow)?;
+    let events = task.activity_log.clone();
+    TaskRepository::update_task(&client, &mut task).await?;
+    rollup_ancestors(&client, task.id).await?;
+    notify_watchers(&client, &task, &events, &[]).await?;
+    Ok(Json(task))
 }
 
-async fn archive_task() -> Result<StatusCode, StatusCode> {
-    let mut task = Task::mock();
-    TaskService::archive_task(&mut task).map(|_| StatusCode::OK).map_err(|_| StatusCode::BAD_REQUEST)
+#[derive(Deserialize)]
+struct DueRequest {
+    // `2024-05-01` for a whole day or an RFC 3339 date-time; null clears the due date
+    due: Option<DueDate>,
+    // IANA zone the due date belongs to; the requesting user's zone when omitted
+    timezone: Option<String>,
+}
+
+async fn set_task_due(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<DueRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    task.set_activity_context(context.clone());
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    let timezone = viewer_time_zone(&client, payload.timezone.as_deref(), context.actor_id).await?;
+    TaskService::set_task_due(&mut task, payload.due, timezone);
+    let events = task.activity_log.clone();
+    TaskRepository::update_task(&client, &mut task).await?;
+    notify_watchers(&client, &task, &events, &[]).await?;
+    Ok(Json(task))
+}
+
+async fn archive_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Task>, AppError> {
+    trash_task(&pool, id, context, TrashState::Archived).await.map(Json)
 }
 
-async fn delete_task() -> Result<StatusCode, StatusCode> {
-    let mut task = Task::mock();
-    TaskService::delete_task(&mut task).map(|_| StatusCode::OK).map_err(|_| StatusCode::BAD_REQUEST)
+async fn delete_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Task>, AppError> {
+    trash_task(&pool, id, context, TrashState::Deleted).await.map(Json)
+}
+
+// Trashes the task together with its subtasks
+async fn trash_task(pool: &DbPool, id: i32, context: ActivityContext, state: TrashState) -> Result<Task, AppError> {
+    let mut client = get_client(pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut subtree = TaskRepository::find_subtree(&tx, id).await?;
+    if let Some(task) = subtree.get(&id) {
+        ensure_can_edit(&tx, task, context.actor_id).await?;
+    }
+    subtree.values_mut().for_each(|t| t.set_activity_context(context.clone()));
+    let changed = TaskService::trash_subtree(&mut subtree, id, state)?;
+    for changed_id in changed {
+        if let Some(task) = subtree.get_mut(&changed_id) {
+            TaskRepository::update_task(&tx, task).await?;
+        }
+    }
+    rollup_ancestors(&tx, id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    subtree.remove(&id).ok_or_else(|| AppError::not_found("Task", id))
 }
 
 #[derive(Deserialize)]
-struct CommentRequest {
-    user_id: i32,
-    comment: String,
+struct BulkRequest {
+    // Either explicit ids or a filter selects the tasks
+    ids: Option<Vec<i32>>,
+    filter: Option<TaskListQuery>,
+    #[serde(flatten)]
+    operation: BulkOperation,
+    #[serde(default)]
+    mode: BulkMode,
 }
 
-async fn add_comment_to_task(Json(payload): Json<CommentRequest>) -> Result<StatusCode, StatusCode> {
-    let mut task = Task::mock();
-    TaskService::add_comment_to_task(&mut task, payload.user_id, payload.comment)
-        .map(|_| StatusCode::OK)
-        .map_err(|_| StatusCode::BAD_REQUEST)
+async fn bulk_update_tasks(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Json(payload): Json<BulkRequest>,
+) -> Result<Json<BulkResult>, AppError> {
+    let mut client = get_client(&pool).await?;
+    match &payload.operation {
+        BulkOperation::Assign { user_id: Some(user_id) } => {
+            UserRepository::find_user_by_id(&client, *user_id).await?;
+        }
+        BulkOperation::MoveToProject { project_id: Some(project_id) } => {
+            let project = ProjectRepository::find_project(&client, *project_id).await?;
+            ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+        }
+        _ => {}
+    }
+
+    let mut tx = client.transaction().await.map_err(AppError::database_error)?;
+    let ids = bulk_task_ids(&tx, payload.ids, payload.filter, context.actor_id).await?;
+    let mut workflows: HashMap<Option<i32>, Workflow> = HashMap::new();
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        let item = match payload.mode {
+            BulkMode::Atomic => {
+                let changed = apply_bulk_operation(&tx, id, &payload.operation, &context, &mut workflows).await?;
+                BulkItemResult::new::<AppError>(id, Ok(changed))
+            }
+            BulkMode::BestEffort => {
+                // A savepoint per task keeps the other tasks' changes when one fails
+                let savepoint = tx.transaction().await.map_err(AppError::database_error)?;
+                let outcome = apply_bulk_operation(&savepoint, id, &payload.operation, &context, &mut workflows).await;
+                let item = BulkItemResult::new(id, outcome);
+                if item.ok {
+                    savepoint.commit().await.map_err(AppError::database_error)?;
+                } else {
+                    savepoint.rollback().await.map_err(AppError::database_error)?;
+                }
+                item
+            }
+        };
+        results.push(item);
+    }
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(BulkResult::new(results)))
+}
+
+async fn bulk_task_ids(
+    client: &impl GenericClient,
+    ids: Option<Vec<i32>>,
+    filter: Option<TaskListQuery>,
+    actor_id: Option<i32>,
+) -> Result<Vec<i32>, AppError> {
+    let ids = match (ids, filter) {
+        (Some(mut ids), None) => {
+            let mut seen = std::collections::HashSet::new();
+            ids.retain(|id| seen.insert(*id));
+            ids
+        }
+        (None, Some(query)) => {
+            let filter = task_filter(client, query, actor_id).await?;
+            let page = PageRequest { limit: Some(MAX_BULK_TASKS as i64), offset: None };
+            let matched = TaskRepository::find_tasks(client, &filter, &page).await?;
+            if matched.total > MAX_BULK_TASKS as i64 {
+                return Err(AppError::validation_error(
+                    "filter",
+                    &format!("Filter matches {} tasks; at most {} can be changed at once", matched.total, MAX_BULK_TASKS),
+                ));
+            }
+            matched.items.iter().map(|t| t.id).collect()
+        }
+        _ => return Err(AppError::validation_error("ids", "Give either ids or a filter")),
+    };
+    if ids.is_empty() || ids.len() > MAX_BULK_TASKS {
+        return Err(AppError::validation_error(
+            "ids",
+            &format!("Between 1 and {} tasks can be changed at once", MAX_BULK_TASKS),
+        ));
+    }
+    Ok(ids)
+}
+
+// Returns whether anything was written; each changed task gets its own activity entry
+async fn apply_bulk_operation(
+    client: &impl GenericClient,
+    id: i32,
+    operation: &BulkOperation,
+    context: &ActivityContext,
+    workflows: &mut HashMap<Option<i32>, Workflow>,
+) -> Result<bool, AppError> {
+    let mut tasks = if operation.applies_to_subtree() {
+        TaskRepository::find_subtree(client, id).await?
+    } else {
+        let task = TaskRepository::find_task_by_id(client, id).await?;
+        HashMap::from([(id, task)])
+    };
+    if let Some(task) = tasks.get(&id) {
+        ensure_can_edit(client, task, context.actor_id).await?;
+    }
+    tasks.values_mut().for_each(|t| t.set_activity_context(context.clone()));
+    let project_id = tasks.get(&id).and_then(|t| t.project_id);
+    if let Entry::Vacant(entry) = workflows.entry(project_id) {
+        entry.insert(WorkflowRepository::find_for_project(client, project_id).await?);
+    }
+
+    let changed = TaskService::apply_bulk_operation(&mut tasks, id, operation, &workflows[&project_id], context.actor_id)?;
+    for changed_id in &changed {
+        if let Some(task) = tasks.get_mut(changed_id) {
+            let events = task.activity_log.clone();
+            TaskRepository::update_task(client, task).await?;
+            notify_watchers(client, task, &events, &[]).await?;
+        }
+    }
+    if !changed.is_empty() {
+        rollup_ancestors(client, id).await?;
+    }
+    Ok(!changed.is_empty())
 }
 
 #[derive(Deserialize)]
 struct SubtaskRequest {
-    subtask: Task,
+    title: String,
+    description: Option<String>,
+    position: Option<usize>,
+    #[serde(default)]
+    custom_fields: HashMap<String, Value>,
+}
+
+async fn add_subtask(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<SubtaskRequest>,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut parent = TaskRepository::find_task_by_id(&tx, id).await?;
+    ensure_can_edit(&tx, &parent, context.actor_id).await?;
+    parent.set_activity_context(context.clone());
+    let workflow = WorkflowRepository::find_for_project(&tx, parent.project_id).await?;
+    let mut subtask = TaskService::create_task(payload.title, payload.description, &workflow)?;
+    subtask.set_activity_context(context);
+    subtask.project_id = parent.project_id;
+    let definitions = CustomFieldRepository::find_definitions(&tx, Some(CustomFieldTarget::Task)).await?;
+    TaskService::set_custom_fields(&mut subtask, &definitions, payload.custom_fields)?;
+    let mut subtask = TaskRepository::create_task(&tx, &mut subtask).await?;
+
+    let ancestors = TaskRepository::find_ancestor_ids(&tx, parent.id).await?;
+    TaskService::add_subtask(&mut parent, &mut subtask, &ancestors, payload.position)?;
+    TaskRepository::update_task(&tx, &mut subtask).await?;
+    TaskRepository::update_task(&tx, &mut parent).await?;
+    TaskRepository::save_subtask_order(&tx, &parent).await?;
+    rollup_ancestors(&tx, subtask.id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(subtask))
+}
+
+// Copies the task, and its subtree unless told otherwise, in one transaction. The copy of the
+// root is linked as cloned from the original and comes first in the response.
+async fn clone_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    options: Option<Json<CloneOptions>>,
+) -> Result<(StatusCode, Json<Vec<Task>>), AppError> {
+    let options = options.map(|Json(options)| options).unwrap_or_default();
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut original = TaskRepository::find_task_by_id(&tx, id).await?;
+    original.set_activity_context(context.clone());
+    ensure_can_edit(&tx, &original, context.actor_id).await?;
+    let sources = if options.include_subtasks {
+        TaskRepository::find_subtree(&tx, id).await?
+    } else {
+        HashMap::from([(id, original.clone())])
+    };
+    let workflow = WorkflowRepository::find_for_project(&tx, original.project_id).await?;
+    let planned = TaskService::plan_clone(&sources, id, &options, &workflow)?;
+    let definitions = CustomFieldRepository::find_definitions(&tx, Some(CustomFieldTarget::Task)).await?;
+
+    let mut clones: Vec<Task> = Vec::with_capacity(planned.len());
+    for plan in &planned {
+        let mut task = plan.task.clone();
+        task.set_activity_context(context.clone());
+        // Re-validated so a clone without custom fields cannot skip required ones
+        let custom_fields = std::mem::take(&mut task.custom_fields);
+        TaskService::set_custom_fields(&mut task, &definitions, custom_fields)?;
+        let mut task = TaskRepository::create_task(&tx, &mut task).await?;
+        task.set_activity_context(context.clone());
+
+        if let Some(parent_index) = plan.parent {
+            let parent = &mut clones[parent_index];
+            let ancestors = TaskRepository::find_ancestor_ids(&tx, parent.id).await?;
+            TaskService::add_subtask(parent, &mut task, &ancestors, None)?;
+            TaskRepository::update_task(&tx, &mut task).await?;
+            TaskRepository::update_task(&tx, parent).await?;
+            TaskRepository::save_subtask_order(&tx, parent).await?;
+        }
+        if options.include_attachments {
+            AttachmentRepository::copy_attachments(&tx, plan.source_id, task.id, task.created_at).await?;
+        }
+        clones.push(task);
+    }
+
+    // The copy of a subtask stays next to it under the same open parent
+    if let Some(parent_id) = original.parent_task {
+        let mut parent = TaskRepository::find_task_by_id(&tx, parent_id).await?;
+        if !parent.is_trashed() && parent.status != TaskStatus::Completed {
+            parent.set_activity_context(context.clone());
+            let ancestors = TaskRepository::find_ancestor_ids(&tx, parent.id).await?;
+            TaskService::add_subtask(&mut parent, &mut clones[0], &ancestors, None)?;
+            TaskRepository::update_task(&tx, &mut parent).await?;
+            TaskRepository::save_subtask_order(&tx, &parent).await?;
+        }
+    }
+
+    let mut links = Vec::new();
+    if options.include_dependencies {
+        let cloned = planned.iter().map(|p| p.source_id).collect::<Vec<_>>();
+        let external = sources
+            .values()
+            .filter(|t| cloned.contains(&t.id))
+            .flat_map(|t| t.dependencies.iter().copied())
+            .filter(|d| !cloned.contains(d))
+            .collect::<Vec<_>>();
+        let mut blockers = TaskRepository::find_tasks_by_ids(&tx, &external)
+            .await?
+            .into_iter()
+            .map(|mut t| {
+                t.set_activity_context(context.clone());
+                (t.id, t)
+            })
+            .collect::<HashMap<_, _>>();
+        links = TaskService::clone_dependencies(&planned, &sources, &mut clones, &mut blockers, context.actor_id);
+        for blocker in blockers.values_mut().filter(|t| !t.activity_log.is_empty()) {
+            TaskRepository::update_task(&tx, blocker).await?;
+        }
+    }
+    links.push(LinkService::link_tasks(&mut clones[0], LinkRelation::ClonedFrom, &mut original, &[], context.actor_id)?);
+    for link in &links {
+        LinkRepository::insert_link(&tx, link).await?;
+    }
+    TaskRepository::update_task(&tx, &mut original).await?;
+    for task in clones.iter_mut() {
+        TaskRepository::update_task(&tx, task).await?;
+    }
+    rollup_ancestors(&tx, clones[0].id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((StatusCode::CREATED, Json(clones)))
+}
+
+#[derive(Deserialize)]
+struct ReparentRequest {
+    parent_id: i32,
+    position: Option<usize>,
+}
+
+async fn reparent_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<ReparentRequest>,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = TaskRepository::find_task_by_id(&tx, id).await?;
+    task.set_activity_context(context.clone());
+    let mut new_parent = TaskRepository::find_task_by_id(&tx, payload.parent_id).await?;
+    ensure_can_edit(&tx, &task, context.actor_id).await?;
+    new_parent.set_activity_context(context.clone());
+    let ancestors = TaskRepository::find_ancestor_ids(&tx, new_parent.id).await?;
+    let mut old_parent = match task.parent_task.filter(|&p| p != new_parent.id) {
+        Some(parent_id) => {
+            let mut old_parent = TaskRepository::find_task_by_id(&tx, parent_id).await?;
+            old_parent.set_activity_context(context);
+            Some(old_parent)
+        }
+        None => None,
+    };
+    if task.parent_task == Some(new_parent.id) {
+        // Already attached here: only the position can change
+        TaskService::reorder_subtask(&mut new_parent, task.id, payload.position.unwrap_or(usize::MAX))?;
+    } else {
+        TaskService::reparent_subtask(&mut task, old_parent.as_mut(), &mut new_parent, &ancestors, payload.position)?;
+    }
+
+    TaskRepository::update_task(&tx, &mut task).await?;
+    TaskRepository::update_task(&tx, &mut new_parent).await?;
+    TaskRepository::save_subtask_order(&tx, &new_parent).await?;
+    if let Some(old_parent) = &mut old_parent {
+        TaskRepository::update_task(&tx, old_parent).await?;
+        TaskRepository::save_subtask_order(&tx, old_parent).await?;
+        rollup_from(&tx, old_parent.id).await?;
+    }
+    rollup_ancestors(&tx, task.id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(task))
+}
+
+async fn detach_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut task = TaskRepository::find_task_by_id(&tx, id).await?;
+    ensure_can_edit(&tx, &task, context.actor_id).await?;
+    task.set_activity_context(context.clone());
+    let parent_id = task
+        .parent_task
+        .ok_or_else(|| AppError::validation_error("parent_task", &format!("Task {} has no parent", id)))?;
+    let mut parent = TaskRepository::find_task_by_id(&tx, parent_id).await?;
+    parent.set_activity_context(context);
+    TaskService::detach_subtask(&mut parent, &mut task)?;
+
+    TaskRepository::update_task(&tx, &mut task).await?;
+    TaskRepository::update_task(&tx, &mut parent).await?;
+    TaskRepository::save_subtask_order(&tx, &parent).await?;
+    rollup_from(&tx, parent.id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(task))
+}
+
+#[derive(Deserialize)]
+struct PositionRequest {
+    position: usize,
+}
+
+async fn reorder_subtask(
+    Extension(pool): Extension<DbPool>,
+    Path((id, subtask_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+    Json(payload): Json<PositionRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    task.set_activity_context(context);
+    TaskService::reorder_subtask(&mut task, subtask_id, payload.position)?;
+    TaskRepository::update_task(&client, &mut task).await?;
+    TaskRepository::save_subtask_order(&client, &task).await?;
+    Ok(Json(task))
+}
+
+#[derive(Deserialize)]
+struct EstimateRequest {
+    minutes: Option<i64>,
+}
+
+async fn set_task_estimate(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<EstimateRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    task.set_activity_context(context);
+    TaskService::set_task_estimate(&mut task, payload.minutes)?;
+    TaskRepository::update_task(&client, &mut task).await?;
+    Ok(Json(task))
+}
+
+#[derive(Deserialize)]
+struct CustomFieldRequest {
+    value: Value,
+}
+
+async fn set_custom_field(
+    Extension(pool): Extension<DbPool>,
+    Path((id, key)): Path<(i32, String)>,
+    context: ActivityContext,
+    Json(payload): Json<CustomFieldRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    task.set_activity_context(context);
+    let definitions = CustomFieldRepository::find_definitions(&client, Some(CustomFieldTarget::Task)).await?;
+    let definition = CustomFieldService::find_definition(&definitions, CustomFieldTarget::Task, &key)?;
+    task.add_custom_field(definition, payload.value)?;
+    TaskRepository::update_task(&client, &mut task).await?;
+    Ok(Json(task))
+}
+
+async fn remove_custom_field(
+    Extension(pool): Extension<DbPool>,
+    Path((id, key)): Path<(i32, String)>,
+    context: ActivityContext,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_edit(&client, &task, context.actor_id).await?;
+    task.set_activity_context(context);
+    let definitions = CustomFieldRepository::find_definitions(&client, Some(CustomFieldTarget::Task)).await?;
+    let definition = CustomFieldService::find_definition(&definitions, CustomFieldTarget::Task, &key)?;
+    task.remove_custom_field(definition)?;
+    TaskRepository::update_task(&client, &mut task).await?;
+    Ok(Json(task))
+}
+
+#[derive(Deserialize)]
+struct ProgressQuery {
+    #[serde(default)]
+    weighted: bool,
+}
+
+#[derive(Serialize)]
+struct ProgressResponse {
+    task_id: i32,
+    progress: u8,
+    weighted: bool,
+    estimate: i64,
+}
+
+async fn get_task_progress(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<ProgressQuery>,
+) -> Result<Json<ProgressResponse>, AppError> {
+    let client = get_client(&pool).await?;
+    let subtree = TaskRepository::find_subtree(&client, id).await?;
+    let task = subtree.get(&id).ok_or_else(|| AppError::not_found("Task", id))?;
+    Ok(Json(ProgressResponse {
+        task_id: id,
+        progress: task.rollup_progress(&subtree, query.weighted),
+        weighted: query.weighted,
+        estimate: task.rollup_estimate(&subtree),
+    }))
+}
+
+async fn get_task_activity(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(filter): Query<ActivityFilter>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<ActivityEvent>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    let events = ActivityRepository::find_events(&client, ActivitySubject::Task, id, &filter, &page).await?;
+    Ok(Json(events))
+}
+
+async fn get_task_history(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<TaskVersion>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    let versions = HistoryRepository::find_versions_page(&client, id, &page).await?;
+    Ok(Json(versions))
+}
+
+#[derive(Deserialize)]
+struct VersionDiffQuery {
+    from: i32,
+    to: i32,
+}
+
+async fn diff_task_versions(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<VersionDiffQuery>,
+) -> Result<Json<Vec<FieldChange>>, AppError> {
+    let client = get_client(&pool).await?;
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    let versions = HistoryRepository::find_versions(&client, id).await?;
+    let changes = TaskService::diff_task_versions(&task, &versions, query.from, query.to)?;
+    Ok(Json(changes))
+}
+
+#[derive(Deserialize)]
+struct AsOfQuery {
+    #[serde(with = "rfc3339")]
+    at: i64,
+}
+
+async fn get_task_as_of(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<AsOfQuery>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    let versions = HistoryRepository::find_versions(&client, id).await?;
+    Ok(Json(TaskService::task_as_of(&task, &versions, query.at)?))
+}
+
+// Changes to a task in a project need edit rights there; tasks outside projects are open to all
+async fn ensure_can_edit(client: &impl GenericClient, task: &Task, actor_id: Option<i32>) -> Result<(), AppError> {
+    if let Some(project_id) = task.project_id {
+        let project = ProjectRepository::find_project(client, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, actor_id)?;
+    }
+    Ok(())
+}
+
+// Recomputes the stored progress of every ancestor of the task, nearest first, saving only
+// those whose progress changed
+pub(crate) async fn rollup_ancestors(client: &impl GenericClient, task_id: i32) -> Result<(), AppError> {
+    let ancestors = TaskRepository::find_ancestor_ids(client, task_id).await?;
+    let Some(&root_id) = ancestors.last() else {
+        return Ok(());
+    };
+    let mut subtree = TaskRepository::find_subtree(client, root_id).await?;
+    for ancestor_id in TaskService::rollup_progress(&mut subtree, &ancestors) {
+        if let Some(ancestor) = subtree.get_mut(&ancestor_id) {
+            TaskRepository::update_task(client, ancestor).await?;
+        }
+    }
+    Ok(())
 }
 
-async fn add_subtask(Json(payload): Json<SubtaskRequest>) -> Result<StatusCode, StatusCode> {
-    let mut task = Task::mock();
-    TaskService::add_subtask(&mut task, payload.subtask)
-        .map(|_| StatusCode::OK)
-        .map_err(|_| StatusCode::BAD_REQUEST)
+// Recomputes the stored progress of the task itself and then of its ancestors
+pub(crate) async fn rollup_from(client: &impl GenericClient, task_id: i32) -> Result<(), AppError> {
+    let mut subtree = TaskRepository::find_subtree(client, task_id).await?;
+    if !TaskService::rollup_progress(&mut subtree, &[task_id]).is_empty() {
+        if let Some(task) = subtree.get_mut(&task_id) {
+            TaskRepository::update_task(client, task).await?;
+        }
+    }
+    rollup_ancestors(client, task_id).await
 }
@@ -0,0 +1,164 @@
+use axum::{extract::Path, routing::{get, post}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::{ProjectService, TaskService, TemplateService},
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            custom_field::CustomFieldTarget,
+            label::normalize_labels,
+            task::Task,
+            template::{TaskTemplate, TemplateTask},
+        },
+        errors::AppError,
+        time::rfc3339_option,
+    },
+    infrastructure::db::{
+        custom_field_repo::CustomFieldRepository,
+        get_client,
+        project_repo::ProjectRepository,
+        task_repo::TaskRepository,
+        template_repo::TemplateRepository,
+        workflow_repo::WorkflowRepository,
+        DbPool,
+    },
+    interfaces::api::routes::task_routes::viewer_time_zone,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub fn template_routes() -> Router {
+    Router::new()
+        .route("/templates", get(list_templates).post(create_template))
+        .route("/templates/:id", get(get_template).put(update_template).delete(delete_template))
+        .route("/templates/:id/instantiate", post(instantiate_template))
+}
+
+async fn list_templates(Extension(pool): Extension<DbPool>) -> Result<Json<Vec<TaskTemplate>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(TemplateRepository::find_templates(&client).await?))
+}
+
+#[derive(Deserialize)]
+struct TemplateRequest {
+    name: String,
+    description: Option<String>,
+    root: TemplateTask,
+}
+
+async fn create_template(
+    Extension(pool): Extension<DbPool>,
+    Json(payload): Json<TemplateRequest>,
+) -> Result<(StatusCode, Json<TaskTemplate>), AppError> {
+    let client = get_client(&pool).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut template = TaskTemplate {
+        id: 0,
+        name: payload.name,
+        description: payload.description,
+        root: payload.root,
+        variables: Vec::new(),
+        created_at: now,
+        updated_at: now,
+    };
+    let definitions = CustomFieldRepository::find_definitions(&client, Some(CustomFieldTarget::Task)).await?;
+    TemplateService::validate_template(&mut template, &definitions)?;
+    let template = TemplateRepository::create_template(&client, &template).await?;
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+async fn get_template(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<TaskTemplate>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(TemplateRepository::find_template(&client, id).await?))
+}
+
+async fn update_template(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Json(payload): Json<TemplateRequest>,
+) -> Result<Json<TaskTemplate>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut template = TemplateRepository::find_template(&client, id).await?;
+    template.name = payload.name;
+    template.description = payload.description;
+    template.root = payload.root;
+    template.updated_at = chrono::Utc::now().timestamp_millis();
+    let definitions = CustomFieldRepository::find_definitions(&client, Some(CustomFieldTarget::Task)).await?;
+    TemplateService::validate_template(&mut template, &definitions)?;
+    TemplateRepository::update_template(&client, &template).await?;
+    Ok(Json(template))
+}
+
+async fn delete_template(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let client = get_client(&pool).await?;
+    TemplateRepository::delete_template(&client, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct InstantiateRequest {
+    project_id: Option<i32>,
+    // Anchor for relative due dates; now when omitted
+    #[serde(default, with = "rfc3339_option")]
+    start_date: Option<i64>,
+    // IANA zone the days are counted in; the requesting user's zone when omitted
+    timezone: Option<String>,
+    #[serde(default)]
+    variables: HashMap<String, String>,
+}
+
+// Creates the whole task tree in one transaction; the root task comes first in the response
+async fn instantiate_template(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<InstantiateRequest>,
+) -> Result<(StatusCode, Json<Vec<Task>>), AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let template = TemplateRepository::find_template(&tx, id).await?;
+    if let Some(project_id) = payload.project_id {
+        let project = ProjectRepository::find_project(&tx, project_id).await?;
+        ProjectService::ensure_can_edit_tasks(&project, context.actor_id)?;
+    }
+    let start_date = payload.start_date.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    let timezone = viewer_time_zone(&tx, payload.timezone.as_deref(), context.actor_id).await?;
+    let planned = TemplateService::plan(&template, &payload.variables, start_date, timezone)?;
+    let definitions = CustomFieldRepository::find_definitions(&tx, Some(CustomFieldTarget::Task)).await?;
+    let workflow = WorkflowRepository::find_for_project(&tx, payload.project_id).await?;
+
+    let mut created: Vec<Task> = Vec::with_capacity(planned.len());
+    for planned_task in planned {
+        let source = planned_task.task;
+        let mut task = TaskService::create_task(source.title, source.description, &workflow)?;
+        task.set_activity_context(context.clone());
+        task.project_id = payload.project_id;
+        task.tags = normalize_labels(&source.tags);
+        task.priority = source.priority;
+        task.due_date = planned_task.due_date;
+        task.due_timezone = planned_task.due_date.map(|_| timezone.name().to_string());
+        task.collaborators = source.collaborators;
+        TaskService::set_custom_fields(&mut task, &definitions, source.custom_fields)?;
+        let mut task = TaskRepository::create_task(&tx, &mut task).await?;
+
+        if let Some(parent_index) = planned_task.parent {
+            let parent = &mut created[parent_index];
+            parent.set_activity_context(context.clone());
+            let ancestors = TaskRepository::find_ancestor_ids(&tx, parent.id).await?;
+            TaskService::add_subtask(parent, &mut task, &ancestors, None)?;
+            TaskRepository::update_task(&tx, &mut task).await?;
+            TaskRepository::update_task(&tx, parent).await?;
+            TaskRepository::save_subtask_order(&tx, parent).await?;
+        }
+        created.push(task);
+    }
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
@@ -0,0 +1,238 @@
+use axum::{extract::{Path, Query}, routing::{delete, get, post, put}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::TimeTrackingService,
+    domain::{
+        entities::{
+            activity::ActivityContext,
+            task::Task,
+            time_tracking::{ActiveTimer, ReportGrouping, TimeReport, TimeSummary, WorkLog, WorkLogSource, MAX_WORK_LOG_MINUTES},
+        },
+        errors::AppError,
+        time::{rfc3339, rfc3339_option},
+        pagination::{Page, PageRequest},
+    },
+    infrastructure::db::{get_client, task_repo::TaskRepository, time_repo::TimeRepository, DbPool},
+};
+use serde::Deserialize;
+
+pub fn time_routes() -> Router {
+    Router::new()
+        .route("/tasks/:id/timer/start", post(start_timer))
+        .route("/tasks/:id/timer/stop", post(stop_timer))
+        .route("/users/:id/timer", get(get_active_timer))
+        .route("/tasks/:id/worklogs", get(list_work_logs).post(log_work))
+        .route("/tasks/:id/worklogs/:log_id", delete(delete_work_log))
+        .route("/tasks/:id/estimate/remaining", put(set_remaining_estimate))
+        .route("/tasks/:id/time", get(get_time_summary))
+        .route("/time/report", get(get_time_report))
+}
+
+// Time is always booked on the acting user
+fn actor(context: &ActivityContext) -> Result<i32, AppError> {
+    context
+        .actor_id
+        .ok_or_else(|| AppError::invalid_input("x-user-id is required to track time"))
+}
+
+async fn start_timer(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<(StatusCode, Json<ActiveTimer>), AppError> {
+    let user_id = actor(&context)?;
+    let client = get_client(&pool).await?;
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    TimeTrackingService::ensure_timer_allowed(&task)?;
+
+    let timer = ActiveTimer {
+        user_id,
+        task_id: task.id,
+        started_at: chrono::Utc::now().timestamp_millis(),
+    };
+    TimeRepository::start_timer(&client, &timer).await?;
+    Ok((StatusCode::CREATED, Json(timer)))
+}
+
+#[derive(Deserialize, Default)]
+struct StopTimerRequest {
+    note: Option<String>,
+}
+
+// Turns the running timer into a work log entry
+async fn stop_timer(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    payload: Option<Json<StopTimerRequest>>,
+) -> Result<Json<WorkLog>, AppError> {
+    let user_id = actor(&context)?;
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let timer = TimeRepository::take_active_timer(&tx, user_id, id)
+        .await?
+        .ok_or_else(|| AppError::validation_error("timer", &format!("No timer is running on task {}", id)))?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut log = timer.into_work_log(now, payload.note);
+    // Long-forgotten timers are capped rather than rejected
+    log.minutes = log.minutes.min(MAX_WORK_LOG_MINUTES);
+    TimeTrackingService::validate_work_log(&mut log, now)?;
+    let log = book_work_log(&tx, &context, log).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(log))
+}
+
+async fn get_active_timer(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<Option<ActiveTimer>>, AppError> {
+    let client = get_client(&pool).await?;
+    Ok(Json(TimeRepository::find_active_timer(&client, user_id).await?))
+}
+
+async fn list_work_logs(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<WorkLog>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    Ok(Json(TimeRepository::find_work_logs(&client, id, &page).await?))
+}
+
+#[derive(Deserialize)]
+struct WorkLogRequest {
+    minutes: i64,
+    #[serde(default, with = "rfc3339_option")]
+    started_at: Option<i64>,
+    note: Option<String>,
+}
+
+async fn log_work(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<WorkLogRequest>,
+) -> Result<(StatusCode, Json<WorkLog>), AppError> {
+    let user_id = actor(&context)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut log = WorkLog {
+        id: 0,
+        task_id: id,
+        user_id,
+        started_at: payload.started_at.unwrap_or(now - payload.minutes * 60_000),
+        minutes: payload.minutes,
+        note: payload.note,
+        source: WorkLogSource::Manual,
+        created_at: now,
+    };
+    TimeTrackingService::validate_work_log(&mut log, now)?;
+
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let log = book_work_log(&tx, &context, log).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((StatusCode::CREATED, Json(log)))
+}
+
+// Stores the entry and burns it down from the task's remaining estimate
+async fn book_work_log(
+    client: &impl deadpool_postgres::GenericClient,
+    context: &ActivityContext,
+    log: WorkLog,
+) -> Result<WorkLog, AppError> {
+    let mut task = TaskRepository::find_task_by_id(client, log.task_id).await?;
+    TimeTrackingService::ensure_loggable(&task)?;
+    task.set_activity_context(context.clone());
+    let log = TimeRepository::insert_work_log(client, &log).await?;
+    task.log_work(&log);
+    TaskRepository::update_task(client, &mut task).await?;
+    Ok(log)
+}
+
+// Only the author can remove an entry
+async fn delete_work_log(
+    Extension(pool): Extension<DbPool>,
+    Path((id, log_id)): Path<(i32, i64)>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let user_id = actor(&context)?;
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let log = TimeRepository::find_work_log(&tx, log_id).await?;
+    if log.task_id != id {
+        return Err(AppError::not_found("Work log", log_id as i32));
+    }
+    if log.user_id != user_id {
+        return Err(AppError::forbidden(user_id, "delete another user's work log"));
+    }
+    let mut task = TaskRepository::find_task_by_id(&tx, id).await?;
+    task.set_activity_context(context);
+    TimeRepository::delete_work_log(&tx, log_id).await?;
+    task.unlog_work(&log);
+    TaskRepository::update_task(&tx, &mut task).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct RemainingEstimateRequest {
+    minutes: Option<i64>,
+}
+
+async fn set_remaining_estimate(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+    Json(payload): Json<RemainingEstimateRequest>,
+) -> Result<Json<Task>, AppError> {
+    let client = get_client(&pool).await?;
+    let mut task = TaskRepository::find_task_by_id(&client, id).await?;
+    task.set_activity_context(context);
+    TimeTrackingService::set_remaining_estimate(&mut task, payload.minutes)?;
+    TaskRepository::update_task(&client, &mut task).await?;
+    Ok(Json(task))
+}
+
+async fn get_time_summary(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<TimeSummary>, AppError> {
+    let client = get_client(&pool).await?;
+    let subtree = TaskRepository::find_subtree(&client, id).await?;
+    let task = subtree.get(&id).ok_or_else(|| AppError::not_found("Task", id))?;
+    let task_ids = subtree.keys().copied().collect::<Vec<_>>();
+    let spent = TimeRepository::time_spent_by_task(&client, &task_ids).await?;
+    Ok(Json(TimeTrackingService::summarize(task, &subtree, &spent)))
+}
+
+#[derive(Deserialize)]
+struct ReportQuery {
+    #[serde(with = "rfc3339")]
+    from: i64,
+    #[serde(with = "rfc3339")]
+    to: i64,
+    group_by: ReportGrouping,
+    user_id: Option<i32>,
+}
+
+async fn get_time_report(
+    Extension(pool): Extension<DbPool>,
+    Query(query): Query<ReportQuery>,
+) -> Result<Json<TimeReport>, AppError> {
+    TimeTrackingService::validate_report_range(query.from, query.to)?;
+    let client = get_client(&pool).await?;
+    let rows = TimeRepository::report(&client, query.from, query.to, query.group_by, query.user_id).await?;
+
+    Ok(Json(TimeReport {
+        from: query.from,
+        to: query.to,
+        group_by: query.group_by,
+        total_minutes: rows.iter().map(|r| r.minutes).sum(),
+        rows,
+    }))
+}
@@ -0,0 +1,143 @@
+use axum::{extract::{Path, Query}, routing::{delete, get, post}, Extension, Router, Json, http::StatusCode};
+use serde_json::json;
+use crate::{
+    application::services::TaskService,
+    domain::{
+        entities::{
+            activity::{ActivityContext, ActivityEvent, ActivityKind, ActivitySubject},
+            task::Task,
+            trash::{TrashState, TrashedUser},
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+    },
+    infrastructure::db::{
+        activity_repo::ActivityRepository,
+        get_client,
+        task_repo::{TaskRepoError, TaskRepository},
+        user_repo::UserRepository,
+        DbPool,
+    },
+    interfaces::api::routes::task_routes::{rollup_ancestors, rollup_from},
+};
+use serde::Deserialize;
+
+pub fn trash_routes() -> Router {
+    Router::new()
+        .route("/trash/tasks", get(list_trashed_tasks))
+        .route("/trash/tasks/:id/restore", post(restore_task))
+        .route("/trash/tasks/:id", delete(purge_task))
+        .route("/trash/users", get(list_trashed_users))
+        .route("/trash/users/:id/restore", post(restore_user))
+        .route("/trash/users/:id", delete(purge_user))
+}
+
+#[derive(Deserialize)]
+struct TrashQuery {
+    state: Option<TrashState>,
+}
+
+async fn list_trashed_tasks(
+    Extension(pool): Extension<DbPool>,
+    Query(query): Query<TrashQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Task>>, AppError> {
+    let client = get_client(&pool).await?;
+    let state = query.state.unwrap_or(TrashState::Deleted);
+    Ok(Json(TaskRepository::find_trashed_tasks(&client, state, &page).await?))
+}
+
+async fn restore_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<Task>, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let mut subtree = TaskRepository::find_subtree(&tx, id).await?;
+    let parent_id = subtree.get(&id).ok_or_else(|| AppError::not_found("Task", id))?.parent_task;
+    let parent = match parent_id {
+        Some(parent_id) => match TaskRepository::find_task_by_id(&tx, parent_id).await {
+            Ok(parent) => Some(parent),
+            Err(TaskRepoError::TaskNotFound(_)) => None,
+            Err(err) => return Err(err.into()),
+        },
+        None => None,
+    };
+
+    subtree.values_mut().for_each(|t| t.set_activity_context(context.clone()));
+    let changed = TaskService::restore_subtree(&mut subtree, id, parent.as_ref())?;
+    for changed_id in changed {
+        if let Some(task) = subtree.get_mut(&changed_id) {
+            TaskRepository::update_task(&tx, task).await?;
+        }
+    }
+    rollup_from(&tx, id).await?;
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    Ok(Json(task))
+}
+
+async fn purge_task(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let task = TaskRepository::find_task_by_id(&tx, id).await?;
+    TaskService::ensure_purgeable(&task)?;
+    TaskRepository::purge_tasks(&tx, &[id]).await?;
+    if let Some(parent_id) = task.parent_task {
+        rollup_from(&tx, parent_id).await?;
+    } else {
+        rollup_ancestors(&tx, id).await?;
+    }
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_trashed_users(
+    Extension(pool): Extension<DbPool>,
+    Query(query): Query<TrashQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<TrashedUser>>, AppError> {
+    let client = get_client(&pool).await?;
+    let state = query.state.unwrap_or(TrashState::Deleted);
+    Ok(Json(UserRepository::find_trashed_users(&client, state, &page).await?))
+}
+
+async fn restore_user(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+    context: ActivityContext,
+) -> Result<Json<TrashedUser>, AppError> {
+    let client = get_client(&pool).await?;
+    let user = UserRepository::find_trashed_user(&client, id).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+    UserRepository::restore_user(&client, id, now).await?;
+
+    let event = ActivityEvent::new(
+        ActivitySubject::User,
+        id,
+        &context,
+        ActivityKind::Restored,
+        Some(json!({ "archived_at": user.archived_at, "deleted_at": user.deleted_at })),
+        None,
+    );
+    ActivityRepository::insert_events(&client, &[event]).await?;
+
+    Ok(Json(TrashedUser { archived_at: None, deleted_at: None, ..user }))
+}
+
+async fn purge_user(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let client = get_client(&pool).await?;
+    UserRepository::purge_user(&client, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
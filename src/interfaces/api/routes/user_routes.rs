@@ -1,6 +1,26 @@
-use axum::{routing::{post, put}, Router, Json, http::StatusCode};
-use crate::{application::services::UserService, domain::entities::user::{User, Role}};
-use serde::{Deserialize};
+use axum::{extract::{Path, Query}, routing::{get, post, put}, Extension, Router, Json, http::StatusCode};
+use crate::{
+    application::services::{ReminderService, UserService},
+    domain::{
+        entities::{
+            activity::{ActivityContext, ActivityEvent, ActivityKind, ActivitySubject},
+            reminder::ReminderSettings,
+            user::{User, Role},
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+        time::parse_time_zone,
+    },
+    infrastructure::db::{
+        activity_repo::{ActivityFilter, ActivityRepository},
+        get_client,
+        reminder_repo::ReminderRepository,
+        user_repo::UserRepository,
+        DbPool,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 pub fn user_routes() -> Router {
     Router::new()
@@ -11,6 +31,9 @@ pub fn user_routes() -> Router {
         .route("/users/:id/archive", put(archive_user))
         .route("/users/:id/delete", put(delete_user))
         .route("/users/:id/role", put(set_user_role))
+        .route("/users/:id/reminders", get(get_reminder_settings).put(update_reminder_settings))
+        .route("/users/:id/timezone", get(get_time_zone).put(set_time_zone))
+        .route("/users/:id/activity", get(get_user_activity))
 }
 
 #[derive(Deserialize)]
@@ -33,11 +56,16 @@ struct UpdateUserRequest {
     email: Option<String>,
 }
 
-async fn update_user_details(Json(payload): Json<UpdateUserRequest>) -> StatusCode {
-    let mut user = User::mock();
-    UserService::update_user_details(&mut user, payload.name, payload.surname, payload.email)
-        .map(|_| StatusCode::OK)
-        .unwrap_or(StatusCode::BAD_REQUEST)
+async fn update_user_details(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<UpdateUserRequest>,
+) -> Result<StatusCode, AppError> {
+    change_user(&pool, context, user_id, |user| {
+        UserService::update_user_details(user, payload.name, payload.surname, payload.email)
+    })
+    .await
 }
 
 #[derive(Deserialize)]
@@ -45,32 +73,38 @@ struct FollowRequest {
     followee_id: i32,
 }
 
-async fn follow_user(Json(payload): Json<FollowRequest>) -> StatusCode {
-    let mut user = User::mock();
-    UserService::follow_user(&mut user, payload.followee_id)
-        .map(|_| StatusCode::OK)
-        .unwrap_or(StatusCode::BAD_REQUEST)
+async fn follow_user(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<FollowRequest>,
+) -> Result<StatusCode, AppError> {
+    change_user(&pool, context, user_id, |user| UserService::follow_user(user, payload.followee_id)).await
 }
 
-async fn unfollow_user(Json(payload): Json<FollowRequest>) -> StatusCode {
-    let mut user = User::mock();
-    UserService::unfollow_user(&mut user, payload.followee_id)
-        .map(|_| StatusCode::OK)
-        .unwrap_or(StatusCode::BAD_REQUEST)
+async fn unfollow_user(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<FollowRequest>,
+) -> Result<StatusCode, AppError> {
+    change_user(&pool, context, user_id, |user| UserService::unfollow_user(user, payload.followee_id)).await
 }
 
-async fn archive_user() -> StatusCode {
-    let mut user = User::mock();
-    UserService::archive_user(&mut user)
-        .map(|_| StatusCode::OK)
-        .unwrap_or(StatusCode::BAD_REQUEST)
+async fn archive_user(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    change_user(&pool, context, user_id, UserService::archive_user).await
 }
 
-async fn delete_user() -> StatusCode {
-    let mut user = User::mock();
-    UserService::delete_user(&mut user)
-        .map(|_| StatusCode::OK)
-        .unwrap_or(StatusCode::BAD_REQUEST)
+async fn delete_user(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    change_user(&pool, context, user_id, UserService::delete_user).await
 }
 
 #[derive(Deserialize)]
@@ -78,8 +112,113 @@ struct RoleRequest {
     role: Role,
 }
 
-async fn set_user_role(Json(payload): Json<RoleRequest>) -> StatusCode {
-    let mut user = User::mock();
-    UserService::set_user_role(&mut user, payload.role);
-    StatusCode::OK
+async fn set_user_role(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<RoleRequest>,
+) -> Result<StatusCode, AppError> {
+    change_user(&pool, context, user_id, |user| {
+        UserService::set_user_role(user, payload.role);
+        Ok(())
+    })
+    .await
+}
+
+// Loads the user, applies `change` and saves it with the activity it recorded
+async fn change_user(
+    pool: &DbPool,
+    context: ActivityContext,
+    user_id: i32,
+    change: impl FnOnce(&mut User) -> Result<(), AppError>,
+) -> Result<StatusCode, AppError> {
+    let mut client = get_client(pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let mut user = UserRepository::find_user_by_id(&tx, user_id).await?;
+    user.set_activity_context(context);
+    change(&mut user)?;
+    UserRepository::update_user(&tx, &mut user).await?;
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_reminder_settings(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<ReminderSettings>, AppError> {
+    let client = get_client(&pool).await?;
+    let settings = ReminderRepository::find_settings(&client, &[user_id]).await?;
+    Ok(Json(settings.get(&user_id).cloned().unwrap_or_else(|| ReminderSettings::default_for(user_id))))
+}
+
+#[derive(Deserialize)]
+struct ReminderSettingsRequest {
+    offsets: Vec<i64>,
+}
+
+async fn update_reminder_settings(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<ReminderSettingsRequest>,
+) -> Result<Json<ReminderSettings>, AppError> {
+    let mut settings = ReminderSettings {
+        user_id,
+        offsets: payload.offsets,
+        updated_at: chrono::Utc::now().timestamp_millis(),
+    };
+    ReminderService::validate_settings(&mut settings)?;
+
+    let client = get_client(&pool).await?;
+    ReminderRepository::save_settings(&client, &settings).await?;
+    Ok(Json(settings))
+}
+
+#[derive(Deserialize, Serialize)]
+struct TimeZoneBody {
+    timezone: String,
+}
+
+async fn get_time_zone(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<TimeZoneBody>, AppError> {
+    let client = get_client(&pool).await?;
+    let timezone = UserRepository::find_time_zone(&client, user_id).await?;
+    Ok(Json(TimeZoneBody { timezone: timezone.name().to_string() }))
+}
+
+async fn set_time_zone(
+    Extension(pool): Extension<DbPool>,
+    context: ActivityContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<TimeZoneBody>,
+) -> Result<Json<TimeZoneBody>, AppError> {
+    let timezone = parse_time_zone(&payload.timezone)?;
+    let mut client = get_client(&pool).await?;
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+    let previous = UserRepository::set_time_zone(&tx, user_id, timezone, chrono::Utc::now().timestamp_millis()).await?;
+    if previous != timezone.name() {
+        let event = ActivityEvent::new(
+            ActivitySubject::User,
+            user_id,
+            &context,
+            ActivityKind::TimeZoneChanged,
+            Some(json!(previous)),
+            Some(json!(timezone.name())),
+        );
+        ActivityRepository::insert_events(&tx, &[event]).await?;
+    }
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok(Json(TimeZoneBody { timezone: timezone.name().to_string() }))
+}
+
+async fn get_user_activity(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+    Query(filter): Query<ActivityFilter>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<ActivityEvent>>, AppError> {
+    let client = get_client(&pool).await?;
+    let events = ActivityRepository::find_events(&client, ActivitySubject::User, user_id, &filter, &page).await?;
+    Ok(Json(events))
 }
@@ -0,0 +1,202 @@
+use axum::{extract::{Path, Query}, routing::{get, post, put}, Extension, Router, Json, http::StatusCode};
+use deadpool_postgres::GenericClient;
+use crate::{
+    application::services::{NotificationService, ProjectService},
+    domain::{
+        entities::{
+            activity::{ActivityContext, ActivityEvent, ActivityKind, ActivitySubject},
+            notification::{parse_mentions, Notification, Watcher},
+            project::parse_task_reference,
+            task::Task,
+        },
+        errors::AppError,
+        pagination::{Page, PageRequest},
+    },
+    infrastructure::db::{
+        activity_repo::ActivityRepository,
+        get_client,
+        notification_repo::NotificationRepository,
+        project_repo::ProjectRepository,
+        task_repo::TaskRepository,
+        user_repo::UserRepository,
+        watcher_repo::WatcherRepository,
+        DbPool,
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+
+const EXCERPT_CHARS: usize = 140;
+
+pub fn watcher_routes() -> Router {
+    Router::new()
+        .route("/tasks/:id/watchers", get(list_watchers))
+        .route("/tasks/:id/watchers/:user_id", put(watch_task).delete(unwatch_task))
+        .route("/users/:id/notifications", get(list_notifications))
+        .route("/users/:id/notifications/read", post(mark_notifications_read))
+}
+
+async fn list_watchers(
+    Extension(pool): Extension<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<Watcher>>, AppError> {
+    let client = get_client(&pool).await?;
+    TaskRepository::find_task_by_id(&client, id).await?;
+    Ok(Json(WatcherRepository::find_watchers(&client, id).await?))
+}
+
+// Users manage their own subscriptions; project managers may manage anyone's
+async fn ensure_can_change_watcher(client: &impl GenericClient, task: &Task, user_id: i32, context: &ActivityContext) -> Result<(), AppError> {
+    if context.actor_id == Some(user_id) {
+        return Ok(());
+    }
+    if let Some(project_id) = task.project_id {
+        let project = ProjectRepository::find_project(client, project_id).await?;
+        ProjectService::ensure_can_manage(&project, context.actor_id)?;
+    }
+    Ok(())
+}
+
+async fn watch_task(
+    Extension(pool): Extension<DbPool>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<Json<Vec<Watcher>>, AppError> {
+    let client = get_client(&pool).await?;
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_change_watcher(&client, &task, user_id, &context).await?;
+    UserRepository::find_user_by_id(&client, user_id).await?;
+    WatcherRepository::add_watchers(&client, id, &[user_id], chrono::Utc::now().timestamp_millis()).await?;
+    Ok(Json(WatcherRepository::find_watchers(&client, id).await?))
+}
+
+async fn unwatch_task(
+    Extension(pool): Extension<DbPool>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    context: ActivityContext,
+) -> Result<StatusCode, AppError> {
+    let client = get_client(&pool).await?;
+    let task = TaskRepository::find_task_by_id(&client, id).await?;
+    ensure_can_change_watcher(&client, &task, user_id, &context).await?;
+    if !WatcherRepository::remove_watcher(&client, id, user_id).await? {
+        return Err(AppError::not_found(&format!("Watcher of task {}", id), user_id));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Notifications are private to their recipient
+fn ensure_own_inbox(user_id: i32, context: &ActivityContext) -> Result<(), AppError> {
+    match context.actor_id {
+        Some(actor_id) if actor_id == user_id => Ok(()),
+        Some(actor_id) => Err(AppError::forbidden(actor_id, &format!("read notifications of user {}", user_id))),
+        None => Err(AppError::invalid_input("x-user-id is required to read notifications")),
+    }
+}
+
+#[derive(Deserialize)]
+struct NotificationQuery {
+    #[serde(default)]
+    unread: bool,
+}
+
+async fn list_notifications(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+    context: ActivityContext,
+    Query(query): Query<NotificationQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<Notification>>, AppError> {
+    ensure_own_inbox(user_id, &context)?;
+    let client = get_client(&pool).await?;
+    Ok(Json(NotificationRepository::find_notifications(&client, user_id, query.unread, &page).await?))
+}
+
+#[derive(Deserialize, Default)]
+struct MarkReadRequest {
+    // Every unread notification when omitted
+    ids: Option<Vec<i64>>,
+}
+
+async fn mark_notifications_read(
+    Extension(pool): Extension<DbPool>,
+    Path(user_id): Path<i32>,
+    context: ActivityContext,
+    payload: Option<Json<MarkReadRequest>>,
+) -> Result<StatusCode, AppError> {
+    ensure_own_inbox(user_id, &context)?;
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let client = get_client(&pool).await?;
+    NotificationRepository::mark_read(&client, user_id, payload.ids.as_deref(), chrono::Utc::now().timestamp_millis()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Mentioned users start watching the task and get a notification; referenced tasks get an
+// activity entry pointing back here. Returns the ids of the mentioned users.
+pub(crate) async fn process_mentions(
+    client: &impl GenericClient,
+    task: &Task,
+    text: &str,
+    context: &ActivityContext,
+) -> Result<Vec<i32>, AppError> {
+    let mentions = parse_mentions(text);
+    if mentions.is_empty() {
+        return Ok(Vec::new());
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let user_ids = if mentions.usernames.is_empty() {
+        Vec::new()
+    } else {
+        UserRepository::find_user_ids_by_usernames(client, &mentions.usernames).await?
+    };
+    if !user_ids.is_empty() {
+        WatcherRepository::add_watchers(client, task.id, &user_ids, now).await?;
+        let excerpt = text.chars().take(EXCERPT_CHARS).collect::<String>();
+        let notifications = NotificationService::mention_notifications(task, &user_ids, context.actor_id, &excerpt, now);
+        NotificationRepository::insert_notifications(client, &notifications).await?;
+    }
+
+    let mut referenced = mentions.task_ids;
+    for reference in &mentions.task_references {
+        let Some((key, number)) = parse_task_reference(reference) else { continue };
+        let found = TaskRepository::find_task_by_reference(client, &key, number).await?;
+        referenced.extend(found.map(|t| t.id));
+    }
+    referenced.retain(|&id| id != task.id);
+    if !referenced.is_empty() {
+        let existing = TaskRepository::find_tasks_by_ids(client, &referenced).await?;
+        let events = existing
+            .iter()
+            .map(|t| {
+                ActivityEvent::new(
+                    ActivitySubject::Task,
+                    t.id,
+                    context,
+                    ActivityKind::Referenced,
+                    None,
+                    Some(json!({ "task_id": task.id, "reference": task.reference })),
+                )
+            })
+            .collect::<Vec<_>>();
+        ActivityRepository::insert_events(client, &events).await?;
+    }
+    Ok(user_ids)
+}
+
+// Fans the comment, status and assignee events out to the task's watchers. A new assignee
+// starts watching first so they hear about the reassignment.
+pub(crate) async fn notify_watchers(
+    client: &impl GenericClient,
+    task: &Task,
+    events: &[ActivityEvent],
+    skip: &[i32],
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    if let Some(assignee) = task.assigned_to.filter(|_| events.iter().any(|e| e.kind == ActivityKind::AssigneeChanged)) {
+        WatcherRepository::add_watchers(client, task.id, &[assignee], now).await?;
+    }
+    let watchers = WatcherRepository::find_watcher_ids(client, task.id).await?;
+    let notifications = NotificationService::watcher_notifications(task, events, &watchers, skip, now);
+    NotificationRepository::insert_notifications(client, &notifications).await?;
+    Ok(())
+}
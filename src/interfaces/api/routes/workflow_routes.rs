@@ -0,0 +1,47 @@
+use axum::{extract::Path, routing::get, Extension, Router, Json};
+use crate::{
+    application::services::WorkflowService,
+    domain::{entities::{task::TaskStatus, workflow::{Transition, Workflow}}, errors::AppError},
+    infrastructure::db::{get_client, workflow_repo::WorkflowRepository, DbPool},
+};
+use serde::{Deserialize};
+
+pub fn workflow_routes() -> Router {
+    Router::new()
+        .route("/projects/:id/workflow", get(get_workflow).put(update_workflow))
+}
+
+async fn get_workflow(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Workflow>, AppError> {
+    let client = get_client(&pool).await?;
+    let workflow = WorkflowRepository::find_for_project(&client, Some(project_id)).await?;
+    Ok(Json(workflow))
+}
+
+#[derive(Deserialize)]
+struct WorkflowRequest {
+    statuses: Vec<TaskStatus>,
+    initial_status: TaskStatus,
+    transitions: Vec<Transition>,
+}
+
+async fn update_workflow(
+    Extension(pool): Extension<DbPool>,
+    Path(project_id): Path<i32>,
+    Json(payload): Json<WorkflowRequest>,
+) -> Result<Json<Workflow>, AppError> {
+    let workflow = Workflow {
+        project_id: Some(project_id),
+        statuses: payload.statuses,
+        initial_status: payload.initial_status,
+        transitions: payload.transitions,
+        updated_at: chrono::Utc::now().timestamp_millis(),
+    };
+    WorkflowService::validate_workflow(&workflow)?;
+
+    let client = get_client(&pool).await?;
+    WorkflowRepository::save_workflow(&client, &workflow).await?;
+    Ok(Json(workflow))
+}
@@ -0,0 +1,25 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use crate::domain::{entities::activity::ActivityContext, errors::AppError};
+
+pub const ACTOR_HEADER: &str = "x-user-id";
+pub const CORRELATION_HEADER: &str = "x-request-id";
+
+// Reads the acting user and request id from headers; both are optional
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ActivityContext {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+
+        let actor_id = header(ACTOR_HEADER)
+            .map(|v| v.parse::<i32>().map_err(|_| AppError::invalid_input("x-user-id must be a user id")))
+            .transpose()?;
+
+        Ok(ActivityContext {
+            actor_id,
+            correlation_id: header(CORRELATION_HEADER),
+        })
+    }
+}
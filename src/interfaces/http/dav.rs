@@ -0,0 +1,412 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+// Just enough XML for WebDAV request bodies: namespaces, elements, attributes and text.
+// Document type declarations are refused, so entity expansion never happens.
+
+pub const DAV: &str = "DAV:";
+pub const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+pub const CALENDARSERVER: &str = "http://calendarserver.org/ns/";
+
+const MAX_DEPTH: usize = 32;
+const KNOWN_PREFIXES: [(&str, &str); 3] = [(DAV, "d"), (CALDAV, "c"), (CALENDARSERVER, "cs")];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XmlName {
+    pub namespace: String,
+    pub name: String,
+}
+
+impl XmlName {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        XmlName { namespace: namespace.to_string(), name: name.to_string() }
+    }
+
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlElement {
+    pub name: XmlName,
+    // Keyed by local name; attributes in other namespaces are not needed
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+    pub text: String,
+}
+
+impl XmlElement {
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.name.is(namespace, name)
+    }
+
+    pub fn child(&self, namespace: &str, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.is(namespace, name))
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+pub fn parse_xml(input: &str) -> Result<XmlElement, String> {
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.element(&[], 0)?;
+    parser.skip_misc()?;
+    if parser.pos < input.len() {
+        return Err("Unexpected content after the document element".to_string());
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        let found = self.rest().find(end).ok_or_else(|| format!("Missing '{}'", end))?;
+        self.pos += found + end.len();
+        Ok(())
+    }
+
+    // Whitespace, comments and processing instructions around the document element
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!") {
+                return Err("Document type declarations are not supported".to_string());
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err("Expected a name".to_string());
+        }
+        self.pos += end;
+        Ok(&rest[..end])
+    }
+
+    fn element(&mut self, scopes: &[(String, String)], depth: usize) -> Result<XmlElement, String> {
+        if depth > MAX_DEPTH {
+            return Err("The document is nested too deeply".to_string());
+        }
+        if !self.rest().starts_with('<') {
+            return Err("Expected an element".to_string());
+        }
+        self.pos += 1;
+        let qualified = self.name()?.to_string();
+
+        let mut scopes = scopes.to_vec();
+        let mut attributes = Vec::new();
+        let empty = loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                break true;
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break false;
+            }
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(format!("Attribute '{}' has no value", key));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = self.rest().chars().next().filter(|c| *c == '"' || *c == '\'');
+            let quote = quote.ok_or_else(|| format!("Attribute '{}' is not quoted", key))?;
+            self.pos += 1;
+            let end = self.rest().find(quote).ok_or("Unterminated attribute value")?;
+            let value = decode_entities(&self.rest()[..end])?;
+            self.pos += end + 1;
+            match key.split_once(':') {
+                None if key == "xmlns" => scopes.push((String::new(), value)),
+                Some(("xmlns", prefix)) => scopes.push((prefix.to_string(), value)),
+                Some((_, local)) => attributes.push((local.to_string(), value)),
+                None => attributes.push((key, value)),
+            }
+        };
+
+        let (prefix, local) = qualified.split_once(':').unwrap_or(("", &qualified));
+        let namespace = scopes
+            .iter()
+            .rev()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, uri)| uri.clone())
+            .or_else(|| prefix.is_empty().then(String::new))
+            .ok_or_else(|| format!("Unknown namespace prefix '{}'", prefix))?;
+        let mut element = XmlElement { name: XmlName::new(&namespace, local), attributes, children: Vec::new(), text: String::new() };
+        if empty {
+            return Ok(element);
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let closing = self.name()?;
+                if closing != qualified {
+                    return Err(format!("Expected </{}> but found </{}>", qualified, closing));
+                }
+                self.skip_whitespace();
+                self.skip_past(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(data) = rest.strip_prefix("<![CDATA[") {
+                let end = data.find("]]>").ok_or("Unterminated CDATA section")?;
+                element.text.push_str(&data[..end]);
+                self.pos += "<![CDATA[".len() + end + 3;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                let child = self.element(&scopes, depth + 1)?;
+                element.children.push(child);
+            } else if rest.is_empty() {
+                return Err(format!("Missing </{}>", qualified));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&decode_entities(&rest[..end])?);
+                self.pos += end;
+            }
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or("Unterminated entity reference")? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("Unknown entity '&{};'", entity))?,
+        };
+        decoded.push(c);
+        rest = &rest[end + 1..];
+    }
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// `d:getetag` for well-known namespaces, `x:name xmlns:x="..."` otherwise
+fn open_tag(name: &XmlName) -> (String, String) {
+    match KNOWN_PREFIXES.iter().find(|(namespace, _)| *namespace == name.namespace) {
+        Some((_, prefix)) => (format!("{}:{}", prefix, name.name), format!("{}:{}", prefix, name.name)),
+        None => (format!("x:{} xmlns:x=\"{}\"", name.name, escape_xml(&name.namespace)), format!("x:{}", name.name)),
+    }
+}
+
+pub fn write_element(out: &mut String, name: &XmlName, inner: &str) {
+    let (open, close) = open_tag(name);
+    if inner.is_empty() {
+        out.push_str(&format!("<{}/>", open));
+    } else {
+        out.push_str(&format!("<{}>{}</{}>", open, inner, close));
+    }
+}
+
+pub fn href(path: &str) -> String {
+    format!("<d:href>{}</d:href>", escape_xml(path))
+}
+
+// The body of a 207 response to PROPFIND and REPORT requests
+pub struct MultiStatus {
+    body: String,
+}
+
+impl Default for MultiStatus {
+    fn default() -> Self {
+        let namespaces = KNOWN_PREFIXES
+            .iter()
+            .map(|(namespace, prefix)| format!(" xmlns:{}=\"{}\"", prefix, namespace))
+            .collect::<String>();
+        MultiStatus { body: format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus{}>", namespaces) }
+    }
+}
+
+impl MultiStatus {
+    // `found` holds properties with their inner XML; `missing` ones are reported as 404
+    pub fn response(&mut self, path: &str, found: &[(XmlName, String)], missing: &[XmlName]) {
+        self.body.push_str("<d:response>");
+        self.body.push_str(&href(path));
+        for (props, status) in [(found.len(), "200 OK"), (missing.len(), "404 Not Found")] {
+            if props == 0 {
+                continue;
+            }
+            self.body.push_str("<d:propstat><d:prop>");
+            if status.starts_with("200") {
+                found.iter().for_each(|(name, inner)| write_element(&mut self.body, name, inner));
+            } else {
+                missing.iter().for_each(|name| write_element(&mut self.body, name, ""));
+            }
+            self.body.push_str(&format!("</d:prop><d:status>HTTP/1.1 {}</d:status></d:propstat>", status));
+        }
+        self.body.push_str("</d:response>");
+    }
+
+    // A member that is gone, or was asked for but never existed
+    pub fn not_found(&mut self, path: &str) {
+        self.body.push_str(&format!("<d:response>{}<d:status>HTTP/1.1 404 Not Found</d:status></d:response>", href(path)));
+    }
+
+    pub fn into_response(mut self, sync_token: Option<&str>) -> Response {
+        if let Some(token) = sync_token {
+            self.body.push_str(&format!("<d:sync-token>{}</d:sync-token>", escape_xml(token)));
+        }
+        self.body.push_str("</d:multistatus>");
+        (StatusCode::MULTI_STATUS, xml_content_type(), self.body).into_response()
+    }
+}
+
+// A failed precondition, e.g. `dav_error(StatusCode::FORBIDDEN, CALDAV, "valid-calendar-data")`
+pub fn dav_error(status: StatusCode, namespace: &str, condition: &str) -> Response {
+    let mut body = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:error xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\">");
+    write_element(&mut body, &XmlName::new(namespace, condition), "");
+    body.push_str("</d:error>");
+    (status, xml_content_type(), body).into_response()
+}
+
+fn xml_content_type() -> [(header::HeaderName, HeaderValue); 1] {
+    [(header::CONTENT_TYPE, HeaderValue::from_static("application/xml; charset=utf-8"))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements_resolve_their_namespaces() {
+        let root = parse_xml(
+            "<?xml version=\"1.0\"?>\n<!-- sync -->\n<D:propfind xmlns:D=\"DAV:\" xmlns=\"urn:ietf:params:xml:ns:caldav\">\
+             <D:prop><D:getetag/><calendar-data/><x:color xmlns:x=\"http://apple.com/ns/ical/\"/></D:prop></D:propfind>",
+        )
+        .unwrap();
+
+        assert!(root.is(DAV, "propfind"));
+        let names = root.child(DAV, "prop").unwrap().children.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![XmlName::new(DAV, "getetag"), XmlName::new(CALDAV, "calendar-data"), XmlName::new("http://apple.com/ns/ical/", "color")]
+        );
+    }
+
+    #[test]
+    fn text_decodes_entities_and_cdata() {
+        let root = parse_xml(
+            "<c:text-match xmlns:c=\"urn:ietf:params:xml:ns:caldav\" negate-condition='yes'>\
+             Fish &amp; chips &#x2014; <![CDATA[<raw>]]> &#233;</c:text-match>",
+        )
+        .unwrap();
+
+        assert_eq!(root.text, "Fish & chips \u{2014} <raw> \u{e9}");
+        assert_eq!(root.attribute("negate-condition"), Some("yes"));
+    }
+
+    #[test]
+    fn malformed_documents_are_refused() {
+        let refused = [
+            "<!DOCTYPE d [<!ENTITY x \"y\">]><d:prop xmlns:d=\"DAV:\"/>",
+            "<d:prop xmlns:d=\"DAV:\"><d:getetag></d:prop>",
+            "<d:prop xmlns:d=\"DAV:\">",
+            "<x:prop/>",
+            "<prop>&bogus;</prop>",
+            "<prop a=unquoted/>",
+            "<prop/><prop/>",
+            "",
+        ];
+        for input in refused {
+            assert!(parse_xml(input).is_err(), "accepted {:?}", input);
+        }
+
+        let deep = format!("{}{}", "<a>".repeat(MAX_DEPTH + 2), "</a>".repeat(MAX_DEPTH + 2));
+        assert!(parse_xml(&deep).is_err());
+        let shallow = format!("{}{}", "<a>".repeat(MAX_DEPTH), "</a>".repeat(MAX_DEPTH));
+        assert!(parse_xml(&shallow).is_ok());
+    }
+
+    #[test]
+    fn responses_split_found_and_missing_properties() {
+        let mut status = MultiStatus::default();
+        let found = [(XmlName::new(DAV, "getetag"), escape_xml("\"abc\""))];
+        let missing = [XmlName::new("http://apple.com/ns/ical/", "calendar-color")];
+        status.response("/caldav/4/tasks/a&b.ics", &found, &missing);
+        status.not_found("/caldav/4/tasks/gone.ics");
+        let body = status.body.clone();
+
+        assert!(body.contains("<d:href>/caldav/4/tasks/a&amp;b.ics</d:href>"));
+        assert!(body.contains(
+            "<d:propstat><d:prop><d:getetag>&quot;abc&quot;</d:getetag></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>"
+        ));
+        assert!(body.contains(
+            "<d:propstat><d:prop><x:calendar-color xmlns:x=\"http://apple.com/ns/ical/\"/></d:prop>\
+             <d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>"
+        ));
+        assert!(body.contains(
+            "<d:response><d:href>/caldav/4/tasks/gone.ics</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>"
+        ));
+        assert_eq!(status.into_response(None).status(), StatusCode::MULTI_STATUS);
+    }
+
+    #[test]
+    fn errors_name_the_failed_precondition() {
+        let response = dav_error(StatusCode::FORBIDDEN, DAV, "valid-sync-token");
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/xml; charset=utf-8");
+    }
+}
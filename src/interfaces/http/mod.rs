@@ -1 +1,4 @@
+pub mod context;
+pub mod dav;
+pub mod errors;
 pub mod handlers;
\ No newline at end of file
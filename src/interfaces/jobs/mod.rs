@@ -0,0 +1,2 @@
+pub mod reminders;
+pub mod retention;
\ No newline at end of file
@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::{
+    application::services::ReminderService,
+    domain::{entities::reminder::{ReminderEvent, MAX_REMINDER_OFFSET_MINUTES}, errors::AppError},
+    infrastructure::{
+        db::{get_client, notification_repo::{NotificationRepoError, NotificationRepository}, reminder_repo::ReminderRepository, task_repo::TaskRepository, DbPool},
+        redis::redis_service::{RedisService, RedisServiceError},
+    },
+};
+
+// Reminders wait here between the scheduler and `deliver_reminders`, which turns them into
+// inbox notifications
+pub const REMINDER_QUEUE: &str = "events:reminders";
+
+// Claims outlive the due date by a week so late ticks still see them
+const CLAIM_GRACE_SECS: u64 = 7 * 24 * 60 * 60;
+// Reminders taken off the queue per transaction
+const DELIVERY_BATCH: usize = 100;
+
+pub fn spawn_reminder_scheduler(pool: DbPool, redis: RedisService<ReminderEvent>, every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            if let Err(err) = run_once(&pool, &redis).await {
+                tracing::error!("Reminder scheduler run failed: {}", err);
+            }
+            match deliver_reminders(&pool, &redis).await {
+                Ok(0) => {}
+                Ok(delivered) => tracing::info!("Delivered {} reminders", delivered),
+                Err(err) => tracing::error!("Reminder delivery failed: {}", err),
+            }
+        }
+    })
+}
+
+// Where emitted reminders go; a claim marks a reminder as taken by one instance
+#[async_trait]
+pub trait ReminderQueue {
+    // False when the reminder was claimed before
+    async fn claim(&self, key: &str, ttl_secs: u64) -> Result<bool, RedisServiceError>;
+    async fn release(&self, key: &str) -> Result<(), RedisServiceError>;
+    async fn push(&self, event: &ReminderEvent) -> Result<(), RedisServiceError>;
+}
+
+#[async_trait]
+impl ReminderQueue for RedisService<ReminderEvent> {
+    async fn claim(&self, key: &str, ttl_secs: u64) -> Result<bool, RedisServiceError> {
+        self.set_if_absent(key, ttl_secs).await
+    }
+
+    async fn release(&self, key: &str) -> Result<(), RedisServiceError> {
+        self.delete_value(key).await
+    }
+
+    async fn push(&self, event: &ReminderEvent) -> Result<(), RedisServiceError> {
+        self.push_value(REMINDER_QUEUE, event).await
+    }
+}
+
+// Emits every reminder due now and flags overdue tasks
+pub async fn run_once(pool: &DbPool, redis: &RedisService<ReminderEvent>) -> Result<usize, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let client = get_client(pool).await?;
+    let tasks = TaskRepository::find_tasks_due_before(&client, now + MAX_REMINDER_OFFSET_MINUTES * 60_000, now).await?;
+
+    let mut user_ids = tasks.iter().flat_map(|t| t.reminder_recipients()).collect::<Vec<_>>();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    let settings = ReminderRepository::find_settings(&client, &user_ids).await?;
+
+    let mut emitted = 0;
+    for mut task in tasks {
+        emitted += emit_reminders(redis, &ReminderService::due_reminders(&task, &settings, now), now).await?;
+
+        if task.is_overdue(now) && task.overdue_at.is_none() {
+            task.mark_overdue(now);
+            TaskRepository::mark_overdue(&client, &mut task).await?;
+        }
+    }
+    Ok(emitted)
+}
+
+// Each reminder is claimed before it is queued, so only one of several running instances
+// emits it; a claim whose push fails is released again so a later run retries the reminder.
+pub async fn emit_reminders(queue: &impl ReminderQueue, events: &[ReminderEvent], now: i64) -> Result<usize, AppError> {
+    let mut emitted = 0;
+    for event in events {
+        let key = event.dedup_key();
+        let ttl = ((event.due_date - now).max(0) / 1000) as u64 + CLAIM_GRACE_SECS;
+        if queue.claim(&key, ttl).await? {
+            if let Err(err) = queue.push(event).await {
+                queue.release(&key).await?;
+                return Err(err.into());
+            }
+            emitted += 1;
+        }
+    }
+    Ok(emitted)
+}
+
+// Saves queued reminders as notifications of their recipients. A batch that cannot be saved
+// is pushed back for the next run.
+pub async fn deliver_reminders(pool: &DbPool, redis: &RedisService<ReminderEvent>) -> Result<usize, AppError> {
+    let mut client = get_client(pool).await?;
+    let mut delivered = 0;
+    loop {
+        let events = redis.pop_values(REMINDER_QUEUE, DELIVERY_BATCH).await?;
+        if events.is_empty() {
+            return Ok(delivered);
+        }
+        let now = chrono::Utc::now().timestamp_millis();
+        let notifications = events.iter().map(|event| ReminderService::reminder_notification(event, now)).collect::<Vec<_>>();
+
+        let saved: Result<(), NotificationRepoError> = async {
+            let tx = client.transaction().await?;
+            NotificationRepository::insert_notifications(&tx, &notifications).await?;
+            tx.commit().await?;
+            Ok(())
+        }
+        .await;
+        if let Err(err) = saved {
+            for event in &events {
+                redis.push_value(REMINDER_QUEUE, event).await?;
+            }
+            return Err(err.into());
+        }
+        delivered += events.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::{
+        notification::NotificationKind,
+        reminder::{ReminderKind, ReminderSettings},
+        task::{Role, Task, TaskStatus},
+    };
+    use redis::{ErrorKind, RedisError};
+    use std::{collections::{HashMap, HashSet}, sync::Mutex};
+
+    const HOUR: i64 = 60 * 60_000;
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn task_due_in(millis: i64) -> Task {
+        let mut task = Task::new("File the report".to_string(), None);
+        task.id = 7;
+        task.assigned_to = Some(1);
+        task.due_date = Some(NOW + millis);
+        task
+    }
+
+    fn kinds(events: &[ReminderEvent]) -> Vec<(i32, ReminderKind)> {
+        events.iter().map(|event| (event.user_id, event.kind)).collect()
+    }
+
+    #[test]
+    fn due_reminders_report_the_closest_passed_offset() {
+        let task = task_due_in(30 * 60_000);
+
+        let events = ReminderService::due_reminders(&task, &HashMap::new(), NOW);
+        assert_eq!(kinds(&events), vec![(1, ReminderKind::Upcoming { minutes_before: 60 })]);
+        assert_eq!(events[0].due_date, NOW + 30 * 60_000);
+        assert_eq!(events[0].fired_at, NOW);
+
+        let far = task_due_in(2 * 24 * HOUR);
+        assert!(ReminderService::due_reminders(&far, &HashMap::new(), NOW).is_empty());
+    }
+
+    #[test]
+    fn due_reminders_use_each_recipients_own_offsets() {
+        let mut task = task_due_in(3 * HOUR);
+        task.add_collaborator(2, Role::Contributor);
+        let settings = HashMap::from([(2, ReminderSettings { user_id: 2, offsets: vec![4 * 60], updated_at: 0 })]);
+
+        let events = ReminderService::due_reminders(&task, &settings, NOW);
+        assert_eq!(
+            kinds(&events),
+            vec![(1, ReminderKind::Upcoming { minutes_before: 24 * 60 }), (2, ReminderKind::Upcoming { minutes_before: 4 * 60 })]
+        );
+    }
+
+    #[test]
+    fn due_reminders_flag_overdue_tasks_and_skip_closed_ones() {
+        let mut task = task_due_in(-HOUR);
+        assert_eq!(kinds(&ReminderService::due_reminders(&task, &HashMap::new(), NOW)), vec![(1, ReminderKind::Overdue)]);
+
+        task.status = TaskStatus::Completed;
+        assert!(ReminderService::due_reminders(&task, &HashMap::new(), NOW).is_empty());
+        let mut archived = task_due_in(-HOUR);
+        archived.archived_at = Some(NOW);
+        assert!(ReminderService::due_reminders(&archived, &HashMap::new(), NOW).is_empty());
+        let mut undated = task_due_in(0);
+        undated.due_date = None;
+        assert!(ReminderService::due_reminders(&undated, &HashMap::new(), NOW).is_empty());
+    }
+
+    #[test]
+    fn reminder_notifications_carry_the_due_date() {
+        let task = task_due_in(-HOUR);
+        let event = &ReminderService::due_reminders(&task, &HashMap::new(), NOW)[0];
+
+        let notification = ReminderService::reminder_notification(event, NOW);
+        assert_eq!((notification.user_id, notification.task_id, notification.kind), (1, 7, NotificationKind::Reminder));
+        assert_eq!(notification.actor_id, None);
+        assert_eq!(notification.payload.unwrap()["due_date"], "2023-11-14T21:13:20.000Z");
+    }
+
+    #[derive(Default)]
+    struct FakeQueue {
+        claims: Mutex<HashSet<String>>,
+        pushed: Mutex<Vec<ReminderEvent>>,
+        failing: bool,
+    }
+
+    #[async_trait]
+    impl ReminderQueue for FakeQueue {
+        async fn claim(&self, key: &str, _ttl_secs: u64) -> Result<bool, RedisServiceError> {
+            Ok(self.claims.lock().unwrap().insert(key.to_string()))
+        }
+
+        async fn release(&self, key: &str) -> Result<(), RedisServiceError> {
+            self.claims.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        async fn push(&self, event: &ReminderEvent) -> Result<(), RedisServiceError> {
+            if self.failing {
+                return Err(RedisError::from((ErrorKind::IoError, "connection lost")).into());
+            }
+            self.pushed.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn claimed_reminders_are_emitted_once() {
+        let queue = FakeQueue::default();
+        let events = ReminderService::due_reminders(&task_due_in(-HOUR), &HashMap::new(), NOW);
+
+        assert_eq!(emit_reminders(&queue, &events, NOW).await.unwrap(), 1);
+        // A later tick, or another instance, finds the claim and skips the reminder
+        assert_eq!(emit_reminders(&queue, &events, NOW + 60_000).await.unwrap(), 0);
+        assert_eq!(queue.pushed.lock().unwrap().len(), 1);
+        assert!(queue.claims.lock().unwrap().contains(&events[0].dedup_key()));
+    }
+
+    #[tokio::test]
+    async fn a_failed_push_releases_the_claim() {
+        let mut queue = FakeQueue { failing: true, ..FakeQueue::default() };
+        let events = ReminderService::due_reminders(&task_due_in(-HOUR), &HashMap::new(), NOW);
+
+        assert!(emit_reminders(&queue, &events, NOW).await.is_err());
+        assert!(queue.claims.lock().unwrap().is_empty());
+
+        queue.failing = false;
+        assert_eq!(emit_reminders(&queue, &events, NOW).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::{
+    domain::{entities::trash::RetentionPolicy, errors::AppError},
+    infrastructure::{
+        db::{attachment_repo::AttachmentRepository, get_client, task_repo::TaskRepository, user_repo::UserRepository, DbPool},
+        storage::SharedStorage,
+    },
+};
+
+// Attachment blobs removed per run; the rest wait for the next one
+const BLOB_DELETION_BATCH: i64 = 500;
+// Uploads queue their keys before writing content and clear them once committed; younger keys
+// may belong to an upload that is still running
+const BLOB_DELETION_GRACE_MILLIS: i64 = 60 * 60 * 1000;
+
+pub fn spawn_retention_job(pool: DbPool, storage: SharedStorage, policy: RetentionPolicy, every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            match run_once(&pool, &policy).await {
+                Ok((0, 0)) => {}
+                Ok((tasks, users)) => tracing::info!("Retention job purged {} tasks and {} users", tasks, users),
+                Err(err) => tracing::error!("Retention job run failed: {}", err),
+            }
+            if let Err(err) = delete_orphaned_blobs(&pool, &storage).await {
+                tracing::error!("Attachment cleanup failed: {}", err);
+            }
+        }
+    })
+}
+
+// Hard-deletes every trashed task and user past its retention period.
+// Returns how many tasks and users were purged.
+pub async fn run_once(pool: &DbPool, policy: &RetentionPolicy) -> Result<(usize, usize), AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let deleted_before = policy.deleted_cutoff(now);
+    let archived_before = policy.archived_cutoff(now);
+    let mut client = get_client(pool).await?;
+    // A run that fails part way leaves everything in place for the next one
+    let tx = client.transaction().await.map_err(AppError::database_error)?;
+
+    let expired = TaskRepository::find_expired_trash(&tx, deleted_before, archived_before).await?;
+    let purged_tasks = if expired.is_empty() {
+        0
+    } else {
+        TaskRepository::purge_tasks(&tx, &expired).await?.len()
+    };
+    let purged_users = UserRepository::purge_expired_users(&tx, deleted_before, archived_before).await?.len();
+
+    tx.commit().await.map_err(AppError::database_error)?;
+    Ok((purged_tasks, purged_users))
+}
+
+// Removes the stored content of attachments whose rows are gone, including those of purged
+// tasks. Keys that fail to delete stay queued for the next run.
+pub async fn delete_orphaned_blobs(pool: &DbPool, storage: &SharedStorage) -> Result<usize, AppError> {
+    let client = get_client(pool).await?;
+    let queued_before = chrono::Utc::now().timestamp_millis() - BLOB_DELETION_GRACE_MILLIS;
+    let keys = AttachmentRepository::find_pending_deletions(&client, queued_before, BLOB_DELETION_BATCH).await?;
+
+    let mut deleted = Vec::with_capacity(keys.len());
+    for key in keys {
+        let result = storage.delete(&key).await;
+        match result {
+            Ok(()) => deleted.push(key),
+            Err(err) => tracing::warn!("Failed to delete attachment {}: {}", key, err),
+        }
+    }
+    if !deleted.is_empty() {
+        AttachmentRepository::clear_pending_deletions(&client, &deleted).await?;
+    }
+    Ok(deleted.len())
+}
\ No newline at end of file
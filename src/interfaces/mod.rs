@@ -1,2 +1,3 @@
 pub mod api;
-pub mod http;
\ No newline at end of file
+pub mod http;
+pub mod jobs;
\ No newline at end of file
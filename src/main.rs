@@ -1,21 +1,86 @@
-use axum::{Router, routing::get};
+use axum::{Extension, Router, routing::get};
 use dotenv::dotenv;
-
-mod infrastructure;
-mod interfaces;
-mod application;
-mod domain;
+use std::{env, time::Duration};
+use taskflow::{domain, infrastructure, interfaces};
 
 #[deny(dead_code)]
 #[forbid(unsafe_code)]
 #[tokio::main]
 async fn main() {
     dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let pool = infrastructure::db::init_db().await.expect("Failed to initialize database");
+    let redis_pool = infrastructure::redis::init_redis().await.expect("Failed to initialize Redis");
+
+    let reminder_interval = env::var("REMINDER_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    interfaces::jobs::reminders::spawn_reminder_scheduler(
+        pool.clone(),
+        infrastructure::redis::redis_service::RedisService::new(redis_pool),
+        Duration::from_secs(reminder_interval),
+    );
+
+    let mut retention = domain::entities::trash::RetentionPolicy::default();
+    if let Some(days) = env::var("TRASH_RETENTION_DAYS").ok().and_then(|v| v.parse().ok()) {
+        retention.deleted_days = days;
+    }
+    retention.archived_days = env::var("ARCHIVE_RETENTION_DAYS").ok().and_then(|v| v.parse().ok());
+    let storage = infrastructure::storage::init_storage().expect("Failed to initialize attachment storage");
+    interfaces::jobs::retention::spawn_retention_job(pool.clone(), storage.clone(), retention, Duration::from_secs(60 * 60));
+
+    let mut comment_policy = domain::entities::comment::CommentPolicy::default();
+    if let Ok(statuses) = env::var("COMMENT_CLOSED_STATUSES") {
+        comment_policy.closed_statuses = statuses
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().expect("Invalid status in COMMENT_CLOSED_STATUSES"))
+            .collect();
+    }
+    if let Some(length) = env::var("COMMENT_MAX_LENGTH").ok().and_then(|v| v.parse().ok()) {
+        comment_policy.max_length = length;
+    }
+    comment_policy.edit_window_minutes = env::var("COMMENT_EDIT_WINDOW_MINUTES").ok().and_then(|v| v.parse().ok());
+
+    let mut attachment_limits = domain::entities::attachment::AttachmentLimits::default();
+    if let Some(bytes) = env::var("ATTACHMENT_MAX_BYTES").ok().and_then(|v| v.parse().ok()) {
+        attachment_limits.max_bytes = bytes;
+    }
+    if let Some(bytes) = env::var("ATTACHMENT_MAX_REQUEST_BYTES").ok().and_then(|v| v.parse().ok()) {
+        attachment_limits.max_request_bytes = bytes;
+    }
+    if let Ok(types) = env::var("ATTACHMENT_ALLOWED_TYPES") {
+        attachment_limits.allowed_types = types.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect();
+    }
 
     let app = Router::new()
         .merge(interfaces::api::routes::user_routes::user_routes())
         .merge(interfaces::api::routes::task_routes::task_routes())
-        .route("/health", get(interfaces::http::handlers::health_check));
+        .merge(interfaces::api::routes::workflow_routes::workflow_routes())
+        .merge(interfaces::api::routes::trash_routes::trash_routes())
+        .merge(interfaces::api::routes::custom_field_routes::custom_field_routes())
+        .merge(interfaces::api::routes::time_routes::time_routes())
+        .merge(interfaces::api::routes::project_routes::project_routes())
+        .merge(interfaces::api::routes::board_routes::board_routes())
+        .merge(interfaces::api::routes::sprint_routes::sprint_routes())
+        .merge(interfaces::api::routes::milestone_routes::milestone_routes())
+        .merge(interfaces::api::routes::template_routes::template_routes())
+        .merge(interfaces::api::routes::watcher_routes::watcher_routes())
+        .merge(interfaces::api::routes::comment_routes::comment_routes())
+        .merge(interfaces::api::routes::attachment_routes::attachment_routes(&attachment_limits))
+        .merge(interfaces::api::routes::label_routes::label_routes())
+        .merge(interfaces::api::routes::link_routes::link_routes())
+        .merge(interfaces::api::routes::checklist_routes::checklist_routes())
+        .merge(interfaces::api::routes::calendar_routes::calendar_routes())
+        .merge(interfaces::api::routes::caldav_routes::caldav_routes())
+        .route("/health", get(interfaces::http::handlers::health_check))
+        .layer(Extension(comment_policy))
+        .layer(Extension(attachment_limits))
+        .layer(Extension(storage))
+        .layer(Extension(pool));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     axum::serve(listener, app).await.unwrap();